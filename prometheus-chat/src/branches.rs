@@ -0,0 +1,466 @@
+//! Conversations as a tree of messages with one active path
+//!
+//! Editing a message or regenerating a reply keeps the old tail as an
+//! inactive branch instead of deleting it, so either app can list, switch to
+//! and prune the alternatives.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::message::ChatMessage;
+
+/// A message that is not on the active path of a conversation, together with its parent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageNode {
+    /// ID of the parent message, or `None` for an alternative first message
+    pub parent_id: Option<String>,
+    pub message: ChatMessage,
+}
+
+/// Summary of one branch (a path from the first message to a leaf) of a conversation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BranchInfo {
+    /// ID of the last message on the branch
+    pub leaf_id: String,
+    /// Index of the first message where the branch diverges from the active path
+    /// (equal to `message_count` for the active branch)
+    pub fork_index: usize,
+    /// Number of messages on the branch
+    pub message_count: usize,
+    /// Content of the first diverging message (or the last message for the active branch)
+    pub preview: String,
+    /// Timestamp of the last message on the branch
+    pub updated_at: String,
+    /// Whether this branch is the active path
+    pub active: bool,
+}
+
+/// Why a branch couldn't be switched to or pruned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BranchError {
+    /// No message has the given ID
+    NotFound(String),
+    /// The active path can't be pruned
+    Active,
+}
+
+impl fmt::Display for BranchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BranchError::NotFound(id) => write!(f, "Branch not found: {}", id),
+            BranchError::Active => write!(f, "Cannot prune the active branch"),
+        }
+    }
+}
+
+impl std::error::Error for BranchError {}
+
+/// A conversation whose messages form a tree
+///
+/// Implementors store the active path and the inactive nodes; the branch
+/// operations are provided.
+pub trait MessageTree {
+    /// Messages on the active path, in order
+    fn messages(&self) -> &[ChatMessage];
+    fn messages_mut(&mut self) -> &mut Vec<ChatMessage>;
+    /// Messages on inactive branches
+    fn branch_nodes(&self) -> &[MessageNode];
+    fn branch_nodes_mut(&mut self) -> &mut Vec<MessageNode>;
+    /// Record that the conversation changed, e.g. by updating its timestamp
+    fn touch(&mut self);
+
+    /// Remove a single message from the conversation
+    ///
+    /// Inactive branches below the removed message are removed with it; messages
+    /// after it on the active path are kept.
+    fn remove_message(&mut self, index: usize) {
+        if index < self.messages().len() {
+            let removed = self.messages_mut().remove(index);
+            let mut dropped = vec![removed.id];
+            let mut i = 0;
+            while i < dropped.len() {
+                for node in self.branch_nodes() {
+                    if node.parent_id.as_deref() == Some(dropped[i].as_str()) {
+                        dropped.push(node.message.id.clone());
+                    }
+                }
+                i += 1;
+            }
+            self.branch_nodes_mut().retain(|n| !dropped.contains(&n.message.id));
+            self.touch();
+        }
+    }
+
+    /// Replace the content of a message by creating an edited sibling
+    ///
+    /// The original message and everything after it are kept as an inactive branch,
+    /// and the active path ends at the edited message.
+    fn update_message(&mut self, index: usize, content: String) {
+        if index < self.messages().len() {
+            let edited = ChatMessage::new(self.messages()[index].role.clone(), content);
+            self.stash_tail(index);
+            self.messages_mut().push(edited);
+            self.touch();
+        }
+    }
+
+    /// Move every message after `index` off the active path
+    ///
+    /// The removed tail is kept as an inactive branch, so a message added
+    /// afterwards becomes a sibling of the old reply (e.g. a regenerated answer).
+    fn clear_messages_after(&mut self, index: usize) {
+        if index < self.messages().len() {
+            self.stash_tail(index + 1);
+            self.touch();
+        }
+    }
+
+    /// Move `messages[start..]` into the branch nodes, preserving parent links
+    fn stash_tail(&mut self, start: usize) {
+        if start >= self.messages().len() {
+            return;
+        }
+        let mut parent_id = start.checked_sub(1).map(|i| self.messages()[i].id.clone());
+        let tail: Vec<ChatMessage> = self.messages_mut().drain(start..).collect();
+        for message in tail {
+            let id = message.id.clone();
+            self.branch_nodes_mut().push(MessageNode { parent_id, message });
+            parent_id = Some(id);
+        }
+    }
+
+    /// Whether the conversation has any inactive branches
+    fn has_branches(&self) -> bool {
+        !self.branch_nodes().is_empty()
+    }
+
+    /// List every branch of the conversation, ordered by the time of its last message
+    fn list_branches(&self) -> Vec<BranchInfo> {
+        let nodes = all_nodes(self);
+        let active_ids: Vec<&str> = self.messages().iter().map(|m| m.id.as_str()).collect();
+        let active_leaf = active_ids.last().copied();
+
+        let mut branches: Vec<BranchInfo> = nodes
+            .iter()
+            .filter(|node| {
+                let is_leaf = !nodes
+                    .iter()
+                    .any(|n| n.parent_id.as_deref() == Some(node.message.id.as_str()));
+                is_leaf || Some(node.message.id.as_str()) == active_leaf
+            })
+            .map(|leaf| {
+                let path = path_to(&nodes, &leaf.message.id);
+                let fork_index = path
+                    .iter()
+                    .zip(active_ids.iter())
+                    .take_while(|(a, b)| a.as_str() == **b)
+                    .count();
+                let active = Some(leaf.message.id.as_str()) == active_leaf;
+                let preview_id = if active || fork_index >= path.len() {
+                    &leaf.message.id
+                } else {
+                    &path[fork_index]
+                };
+                let preview = nodes
+                    .iter()
+                    .find(|n| &n.message.id == preview_id)
+                    .map(|n| n.message.content.clone())
+                    .unwrap_or_default();
+
+                BranchInfo {
+                    leaf_id: leaf.message.id.clone(),
+                    fork_index,
+                    message_count: path.len(),
+                    preview,
+                    updated_at: leaf.message.timestamp.clone(),
+                    active,
+                }
+            })
+            .collect();
+
+        branches.sort_by(|a, b| a.updated_at.cmp(&b.updated_at));
+        branches
+    }
+
+    /// Make the branch containing `message_id` the active path
+    ///
+    /// If the message has replies, the most recent reply is followed until a leaf is reached.
+    fn switch_branch(&mut self, message_id: &str) -> Result<(), BranchError> {
+        let nodes = all_nodes(self);
+        if !nodes.iter().any(|n| n.message.id == message_id) {
+            return Err(BranchError::NotFound(message_id.to_string()));
+        }
+
+        let mut leaf_id = message_id.to_string();
+        while let Some(child) = nodes
+            .iter()
+            .filter(|n| n.parent_id.as_deref() == Some(leaf_id.as_str()))
+            .max_by(|a, b| a.message.timestamp.cmp(&b.message.timestamp))
+        {
+            leaf_id = child.message.id.clone();
+        }
+
+        let path = path_to(&nodes, &leaf_id);
+        let mut messages = Vec::with_capacity(path.len());
+        let mut branch_nodes = Vec::new();
+        for node in nodes {
+            if path.contains(&node.message.id) {
+                messages.push(node);
+            } else {
+                branch_nodes.push(node);
+            }
+        }
+        messages.sort_by_key(|n| path.iter().position(|id| id == &n.message.id));
+
+        *self.messages_mut() = messages.into_iter().map(|n| n.message).collect();
+        *self.branch_nodes_mut() = branch_nodes;
+        self.touch();
+        Ok(())
+    }
+
+    /// Delete an inactive branch, keeping any messages it shares with other branches
+    ///
+    /// # Returns
+    /// The number of messages removed
+    fn prune_branch(&mut self, leaf_id: &str) -> Result<usize, BranchError> {
+        if self.messages().iter().any(|m| m.id == leaf_id) {
+            return Err(BranchError::Active);
+        }
+        if !self.branch_nodes().iter().any(|n| n.message.id == leaf_id) {
+            return Err(BranchError::NotFound(leaf_id.to_string()));
+        }
+
+        let mut removed = 0;
+        let mut current = Some(leaf_id.to_string());
+        while let Some(id) = current {
+            let has_children = self
+                .branch_nodes()
+                .iter()
+                .any(|n| n.parent_id.as_deref() == Some(id.as_str()));
+            let Some(pos) = self.branch_nodes().iter().position(|n| n.message.id == id) else {
+                break;
+            };
+            if has_children {
+                break;
+            }
+            let node = self.branch_nodes_mut().remove(pos);
+            removed += 1;
+            current = node.parent_id;
+        }
+
+        self.touch();
+        Ok(removed)
+    }
+
+    /// Delete every inactive branch, keeping only the active path
+    ///
+    /// # Returns
+    /// The number of messages removed
+    fn prune_inactive_branches(&mut self) -> usize {
+        let removed = self.branch_nodes().len();
+        if removed > 0 {
+            self.branch_nodes_mut().clear();
+            self.touch();
+        }
+        removed
+    }
+}
+
+/// Every message in the conversation tree, active path first
+fn all_nodes<T: MessageTree + ?Sized>(tree: &T) -> Vec<MessageNode> {
+    let mut nodes = Vec::with_capacity(tree.messages().len() + tree.branch_nodes().len());
+    let mut parent_id = None;
+    for message in tree.messages() {
+        nodes.push(MessageNode {
+            parent_id: parent_id.clone(),
+            message: message.clone(),
+        });
+        parent_id = Some(message.id.clone());
+    }
+    nodes.extend(tree.branch_nodes().iter().cloned());
+    nodes
+}
+
+/// IDs of the messages on the path from the root to `id`, in order
+fn path_to(nodes: &[MessageNode], id: &str) -> Vec<String> {
+    let mut path = Vec::new();
+    let mut current = Some(id.to_string());
+    while let Some(current_id) = current {
+        match nodes.iter().find(|n| n.message.id == current_id) {
+            Some(node) => {
+                path.push(current_id);
+                current = node.parent_id.clone();
+            }
+            None => break,
+        }
+        // Guard against cycles in hand-edited files
+        if path.len() > nodes.len() {
+            break;
+        }
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Tree {
+        messages: Vec<ChatMessage>,
+        branch_nodes: Vec<MessageNode>,
+    }
+
+    impl MessageTree for Tree {
+        fn messages(&self) -> &[ChatMessage] {
+            &self.messages
+        }
+        fn messages_mut(&mut self) -> &mut Vec<ChatMessage> {
+            &mut self.messages
+        }
+        fn branch_nodes(&self) -> &[MessageNode] {
+            &self.branch_nodes
+        }
+        fn branch_nodes_mut(&mut self) -> &mut Vec<MessageNode> {
+            &mut self.branch_nodes
+        }
+        fn touch(&mut self) {}
+    }
+
+    fn tree_with(messages: &[(&str, &str)]) -> Tree {
+        let mut tree = Tree::default();
+        for (role, content) in messages {
+            tree.messages.push(ChatMessage::new(role.to_string(), content.to_string()));
+        }
+        tree
+    }
+
+    fn contents(tree: &Tree) -> Vec<&str> {
+        tree.messages.iter().map(|m| m.content.as_str()).collect()
+    }
+
+    fn reply(tree: &mut Tree, content: &str) {
+        tree.messages.push(ChatMessage::new("assistant".to_string(), content.to_string()));
+    }
+
+    #[test]
+    fn test_update_message_creates_sibling_branch() {
+        let mut tree = tree_with(&[
+            ("user", "Hello"),
+            ("assistant", "Hi"),
+            ("user", "Typo questoin"),
+            ("assistant", "Answer"),
+        ]);
+
+        tree.update_message(2, "Fixed question".to_string());
+
+        assert_eq!(contents(&tree), vec!["Hello", "Hi", "Fixed question"]);
+        assert_eq!(tree.branch_nodes.len(), 2);
+
+        let branches = tree.list_branches();
+        assert_eq!(branches.len(), 2);
+        let old = branches.iter().find(|b| !b.active).unwrap();
+        assert_eq!(old.fork_index, 2);
+        assert_eq!(old.message_count, 4);
+        assert_eq!(old.preview, "Typo questoin");
+    }
+
+    #[test]
+    fn test_clear_messages_after_keeps_tail_as_branch() {
+        let mut tree = tree_with(&[("user", "Question"), ("assistant", "Bad answer")]);
+
+        tree.clear_messages_after(0);
+        reply(&mut tree, "Better answer");
+
+        assert_eq!(contents(&tree), vec!["Question", "Better answer"]);
+        let branches = tree.list_branches();
+        assert_eq!(branches.len(), 2);
+        let old = branches.iter().find(|b| !b.active).unwrap();
+        assert_eq!(old.fork_index, 1);
+        assert_eq!(old.preview, "Bad answer");
+    }
+
+    #[test]
+    fn test_switch_branch_restores_original_path() {
+        let mut tree = tree_with(&[("user", "Question"), ("assistant", "First answer")]);
+        tree.clear_messages_after(0);
+        reply(&mut tree, "Second answer");
+
+        let original = tree.list_branches().into_iter().find(|b| !b.active).unwrap();
+        tree.switch_branch(&original.leaf_id).unwrap();
+
+        assert_eq!(contents(&tree), vec!["Question", "First answer"]);
+        assert_eq!(tree.branch_nodes.len(), 1);
+        assert_eq!(tree.branch_nodes[0].message.content, "Second answer");
+    }
+
+    #[test]
+    fn test_switch_branch_follows_latest_reply() {
+        let mut tree = tree_with(&[("user", "Question"), ("assistant", "Answer")]);
+        let question_id = tree.messages[0].id.clone();
+        tree.update_message(0, "Edited question".to_string());
+
+        tree.switch_branch(&question_id).unwrap();
+
+        assert_eq!(contents(&tree), vec!["Question", "Answer"]);
+    }
+
+    #[test]
+    fn test_switch_unknown_branch_fails() {
+        let mut tree = tree_with(&[("user", "Question")]);
+        assert_eq!(
+            tree.switch_branch("missing"),
+            Err(BranchError::NotFound("missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_prune_branch_keeps_shared_messages() {
+        let mut tree = tree_with(&[("user", "Q1"), ("assistant", "A1"), ("user", "Q2"), ("assistant", "A2")]);
+        tree.update_message(2, "Q2 edited".to_string());
+
+        let old = tree.list_branches().into_iter().find(|b| !b.active).unwrap();
+        let removed = tree.prune_branch(&old.leaf_id).unwrap();
+
+        assert_eq!(removed, 2);
+        assert!(!tree.has_branches());
+        assert_eq!(contents(&tree), vec!["Q1", "A1", "Q2 edited"]);
+    }
+
+    #[test]
+    fn test_prune_active_branch_fails() {
+        let mut tree = tree_with(&[("user", "Q"), ("assistant", "A")]);
+        let leaf_id = tree.messages[1].id.clone();
+        assert_eq!(tree.prune_branch(&leaf_id), Err(BranchError::Active));
+        assert_eq!(tree.messages.len(), 2);
+    }
+
+    #[test]
+    fn test_prune_inactive_branches() {
+        let mut tree = tree_with(&[("user", "Q"), ("assistant", "A")]);
+        tree.update_message(0, "Q edited".to_string());
+
+        assert_eq!(tree.prune_inactive_branches(), 2);
+        assert!(tree.list_branches().iter().all(|b| b.active));
+    }
+
+    #[test]
+    fn test_remove_message_drops_its_branches() {
+        let mut tree = tree_with(&[("user", "Hello"), ("assistant", "Hi there")]);
+        tree.clear_messages_after(0);
+        reply(&mut tree, "Hi");
+        tree.messages.push(ChatMessage::new("user".to_string(), "Q".to_string()));
+        reply(&mut tree, "A1");
+        tree.clear_messages_after(2);
+        reply(&mut tree, "A2");
+
+        // Undo the last exchange, reply first
+        tree.remove_message(3);
+        tree.remove_message(2);
+
+        assert_eq!(contents(&tree), vec!["Hello", "Hi"]);
+        assert_eq!(tree.branch_nodes.len(), 1);
+        assert_eq!(tree.branch_nodes[0].message.content, "Hi there");
+    }
+}
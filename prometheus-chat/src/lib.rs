@@ -0,0 +1,17 @@
+//! Chat message types and helpers shared by the Prometheus CLI and desktop app
+//!
+//! Both apps write conversation files in the same format, so the messages,
+//! the branches they form and the metadata recorded on each live here rather
+//! than in either frontend, along with the prompt and cleanup used to title
+//! conversations and the parsing of the thinking section some models start
+//! their responses with.
+
+pub mod branches;
+pub mod message;
+pub mod metadata;
+pub mod thinking;
+pub mod titling;
+
+pub use branches::{BranchError, BranchInfo, MessageNode, MessageTree};
+pub use message::ChatMessage;
+pub use metadata::{FinalChunkStats, GenerationOptions, MessageMetadata, OptionError};
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::metadata::MessageMetadata;
+
+/// A message as stored in conversation files
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// Unique message ID, used to link messages into a conversation tree.
+    /// Files written before branching existed have no IDs, so one is generated on load.
+    #[serde(default = "new_message_id")]
+    pub id: String,
+    pub role: String,
+    pub content: String,
+    pub timestamp: String,
+    /// How the message was produced; empty for user messages and older files
+    #[serde(default, skip_serializing_if = "MessageMetadata::is_empty")]
+    pub metadata: MessageMetadata,
+    /// Reasoning the model produced before its answer, kept out of `content`
+    /// so it is never sent back as context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<String>,
+}
+
+fn new_message_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+impl ChatMessage {
+    pub fn new(role: String, content: String) -> Self {
+        Self {
+            id: new_message_id(),
+            role,
+            content,
+            timestamp: chrono::Local::now().to_rfc3339(),
+            metadata: MessageMetadata::default(),
+            thinking: None,
+        }
+    }
+
+    /// Attach generation metadata to the message
+    pub fn with_metadata(mut self, metadata: MessageMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Attach the model's thinking to the message
+    pub fn with_thinking(mut self, thinking: Option<String>) -> Self {
+        self.thinking = thinking;
+        self
+    }
+}
@@ -0,0 +1,310 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Ollama generation options; unset ones use the model's defaults
+///
+/// Stored with a conversation's settings and recorded on each response
+/// generated with them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    /// Maximum number of tokens to generate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<u32>,
+    /// Context window size in tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_penalty: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+}
+
+/// Error from [`GenerationOptions::set`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionError {
+    /// No option has the given name
+    Unknown(String),
+    /// The value could not be parsed or is out of range
+    Invalid { expected: &'static str, value: String },
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OptionError::Unknown(name) => write!(
+                f,
+                "Unknown parameter '{}'. Available: {}",
+                name,
+                GenerationOptions::NAMES.join(", ")
+            ),
+            OptionError::Invalid { expected, value } => {
+                write!(f, "Expected {}, got '{}'", expected, value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OptionError {}
+
+impl GenerationOptions {
+    /// Names accepted by [`GenerationOptions::set`]
+    pub const NAMES: &'static [&'static str] = &[
+        "temperature",
+        "top_p",
+        "top_k",
+        "num_predict",
+        "num_ctx",
+        "repeat_penalty",
+        "seed",
+    ];
+
+    /// Set an option by name, or unset it with the value `default`
+    ///
+    /// `max_tokens` is accepted as another name for `num_predict`.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), OptionError> {
+        let value = (!value.eq_ignore_ascii_case("default")).then_some(value);
+        let positive = |v: &u32| *v > 0;
+        match name.to_lowercase().as_str() {
+            "temperature" => {
+                let valid = |v: &f32| (0.0..=2.0).contains(v);
+                self.temperature = parse_option(value, valid, "a number between 0.0 and 2.0")?;
+            }
+            "top_p" => {
+                let valid = |v: &f32| (0.0..=1.0).contains(v);
+                self.top_p = parse_option(value, valid, "a number between 0.0 and 1.0")?;
+            }
+            "top_k" => self.top_k = parse_option(value, positive, "a positive whole number")?,
+            "num_predict" | "max_tokens" => {
+                self.num_predict = parse_option(value, positive, "a positive whole number")?;
+            }
+            "num_ctx" => self.num_ctx = parse_option(value, positive, "a positive whole number")?,
+            "repeat_penalty" => {
+                let valid = |v: &f32| *v >= 0.0;
+                self.repeat_penalty = parse_option(value, valid, "a number of at least 0.0")?;
+            }
+            "seed" => self.seed = parse_option(value, |_: &i64| true, "a whole number")?,
+            _ => return Err(OptionError::Unknown(name.to_string())),
+        }
+        Ok(())
+    }
+
+    /// `(name, value)` of every option that is set, in the order of [`GenerationOptions::NAMES`]
+    pub fn values(&self) -> Vec<(&'static str, String)> {
+        let values = [
+            self.temperature.map(|v| v.to_string()),
+            self.top_p.map(|v| v.to_string()),
+            self.top_k.map(|v| v.to_string()),
+            self.num_predict.map(|v| v.to_string()),
+            self.num_ctx.map(|v| v.to_string()),
+            self.repeat_penalty.map(|v| v.to_string()),
+            self.seed.map(|v| v.to_string()),
+        ];
+        Self::NAMES
+            .iter()
+            .zip(values)
+            .filter_map(|(name, value)| value.map(|value| (*name, value)))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Parse an option value, checking it is in range; `None` unsets the option
+fn parse_option<T: FromStr>(
+    value: Option<&str>,
+    valid: impl Fn(&T) -> bool,
+    expected: &'static str,
+) -> Result<Option<T>, OptionError> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match value.parse::<T>() {
+        Ok(parsed) if valid(&parsed) => Ok(Some(parsed)),
+        _ => Err(OptionError::Invalid { expected, value: value.to_string() }),
+    }
+}
+
+/// Provenance and statistics of a generated message
+///
+/// Every field is optional: user messages carry none of them, and backends
+/// that don't report token counts or a finish reason leave those unset.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MessageMetadata {
+    /// Model that generated the message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Backend URL the request was sent to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// Persona active when the message was generated
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persona_id: Option<String>,
+    #[serde(default, skip_serializing_if = "GenerationOptions::is_empty")]
+    pub options: GenerationOptions,
+    /// Number of tokens in the prompt, as reported by the backend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_tokens: Option<u32>,
+    /// Number of tokens generated, as reported by the backend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_tokens: Option<u32>,
+    /// Wall-clock time from sending the request to the last token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// Why generation stopped, e.g. "stop", "length" or "interrupted"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+}
+
+impl MessageMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Copy the statistics from Ollama's final `done` chunk
+    pub fn record_final_chunk(&mut self, json: &serde_json::Value) {
+        let stats = FinalChunkStats::from_json(json);
+        self.prompt_tokens = stats.prompt_tokens;
+        self.completion_tokens = stats.completion_tokens;
+        self.finish_reason = stats.finish_reason;
+    }
+
+    /// Human-readable `key: value` pairs for the fields that are set
+    pub fn summary(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if let Some(model) = &self.model {
+            fields.push(("model", model.clone()));
+        }
+        if let Some(endpoint) = &self.endpoint {
+            fields.push(("endpoint", endpoint.clone()));
+        }
+        if let Some(persona_id) = &self.persona_id {
+            fields.push(("persona", persona_id.clone()));
+        }
+        fields.extend(self.options.values());
+        if let Some(prompt_tokens) = self.prompt_tokens {
+            fields.push(("prompt tokens", prompt_tokens.to_string()));
+        }
+        if let Some(completion_tokens) = self.completion_tokens {
+            fields.push(("completion tokens", completion_tokens.to_string()));
+        }
+        if let Some(latency_ms) = self.latency_ms {
+            fields.push(("latency", format!("{:.2}s", latency_ms as f64 / 1000.0)));
+        }
+        if let Some(finish_reason) = &self.finish_reason {
+            fields.push(("finish reason", finish_reason.clone()));
+        }
+        fields
+    }
+}
+
+/// Statistics Ollama reports in the final `done` chunk of a stream
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FinalChunkStats {
+    /// Tokens in the prompt (`prompt_eval_count`)
+    pub prompt_tokens: Option<u32>,
+    /// Tokens generated (`eval_count`)
+    pub completion_tokens: Option<u32>,
+    /// Why generation stopped (`done_reason`)
+    pub finish_reason: Option<String>,
+}
+
+impl FinalChunkStats {
+    /// Read the statistics from a final chunk; missing fields are left unset
+    pub fn from_json(json: &serde_json::Value) -> Self {
+        let count = |key: &str| {
+            json.get(key)
+                .and_then(|v| v.as_u64())
+                .and_then(|n| u32::try_from(n).ok())
+        };
+        Self {
+            prompt_tokens: count("prompt_eval_count"),
+            completion_tokens: count("eval_count"),
+            finish_reason: json
+                .get("done_reason")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_final_chunk() {
+        let mut metadata = MessageMetadata {
+            model: Some("llama3".to_string()),
+            ..Default::default()
+        };
+        metadata.record_final_chunk(&serde_json::json!({
+            "done": true,
+            "done_reason": "length",
+            "prompt_eval_count": 12,
+            "eval_count": 34
+        }));
+
+        assert_eq!(metadata.model.as_deref(), Some("llama3"));
+        assert_eq!(metadata.prompt_tokens, Some(12));
+        assert_eq!(metadata.completion_tokens, Some(34));
+        assert_eq!(metadata.finish_reason.as_deref(), Some("length"));
+    }
+
+    #[test]
+    fn test_final_chunk_without_stats() {
+        let stats = FinalChunkStats::from_json(&serde_json::json!({ "done": true }));
+        assert_eq!(stats, FinalChunkStats::default());
+    }
+
+    #[test]
+    fn test_generation_options_set() {
+        let mut options = GenerationOptions::default();
+        options.set("temperature", "0.2").unwrap();
+        options.set("MAX_TOKENS", "512").unwrap();
+        options.set("seed", "-7").unwrap();
+        assert_eq!(options.temperature, Some(0.2));
+        assert_eq!(options.num_predict, Some(512));
+        assert_eq!(
+            options.values(),
+            vec![
+                ("temperature", "0.2".to_string()),
+                ("num_predict", "512".to_string()),
+                ("seed", "-7".to_string()),
+            ]
+        );
+
+        assert!(options.set("temperature", "3").is_err());
+        assert!(options.set("top_k", "0").is_err());
+        assert!(options.set("top_p", "high").is_err());
+        assert!(options.set("mirostat", "1").is_err());
+        assert_eq!(options.temperature, Some(0.2));
+
+        options.set("temperature", "default").unwrap();
+        assert_eq!(options.temperature, None);
+    }
+
+    #[test]
+    fn test_option_errors_name_the_expected_value() {
+        let mut options = GenerationOptions::default();
+        assert_eq!(
+            options.set("top_k", "0").unwrap_err().to_string(),
+            "Expected a positive whole number, got '0'"
+        );
+        assert!(options.set("mirostat", "1").unwrap_err().to_string().starts_with("Unknown parameter 'mirostat'"));
+    }
+
+    #[test]
+    fn test_empty_metadata_is_not_serialized() {
+        let json = serde_json::to_value(MessageMetadata::default()).unwrap();
+        assert_eq!(json, serde_json::json!({}));
+    }
+}
@@ -0,0 +1,262 @@
+//! Separating the thinking section some models start their responses with from the answer
+
+/// Tag opening the reasoning section of models like deepseek-r1 and qwen3
+const OPEN_TAG: &str = "<think>";
+/// Tag closing the reasoning section
+const CLOSE_TAG: &str = "</think>";
+
+/// A piece of a streamed response
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// Part of the model's reasoning, between `<think>` and `</think>`
+    Thinking(String),
+    /// Part of the answer
+    Answer(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Nothing but whitespace seen yet; a thinking section can still open
+    Start,
+    Thinking,
+    Answer,
+}
+
+/// Splits a streamed response into thinking and answer segments
+///
+/// Only a `<think>` tag at the very start of the response opens a thinking
+/// section, so answers that merely mention the tag (e.g. in a code block)
+/// are left alone. Tags split across chunks are held back until complete.
+/// Whitespace right after either tag is dropped, as models separate the
+/// sections with blank lines.
+#[derive(Debug)]
+pub struct ThinkingParser {
+    state: State,
+    /// Text that can't be classified until more arrives
+    pending: String,
+    /// Drop leading whitespace of the next text emitted
+    trim_start: bool,
+}
+
+impl ThinkingParser {
+    pub fn new() -> Self {
+        Self {
+            state: State::Start,
+            pending: String::new(),
+            trim_start: false,
+        }
+    }
+
+    /// Whether the response is currently in its thinking section
+    pub fn is_thinking(&self) -> bool {
+        self.state == State::Thinking
+    }
+
+    /// Add a streamed chunk, returning the segments it completes
+    pub fn push(&mut self, chunk: &str) -> Vec<Segment> {
+        self.pending.push_str(chunk);
+        let mut segments = Vec::new();
+
+        loop {
+            match self.state {
+                State::Start => {
+                    let text = self.pending.trim_start();
+                    if let Some(rest) = text.strip_prefix(OPEN_TAG) {
+                        self.pending = rest.to_string();
+                        self.state = State::Thinking;
+                        self.trim_start = true;
+                    } else if OPEN_TAG.starts_with(text) {
+                        // Empty, or a tag still being streamed
+                        break;
+                    } else {
+                        self.state = State::Answer;
+                    }
+                }
+                State::Thinking => match self.pending.find(CLOSE_TAG) {
+                    Some(pos) => {
+                        let thinking: String = self.pending.drain(..pos + CLOSE_TAG.len()).collect();
+                        self.emit(&mut segments, &thinking[..pos]);
+                        self.state = State::Answer;
+                        self.trim_start = true;
+                    }
+                    None => {
+                        // Hold back what could be the start of the closing tag
+                        let keep = partial_tag_len(&self.pending, CLOSE_TAG);
+                        let thinking: String = self.pending.drain(..self.pending.len() - keep).collect();
+                        self.emit(&mut segments, &thinking);
+                        break;
+                    }
+                },
+                State::Answer => {
+                    let answer = std::mem::take(&mut self.pending);
+                    self.emit(&mut segments, &answer);
+                    break;
+                }
+            }
+        }
+
+        segments
+    }
+
+    /// Flush what is held back at the end of the stream
+    ///
+    /// A thinking section that never closed stays thinking.
+    pub fn finish(&mut self) -> Vec<Segment> {
+        let rest = std::mem::take(&mut self.pending);
+        if self.state == State::Start {
+            self.state = State::Answer;
+        }
+        let mut segments = Vec::new();
+        self.emit(&mut segments, &rest);
+        segments
+    }
+
+    fn emit(&mut self, segments: &mut Vec<Segment>, text: &str) {
+        let text = if self.trim_start { text.trim_start() } else { text };
+        if text.is_empty() {
+            return;
+        }
+        self.trim_start = false;
+        let text = text.to_string();
+        match (self.state, segments.last_mut()) {
+            (State::Thinking, Some(Segment::Thinking(last))) => last.push_str(&text),
+            (State::Thinking, _) => segments.push(Segment::Thinking(text)),
+            (_, Some(Segment::Answer(last))) => last.push_str(&text),
+            _ => segments.push(Segment::Answer(text)),
+        }
+    }
+}
+
+impl Default for ThinkingParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A complete response with its thinking section separated
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplitResponse {
+    pub answer: String,
+    /// The reasoning, if the model produced any
+    pub thinking: Option<String>,
+}
+
+/// Separate the thinking section of a complete response from its answer
+pub fn split_thinking(text: &str) -> SplitResponse {
+    let mut parser = ThinkingParser::new();
+    let mut segments = parser.push(text);
+    segments.extend(parser.finish());
+
+    let mut response = SplitResponse::default();
+    for segment in segments {
+        match segment {
+            Segment::Thinking(thinking) => response.thinking = Some(thinking.trim_end().to_string()),
+            Segment::Answer(answer) => response.answer = answer,
+        }
+    }
+    response
+}
+
+/// Length of the longest suffix of `text` that is a proper prefix of `tag`
+fn partial_tag_len(text: &str, tag: &str) -> usize {
+    (1..tag.len())
+        .rev()
+        .find(|len| text.ends_with(&tag[..*len]))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(chunks: &[&str]) -> Vec<Segment> {
+        let mut parser = ThinkingParser::new();
+        let mut segments: Vec<Segment> = Vec::new();
+        for chunk in chunks.iter().copied() {
+            segments.extend(parser.push(chunk));
+        }
+        segments.extend(parser.finish());
+
+        // Merge the segments of consecutive chunks
+        let mut merged: Vec<Segment> = Vec::new();
+        for segment in segments {
+            match (merged.last_mut(), segment) {
+                (Some(Segment::Thinking(last)), Segment::Thinking(text)) => last.push_str(&text),
+                (Some(Segment::Answer(last)), Segment::Answer(text)) => last.push_str(&text),
+                (_, segment) => merged.push(segment),
+            }
+        }
+        merged
+    }
+
+    fn thinking(text: &str) -> Segment {
+        Segment::Thinking(text.to_string())
+    }
+
+    fn answer(text: &str) -> Segment {
+        Segment::Answer(text.to_string())
+    }
+
+    #[test]
+    fn test_response_without_thinking() {
+        assert_eq!(parse(&["Hello", " world"]), vec![answer("Hello world")]);
+        assert_eq!(parse(&["<b>bold</b>"]), vec![answer("<b>bold</b>")]);
+    }
+
+    #[test]
+    fn test_thinking_then_answer() {
+        assert_eq!(
+            parse(&["<think>\nThe user greets me.\n</think>\n\nHello!"]),
+            vec![thinking("The user greets me.\n"), answer("Hello!")]
+        );
+    }
+
+    #[test]
+    fn test_tags_split_across_chunks() {
+        assert_eq!(
+            parse(&["\n<th", "ink>Hmm", "m</thi", "nk", ">\n", "Answer"]),
+            vec![thinking("Hmmm"), answer("Answer")]
+        );
+    }
+
+    #[test]
+    fn test_only_leading_tag_opens_thinking() {
+        assert_eq!(
+            parse(&["Use `<think>` tags</think>"]),
+            vec![answer("Use `<think>` tags</think>")]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_thinking_at_end() {
+        assert_eq!(parse(&["<think>still going </th"]), vec![thinking("still going </th")]);
+    }
+
+    #[test]
+    fn test_is_thinking() {
+        let mut parser = ThinkingParser::new();
+        assert!(!parser.is_thinking());
+        parser.push("<think>a");
+        assert!(parser.is_thinking());
+        parser.push("</think>b");
+        assert!(!parser.is_thinking());
+    }
+
+    #[test]
+    fn test_split_thinking() {
+        assert_eq!(
+            split_thinking("<think>\nReasoning.\n\n</think>\n\nAnswer"),
+            SplitResponse {
+                answer: "Answer".to_string(),
+                thinking: Some("Reasoning.".to_string()),
+            }
+        );
+        assert_eq!(
+            split_thinking("Plain answer\n"),
+            SplitResponse {
+                answer: "Plain answer\n".to_string(),
+                thinking: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,127 @@
+//! Conversation titles generated from the first exchange
+//!
+//! The prompt and the cleanup of the model's reply are shared so both apps
+//! title conversations the same way; each app sends the request itself.
+
+use crate::thinking::split_thinking;
+
+/// Longest title kept, in characters
+const MAX_TITLE_CHARS: usize = 60;
+
+/// How much of each message is included in the titling prompt, in characters
+const MAX_EXCERPT_CHARS: usize = 1000;
+
+/// Build the prompt asking a model to title a conversation
+///
+/// # Arguments
+/// * `user` - The first user message
+/// * `assistant` - The first assistant response
+pub fn build_title_prompt(user: &str, assistant: &str) -> String {
+    format!(
+        "Write a short title (at most 6 words) for the conversation below. \
+         Reply with the title only, without quotes or punctuation at the end.\n\n\
+         User: {}\n\nAssistant: {}\n\nTitle:",
+        excerpt(user),
+        excerpt(assistant)
+    )
+}
+
+/// Turn a model's reply into a usable title
+///
+/// Drops the thinking section of reasoning models, keeps the first non-empty
+/// line of the answer, strips a leading "Title:" label,
+/// surrounding quotes or markdown and trailing punctuation, and limits the
+/// length.
+///
+/// # Returns
+/// `None` if nothing usable is left
+pub fn clean_title(raw: &str) -> Option<String> {
+    let answer = split_thinking(raw).answer;
+    let line = answer.lines().map(str::trim).find(|l| !l.is_empty())?;
+
+    let line = match line.get(..6) {
+        Some(label) if label.eq_ignore_ascii_case("title:") => line[6..].trim(),
+        _ => line,
+    };
+
+    let title = line
+        .trim_matches(|c: char| matches!(c, '"' | '\'' | '`' | '*' | '#' | '“' | '”'))
+        .trim()
+        .trim_end_matches(['.', '!', '?', ':', ';', ','])
+        .trim();
+
+    if title.is_empty() {
+        return None;
+    }
+
+    if title.chars().count() <= MAX_TITLE_CHARS {
+        return Some(title.to_string());
+    }
+
+    // Cut at the last word boundary that fits
+    let truncated: String = title.chars().take(MAX_TITLE_CHARS).collect();
+    let cut = truncated.rfind(' ').unwrap_or(truncated.len());
+    Some(format!("{}...", truncated[..cut].trim_end()))
+}
+
+fn excerpt(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= MAX_EXCERPT_CHARS {
+        text.to_string()
+    } else {
+        let cut: String = text.chars().take(MAX_EXCERPT_CHARS).collect();
+        format!("{}...", cut)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_title_prompt_includes_exchange() {
+        let prompt = build_title_prompt("How do I sort a Vec?", "Use `sort()`.");
+        assert!(prompt.contains("User: How do I sort a Vec?"));
+        assert!(prompt.contains("Assistant: Use `sort()`."));
+        assert!(prompt.ends_with("Title:"));
+    }
+
+    #[test]
+    fn test_build_title_prompt_truncates_long_messages() {
+        let long = "a".repeat(5000);
+        let prompt = build_title_prompt(&long, "short");
+        assert!(prompt.len() < 2000);
+    }
+
+    #[test]
+    fn test_clean_title_strips_label_quotes_and_punctuation() {
+        assert_eq!(clean_title("\"Sorting Vectors in Rust\"").as_deref(), Some("Sorting Vectors in Rust"));
+        assert_eq!(clean_title("Title: Sorting Vectors.").as_deref(), Some("Sorting Vectors"));
+        assert_eq!(clean_title("**Rust Sorting**").as_deref(), Some("Rust Sorting"));
+        assert_eq!(clean_title("\n\n  Rust Sorting\nExtra line").as_deref(), Some("Rust Sorting"));
+    }
+
+    #[test]
+    fn test_clean_title_rejects_empty_replies() {
+        assert_eq!(clean_title(""), None);
+        assert_eq!(clean_title("   \n  "), None);
+        assert_eq!(clean_title("\"\""), None);
+        assert_eq!(clean_title("Title:"), None);
+    }
+
+    #[test]
+    fn test_clean_title_drops_thinking() {
+        assert_eq!(
+            clean_title("<think>\nThe user asks about sorting.\n</think>\n\nRust Sorting").as_deref(),
+            Some("Rust Sorting")
+        );
+        assert_eq!(clean_title("<think>Only reasoning</think>"), None);
+    }
+
+    #[test]
+    fn test_clean_title_limits_length() {
+        let title = clean_title(&"word ".repeat(40)).unwrap();
+        assert!(title.chars().count() <= MAX_TITLE_CHARS + 3);
+        assert!(title.ends_with("word..."));
+    }
+}
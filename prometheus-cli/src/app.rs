@@ -1,27 +1,52 @@
 use anyhow::{Context, Result};
 use crate::backend::BackendClient;
-use crate::commands::{Command, display_help};
+use crate::commands::{BranchAction, CodeAction, Command, ThinkAction, display_help};
 use crate::error::{ErrorDisplay, ErrorContext};
+use crate::highlight::{self, CodeStyle};
+use crate::line_editor::{Input, LineEditor};
+use crate::markdown_renderer::{self, CodeBlock};
 use crate::ollama_service::OllamaServiceManager;
 use crate::streaming::StreamingHandler;
 use crate::terminal::Terminal;
 use crate::config::AppConfig;
-use crate::conversation::{ChatMessage, Conversation, ConversationManager};
+use crate::conversation::{ChatMessage, Conversation, ConversationManager, MessageMetadata, MessageTree};
+use crate::retention;
+use crate::search::{self, SearchHit, SearchMode, SearchOptions};
+use crate::semantic;
+use crate::titling;
 use crate::update::{UpdateManager, UpdateStatus};
+use prometheus_personas::PersonaManager;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc;
 
 /// CLI application state and REPL loop
 pub struct CliApp {
-    #[allow(dead_code)]
     config: AppConfig,
     conversation: Conversation,
     conversation_manager: ConversationManager,
     backend_client: BackendClient,
     terminal: Terminal,
+    /// Reads the prompt with history and keybindings
+    line_editor: LineEditor,
     running: bool,
     model: String,
     backend_url: String,
     timeout_seconds: u64,
+    /// Prompt queued by `/retry` or `/edit`, sent by the REPL loop after the command returns
+    pending_prompt: Option<String>,
+    /// Results of the last `/search`, numbered for `/load <n>`
+    last_search: Vec<SearchHit>,
+    /// Highlighting of code blocks in streamed responses, changed with `/theme`
+    code_style: CodeStyle,
+    /// Show the thinking of reasoning models while streaming, changed with `/think`
+    show_thinking: bool,
+    /// Personas shared with the desktop app, used by `/persona`
+    personas: PersonaManager,
+    /// Background title generation sends `(conversation_id, title)` here
+    title_tx: mpsc::UnboundedSender<(String, String)>,
+    title_rx: mpsc::UnboundedReceiver<(String, String)>,
 }
 
 impl CliApp {
@@ -56,19 +81,39 @@ impl CliApp {
 
         let terminal = Terminal::new().context("Failed to create terminal")?;
 
-        let conversation_manager = ConversationManager::new();
+        let mut conversation_manager = ConversationManager::open()
+            .context("Failed to open conversation history")?;
+        if let Err(e) = conversation_manager.enable_search() {
+            log::warn!("Failed to load search index: {}", e);
+        }
+        retention::prune_on_startup(&config, &conversation_manager);
+        let personas = PersonaManager::load();
+        let line_editor = LineEditor::new(&config.ui, conversation_manager.input_history_path())?;
+        line_editor.update_completions(|c| {
+            c.endpoints = config.backend.saved_urls.clone();
+            c.personas = personas.get_all_personas().iter().map(|p| p.id.clone()).collect();
+        });
         let conversation = Conversation::with_timestamp_name(Some(model_name.clone()));
+        let (title_tx, title_rx) = mpsc::unbounded_channel();
 
         Ok(Self {
             timeout_seconds: config.backend.timeout_seconds,
+            code_style: CodeStyle::new(&config.ui.code_theme, config.ui.code_line_numbers),
+            show_thinking: config.ui.show_thinking,
+            personas,
             config,
             conversation,
             conversation_manager,
             backend_client,
             terminal,
+            line_editor,
             running: true,
             model: model_name,
             backend_url: url,
+            pending_prompt: None,
+            last_search: Vec::new(),
+            title_tx,
+            title_rx,
         })
     }
 
@@ -87,19 +132,35 @@ impl CliApp {
 
         let terminal = Terminal::new().context("Failed to create terminal")?;
 
-        let conversation_manager = ConversationManager::with_directory(temp_dir);
+        let mut conversation_manager = ConversationManager::with_directory(temp_dir);
+        conversation_manager.enable_search()?;
+        let personas = PersonaManager::from_file(temp_dir.join(prometheus_personas::PERSONAS_FILE));
+        let line_editor = LineEditor::new(&config.ui, conversation_manager.input_history_path())?;
+        line_editor.update_completions(|c| {
+            c.endpoints = config.backend.saved_urls.clone();
+            c.personas = personas.get_all_personas().iter().map(|p| p.id.clone()).collect();
+        });
         let conversation = Conversation::with_timestamp_name(Some(model_name.clone()));
+        let (title_tx, title_rx) = mpsc::unbounded_channel();
 
         Ok(Self {
             timeout_seconds: config.backend.timeout_seconds,
+            code_style: CodeStyle::new(&config.ui.code_theme, config.ui.code_line_numbers),
+            show_thinking: config.ui.show_thinking,
+            personas,
             config,
             conversation,
             conversation_manager,
             backend_client,
             terminal,
+            line_editor,
             running: true,
             model: model_name,
             backend_url: url,
+            pending_prompt: None,
+            last_search: Vec::new(),
+            title_tx,
+            title_rx,
         })
     }
 
@@ -312,26 +373,19 @@ impl CliApp {
         let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
             .context("Failed to set up SIGTERM handler")?;
 
+        self.spawn_model_completion_refresh();
+
         while self.running {
-            // Display prompt
-            self.terminal.write("> ")?;
+            self.refresh_conversation_completions();
 
-            // Create a channel for reading input
-            let (tx, mut rx) = tokio::sync::mpsc::channel(1);
-            
-            // Spawn a task to read input
-            let terminal_clone = Terminal::new()?;
-            tokio::spawn(async move {
-                let mut term = terminal_clone;
-                if let Ok(input) = term.read_line() {
-                    let _ = tx.send(input).await;
-                }
-            });
+            // Read input on a blocking thread so signals are still handled
+            let line_editor = self.line_editor.clone();
 
             // Wait for either input or signal
             tokio::select! {
-                // Handle SIGINT (Ctrl+C) at prompt
+                // Handle SIGINT sent while at the prompt
                 _ = sigint.recv() => {
+                    self.line_editor.abandon();
                     self.terminal.write("\n")?;
                     self.terminal.write_info("Received interrupt signal. Saving conversation and exiting...")?;
                     self.running = false;
@@ -339,13 +393,28 @@ impl CliApp {
                 }
                 // Handle SIGTERM
                 _ = sigterm.recv() => {
+                    self.line_editor.abandon();
                     self.terminal.write("\n")?;
                     self.terminal.write_info("Received termination signal. Saving conversation and exiting...")?;
                     self.running = false;
                     break;
                 }
                 // Handle user input
-                Some(input) = rx.recv() => {
+                input = line_editor.read_line("> ") => {
+                    let input = match input? {
+                        Input::Line(input) => input,
+                        // The editor reads Ctrl+C as a key rather than raising SIGINT
+                        Input::Interrupted => {
+                            self.terminal.write_info("Received interrupt signal. Saving conversation and exiting...")?;
+                            self.running = false;
+                            break;
+                        }
+                        Input::Eof => {
+                            self.running = false;
+                            break;
+                        }
+                    };
+
                     // Handle empty input
                     if input.trim().is_empty() {
                         continue;
@@ -354,10 +423,17 @@ impl CliApp {
                     // Check if it's a command or a prompt
                     if input.starts_with('/') {
                         self.handle_command(&input).await?;
+
+                        // /retry, /edit and /compose queue a prompt to send with signal support
+                        if let Some(prompt) = self.pending_prompt.take() {
+                            self.stream_response_with_signals(prompt, &mut sigint).await?;
+                        }
                     } else {
                         // Handle prompt with signal support during streaming
                         self.handle_prompt_with_signals(input, &mut sigint).await?;
                     }
+
+                    self.apply_generated_titles()?;
                 }
             }
         }
@@ -365,6 +441,28 @@ impl CliApp {
         self.shutdown().await
     }
 
+    /// Fetch the backend's models in the background for `/model` completion
+    fn spawn_model_completion_refresh(&self) {
+        let client = self.backend_client.clone();
+        let line_editor = self.line_editor.clone();
+        tokio::spawn(async move {
+            match client.fetch_models().await {
+                Ok(models) => line_editor.update_completions(|c| c.models = models),
+                Err(e) => log::debug!("Failed to fetch models for completion: {}", e),
+            }
+        });
+    }
+
+    /// Update the conversation IDs completed after `/load`
+    fn refresh_conversation_completions(&self) {
+        match self.conversation_manager.list_conversations() {
+            Ok(conversations) => self.line_editor.update_completions(|c| {
+                c.conversations = conversations.into_iter().map(|m| (m.id, m.name)).collect();
+            }),
+            Err(e) => log::debug!("Failed to list conversations for completion: {}", e),
+        }
+    }
+
     /// Handle a user prompt
     #[allow(dead_code)]
     async fn handle_prompt(&mut self, prompt: String) -> Result<()> {
@@ -468,6 +566,116 @@ impl CliApp {
         let user_message = ChatMessage::new("user".to_string(), prompt.clone());
         self.conversation.add_message(user_message);
 
+        self.stream_response_with_signals(prompt, sigint).await
+    }
+
+    /// Metadata for a response generated by the current model and backend
+    ///
+    /// # Arguments
+    /// * `started` - When the request was sent, used to compute the latency
+    fn response_metadata(&self, started: Instant) -> MessageMetadata {
+        MessageMetadata {
+            model: Some(self.model.clone()),
+            endpoint: Some(self.backend_client.base_url().to_string()),
+            latency_ms: Some(started.elapsed().as_millis() as u64),
+            persona_id: self.conversation.settings.persona.clone(),
+            options: self.conversation.settings.options.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Start generating a title for the conversation after its first exchange
+    ///
+    /// Runs in a background task so the REPL never waits for it; the result
+    /// is picked up by `apply_generated_titles`. Does nothing when
+    /// `conversations.auto_title` is disabled.
+    fn spawn_title_generation(&self) {
+        let settings = &self.config.conversations;
+        if !settings.auto_title {
+            return;
+        }
+        let (user, assistant) = match self.conversation.messages.as_slice() {
+            [user, assistant] if user.role == "user" && assistant.role == "assistant" => {
+                (user.content.clone(), assistant.content.clone())
+            }
+            _ => return,
+        };
+
+        let backend_client = self.backend_client.clone();
+        let model = settings.title_model.clone().unwrap_or_else(|| self.model.clone());
+        let conversation_id = self.conversation.id.clone();
+        let title_tx = self.title_tx.clone();
+        tokio::spawn(async move {
+            match titling::generate_title(&backend_client, &model, &user, &assistant).await {
+                Ok(title) => {
+                    let _ = title_tx.send((conversation_id, title));
+                }
+                Err(e) => log::warn!("Failed to generate conversation title: {}", e),
+            }
+        });
+    }
+
+    /// Embed the conversation's new messages for semantic search
+    ///
+    /// Runs in a background task when `conversations.semantic_search` is enabled;
+    /// failures (e.g. the embedding model not being pulled) are only logged, and
+    /// missed messages are embedded by the next semantic search.
+    fn spawn_embedding(&self) {
+        if !self.config.conversations.semantic_search {
+            return;
+        }
+
+        let manager = self.conversation_manager.clone();
+        let backend_client = self.backend_client.clone();
+        let model = self.config.conversations.embedding_model.clone();
+        let conversation = self.conversation.clone();
+        tokio::spawn(async move {
+            if let Err(e) = semantic::embed_conversation(&manager, &backend_client, &model, &conversation).await {
+                log::debug!("Failed to embed messages: {}", e);
+            }
+        });
+    }
+
+    /// Rename conversations whose background-generated titles have arrived
+    ///
+    /// The title may belong to a conversation that is no longer current
+    /// (e.g. after `/new`), in which case the saved file is updated instead.
+    fn apply_generated_titles(&mut self) -> Result<()> {
+        while let Ok((conversation_id, title)) = self.title_rx.try_recv() {
+            let result = if conversation_id == self.conversation.id {
+                self.conversation.name = title;
+                self.conversation_manager.save_conversation(&self.conversation)
+            } else {
+                self.conversation_manager
+                    .load_conversation(&conversation_id)
+                    .and_then(|mut conversation| {
+                        conversation.name = title;
+                        self.conversation_manager.save_conversation(&conversation)
+                    })
+            };
+
+            if let Err(e) = result {
+                let mut error_display = ErrorDisplay::new(Terminal::new()?);
+                let context = ErrorContext::Filesystem {
+                    operation: "save".to_string(),
+                    path: "conversation".to_string(),
+                };
+                error_display.display_error_with_context(&e, context)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send a prompt that is already the last message of the conversation
+    ///
+    /// Used by `handle_prompt_with_signals` and by `/retry` and `/edit`, which
+    /// resend an existing user message instead of adding a new one.
+    async fn stream_response_with_signals(
+        &mut self,
+        prompt: String,
+        sigint: &mut tokio::signal::unix::Signal,
+    ) -> Result<()> {
         // Display user prompt
         self.terminal.write_user_prompt(&prompt)?;
 
@@ -475,18 +683,22 @@ impl CliApp {
         self.terminal.show_spinner()?;
 
         // Create streaming handler with Arc<Mutex> for thread-safe access
-        let streaming_handler = Arc::new(std::sync::Mutex::new(StreamingHandler::new(Terminal::new()?)));
+        let handler = StreamingHandler::with_code_style(Terminal::new()?, &self.code_style)
+            .with_thinking_shown(self.show_thinking);
+        let streaming_handler = Arc::new(std::sync::Mutex::new(handler));
         let streaming_handler_clone = Arc::clone(&streaming_handler);
 
         // Create a channel to signal cancellation
         let (cancel_tx, _cancel_rx) = tokio::sync::mpsc::channel::<()>(1);
 
         // Spawn the backend request in a separate task
+        let started = Instant::now();
         let backend_client = self.backend_client.clone();
         let model = self.model.clone();
+        let settings = self.conversation.settings.clone();
         let request_task = tokio::spawn(async move {
             backend_client
-                .send_prompt_streaming(&prompt, &model, |chunk| {
+                .send_prompt_streaming_with_settings(&prompt, &model, &settings, |chunk| {
                     // Use std::sync::Mutex for synchronous access in callback
                     let mut handler = streaming_handler_clone.lock().unwrap();
                     handler.on_chunk(chunk)
@@ -514,6 +726,7 @@ impl CliApp {
                 // Get partial response
                 let handler = streaming_handler.lock().unwrap();
                 let partial = handler.buffer().to_string();
+                let thinking = handler.thinking();
                 drop(handler);
                 
                 // Display interruption message
@@ -522,7 +735,11 @@ impl CliApp {
                 
                 // Save partial response if any
                 if !partial.is_empty() {
-                    let ai_message = ChatMessage::new("assistant".to_string(), partial);
+                    let mut metadata = self.response_metadata(started);
+                    metadata.finish_reason = Some("interrupted".to_string());
+                    let ai_message = ChatMessage::new("assistant".to_string(), partial)
+                        .with_metadata(metadata)
+                        .with_thinking(thinking);
                     self.conversation.add_message(ai_message);
                     
                     if let Err(e) = self.conversation_manager.save_conversation(&self.conversation) {
@@ -544,14 +761,20 @@ impl CliApp {
 
         // Handle response or error
         match result {
-            Ok(_response) => {
+            Ok(response) => {
                 // Finalize streaming (adds newline)
                 let mut handler = streaming_handler.lock().unwrap();
                 let final_response = handler.finalize()?;
                 drop(handler);
 
-                // Add AI response to conversation
-                let ai_message = ChatMessage::new("assistant".to_string(), final_response);
+                // Add AI response to conversation, with the stats reported by the backend
+                let mut metadata = self.response_metadata(started);
+                metadata.prompt_tokens = response.prompt_tokens;
+                metadata.completion_tokens = response.completion_tokens;
+                metadata.finish_reason = response.finish_reason;
+                let ai_message = ChatMessage::new("assistant".to_string(), final_response)
+                    .with_metadata(metadata)
+                    .with_thinking(response.thinking);
                 self.conversation.add_message(ai_message);
 
                 // Save conversation
@@ -563,6 +786,9 @@ impl CliApp {
                     };
                     error_display.display_error_with_context(&e, context)?;
                 }
+
+                self.spawn_title_generation();
+                self.spawn_embedding();
             }
             Err(e) => {
                 // Display error with appropriate context
@@ -576,6 +802,7 @@ impl CliApp {
                 // Save partial response if any
                 let handler = streaming_handler.lock().unwrap();
                 let partial = handler.buffer().to_string();
+                let thinking = handler.thinking();
                 drop(handler);
 
                 if !partial.is_empty() {
@@ -583,7 +810,11 @@ impl CliApp {
                     self.terminal.write("\n")?;
                     
                     // Save partial response to conversation
-                    let ai_message = ChatMessage::new("assistant".to_string(), partial);
+                    let mut metadata = self.response_metadata(started);
+                    metadata.finish_reason = Some("error".to_string());
+                    let ai_message = ChatMessage::new("assistant".to_string(), partial)
+                        .with_metadata(metadata)
+                        .with_thinking(thinking);
                     self.conversation.add_message(ai_message);
 
                     // Try to save conversation with partial response
@@ -859,6 +1090,7 @@ impl CliApp {
         
         match self.backend_client.fetch_models().await {
             Ok(models) => {
+                self.line_editor.update_completions(|c| c.models = models.clone());
                 if models.is_empty() {
                     self.terminal.write_info("⚠️  No models available on this endpoint")?;
                 } else {
@@ -906,8 +1138,10 @@ impl CliApp {
                     .save_conversation(&self.conversation)
                     .context("Failed to save current conversation")?;
 
-                // Create new conversation
+                // Create new conversation, keeping the system prompt and parameters
+                let settings = self.conversation.settings.clone();
                 self.conversation = Conversation::with_timestamp_name(Some(self.model.clone()));
+                self.conversation.settings = settings;
                 self.terminal.write_info("Started new conversation")?;
             }
             Command::Help => {
@@ -918,6 +1152,7 @@ impl CliApp {
                 self.terminal.write_info("Fetching available models...")?;
                 match self.backend_client.fetch_models().await {
                     Ok(models) => {
+                        self.line_editor.update_completions(|c| c.models = models.clone());
                         self.terminal.write("\nAvailable models:\n")?;
                         for model in models {
                             self.terminal.write(&format!("  - {}\n", model))?;
@@ -934,26 +1169,787 @@ impl CliApp {
                     }
                 }
             }
-            Command::Update => {
-                self.handle_update().await?;
+            Command::Model(name) => {
+                self.handle_model(name).await?;
+            }
+            Command::Set(param, value) => {
+                self.handle_set(&param, &value)?;
+            }
+            Command::Params => {
+                self.handle_params()?;
+            }
+            Command::System(text) => {
+                self.handle_system(text)?;
+            }
+            Command::Persona(id) => {
+                self.handle_persona(id)?;
+            }
+            Command::Personas => {
+                self.handle_personas()?;
+            }
+            Command::Update => {
+                self.handle_update().await?;
+            }
+            Command::UpdateCheck => {
+                self.handle_update_check().await?;
+            }
+            Command::StartLocal => {
+                self.handle_start_local().await?;
+            }
+            Command::Switch(target) => {
+                self.handle_switch(&target).await?;
+            }
+            Command::Branch(action) => {
+                self.handle_branch(action)?;
+            }
+            Command::Retry => {
+                self.handle_retry()?;
+            }
+            Command::Edit => {
+                self.handle_edit()?;
+            }
+            Command::Compose => {
+                self.handle_compose()?;
+            }
+            Command::Undo => {
+                self.handle_undo()?;
+            }
+            Command::Info => {
+                self.handle_info()?;
+            }
+            Command::Export(path) => {
+                self.handle_export(&path)?;
+            }
+            Command::Tag(tags) => {
+                self.handle_tag(&tags)?;
+            }
+            Command::Pin => {
+                self.conversation.pinned = true;
+                self.save_conversation_reporting_errors()?;
+                self.terminal.write_info("Pinned conversation")?;
+            }
+            Command::Unpin => {
+                self.conversation.pinned = false;
+                self.save_conversation_reporting_errors()?;
+                self.terminal.write_info("Unpinned conversation")?;
+            }
+            Command::Folder(folder) => {
+                self.handle_folder(folder.as_deref())?;
+            }
+            Command::Theme(theme) => {
+                self.handle_theme(theme.as_deref())?;
+            }
+            Command::Code(action) => {
+                self.handle_code(action)?;
+            }
+            Command::Think(action) => {
+                self.handle_think(action)?;
+            }
+            Command::Search(options) => {
+                self.handle_search(&options).await?;
+            }
+            Command::Load(target) => {
+                self.handle_load(&target)?;
+            }
+            Command::Unknown(cmd) => {
+                self.terminal.write_error(&format!(
+                    "Unknown command: /{}. Type /help for available commands",
+                    cmd
+                ))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle the /branch command
+    ///
+    /// Branches are numbered in the order returned by `Conversation::list_branches`,
+    /// so the numbers shown by `/branch list` can be passed to the other actions.
+    fn handle_branch(&mut self, action: BranchAction) -> Result<()> {
+        let branches = self.conversation.list_branches();
+
+        let select = |n: usize| branches.get(n - 1).map(|b| b.leaf_id.clone());
+
+        match action {
+            BranchAction::List => {
+                if !self.conversation.has_branches() {
+                    self.terminal.write_info("This conversation has no other branches")?;
+                    return Ok(());
+                }
+                self.terminal.write(&format!("\nBranches ({}):\n", branches.len()))?;
+                for (i, branch) in branches.iter().enumerate() {
+                    let preview: String = branch.preview.lines().next().unwrap_or("").chars().take(50).collect();
+                    let location = if branch.active {
+                        "active".to_string()
+                    } else {
+                        format!("forks at #{}", branch.fork_index + 1)
+                    };
+                    let marker = if branch.active { "*" } else { " " };
+                    self.terminal.write(&format!(
+                        "  {} {}. {} message(s), {}: {}\n",
+                        marker,
+                        i + 1,
+                        branch.message_count,
+                        location,
+                        preview
+                    ))?;
+                }
+                self.terminal.write("\n")?;
+                return Ok(());
+            }
+            BranchAction::Switch(n) => {
+                let Some(leaf_id) = select(n) else {
+                    self.terminal.write_error(&format!("No branch {}. Use /branch list to see branches", n))?;
+                    return Ok(());
+                };
+                self.conversation.switch_branch(&leaf_id)?;
+                self.terminal.write_info(&format!(
+                    "Switched to branch {} ({} message(s))",
+                    n,
+                    self.conversation.messages.len()
+                ))?;
+                if let Some(last) = self.conversation.messages.last() {
+                    self.terminal.write(&format!("{}: {}\n", last.role, last.content))?;
+                }
+            }
+            BranchAction::Prune(n) => {
+                let Some(leaf_id) = select(n) else {
+                    self.terminal.write_error(&format!("No branch {}. Use /branch list to see branches", n))?;
+                    return Ok(());
+                };
+                match self.conversation.prune_branch(&leaf_id) {
+                    Ok(removed) => {
+                        self.terminal.write_info(&format!("Pruned branch {} ({} message(s) removed)", n, removed))?;
+                    }
+                    Err(e) => {
+                        self.terminal.write_error(&e.to_string())?;
+                        return Ok(());
+                    }
+                }
+            }
+            BranchAction::PruneAll => {
+                let removed = self.conversation.prune_inactive_branches();
+                self.terminal.write_info(&format!("Pruned all inactive branches ({} message(s) removed)", removed))?;
+            }
+        }
+
+        if let Err(e) = self.conversation_manager.save_conversation(&self.conversation) {
+            let mut error_display = ErrorDisplay::new(Terminal::new()?);
+            let context = ErrorContext::Filesystem {
+                operation: "save".to_string(),
+                path: "conversation".to_string(),
+            };
+            error_display.display_error_with_context(&e, context)?;
+        }
+
+        Ok(())
+    }
+
+    /// Index of the last user message on the active path
+    fn last_user_message_index(&self) -> Option<usize> {
+        self.conversation.messages.iter().rposition(|m| m.role == "user")
+    }
+
+    /// Handle the /model command
+    ///
+    /// Switches to `name`, or lets the user pick one of the backend's models.
+    /// The model is saved with the conversation and restored by `/load`.
+    async fn handle_model(&mut self, name: Option<String>) -> Result<()> {
+        let available = self.backend_client.fetch_models().await;
+        if let Ok(models) = &available {
+            self.line_editor.update_completions(|c| c.models = models.clone());
+        }
+
+        let model = match (name, available) {
+            (Some(name), Ok(models)) if !models.contains(&name) => {
+                return self.terminal.write_error(&format!("Model '{}' is not available (see /models)", name));
+            }
+            (Some(name), Ok(_)) => name,
+            (Some(name), Err(e)) => {
+                self.terminal.write_warning(&format!("Could not check that '{}' is available: {}", name, e))?;
+                name
+            }
+            (None, Ok(models)) => self.select_model_from_list(models).await?,
+            (None, Err(e)) => {
+                return self.terminal.write_error(&format!("Failed to fetch models: {}", e));
+            }
+        };
+
+        self.model = model.clone();
+        self.conversation.model = Some(model);
+        self.terminal.write_info(&format!("Using model {}", self.model))
+    }
+
+    /// Handle the /set command
+    ///
+    /// Parameters are saved with the conversation; `default` unsets one.
+    fn handle_set(&mut self, param: &str, value: &str) -> Result<()> {
+        if let Err(e) = self.conversation.settings.options.set(param, value) {
+            return self.terminal.write_error(&e.to_string());
+        }
+        if value.eq_ignore_ascii_case("default") {
+            self.terminal.write_info(&format!("{} reset to the model's default", param))
+        } else {
+            self.terminal.write_info(&format!("{} set to {}", param, value))
+        }
+    }
+
+    /// Handle the /params command
+    fn handle_params(&mut self) -> Result<()> {
+        let settings = &self.conversation.settings;
+        let mut lines = vec![format!("Model: {}", self.model)];
+        if let Some(persona) = &settings.persona {
+            lines.push(format!("Persona: {}", persona));
+        }
+        lines.push(format!("System prompt: {}", settings.system.as_deref().unwrap_or("(model default)")));
+        let values = settings.options.values();
+        if values.is_empty() {
+            lines.push("Parameters: (model defaults)".to_string());
+        } else {
+            lines.push("Parameters:".to_string());
+            lines.extend(values.into_iter().map(|(name, value)| format!("  {} = {}", name, value)));
+        }
+        self.terminal.write(&format!("\n{}\n\n", lines.join("\n")))
+    }
+
+    /// Handle the /system command
+    ///
+    /// Shows the system prompt, sets it, or removes it with `-`.
+    fn handle_system(&mut self, text: Option<String>) -> Result<()> {
+        match text.as_deref() {
+            None => match self.conversation.settings.system.clone() {
+                Some(system) => self.terminal.write(&format!("\n{}\n\n", system)),
+                None => self.terminal.write_info("No system prompt set, the model's default is used"),
+            },
+            Some("-") => {
+                self.conversation.settings.system = None;
+                self.conversation.settings.persona = None;
+                self.terminal.write_info("System prompt removed")
+            }
+            Some(text) => {
+                self.set_system_prompt(text.to_string());
+                self.terminal.write_info("System prompt set")
+            }
+        }
+    }
+
+    /// Replace the conversation's system prompt, dropping any persona
+    pub fn set_system_prompt(&mut self, system: String) {
+        self.conversation.settings.system = Some(system);
+        self.conversation.settings.persona = None;
+    }
+
+    /// Use a persona's prompt as the conversation's system prompt
+    ///
+    /// # Errors
+    /// Returns an error if no persona has this ID
+    pub fn set_persona(&mut self, id: &str) -> Result<()> {
+        let persona = self
+            .personas
+            .get_persona(id)
+            .ok_or_else(|| anyhow::anyhow!("Persona '{}' not found (see /personas)", id))?;
+        self.conversation.settings.system = Some(persona.system_prompt.clone());
+        self.conversation.settings.persona = Some(persona.id.clone());
+        Ok(())
+    }
+
+    /// Handle the /persona command
+    ///
+    /// Shows the persona, switches to one, or removes it and its prompt with `-`.
+    fn handle_persona(&mut self, id: Option<String>) -> Result<()> {
+        match id.as_deref() {
+            None => match self.conversation.settings.persona.as_deref() {
+                Some(id) => {
+                    let label = match self.personas.get_persona(id) {
+                        Some(persona) => format!("{} {} ({})", persona.icon, persona.name, persona.id),
+                        None => id.to_string(),
+                    };
+                    self.terminal.write_info(&format!("Using persona {}", label))
+                }
+                None => self.terminal.write_info("No persona set (see /personas)"),
+            },
+            Some("-") => {
+                if self.conversation.settings.persona.take().is_none() {
+                    return self.terminal.write_info("No persona set");
+                }
+                self.conversation.settings.system = None;
+                self.terminal.write_info("Persona removed")
+            }
+            Some(id) => {
+                if let Err(e) = self.set_persona(id) {
+                    return self.terminal.write_error(&e.to_string());
+                }
+                self.handle_persona(None)
+            }
+        }
+    }
+
+    /// Handle the /personas command
+    fn handle_personas(&mut self) -> Result<()> {
+        let active = self.conversation.settings.persona.as_deref();
+        let mut output = String::from("\nPersonas:\n");
+        for persona in self.personas.get_all_personas() {
+            let marker = if active == Some(persona.id.as_str()) { "*" } else { " " };
+            output.push_str(&format!("{} {:<18} {} {}", marker, persona.id, persona.icon, persona.name));
+            if let Some(description) = &persona.description {
+                output.push_str(&format!(" - {}", description));
+            }
+            output.push('\n');
+        }
+        if let Some(path) = prometheus_personas::personas_path() {
+            output.push_str(&format!("\nAdd personas in {}\n", path.display()));
+        }
+        output.push('\n');
+        self.terminal.write(&output)
+    }
+
+    /// Handle the /retry command
+    ///
+    /// Moves the last response onto an inactive branch and queues the last
+    /// user message to be sent again.
+    fn handle_retry(&mut self) -> Result<()> {
+        let Some(index) = self.last_user_message_index() else {
+            self.terminal.write_error("There is no message to retry")?;
+            return Ok(());
+        };
+
+        self.conversation.clear_messages_after(index);
+        self.pending_prompt = Some(self.conversation.messages[index].content.clone());
+        Ok(())
+    }
+
+    /// Handle the /edit command
+    ///
+    /// Opens the last user message in `$EDITOR` (falling back to `vi`). If the text
+    /// was changed, the edited message replaces it on the active path and is resent.
+    fn handle_edit(&mut self) -> Result<()> {
+        let Some(index) = self.last_user_message_index() else {
+            self.terminal.write_error("There is no message to edit")?;
+            return Ok(());
+        };
+
+        let original = self.conversation.messages[index].content.clone();
+        let edited = match edit_in_external_editor(&original) {
+            Ok(edited) => edited,
+            Err(e) => {
+                self.terminal.write_error(&format!("Failed to edit message: {}", e))?;
+                return Ok(());
+            }
+        };
+
+        let edited = edited.trim_end().to_string();
+        if edited.trim().is_empty() || edited == original.trim_end() {
+            self.terminal.write_info("Message unchanged, nothing to resend")?;
+            return Ok(());
+        }
+
+        self.conversation.update_message(index, edited.clone());
+        self.pending_prompt = Some(edited);
+        Ok(())
+    }
+
+    /// Handle the /compose command
+    ///
+    /// Opens `$EDITOR` on a template and sends the saved text as a new message.
+    /// Saving without writing anything cancels it.
+    fn handle_compose(&mut self) -> Result<()> {
+        let composed = match edit_in_external_editor(&format!("\n\n{}\n", COMPOSE_HINT)) {
+            Ok(composed) => composed,
+            Err(e) => {
+                self.terminal.write_error(&format!("Failed to compose message: {}", e))?;
+                return Ok(());
+            }
+        };
+
+        let message = composed.replace(COMPOSE_HINT, "");
+        let message = message.trim_start_matches(['\r', '\n']).trim_end().to_string();
+        if message.trim().is_empty() {
+            self.terminal.write_info("Message empty, nothing to send")?;
+            return Ok(());
+        }
+
+        self.conversation.add_message(ChatMessage::new("user".to_string(), message.clone()));
+        self.pending_prompt = Some(message);
+        Ok(())
+    }
+
+    /// Handle the /undo command
+    ///
+    /// Removes the last response and the user message that prompted it. A user
+    /// message without a response (e.g. after a failed request) is removed on its own.
+    fn handle_undo(&mut self) -> Result<()> {
+        let Some(index) = self.last_user_message_index() else {
+            self.terminal.write_error("There is nothing to undo")?;
+            return Ok(());
+        };
+
+        let removed = self.conversation.messages.len() - index;
+        for i in (index..self.conversation.messages.len()).rev() {
+            self.conversation.remove_message(i);
+        }
+        self.terminal.write_info(&format!("Removed last exchange ({} message(s))", removed))?;
+
+        if let Err(e) = self.conversation_manager.save_conversation(&self.conversation) {
+            let mut error_display = ErrorDisplay::new(Terminal::new()?);
+            let context = ErrorContext::Filesystem {
+                operation: "save".to_string(),
+                path: "conversation".to_string(),
+            };
+            error_display.display_error_with_context(&e, context)?;
+        }
+
+        Ok(())
+    }
+
+    /// Handle the /info command
+    ///
+    /// Shows the current conversation and the model, endpoint and stats
+    /// recorded for its most recent response.
+    fn handle_info(&mut self) -> Result<()> {
+        let conversation = &self.conversation;
+        let mut info = format!("\nConversation: {}\n", conversation.name);
+        info.push_str(&format!("  ID:       {}\n", conversation.id));
+        info.push_str(&format!("  Created:  {}\n", conversation.created_at));
+        info.push_str(&format!("  Updated:  {}\n", conversation.updated_at));
+        info.push_str(&format!("  Messages: {}\n", conversation.messages.len()));
+        info.push_str(&format!("  Model:    {}\n", self.model));
+        info.push_str(&format!("  Endpoint: {}\n", self.backend_client.base_url()));
+
+        let last_response = conversation
+            .messages
+            .iter()
+            .rev()
+            .find(|m| !m.metadata.is_empty());
+        match last_response {
+            Some(message) => {
+                info.push_str(&format!("\nLast response ({}):\n", message.timestamp));
+                for (key, value) in message.metadata.summary() {
+                    info.push_str(&format!("  {:<18} {}\n", format!("{}:", key), value));
+                }
+            }
+            None => info.push_str("\nNo responses recorded yet\n"),
+        }
+        info.push('\n');
+
+        self.terminal.write(&info)
+    }
+
+    /// Handle the /export command
+    ///
+    /// # Arguments
+    /// * `path` - Destination file; `.json` exports JSON, anything else Markdown
+    fn handle_export(&mut self, path: &str) -> Result<()> {
+        let path = match (path.strip_prefix("~/"), std::env::var("HOME")) {
+            (Some(rest), Ok(home)) => Path::new(&home).join(rest),
+            _ => Path::new(path).to_path_buf(),
+        };
+
+        match self.conversation.export_to_file(&path) {
+            Ok(()) => self
+                .terminal
+                .write_info(&format!("Exported conversation to {}", path.display())),
+            Err(e) => {
+                let mut error_display = ErrorDisplay::new(Terminal::new()?);
+                let context = ErrorContext::Filesystem {
+                    operation: "export".to_string(),
+                    path: path.display().to_string(),
+                };
+                error_display.display_error_with_context(&e, context)
+            }
+        }
+    }
+
+    /// Handle the /tag command
+    ///
+    /// # Arguments
+    /// * `tags` - Tags to add; tags prefixed with `-` are removed. Empty lists the tags.
+    fn handle_tag(&mut self, tags: &[String]) -> Result<()> {
+        if tags.is_empty() {
+            if self.conversation.tags.is_empty() {
+                self.terminal.write_info("This conversation has no tags")?;
+            } else {
+                self.terminal
+                    .write_info(&format!("Tags: {}", self.conversation.tags.join(", ")))?;
+            }
+            return Ok(());
+        }
+
+        for tag in tags {
+            if let Some(tag) = tag.strip_prefix('-') {
+                if !self.conversation.remove_tag(tag) {
+                    self.terminal.write_error(&format!("Conversation is not tagged '{}'", tag))?;
+                }
+            } else if let Err(e) = self.conversation.add_tag(tag) {
+                self.terminal.write_error(&e.to_string())?;
+            }
+        }
+        self.save_conversation_reporting_errors()?;
+
+        let tags = if self.conversation.tags.is_empty() {
+            "(none)".to_string()
+        } else {
+            self.conversation.tags.join(", ")
+        };
+        self.terminal.write_info(&format!("Tags: {}", tags))
+    }
+
+    /// Handle the /folder command
+    ///
+    /// # Arguments
+    /// * `folder` - New folder path, `-` to remove the folder, or `None` to show it
+    fn handle_folder(&mut self, folder: Option<&str>) -> Result<()> {
+        let Some(folder) = folder else {
+            let current = self.conversation.folder.as_deref().unwrap_or("(none)");
+            return self.terminal.write_info(&format!("Folder: {}", current));
+        };
+
+        let folder = if folder == "-" { None } else { Some(folder) };
+        if let Err(e) = self.conversation.set_folder(folder) {
+            return self.terminal.write_error(&e.to_string());
+        }
+        self.save_conversation_reporting_errors()?;
+
+        match &self.conversation.folder {
+            Some(folder) => self.terminal.write_info(&format!("Moved conversation to {}", folder)),
+            None => self.terminal.write_info("Removed conversation from its folder"),
+        }
+    }
+
+    /// Handle the /theme command
+    ///
+    /// Without a name, lists the code themes and marks the current one. The
+    /// theme is changed for this session; `ui.code_theme` sets the default.
+    fn handle_theme(&mut self, theme: Option<&str>) -> Result<()> {
+        let Some(name) = theme else {
+            self.terminal.write("\nCode themes:\n")?;
+            for theme in highlight::theme_names() {
+                let marker = if theme.eq_ignore_ascii_case(&self.code_style.theme) { "*" } else { " " };
+                self.terminal.write(&format!("  {} {}\n", marker, theme))?;
+            }
+            return Ok(());
+        };
+
+        match highlight::find_theme(name) {
+            Some(theme) => {
+                self.code_style.theme = theme.to_string();
+                self.terminal.write_info(&format!("Code theme: {}", theme))
+            }
+            None => self.terminal.write_error(&format!(
+                "Unknown code theme: {}. Type /theme to list them",
+                name
+            )),
+        }
+    }
+
+    /// Handle the /code command
+    ///
+    /// Blocks are taken from the last response and numbered as listed by `/code`.
+    fn handle_code(&mut self, action: CodeAction) -> Result<()> {
+        let blocks = self
+            .conversation
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "assistant")
+            .map(|m| markdown_renderer::code_blocks(&m.content))
+            .unwrap_or_default();
+        if blocks.is_empty() {
+            return self.terminal.write_info("The last response has no code blocks");
+        }
+
+        let block = |n: usize| blocks.get(n - 1).ok_or(n);
+        match action {
+            CodeAction::List => {
+                self.terminal.write("\nCode blocks:\n")?;
+                for (i, block) in blocks.iter().enumerate() {
+                    let lines = block.code.lines().count();
+                    self.terminal.write(&format!(
+                        "  {}. {} ({} line{})\n",
+                        i + 1,
+                        block.language.as_deref().unwrap_or("text"),
+                        lines,
+                        if lines == 1 { "" } else { "s" }
+                    ))?;
+                }
+                Ok(())
+            }
+            CodeAction::Save(n, path) => match block(n) {
+                Ok(block) => self.save_code_block(block, &path),
+                Err(n) => self.write_no_code_block(n, blocks.len()),
+            },
+            CodeAction::Copy(n) => match block(n) {
+                Ok(block) => {
+                    self.terminal.copy_to_clipboard(&block.code)?;
+                    self.terminal.write_info(&format!("Copied code block {} to the clipboard", n))
+                }
+                Err(n) => self.write_no_code_block(n, blocks.len()),
+            },
+        }
+    }
+
+    fn write_no_code_block(&mut self, n: usize, count: usize) -> Result<()> {
+        self.terminal.write_error(&format!(
+            "No code block {}; the last response has {}. Type /code to list them",
+            n, count
+        ))
+    }
+
+    /// Write a code block to a file, asking before overwriting an existing one
+    fn save_code_block(&mut self, block: &CodeBlock, path: &str) -> Result<()> {
+        let path = match (path.strip_prefix("~/"), std::env::var("HOME")) {
+            (Some(rest), Ok(home)) => Path::new(&home).join(rest),
+            _ => Path::new(path).to_path_buf(),
+        };
+
+        if path.exists() {
+            self.terminal
+                .write(&format!("{} exists. Overwrite? [y/N] ", path.display()))?;
+            let answer = self.terminal.read_line()?;
+            if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+                return self.terminal.write_info("Not saved");
+            }
+        }
+
+        let mut code = block.code.clone();
+        code.push('\n');
+        match std::fs::write(&path, code) {
+            Ok(()) => self
+                .terminal
+                .write_info(&format!("Saved code block to {}", path.display())),
+            Err(e) => {
+                let mut error_display = ErrorDisplay::new(Terminal::new()?);
+                let context = ErrorContext::Filesystem {
+                    operation: "save code block".to_string(),
+                    path: path.display().to_string(),
+                };
+                error_display.display_error_with_context(&anyhow::Error::from(e), context)
             }
-            Command::UpdateCheck => {
-                self.handle_update_check().await?;
+        }
+    }
+
+    /// Handle the /think command
+    ///
+    /// Showing or collapsing thinking applies to this session; `ui.show_thinking`
+    /// sets the default.
+    fn handle_think(&mut self, action: ThinkAction) -> Result<()> {
+        match action {
+            ThinkAction::Last => {
+                let thinking = self
+                    .conversation
+                    .messages
+                    .iter()
+                    .rev()
+                    .find(|m| m.role == "assistant")
+                    .and_then(|m| m.thinking.clone());
+                match thinking {
+                    Some(thinking) => self.terminal.write_dimmed(&format!("\n{}\n\n", thinking)),
+                    None => self.terminal.write_info("The last response has no thinking"),
+                }
             }
-            Command::StartLocal => {
-                self.handle_start_local().await?;
+            ThinkAction::Show => {
+                self.show_thinking = true;
+                self.terminal.write_info("Thinking will be shown while responses stream")
             }
-            Command::Switch(target) => {
-                self.handle_switch(&target).await?;
+            ThinkAction::Hide => {
+                self.show_thinking = false;
+                self.terminal.write_info("Thinking will be collapsed while responses stream")
             }
-            Command::Unknown(cmd) => {
-                self.terminal.write_error(&format!(
-                    "Unknown command: /{}. Type /help for available commands",
-                    cmd
-                ))?;
+        }
+    }
+
+    /// Handle the /search command
+    ///
+    /// Results are kept so that `/load <n>` can open the n-th one.
+    async fn handle_search(&mut self, options: &SearchOptions) -> Result<()> {
+        if options.mode != SearchMode::Keyword {
+            self.terminal.write_info("Embedding new messages...")?;
+        }
+        let search = semantic::search_history(
+            &self.conversation_manager,
+            &self.backend_client,
+            &self.config.conversations.embedding_model,
+            options,
+        );
+        let hits = match search.await {
+            Ok(hits) => hits,
+            Err(e) => return self.terminal.write_error(&format!("Search failed: {}", e)),
+        };
+
+        if hits.is_empty() {
+            self.last_search.clear();
+            return self.terminal.write_info(&format!("No matches for '{}'", options.text));
+        }
+
+        self.terminal.write(&format!("\n{}\n", search::format_search_results(&hits, true)))?;
+        self.terminal.write_info("Use /load <n> to open a result")?;
+        self.last_search = hits;
+        Ok(())
+    }
+
+    /// Handle the /load command
+    ///
+    /// `target` is a result number from the last `/search`, or a conversation ID
+    /// or unique ID prefix. The current conversation is saved first.
+    fn handle_load(&mut self, target: &str) -> Result<()> {
+        let hit = target
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|i| self.last_search.get(i));
+        let (id, message_index) = match hit {
+            Some(hit) => (hit.conversation_id.clone(), Some(hit.message_index)),
+            None => {
+                let matches: Vec<String> = self
+                    .conversation_manager
+                    .list_conversations()?
+                    .into_iter()
+                    .map(|c| c.id)
+                    .filter(|id| id.starts_with(target))
+                    .collect();
+                match matches.as_slice() {
+                    [id] => (id.clone(), None),
+                    [] => return self.terminal.write_error(&format!("No conversation matches '{}'", target)),
+                    _ => return self.terminal.write_error(&format!("'{}' matches several conversations", target)),
+                }
             }
+        };
+
+        let conversation = match self.conversation_manager.load_conversation(&id) {
+            Ok(conversation) => conversation,
+            Err(e) => return self.terminal.write_error(&format!("Failed to load conversation: {}", e)),
+        };
+        if !self.conversation.messages.is_empty() {
+            self.save_conversation_reporting_errors()?;
+        }
+        self.conversation = conversation;
+
+        self.terminal.write_info(&format!(
+            "Loaded '{}' ({} messages)",
+            self.conversation.name,
+            self.conversation.messages.len()
+        ))?;
+        // Continue with the model the conversation was using
+        if let Some(model) = self.conversation.model.clone().filter(|m| *m != self.model) {
+            self.model = model;
+            self.terminal.write_info(&format!("Model: {}", self.model))?;
+        }
+        if let Some(message) = message_index.and_then(|i| self.conversation.messages.get(i)) {
+            self.terminal.write(&format!("\n[{}] {}\n\n", message.role, message.content))?;
         }
+        Ok(())
+    }
 
+    /// Save the current conversation, displaying (rather than returning) any error
+    fn save_conversation_reporting_errors(&mut self) -> Result<()> {
+        if let Err(e) = self.conversation_manager.save_conversation(&self.conversation) {
+            let mut error_display = ErrorDisplay::new(Terminal::new()?);
+            let context = ErrorContext::Filesystem {
+                operation: "save".to_string(),
+                path: "conversation".to_string(),
+            };
+            error_display.display_error_with_context(&e, context)?;
+        }
         Ok(())
     }
 
@@ -1152,10 +2148,16 @@ impl CliApp {
 
     /// Shutdown the application gracefully
     async fn shutdown(&mut self) -> Result<()> {
+        // Pick up titles that finished generating while the last command ran
+        self.apply_generated_titles()?;
+
         // Save final conversation state
         self.conversation_manager
             .save_conversation(&self.conversation)
             .context("Failed to save conversation on shutdown")?;
+        if let Err(e) = self.conversation_manager.save_search_index() {
+            log::warn!("Failed to save search index: {}", e);
+        }
 
         // Try to write goodbye message, but don't fail if terminal write fails
         // (this can happen in tests or when output is redirected)
@@ -1164,6 +2166,42 @@ impl CliApp {
     }
 }
 
+/// Written below the message in the file opened by `/compose`, and never sent
+const COMPOSE_HINT: &str =
+    "<!-- Write your message above. Save and quit to send it, or leave it empty to cancel. -->";
+
+/// Open `content` in the user's editor and return the saved text
+///
+/// Uses `$VISUAL` or `$EDITOR` (which may include arguments, e.g. `code -w`),
+/// falling back to `vi`.
+fn edit_in_external_editor(content: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().context("No editor configured")?;
+
+    let path = std::env::temp_dir().join(format!("prometheus-edit-{}.md", uuid::Uuid::new_v4()));
+    std::fs::write(&path, content).context("Failed to create temporary file")?;
+
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status()
+        .with_context(|| format!("Failed to launch editor '{}'", editor));
+
+    let result = match status {
+        Ok(status) if status.success() => {
+            std::fs::read_to_string(&path).context("Failed to read edited message")
+        }
+        Ok(status) => Err(anyhow::anyhow!("Editor exited with {}", status)),
+        Err(e) => Err(e),
+    };
+
+    let _ = std::fs::remove_file(&path);
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1292,7 +2330,8 @@ mod tests {
     #[tokio::test]
     async fn test_cli_app_creation() {
         let config = AppConfig::default();
-        let result = CliApp::new(config, None, None);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let result = CliApp::new_with_temp_dir(config, None, None, temp_dir.path());
         assert!(result.is_ok());
     }
 
@@ -1300,7 +2339,8 @@ mod tests {
     async fn test_cli_app_with_url_override() {
         let config = AppConfig::default();
         let custom_url = "https://custom-server:8080".to_string();
-        let app = CliApp::new(config, Some(custom_url.clone()), None).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app = CliApp::new_with_temp_dir(config, Some(custom_url.clone()), None, temp_dir.path()).unwrap();
         assert_eq!(app.backend_client.base_url(), "https://custom-server:8080");
     }
 
@@ -1308,14 +2348,16 @@ mod tests {
     async fn test_cli_app_with_model_override() {
         let config = AppConfig::default();
         let custom_model = "custom-model".to_string();
-        let app = CliApp::new(config, None, Some(custom_model.clone())).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app = CliApp::new_with_temp_dir(config, None, Some(custom_model.clone()), temp_dir.path()).unwrap();
         assert_eq!(app.model, "custom-model");
     }
 
     #[tokio::test]
     async fn test_display_welcome() {
         let config = AppConfig::default();
-        let mut app = CliApp::new(config, None, None).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
         let result = app.display_welcome();
         assert!(result.is_ok());
     }
@@ -1323,7 +2365,8 @@ mod tests {
     #[tokio::test]
     async fn test_handle_exit_command() {
         let config = AppConfig::default();
-        let mut app = CliApp::new(config, None, None).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
         assert!(app.running);
 
         app.handle_command("/exit").await.unwrap();
@@ -1333,7 +2376,8 @@ mod tests {
     #[tokio::test]
     async fn test_handle_quit_command() {
         let config = AppConfig::default();
-        let mut app = CliApp::new(config, None, None).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
         assert!(app.running);
 
         app.handle_command("/quit").await.unwrap();
@@ -1354,7 +2398,8 @@ mod tests {
     #[tokio::test]
     async fn test_handle_help_command() {
         let config = AppConfig::default();
-        let mut app = CliApp::new(config, None, None).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
         let result = app.handle_command("/help").await;
         assert!(result.is_ok());
     }
@@ -1362,7 +2407,8 @@ mod tests {
     #[tokio::test]
     async fn test_handle_clear_command() {
         let config = AppConfig::default();
-        let mut app = CliApp::new(config, None, None).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
         let result = app.handle_command("/clear").await;
         assert!(result.is_ok());
     }
@@ -1370,11 +2416,237 @@ mod tests {
     #[tokio::test]
     async fn test_handle_unknown_command() {
         let config = AppConfig::default();
-        let mut app = CliApp::new(config, None, None).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
         let result = app.handle_command("/unknown").await;
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_handle_retry_queues_last_prompt() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+        app.conversation.add_message(ChatMessage::new("user".to_string(), "Question".to_string()));
+        app.conversation.add_message(ChatMessage::new("assistant".to_string(), "Bad answer".to_string()));
+
+        app.handle_command("/retry").await.unwrap();
+
+        assert_eq!(app.pending_prompt, Some("Question".to_string()));
+        assert_eq!(app.conversation.messages.len(), 1);
+        // The old answer is kept as an inactive branch
+        assert!(app.conversation.has_branches());
+    }
+
+    #[tokio::test]
+    async fn test_handle_retry_without_messages() {
+        let config = AppConfig::default();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+
+        app.handle_command("/retry").await.unwrap();
+        assert_eq!(app.pending_prompt, None);
+    }
+
+    /// Serializes tests that point `VISUAL` at a scripted editor
+    static EDITOR_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    /// Write a shell script that replaces the edited file with `text`, and return
+    /// a `VISUAL` value that runs it
+    fn scripted_editor(dir: &std::path::Path, text: &str) -> String {
+        let script = dir.join("editor.sh");
+        std::fs::write(&script, format!("printf '%s' '{}' > \"$1\"\n", text)).unwrap();
+        format!("sh {}", script.display())
+    }
+
+    #[tokio::test]
+    async fn test_handle_edit_resends_edited_message() {
+        let _editor = EDITOR_LOCK.lock().await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+        app.conversation.add_message(ChatMessage::new("user".to_string(), "Helo world".to_string()));
+        app.conversation.add_message(ChatMessage::new("assistant".to_string(), "Answer".to_string()));
+
+        std::env::set_var("VISUAL", scripted_editor(temp_dir.path(), "Hello world"));
+        app.handle_command("/edit").await.unwrap();
+        std::env::remove_var("VISUAL");
+
+        assert_eq!(app.pending_prompt, Some("Hello world".to_string()));
+        assert_eq!(app.conversation.messages.len(), 1);
+        assert_eq!(app.conversation.messages[0].content, "Hello world");
+    }
+
+    #[tokio::test]
+    async fn test_handle_compose_sends_written_message() {
+        let _editor = EDITOR_LOCK.lock().await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+
+        std::env::set_var("VISUAL", scripted_editor(temp_dir.path(), "Explain"));
+        app.handle_command("/compose").await.unwrap();
+        std::env::remove_var("VISUAL");
+
+        assert_eq!(app.pending_prompt, Some("Explain".to_string()));
+        assert_eq!(app.conversation.messages.len(), 1);
+        assert_eq!(app.conversation.messages[0].role, "user");
+    }
+
+    #[tokio::test]
+    async fn test_handle_compose_cancels_empty_message() {
+        let _editor = EDITOR_LOCK.lock().await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+
+        std::env::set_var("VISUAL", "true");
+        app.handle_command("/compose").await.unwrap();
+        std::env::remove_var("VISUAL");
+
+        assert_eq!(app.pending_prompt, None);
+        assert!(app.conversation.messages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_set_and_system_change_conversation_settings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+
+        app.handle_command("/set temperature 0.4").await.unwrap();
+        app.handle_command("/set top_k 0").await.unwrap();
+        app.handle_command("/system Answer in French.").await.unwrap();
+        assert_eq!(app.conversation.settings.options.temperature, Some(0.4));
+        assert_eq!(app.conversation.settings.options.top_k, None);
+        assert_eq!(app.conversation.settings.system.as_deref(), Some("Answer in French."));
+
+        // Settings carry over to a new conversation
+        app.handle_command("/new").await.unwrap();
+        assert_eq!(app.conversation.settings.options.temperature, Some(0.4));
+
+        app.handle_command("/set temperature default").await.unwrap();
+        app.handle_command("/system -").await.unwrap();
+        assert!(app.conversation.settings.is_default());
+    }
+
+    #[tokio::test]
+    async fn test_persona_sets_the_system_prompt() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            temp_dir.path().join(prometheus_personas::PERSONAS_FILE),
+            r#"{"personas": [{"id": "teacher", "name": "Patient Teacher", "icon": "🎓", "system_prompt": "Explain patiently."}]}"#,
+        )
+        .unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+
+        app.handle_command("/persona missing").await.unwrap();
+        assert!(app.conversation.settings.is_default());
+
+        app.handle_command("/persona teacher").await.unwrap();
+        assert_eq!(app.conversation.settings.persona.as_deref(), Some("teacher"));
+        assert_eq!(app.conversation.settings.system.as_deref(), Some("Explain patiently."));
+        assert_eq!(app.response_metadata(Instant::now()).persona_id.as_deref(), Some("teacher"));
+
+        // A system prompt of its own replaces the persona
+        app.handle_command("/system Be terse.").await.unwrap();
+        assert_eq!(app.conversation.settings.persona, None);
+
+        app.handle_command("/persona teacher").await.unwrap();
+        app.handle_command("/persona -").await.unwrap();
+        assert!(app.conversation.settings.is_default());
+    }
+
+    #[tokio::test]
+    async fn test_model_command_switches_model_and_load_restores_it() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("GET", "/api/tags")
+            .with_body(r#"{"models":[{"name":"llama3.2"},{"name":"qwen3:8b"}]}"#)
+            .create_async()
+            .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(
+            config,
+            Some(server.url()),
+            Some("llama3.2".to_string()),
+            temp_dir.path(),
+        )
+        .unwrap();
+
+        app.handle_command("/model missing").await.unwrap();
+        assert_eq!(app.model, "llama3.2");
+
+        app.handle_command("/model qwen3:8b").await.unwrap();
+        assert_eq!(app.model, "qwen3:8b");
+        assert_eq!(app.conversation.model.as_deref(), Some("qwen3:8b"));
+
+        app.conversation.add_message(ChatMessage::new("user".to_string(), "Hi".to_string()));
+        let id = app.conversation.id.clone();
+        app.handle_command("/new").await.unwrap();
+        app.handle_command("/model llama3.2").await.unwrap();
+
+        app.handle_command(&format!("/load {}", id)).await.unwrap();
+        assert_eq!(app.model, "qwen3:8b");
+    }
+
+    #[tokio::test]
+    async fn test_handle_undo_removes_last_exchange() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+        app.conversation.add_message(ChatMessage::new("user".to_string(), "First".to_string()));
+        app.conversation.add_message(ChatMessage::new("assistant".to_string(), "First answer".to_string()));
+        app.conversation.add_message(ChatMessage::new("user".to_string(), "Second".to_string()));
+        app.conversation.add_message(ChatMessage::new("assistant".to_string(), "Second answer".to_string()));
+
+        app.handle_command("/undo").await.unwrap();
+
+        assert_eq!(app.conversation.messages.len(), 2);
+        assert_eq!(app.conversation.messages[1].content, "First answer");
+
+        // The change is persisted
+        let loaded = app.conversation_manager.load_conversation(&app.conversation.id).unwrap();
+        assert_eq!(loaded.messages.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_handle_undo_after_retry_drops_alternative_answers() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+        app.conversation.add_message(ChatMessage::new("user".to_string(), "First".to_string()));
+        app.conversation.add_message(ChatMessage::new("assistant".to_string(), "First answer".to_string()));
+        app.conversation.add_message(ChatMessage::new("user".to_string(), "Second".to_string()));
+        app.conversation.add_message(ChatMessage::new("assistant".to_string(), "Bad answer".to_string()));
+
+        app.handle_command("/retry").await.unwrap();
+        app.conversation.add_message(ChatMessage::new("assistant".to_string(), "Better answer".to_string()));
+        app.handle_command("/undo").await.unwrap();
+
+        assert_eq!(app.conversation.messages.len(), 2);
+        assert_eq!(app.conversation.messages[1].content, "First answer");
+        // The retried answer goes with its question rather than hanging off the previous reply
+        assert!(!app.conversation.has_branches());
+    }
+
+    #[tokio::test]
+    async fn test_handle_undo_removes_unanswered_message() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+        app.conversation.add_message(ChatMessage::new("user".to_string(), "First".to_string()));
+        app.conversation.add_message(ChatMessage::new("assistant".to_string(), "First answer".to_string()));
+        app.conversation.add_message(ChatMessage::new("user".to_string(), "Failed".to_string()));
+
+        app.handle_command("/undo").await.unwrap();
+
+        assert_eq!(app.conversation.messages.len(), 2);
+        assert_eq!(app.conversation.messages[1].role, "assistant");
+    }
+
     /// **Feature: cli-version, Property 5: Configuration override precedence**
     /// For any configuration value (URL or model) that exists in both config.toml
     /// and command-line arguments, the command-line argument value should be used
@@ -1413,7 +2685,8 @@ mod tests {
             config.backend.ollama_url = config_url.clone();
 
             // Create app with CLI overrides
-            let app = match CliApp::new(config, Some(cli_url.clone()), Some(cli_model.clone())) {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let app = match CliApp::new_with_temp_dir(config, Some(cli_url.clone()), Some(cli_model.clone()), temp_dir.path()) {
                 Ok(app) => app,
                 Err(_) => return TestResult::discard(),
             };
@@ -1477,7 +2750,8 @@ mod tests {
             let config = AppConfig::default();
 
             // Create app with CLI URL override
-            let app = match CliApp::new(config, Some(backend_url.clone()), None) {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let app = match CliApp::new_with_temp_dir(config, Some(backend_url.clone()), None, temp_dir.path()) {
                 Ok(app) => app,
                 Err(_) => return TestResult::discard(),
             };
@@ -1529,7 +2803,8 @@ mod tests {
             let config = AppConfig::default();
 
             // Create app with CLI model override
-            let app = match CliApp::new(config, None, Some(model_name.clone())) {
+            let temp_dir = tempfile::tempdir().unwrap();
+            let app = match CliApp::new_with_temp_dir(config, None, Some(model_name.clone()), temp_dir.path()) {
                 Ok(app) => app,
                 Err(_) => return TestResult::discard(),
             };
@@ -1605,7 +2880,8 @@ theme = "Hacker Green"
     async fn test_default_value_fallback() {
         // Create app with default config (simulating missing config file)
         let config = AppConfig::default();
-        let app = CliApp::new(config.clone(), None, None).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app = CliApp::new_with_temp_dir(config.clone(), None, None, temp_dir.path()).unwrap();
 
         // Verify default values are used
         assert_eq!(app.backend_url(), config.backend.ollama_url);
@@ -1620,7 +2896,8 @@ theme = "Hacker Green"
         let cli_url = "https://custom-server:9999".to_string();
         let cli_model = "custom-model".to_string();
 
-        let app = CliApp::new(config, Some(cli_url.clone()), Some(cli_model.clone())).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app = CliApp::new_with_temp_dir(config, Some(cli_url.clone()), Some(cli_model.clone()), temp_dir.path()).unwrap();
 
         // Verify CLI arguments override config values
         assert_eq!(app.backend_url(), cli_url);
@@ -1659,7 +2936,8 @@ window_title = "Test App"
 
         // In the actual app, this is handled by using AppConfig::default()
         let default_config = AppConfig::default();
-        let app = CliApp::new(default_config, None, None);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app = CliApp::new_with_temp_dir(default_config, None, None, temp_dir.path());
         assert!(app.is_ok());
     }
 
@@ -1670,7 +2948,8 @@ window_title = "Test App"
         let mut config = AppConfig::default();
         config.backend.ollama_url = "https://config-server:7777".to_string();
 
-        let app = CliApp::new(config.clone(), None, None).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app = CliApp::new_with_temp_dir(config.clone(), None, None, temp_dir.path()).unwrap();
 
         // Verify config values are used
         assert_eq!(app.backend_url(), config.backend.ollama_url);
@@ -1685,7 +2964,8 @@ window_title = "Test App"
         config.backend.ollama_url = "https://config-server:7777".to_string();
 
         let cli_url = "https://cli-server:8888".to_string();
-        let app = CliApp::new(config, Some(cli_url.clone()), None).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app = CliApp::new_with_temp_dir(config, Some(cli_url.clone()), None, temp_dir.path()).unwrap();
 
         // Verify URL is overridden but model uses default
         assert_eq!(app.backend_url(), cli_url);
@@ -1700,7 +2980,8 @@ window_title = "Test App"
         config.backend.ollama_url = "https://config-server:7777".to_string();
 
         let cli_model = "cli-model".to_string();
-        let app = CliApp::new(config.clone(), None, Some(cli_model.clone())).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app = CliApp::new_with_temp_dir(config.clone(), None, Some(cli_model.clone()), temp_dir.path()).unwrap();
 
         // Verify model is overridden but URL uses config
         assert_eq!(app.backend_url(), config.backend.ollama_url);
@@ -1731,8 +3012,9 @@ window_title = "Test App"
                 return TestResult::discard();
             }
 
-            // Create a conversation manager
-            let manager = ConversationManager::new();
+            // Create a conversation manager in a throwaway directory
+            let temp_dir = tempfile::tempdir().unwrap();
+            let manager = ConversationManager::with_directory(temp_dir.path());
 
             // Create a new conversation with a unique ID for this test
             let mut conversation = Conversation::new(
@@ -1817,7 +3099,8 @@ window_title = "Test App"
     #[tokio::test]
     async fn test_conversation_creation_with_timestamp_name() {
         let config = AppConfig::default();
-        let app = CliApp::new(config, None, Some("test-model".to_string())).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app = CliApp::new_with_temp_dir(config, None, Some("test-model".to_string()), temp_dir.path()).unwrap();
 
         // Verify conversation was created
         assert!(!app.conversation.id.is_empty());
@@ -1842,7 +3125,8 @@ window_title = "Test App"
     #[tokio::test]
     async fn test_message_addition() {
         let config = AppConfig::default();
-        let mut app = CliApp::new(config, None, None).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
 
         // Initially empty
         assert_eq!(app.conversation.messages.len(), 0);
@@ -2207,4 +3491,149 @@ window_title = "Test App"
         // Clean up
         manager.delete_conversation(&conversation_id).ok();
     }
+
+    #[tokio::test]
+    async fn test_handle_export_includes_metadata() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+        app.conversation.add_message(ChatMessage::new("user".to_string(), "Question".to_string()));
+        app.conversation.add_message(
+            ChatMessage::new("assistant".to_string(), "Answer".to_string()).with_metadata(
+                MessageMetadata {
+                    model: Some("llama2".to_string()),
+                    completion_tokens: Some(12),
+                    ..Default::default()
+                },
+            ),
+        );
+
+        let md_path = temp_dir.path().join("chat.md");
+        app.handle_command(&format!("/export {}", md_path.display())).await.unwrap();
+        let markdown = std::fs::read_to_string(&md_path).unwrap();
+        assert!(markdown.contains("Answer"));
+        assert!(markdown.contains("> model: llama2"));
+        assert!(markdown.contains("> completion tokens: 12"));
+
+        let json_path = temp_dir.path().join("chat.json");
+        app.handle_command(&format!("/export {}", json_path.display())).await.unwrap();
+        let exported: Conversation =
+            serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(exported.messages[1].metadata.completion_tokens, Some(12));
+    }
+
+    #[test]
+    fn test_response_metadata_records_model_and_endpoint() {
+        let config = AppConfig::default();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let app = CliApp::new_with_temp_dir(config, None, Some("mistral".to_string()), temp_dir.path()).unwrap();
+
+        let metadata = app.response_metadata(Instant::now());
+        assert_eq!(metadata.model.as_deref(), Some("mistral"));
+        assert_eq!(metadata.endpoint.as_deref(), Some(app.backend_client.base_url()));
+        assert!(metadata.latency_ms.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_response_metadata_records_generation_options() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/api/generate")
+            .with_body("{\"response\":\"Answer\",\"done\":true}\n")
+            .create_async()
+            .await;
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, Some(server.url()), None, temp_dir.path()).unwrap();
+        let mut sigint = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt()).unwrap();
+
+        app.handle_command("/set temperature 0.4").await.unwrap();
+        app.handle_prompt_with_signals("Question".to_string(), &mut sigint).await.unwrap();
+
+        let response = app.conversation.messages.last().unwrap();
+        assert_eq!(response.content, "Answer");
+        assert_eq!(response.metadata.options.temperature, Some(0.4));
+    }
+
+    #[tokio::test]
+    async fn test_apply_generated_titles_renames_current_conversation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+        app.conversation.add_message(ChatMessage::new("user".to_string(), "Question".to_string()));
+
+        let id = app.conversation.id.clone();
+        app.title_tx.send((id.clone(), "Sorting Vectors".to_string())).unwrap();
+        app.apply_generated_titles().unwrap();
+
+        assert_eq!(app.conversation.name, "Sorting Vectors");
+        let metadata = app.conversation_manager.list_conversations().unwrap();
+        assert_eq!(metadata[0].name, "Sorting Vectors");
+    }
+
+    #[tokio::test]
+    async fn test_apply_generated_titles_renames_previous_conversation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+        app.conversation.add_message(ChatMessage::new("user".to_string(), "Question".to_string()));
+        let previous_id = app.conversation.id.clone();
+
+        app.handle_command("/new").await.unwrap();
+        app.title_tx.send((previous_id.clone(), "Earlier Chat".to_string())).unwrap();
+        app.apply_generated_titles().unwrap();
+
+        assert_ne!(app.conversation.name, "Earlier Chat");
+        let previous = app.conversation_manager.load_conversation(&previous_id).unwrap();
+        assert_eq!(previous.name, "Earlier Chat");
+    }
+
+    #[tokio::test]
+    async fn test_title_generation_disabled_by_config() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut config = AppConfig::default();
+        config.conversations.auto_title = false;
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+        app.conversation.add_message(ChatMessage::new("user".to_string(), "Question".to_string()));
+        app.conversation.add_message(ChatMessage::new("assistant".to_string(), "Answer".to_string()));
+
+        app.spawn_title_generation();
+        tokio::task::yield_now().await;
+
+        assert!(app.title_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handle_tag_adds_and_removes_tags() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+
+        app.handle_command("/tag Rust draft").await.unwrap();
+        assert_eq!(app.conversation.tags, vec!["draft", "rust"]);
+
+        app.handle_command("/tag -draft").await.unwrap();
+        assert_eq!(app.conversation.tags, vec!["rust"]);
+
+        let saved = app.conversation_manager.list_conversations().unwrap();
+        assert_eq!(saved[0].tags, vec!["rust"]);
+    }
+
+    #[tokio::test]
+    async fn test_handle_pin_and_folder() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config = AppConfig::default();
+        let mut app = CliApp::new_with_temp_dir(config, None, None, temp_dir.path()).unwrap();
+
+        app.handle_command("/pin").await.unwrap();
+        app.handle_command("/folder work/reviews").await.unwrap();
+        let saved = app.conversation_manager.list_conversations().unwrap();
+        assert!(saved[0].pinned);
+        assert_eq!(saved[0].folder.as_deref(), Some("work/reviews"));
+
+        app.handle_command("/unpin").await.unwrap();
+        app.handle_command("/folder -").await.unwrap();
+        assert!(!app.conversation.pinned);
+        assert_eq!(app.conversation.folder, None);
+    }
 }
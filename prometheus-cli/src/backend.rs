@@ -1,8 +1,10 @@
 use anyhow::{Context, Result};
 use futures::StreamExt;
+use prometheus_chat::{FinalChunkStats, GenerationOptions};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
+use crate::thinking;
 use crate::url_validator::UrlValidator;
 
 /// Response from Ollama's /api/tags endpoint
@@ -18,6 +20,64 @@ pub struct OllamaModelsResponse {
     pub models: Vec<OllamaModel>,
 }
 
+/// Response from Ollama's /api/embed endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbedResponse {
+    pub embeddings: Vec<Vec<f32>>,
+}
+
+/// Result of a completed generation request
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GenerationResponse {
+    /// The complete response text, without its thinking section
+    pub text: String,
+    /// Reasoning of models that think before answering (`<think>` sections)
+    pub thinking: Option<String>,
+    /// Tokens in the prompt (Ollama's `prompt_eval_count`)
+    pub prompt_tokens: Option<u32>,
+    /// Tokens generated (Ollama's `eval_count`)
+    pub completion_tokens: Option<u32>,
+    /// Why generation stopped (Ollama's `done_reason`)
+    pub finish_reason: Option<String>,
+}
+
+impl GenerationResponse {
+    /// Copy the statistics from the final `done` chunk of a stream
+    fn record_final_chunk(&mut self, json: &serde_json::Value) {
+        let stats = FinalChunkStats::from_json(json);
+        self.prompt_tokens = stats.prompt_tokens;
+        self.completion_tokens = stats.completion_tokens;
+        self.finish_reason = stats.finish_reason;
+    }
+
+    /// Move the thinking section of the streamed text into `thinking`
+    fn separate_thinking(mut self) -> Self {
+        let split = thinking::split_thinking(&self.text);
+        self.text = split.answer;
+        self.thinking = split.thinking;
+        self
+    }
+}
+
+/// System prompt and options sent with every request of a conversation
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationSettings {
+    /// Persona whose prompt is the system prompt; cleared when the prompt is changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persona: Option<String>,
+    /// Replaces the model's default system prompt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(default, skip_serializing_if = "GenerationOptions::is_empty")]
+    pub options: GenerationOptions,
+}
+
+impl GenerationSettings {
+    pub fn is_default(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
 /// Backend client for communicating with Ollama instances
 #[derive(Clone)]
 pub struct BackendClient {
@@ -59,29 +119,57 @@ impl BackendClient {
 
     /// Send a prompt to the backend with streaming response handling
     ///
+    /// Uses the model's default system prompt and options; see
+    /// [`BackendClient::send_prompt_streaming_with_settings`].
+    pub async fn send_prompt_streaming<F>(
+        &self,
+        prompt: &str,
+        model: &str,
+        callback: F,
+    ) -> Result<GenerationResponse>
+    where
+        F: FnMut(String) -> Result<()>,
+    {
+        self.send_prompt_streaming_with_settings(prompt, model, &GenerationSettings::default(), callback)
+            .await
+    }
+
+    /// Send a prompt to the backend with a system prompt and options, streaming the response
+    ///
     /// # Arguments
     /// * `prompt` - The prompt to send to the model
     /// * `model` - The model name to use
-    /// * `callback` - A callback function that receives each chunk of the response
+    /// * `settings` - System prompt and generation options; unset ones use the model's defaults
+    /// * `callback` - A callback function that receives each chunk of the response,
+    ///   including any thinking section
     ///
     /// # Returns
-    /// The complete response text
-    pub async fn send_prompt_streaming<F>(
+    /// The complete response text, with the thinking section separated and
+    /// token counts and finish reason when the backend reports them
+    pub async fn send_prompt_streaming_with_settings<F>(
         &self,
         prompt: &str,
         model: &str,
+        settings: &GenerationSettings,
         mut callback: F,
-    ) -> Result<String>
+    ) -> Result<GenerationResponse>
     where
         F: FnMut(String) -> Result<()>,
     {
         let url = format!("{}/api/generate", self.base_url);
 
-        let request_body = serde_json::json!({
+        let mut request_body = serde_json::json!({
             "model": model,
             "prompt": prompt,
             "stream": true
         });
+        if let Some(system) = &settings.system {
+            request_body["system"] = serde_json::json!(system);
+        }
+        if !settings.options.is_empty() {
+            request_body["options"] = serde_json::to_value(&settings.options)
+                .context("Failed to serialize generation options")?;
+        }
 
         let response = self
             .client
@@ -103,7 +191,7 @@ impl BackendClient {
 
         let mut stream = response.bytes_stream();
         let mut line_buffer = String::new();
-        let mut full_response = String::new();
+        let mut full_response = GenerationResponse::default();
 
         while let Some(chunk) = stream.next().await {
             let bytes = chunk.context("Failed to read stream chunk")?;
@@ -125,13 +213,14 @@ impl BackendClient {
                     Ok(json) => {
                         if let Some(response_text) = json.get("response").and_then(|v| v.as_str())
                         {
-                            full_response.push_str(response_text);
+                            full_response.text.push_str(response_text);
                             callback(response_text.to_string())?;
                         }
 
                         // Check if done
                         if json.get("done").and_then(|v| v.as_bool()).unwrap_or(false) {
-                            return Ok(full_response);
+                            full_response.record_final_chunk(&json);
+                            return Ok(full_response.separate_thinking());
                         }
                     }
                     Err(e) => {
@@ -141,7 +230,7 @@ impl BackendClient {
             }
         }
 
-        Ok(full_response)
+        Ok(full_response.separate_thinking())
     }
 
     /// Fetch available models from the backend
@@ -219,6 +308,54 @@ impl BackendClient {
         Ok(model_names)
     }
 
+    /// Compute embeddings with Ollama's /api/embed endpoint
+    ///
+    /// # Arguments
+    /// * `model` - The embedding model, e.g. "nomic-embed-text"
+    /// * `inputs` - The texts to embed
+    ///
+    /// # Returns
+    /// One vector per input, in order
+    pub async fn embed(&self, model: &str, inputs: &[String]) -> Result<Vec<Vec<f32>>> {
+        let url = format!("{}/api/embed", self.base_url);
+        let request_body = serde_json::json!({
+            "model": model,
+            "input": inputs,
+        });
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&request_body)
+            .send()
+            .await
+            .context(format!("Failed to connect to {}", self.base_url))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_body = response.text().await.unwrap_or_default();
+            anyhow::bail!(
+                "Backend returned error status: {} - {}",
+                status.as_u16(),
+                error_body
+            );
+        }
+
+        let response: EmbedResponse = response
+            .json()
+            .await
+            .context("Failed to parse embedding response")?;
+
+        if response.embeddings.len() != inputs.len() {
+            anyhow::bail!(
+                "Expected {} embeddings, got {}",
+                inputs.len(),
+                response.embeddings.len()
+            );
+        }
+        Ok(response.embeddings)
+    }
+
     /// Get the base URL of this client
     pub fn base_url(&self) -> &str {
         &self.base_url
@@ -322,6 +459,52 @@ mod tests {
         assert!(client.is_err());
     }
 
+    #[test]
+    fn test_generation_response_records_final_chunk() {
+        let final_chunk = serde_json::json!({
+            "model": "llama2",
+            "response": "",
+            "done": true,
+            "done_reason": "length",
+            "prompt_eval_count": 26,
+            "eval_count": 298,
+            "total_duration": 5043500667u64
+        });
+
+        let mut response = GenerationResponse {
+            text: "Hello".to_string(),
+            ..Default::default()
+        };
+        response.record_final_chunk(&final_chunk);
+
+        assert_eq!(response.text, "Hello");
+        assert_eq!(response.prompt_tokens, Some(26));
+        assert_eq!(response.completion_tokens, Some(298));
+        assert_eq!(response.finish_reason.as_deref(), Some("length"));
+    }
+
+    #[test]
+    fn test_generation_response_without_stats() {
+        let mut response = GenerationResponse::default();
+        response.record_final_chunk(&serde_json::json!({ "done": true }));
+
+        assert_eq!(response.prompt_tokens, None);
+        assert_eq!(response.completion_tokens, None);
+        assert_eq!(response.finish_reason, None);
+    }
+
+    #[test]
+    fn test_generation_response_separates_thinking() {
+        let response = GenerationResponse {
+            text: "<think>\nCount the letters.\n</think>\n\nThree.".to_string(),
+            ..Default::default()
+        }
+        .separate_thinking();
+
+        assert_eq!(response.text, "Three.");
+        assert_eq!(response.thinking.as_deref(), Some("Count the letters."));
+    }
+
     #[test]
     fn test_ollama_model_serialization() {
         let model = OllamaModel {
@@ -367,4 +550,65 @@ mod tests {
         assert_eq!(deserialized.models[0].name, "model1");
         assert_eq!(deserialized.models[1].name, "model2");
     }
+
+    #[tokio::test]
+    async fn test_send_prompt_with_settings() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/generate")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "model": "llama3.2",
+                "system": "Answer in French.",
+                "options": { "temperature": 0.5, "num_ctx": 8192 },
+            })))
+            .with_body("{\"response\":\"Bonjour\",\"done\":true}\n")
+            .create_async()
+            .await;
+
+        let mut settings = GenerationSettings {
+            system: Some("Answer in French.".to_string()),
+            ..Default::default()
+        };
+        settings.options.set("temperature", "0.5").unwrap();
+        settings.options.set("num_ctx", "8192").unwrap();
+
+        let client = BackendClient::new(server.url(), 5).unwrap();
+        let response = client
+            .send_prompt_streaming_with_settings("Hello", "llama3.2", &settings, |_| Ok(()))
+            .await
+            .unwrap();
+        assert_eq!(response.text, "Bonjour");
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn test_embed() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/api/embed")
+            .match_body(mockito::Matcher::Json(serde_json::json!({
+                "model": "nomic-embed-text",
+                "input": ["first", "second"],
+            })))
+            .with_body(r#"{"model":"nomic-embed-text","embeddings":[[0.1,0.2],[0.3,0.4]]}"#)
+            .create_async()
+            .await;
+
+        let client = BackendClient::new(server.url(), 5).unwrap();
+        let embeddings = client
+            .embed("nomic-embed-text", &["first".to_string(), "second".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(embeddings, vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+        mock.assert_async().await;
+
+        server
+            .mock("POST", "/api/embed")
+            .with_status(404)
+            .with_body(r#"{"error":"model not found"}"#)
+            .create_async()
+            .await;
+        let error = client.embed("missing", &["text".to_string()]).await.unwrap_err();
+        assert!(error.to_string().contains("404"));
+    }
 }
@@ -1,3 +1,84 @@
+use crate::search::SearchOptions;
+
+/// Actions for the /branch command
+///
+/// Branch numbers are 1-based and refer to the order shown by `/branch list`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BranchAction {
+    /// List all branches of the current conversation
+    List,
+    /// Make the given branch the active path
+    Switch(usize),
+    /// Delete the given inactive branch
+    Prune(usize),
+    /// Delete every inactive branch
+    PruneAll,
+}
+
+impl BranchAction {
+    /// Parse the arguments following `/branch`
+    fn parse(args: &[&str]) -> Option<Self> {
+        match args {
+            [] | ["list"] => Some(BranchAction::List),
+            [n] | ["switch", n] => n.parse().ok().filter(|n| *n > 0).map(BranchAction::Switch),
+            ["prune", "all"] => Some(BranchAction::PruneAll),
+            ["prune", n] => n.parse().ok().filter(|n| *n > 0).map(BranchAction::Prune),
+            _ => None,
+        }
+    }
+}
+
+/// Actions for the /code command
+///
+/// Block numbers are 1-based and refer to the order shown by `/code`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CodeAction {
+    /// List the code blocks of the last response
+    List,
+    /// Write the given block to a file
+    Save(usize, String),
+    /// Copy the given block to the clipboard
+    Copy(usize),
+}
+
+impl CodeAction {
+    /// Parse the arguments following `/code`, keeping the path's original case
+    fn parse(args: &[&str]) -> Option<Self> {
+        let block = |n: &str| n.parse().ok().filter(|n| *n > 0);
+        match args {
+            [] => Some(CodeAction::List),
+            [action, n] if action.eq_ignore_ascii_case("copy") => block(n).map(CodeAction::Copy),
+            [action, n, path @ ..] if action.eq_ignore_ascii_case("save") && !path.is_empty() => {
+                block(n).map(|n| CodeAction::Save(n, path.join(" ")))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Actions for the /think command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThinkAction {
+    /// Display the thinking of the last response
+    Last,
+    /// Show thinking while responses stream
+    Show,
+    /// Collapse thinking into a single line while responses stream
+    Hide,
+}
+
+impl ThinkAction {
+    /// Parse the arguments following `/think`
+    fn parse(args: &[&str]) -> Option<Self> {
+        match args {
+            [] => Some(ThinkAction::Last),
+            ["show"] => Some(ThinkAction::Show),
+            ["hide"] => Some(ThinkAction::Hide),
+            _ => None,
+        }
+    }
+}
+
 /// Commands that can be executed in the CLI
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Command {
@@ -13,6 +94,18 @@ pub enum Command {
     Help,
     /// List available models
     Models,
+    /// Switch to a model, or pick one from a list
+    Model(Option<String>),
+    /// Set a generation parameter of the current conversation
+    Set(String, String),
+    /// Show the model, system prompt and parameters of the current conversation
+    Params,
+    /// Show the system prompt, or set it (`-` removes it)
+    System(Option<String>),
+    /// Show the persona, or switch to one (`-` removes it)
+    Persona(Option<String>),
+    /// List the available personas
+    Personas,
     /// Update the CLI to the latest version
     Update,
     /// Check for available updates
@@ -21,6 +114,38 @@ pub enum Command {
     StartLocal,
     /// Switch to a different endpoint (local, URL, or saved endpoint name)
     Switch(String),
+    /// List, switch or prune conversation branches
+    Branch(BranchAction),
+    /// Resend the last user message
+    Retry,
+    /// Edit the last user message in $EDITOR and resend it
+    Edit,
+    /// Write a new message in $EDITOR and send it
+    Compose,
+    /// Remove the last exchange from the conversation
+    Undo,
+    /// Show details of the current conversation and its last response
+    Info,
+    /// Export the current conversation to a file (Markdown, or JSON for `.json`)
+    Export(String),
+    /// Show the conversation's tags, or add tags and remove `-`-prefixed ones
+    Tag(Vec<String>),
+    /// Pin the current conversation to the top of the history
+    Pin,
+    /// Unpin the current conversation
+    Unpin,
+    /// Show the conversation's folder, or move it (`-` removes it from its folder)
+    Folder(Option<String>),
+    /// List code highlighting themes, or switch to one
+    Theme(Option<String>),
+    /// List, save or copy the code blocks of the last response
+    Code(CodeAction),
+    /// Display the last response's thinking, or show or collapse thinking while streaming
+    Think(ThinkAction),
+    /// Search the conversation history
+    Search(SearchOptions),
+    /// Open a conversation by search result number or ID prefix
+    Load(String),
     /// Unknown command
     Unknown(String),
 }
@@ -80,6 +205,107 @@ impl Command {
             return Command::Unknown(command.to_string());
         }
 
+        // Handle export with a path argument, keeping its original case
+        let parts: Vec<&str> = command.split_whitespace().collect();
+        if parts.first().map(|p| p.eq_ignore_ascii_case("export")).unwrap_or(false) {
+            if parts.len() > 1 {
+                return Command::Export(parts[1..].join(" "));
+            }
+            return Command::Unknown(command.to_string());
+        }
+
+        // Handle tag with optional tags to add or remove
+        if parts.first().map(|p| p.eq_ignore_ascii_case("tag")).unwrap_or(false) {
+            return Command::Tag(parts[1..].iter().map(|t| t.to_string()).collect());
+        }
+
+        // Handle folder with an optional path, keeping its original case
+        if parts.first().map(|p| p.eq_ignore_ascii_case("folder")).unwrap_or(false) {
+            let folder = (parts.len() > 1).then(|| parts[1..].join(" "));
+            return Command::Folder(folder);
+        }
+
+        // Handle theme with an optional name, keeping its original case
+        if parts.first().map(|p| p.eq_ignore_ascii_case("theme")).unwrap_or(false) {
+            let theme = (parts.len() > 1).then(|| parts[1..].join(" "));
+            return Command::Theme(theme);
+        }
+
+        // Handle code with an optional action
+        if parts.first().map(|p| p.eq_ignore_ascii_case("code")).unwrap_or(false) {
+            return match CodeAction::parse(&parts[1..]) {
+                Some(action) => Command::Code(action),
+                None => Command::Unknown(command.to_string()),
+            };
+        }
+
+        // Handle search with flags and a query, keeping the query's original case
+        if parts.first().map(|p| p.eq_ignore_ascii_case("search")).unwrap_or(false) {
+            return match SearchOptions::parse(&parts[1..]) {
+                Some(options) => Command::Search(options),
+                None => Command::Unknown(command.to_string()),
+            };
+        }
+
+        // Handle model with an optional name, keeping its original case
+        if parts.first().map(|p| p.eq_ignore_ascii_case("model")).unwrap_or(false) {
+            return match parts[1..] {
+                [] => Command::Model(None),
+                [name] => Command::Model(Some(name.to_string())),
+                _ => Command::Unknown(command.to_string()),
+            };
+        }
+
+        // Handle set with a parameter and value; without them it shows the parameters
+        if parts.first().map(|p| p.eq_ignore_ascii_case("set")).unwrap_or(false) {
+            return match parts[1..] {
+                [] => Command::Params,
+                [param, value] => Command::Set(param.to_lowercase(), value.to_string()),
+                _ => Command::Unknown(command.to_string()),
+            };
+        }
+
+        // Handle system with optional text, keeping its original case and line breaks
+        if parts.first().map(|p| p.eq_ignore_ascii_case("system")).unwrap_or(false) {
+            let text = command.trim_start()[parts[0].len()..].trim();
+            return Command::System((!text.is_empty()).then(|| text.to_string()));
+        }
+
+        // Handle persona with an optional ID, keeping its original case
+        if parts.first().map(|p| p.eq_ignore_ascii_case("persona")).unwrap_or(false) {
+            return match parts[1..] {
+                [] => Command::Persona(None),
+                [id] => Command::Persona(Some(id.to_string())),
+                _ => Command::Unknown(command.to_string()),
+            };
+        }
+
+        // Handle load with a result number or conversation ID
+        if parts.first().map(|p| p.eq_ignore_ascii_case("load")).unwrap_or(false) {
+            return match parts[1..] {
+                [target] => Command::Load(target.to_string()),
+                _ => Command::Unknown(command.to_string()),
+            };
+        }
+
+        // Handle branch with optional action
+        let lowercase = command.to_lowercase();
+        let parts: Vec<&str> = lowercase.split_whitespace().collect();
+        if parts.first() == Some(&"branch") {
+            return match BranchAction::parse(&parts[1..]) {
+                Some(action) => Command::Branch(action),
+                None => Command::Unknown(command.to_string()),
+            };
+        }
+
+        // Handle think with optional action
+        if parts.first() == Some(&"think") {
+            return match ThinkAction::parse(&parts[1..]) {
+                Some(action) => Command::Think(action),
+                None => Command::Unknown(command.to_string()),
+            };
+        }
+
         // Convert to lowercase for case-insensitive matching
         match command.to_lowercase().as_str() {
             "exit" => Command::Exit,
@@ -88,7 +314,16 @@ impl Command {
             "new" => Command::New,
             "help" => Command::Help,
             "models" => Command::Models,
+            "params" => Command::Params,
+            "personas" => Command::Personas,
             "start-local" => Command::StartLocal,
+            "retry" => Command::Retry,
+            "edit" => Command::Edit,
+            "compose" => Command::Compose,
+            "undo" => Command::Undo,
+            "info" => Command::Info,
+            "pin" => Command::Pin,
+            "unpin" => Command::Unpin,
             _ => Command::Unknown(command.to_string()),
         }
     }
@@ -102,10 +337,32 @@ impl Command {
             Command::New => "Start a new conversation",
             Command::Help => "Display this help message",
             Command::Models => "List available models from the backend",
+            Command::Model(_) => "Switch the model, or pick one from a list",
+            Command::Set(_, _) => "Set a generation parameter of this conversation ('default' unsets it)",
+            Command::Params => "Show the model, system prompt and parameters of this conversation",
+            Command::System(_) => "Show or set this conversation's system prompt ('-' removes it)",
+            Command::Persona(_) => "Show the persona, or use its system prompt in this conversation ('-' removes it)",
+            Command::Personas => "List the personas shared with the desktop app",
             Command::Update => "Update the CLI to the latest version",
             Command::UpdateCheck => "Check for available updates",
             Command::StartLocal => "Start local Ollama instance and switch to it",
             Command::Switch(_) => "Switch to a different endpoint (local, URL, or saved name)",
+            Command::Branch(_) => "List, switch to or prune conversation branches",
+            Command::Retry => "Resend the last message and generate a new response",
+            Command::Edit => "Edit the last message in $EDITOR and resend it",
+            Command::Compose => "Write a message in $EDITOR and send it when saved",
+            Command::Undo => "Remove the last exchange from the conversation",
+            Command::Info => "Show conversation details and stats of the last response",
+            Command::Export(_) => "Export the conversation to Markdown (or JSON for .json)",
+            Command::Tag(_) => "Show tags, add tags, or remove tags prefixed with -",
+            Command::Pin => "Pin the conversation to the top of the history",
+            Command::Unpin => "Unpin the conversation",
+            Command::Folder(_) => "Show or set the conversation's folder (- to clear)",
+            Command::Theme(_) => "List code highlighting themes, or switch to one",
+            Command::Code(_) => "List code blocks of the last response, or save or copy one",
+            Command::Think(_) => "Display the last response's thinking, or show or collapse it while streaming",
+            Command::Search(_) => "Search history (-c case, -w whole words, -a accents, -f fuzzy, --stem <lang>)",
+            Command::Load(_) => "Open a search result by number, or a conversation by ID",
             Command::Unknown(_) => "Unknown command",
         }
     }
@@ -119,33 +376,107 @@ impl Command {
             Command::New => "new".to_string(),
             Command::Help => "help".to_string(),
             Command::Models => "models".to_string(),
+            Command::Model(None) => "model [<name>]".to_string(),
+            Command::Model(Some(name)) => format!("model {}", name),
+            Command::Set(param, value) => format!("set {} {}", param, value),
+            Command::Params => "params".to_string(),
+            Command::System(None) => "system [<text>|-]".to_string(),
+            Command::System(Some(text)) => format!("system {}", text),
+            Command::Persona(None) => "persona [<id>|-]".to_string(),
+            Command::Persona(Some(id)) => format!("persona {}", id),
+            Command::Personas => "personas".to_string(),
             Command::Update => "update".to_string(),
             Command::UpdateCheck => "update --check".to_string(),
             Command::StartLocal => "start-local".to_string(),
             Command::Switch(target) => format!("switch {}", target),
+            Command::Branch(BranchAction::List) => "branch [list|<n>|prune <n|all>]".to_string(),
+            Command::Branch(BranchAction::Switch(n)) => format!("branch {}", n),
+            Command::Branch(BranchAction::Prune(n)) => format!("branch prune {}", n),
+            Command::Branch(BranchAction::PruneAll) => "branch prune all".to_string(),
+            Command::Retry => "retry".to_string(),
+            Command::Edit => "edit".to_string(),
+            Command::Compose => "compose".to_string(),
+            Command::Undo => "undo".to_string(),
+            Command::Info => "info".to_string(),
+            Command::Export(path) => format!("export {}", path),
+            Command::Tag(tags) if tags.is_empty() => "tag [<tag>|-<tag>...]".to_string(),
+            Command::Tag(tags) => format!("tag {}", tags.join(" ")),
+            Command::Pin => "pin".to_string(),
+            Command::Unpin => "unpin".to_string(),
+            Command::Folder(None) => "folder [<path>|-]".to_string(),
+            Command::Folder(Some(folder)) => format!("folder {}", folder),
+            Command::Theme(None) => "theme [<name>]".to_string(),
+            Command::Theme(Some(theme)) => format!("theme {}", theme),
+            Command::Code(CodeAction::List) => "code [save <n> <path>|copy <n>]".to_string(),
+            Command::Code(CodeAction::Save(n, path)) => format!("code save {} {}", n, path),
+            Command::Code(CodeAction::Copy(n)) => format!("code copy {}", n),
+            Command::Think(ThinkAction::Last) => "think [show|hide]".to_string(),
+            Command::Think(ThinkAction::Show) => "think show".to_string(),
+            Command::Think(ThinkAction::Hide) => "think hide".to_string(),
+            Command::Search(options) => format!("search {}", options.text),
+            Command::Load(target) => format!("load {}", target),
             Command::Unknown(cmd) => cmd.clone(),
         }
     }
 }
 
-/// Display help information for all available commands
-pub fn display_help() -> String {
-    let mut help = String::from("Available commands:\n\n");
-    
-    let commands = vec![
+/// Commands listed by `/help`, in display order
+fn listed_commands() -> Vec<Command> {
+    vec![
         Command::Exit,
         Command::Quit,
         Command::Clear,
         Command::New,
+        Command::Retry,
+        Command::Edit,
+        Command::Compose,
+        Command::Undo,
+        Command::Info,
+        Command::Export("<path>".to_string()),
+        Command::Tag(Vec::new()),
+        Command::Pin,
+        Command::Unpin,
+        Command::Folder(None),
+        Command::Search(SearchOptions {
+            text: "[-c] [-w] [-a] [-f] [--stem <lang>] <query>".to_string(),
+            ..Default::default()
+        }),
+        Command::Load("<n|id>".to_string()),
+        Command::Theme(None),
+        Command::Code(CodeAction::List),
+        Command::Think(ThinkAction::Last),
         Command::Help,
         Command::Models,
+        Command::Model(None),
+        Command::Set("<param>".to_string(), "<value>".to_string()),
+        Command::Params,
+        Command::System(None),
+        Command::Persona(None),
+        Command::Personas,
         Command::Update,
         Command::UpdateCheck,
         Command::StartLocal,
         Command::Switch("local|<url>|<name>".to_string()),
-    ];
+        Command::Branch(BranchAction::List),
+    ]
+}
 
-    for cmd in commands {
+/// Names of all listed commands without the leading slash, sorted, for completion
+pub fn command_names() -> Vec<String> {
+    let mut names: Vec<String> = listed_commands()
+        .iter()
+        .filter_map(|cmd| cmd.name().split_whitespace().next().map(str::to_string))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Display help information for all available commands
+pub fn display_help() -> String {
+    let mut help = String::from("Available commands:\n\n");
+
+    for cmd in listed_commands() {
         help.push_str(&format!("  /{:<30} - {}\n", cmd.name(), cmd.description()));
     }
 
@@ -452,4 +783,199 @@ mod tests {
         assert!(help.contains("/switch"));
         assert!(help.contains("Switch to a different endpoint"));
     }
+
+    #[test]
+    fn test_parse_branch_command() {
+        assert_eq!(Command::parse("/branch"), Command::Branch(BranchAction::List));
+        assert_eq!(Command::parse("/branch list"), Command::Branch(BranchAction::List));
+        assert_eq!(Command::parse("/branch 2"), Command::Branch(BranchAction::Switch(2)));
+        assert_eq!(Command::parse("/branch switch 3"), Command::Branch(BranchAction::Switch(3)));
+        assert_eq!(Command::parse("/branch prune 1"), Command::Branch(BranchAction::Prune(1)));
+        assert_eq!(Command::parse("/BRANCH Prune All"), Command::Branch(BranchAction::PruneAll));
+    }
+
+    #[test]
+    fn test_parse_branch_invalid_arguments() {
+        assert_eq!(
+            Command::parse("/branch 0"),
+            Command::Unknown("branch 0".to_string())
+        );
+        assert_eq!(
+            Command::parse("/branch prune"),
+            Command::Unknown("branch prune".to_string())
+        );
+        assert_eq!(
+            Command::parse("/branch foo"),
+            Command::Unknown("branch foo".to_string())
+        );
+    }
+
+    #[test]
+    fn test_display_help_includes_branch() {
+        let help = display_help();
+        assert!(help.contains("/branch"));
+        assert!(help.contains("conversation branches"));
+    }
+
+    #[test]
+    fn test_parse_history_editing_commands() {
+        assert_eq!(Command::parse("/retry"), Command::Retry);
+        assert_eq!(Command::parse("/EDIT"), Command::Edit);
+        assert_eq!(Command::parse("/compose"), Command::Compose);
+        assert_eq!(Command::parse("  /undo  "), Command::Undo);
+    }
+
+    #[test]
+    fn test_parse_session_settings_commands() {
+        assert_eq!(Command::parse("/model"), Command::Model(None));
+        assert_eq!(Command::parse("/MODEL Qwen3:8b"), Command::Model(Some("Qwen3:8b".to_string())));
+        assert!(matches!(Command::parse("/model a b"), Command::Unknown(_)));
+        assert_eq!(Command::parse("/models"), Command::Models);
+
+        assert_eq!(
+            Command::parse("/set Temperature 0.2"),
+            Command::Set("temperature".to_string(), "0.2".to_string())
+        );
+        assert_eq!(Command::parse("/set"), Command::Params);
+        assert_eq!(Command::parse("/params"), Command::Params);
+        assert!(matches!(Command::parse("/set temperature"), Command::Unknown(_)));
+
+        assert_eq!(Command::parse("/system"), Command::System(None));
+        assert_eq!(
+            Command::parse("/system You are terse.\nAnswer  in French."),
+            Command::System(Some("You are terse.\nAnswer  in French.".to_string()))
+        );
+        assert_eq!(Command::parse("/system -"), Command::System(Some("-".to_string())));
+        assert!(matches!(Command::parse("/systemd"), Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_persona_commands() {
+        assert_eq!(Command::parse("/persona"), Command::Persona(None));
+        assert_eq!(
+            Command::parse("/Persona code-reviewer"),
+            Command::Persona(Some("code-reviewer".to_string()))
+        );
+        assert_eq!(Command::parse("/persona -"), Command::Persona(Some("-".to_string())));
+        assert!(matches!(Command::parse("/persona a b"), Command::Unknown(_)));
+        assert_eq!(Command::parse("/PERSONAS"), Command::Personas);
+        assert!(matches!(Command::parse("/personas teacher"), Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_command_names() {
+        let names = command_names();
+        assert!(names.contains(&"switch".to_string()));
+        assert!(names.contains(&"branch".to_string()));
+        assert!(names.iter().all(|name| !name.starts_with('/') && !name.contains(' ')));
+        assert_eq!(names.iter().filter(|name| *name == "update").count(), 1);
+    }
+
+    #[test]
+    fn test_display_help_includes_history_editing_commands() {
+        let help = display_help();
+        assert!(help.contains("/retry"));
+        assert!(help.contains("/edit"));
+        assert!(help.contains("/undo"));
+    }
+
+    #[test]
+    fn test_parse_info_command() {
+        assert_eq!(Command::parse("/info"), Command::Info);
+        assert_eq!(Command::parse("/INFO"), Command::Info);
+    }
+
+    #[test]
+    fn test_parse_export_command_keeps_path_case() {
+        assert_eq!(
+            Command::parse("/export ~/Notes/Chat.md"),
+            Command::Export("~/Notes/Chat.md".to_string())
+        );
+        assert_eq!(
+            Command::parse("/EXPORT chat.json"),
+            Command::Export("chat.json".to_string())
+        );
+        assert_eq!(Command::parse("/export"), Command::Unknown("export".to_string()));
+    }
+
+    #[test]
+    fn test_display_help_includes_info_and_export() {
+        let help = display_help();
+        assert!(help.contains("/info"));
+        assert!(help.contains("/export <path>"));
+    }
+
+    #[test]
+    fn test_parse_tag_command() {
+        assert_eq!(Command::parse("/tag"), Command::Tag(Vec::new()));
+        assert_eq!(
+            Command::parse("/tag rust -draft"),
+            Command::Tag(vec!["rust".to_string(), "-draft".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_pin_and_folder_commands() {
+        assert_eq!(Command::parse("/pin"), Command::Pin);
+        assert_eq!(Command::parse("/UNPIN"), Command::Unpin);
+        assert_eq!(Command::parse("/folder"), Command::Folder(None));
+        assert_eq!(
+            Command::parse("/folder Work/Reviews"),
+            Command::Folder(Some("Work/Reviews".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_theme_command() {
+        assert_eq!(Command::parse("/theme"), Command::Theme(None));
+        assert_eq!(
+            Command::parse("/THEME Solarized (dark)"),
+            Command::Theme(Some("Solarized (dark)".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_code_command() {
+        assert_eq!(Command::parse("/code"), Command::Code(CodeAction::List));
+        assert_eq!(Command::parse("/code copy 2"), Command::Code(CodeAction::Copy(2)));
+        assert_eq!(
+            Command::parse("/CODE Save 1 ~/My Scripts/run.sh"),
+            Command::Code(CodeAction::Save(1, "~/My Scripts/run.sh".to_string()))
+        );
+        assert!(matches!(Command::parse("/code copy 0"), Command::Unknown(_)));
+        assert!(matches!(Command::parse("/code save 1"), Command::Unknown(_)));
+        assert!(matches!(Command::parse("/code list"), Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_think_command() {
+        assert_eq!(Command::parse("/think"), Command::Think(ThinkAction::Last));
+        assert_eq!(Command::parse("/think show"), Command::Think(ThinkAction::Show));
+        assert_eq!(Command::parse("/THINK Hide"), Command::Think(ThinkAction::Hide));
+        assert!(matches!(Command::parse("/think maybe"), Command::Unknown(_)));
+    }
+
+    #[test]
+    fn test_parse_search_and_load_commands() {
+        assert_eq!(
+            Command::parse("/search -w Borrow Checker"),
+            Command::Search(SearchOptions {
+                text: "Borrow Checker".to_string(),
+                whole_word: true,
+                ..Default::default()
+            })
+        );
+        assert_eq!(Command::parse("/search"), Command::Unknown("search".to_string()));
+        assert_eq!(Command::parse("/load 3"), Command::Load("3".to_string()));
+        assert_eq!(Command::parse("/load"), Command::Unknown("load".to_string()));
+    }
+
+    #[test]
+    fn test_display_help_includes_organization_commands() {
+        let help = display_help();
+        assert!(help.contains("/tag"));
+        assert!(help.contains("/pin"));
+        assert!(help.contains("/unpin"));
+        assert!(help.contains("/folder"));
+    }
 }
@@ -0,0 +1,223 @@
+//! Tab completion and inline hints for the interactive prompt
+//!
+//! Completes command names, then the argument of the command being typed:
+//! model names after `/model`, parameters after `/set`, persona IDs after
+//! `/persona`, endpoints after `/switch`, conversation IDs after `/load` and
+//! file paths after `/export` and `/code save <n>`.
+
+use rustyline::completion::{FilenameCompleter, Pair};
+
+use prometheus_chat::GenerationOptions;
+use crate::commands;
+
+/// Values completed after commands, kept up to date by the app
+#[derive(Debug, Clone, Default)]
+pub struct CompletionContext {
+    /// Models from the last successful `fetch_models`
+    pub models: Vec<String>,
+    /// IDs of the available personas
+    pub personas: Vec<String>,
+    /// Saved endpoint URLs
+    pub endpoints: Vec<String>,
+    /// Stored conversations as `(id, name)`
+    pub conversations: Vec<(String, String)>,
+}
+
+/// What the word under the cursor completes to
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Target {
+    Command,
+    Model,
+    Parameter,
+    Persona,
+    Endpoint,
+    Conversation,
+    Path,
+    Nothing,
+}
+
+/// Find where the word under the cursor starts and what it completes to
+///
+/// # Arguments
+/// * `line` - The input up to the cursor
+fn target(line: &str) -> (usize, Target) {
+    let start = line.rfind(char::is_whitespace).map(|i| i + 1).unwrap_or(0);
+    if !line.starts_with('/') {
+        return (start, Target::Nothing);
+    }
+    if start == 0 {
+        return (1, Target::Command);
+    }
+
+    let before: Vec<&str> = line[1..start].split_whitespace().collect();
+    let target = match before.as_slice() {
+        [command] if command.eq_ignore_ascii_case("model") => Target::Model,
+        [command] if command.eq_ignore_ascii_case("set") => Target::Parameter,
+        [command] if command.eq_ignore_ascii_case("persona") => Target::Persona,
+        [command] if command.eq_ignore_ascii_case("switch") => Target::Endpoint,
+        [command] if command.eq_ignore_ascii_case("load") => Target::Conversation,
+        [command] if command.eq_ignore_ascii_case("export") => Target::Path,
+        [command, action, _] if command.eq_ignore_ascii_case("code") && action.eq_ignore_ascii_case("save") => {
+            Target::Path
+        }
+        _ => Target::Nothing,
+    };
+    (start, target)
+}
+
+/// `local`, then each saved URL and its host name, which `/switch` also accepts
+fn endpoint_names(urls: &[String]) -> Vec<String> {
+    let mut names = Vec::new();
+    for url in urls {
+        if let Some(host) = url::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)) {
+            names.push(host);
+        }
+        names.push(url.clone());
+    }
+    names.sort();
+    names.dedup();
+    names.insert(0, "local".to_string());
+    names
+}
+
+fn candidate(value: &str) -> Pair {
+    Pair {
+        display: value.to_string(),
+        replacement: value.to_string(),
+    }
+}
+
+/// Complete the word under the cursor
+///
+/// # Returns
+/// The position the word starts at and the candidates that replace it
+pub fn complete(line: &str, pos: usize, context: &CompletionContext) -> rustyline::Result<(usize, Vec<Pair>)> {
+    let (start, target) = target(&line[..pos]);
+    let candidates = match target {
+        Target::Command => commands::command_names().iter().map(|name| candidate(name)).collect(),
+        Target::Model => context.models.iter().map(|model| candidate(model)).collect(),
+        Target::Parameter => GenerationOptions::NAMES.iter().map(|name| candidate(name)).collect(),
+        Target::Persona => context.personas.iter().map(|id| candidate(id)).collect(),
+        Target::Endpoint => endpoint_names(&context.endpoints).iter().map(|name| candidate(name)).collect(),
+        Target::Conversation => context
+            .conversations
+            .iter()
+            .map(|(id, name)| Pair {
+                display: format!("{}  {}", id, name),
+                replacement: id.clone(),
+            })
+            .collect(),
+        Target::Path => return FilenameCompleter::new().complete_path(line, pos),
+        Target::Nothing => Vec::new(),
+    };
+
+    let word = line[start..pos].to_lowercase();
+    let candidates = candidates
+        .into_iter()
+        .filter(|c: &Pair| c.replacement.to_lowercase().starts_with(&word))
+        .collect();
+    Ok((start, candidates))
+}
+
+/// The rest of the first completion of the word at the end of the line
+///
+/// File paths are not hinted, to keep the filesystem out of every keystroke.
+pub fn hint(line: &str, pos: usize, context: &CompletionContext) -> Option<String> {
+    if pos < line.len() {
+        return None;
+    }
+    let (start, target) = target(line);
+    let word = &line[start..];
+    if word.is_empty() || matches!(target, Target::Path | Target::Nothing) {
+        return None;
+    }
+
+    let (_, candidates) = complete(line, pos, context).ok()?;
+    candidates
+        .into_iter()
+        .find(|c| c.replacement.starts_with(word) && c.replacement.len() > word.len())
+        .map(|c| c.replacement[word.len()..].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> CompletionContext {
+        CompletionContext {
+            models: vec!["llama3.2:latest".to_string(), "qwen3:8b".to_string()],
+            personas: vec!["default".to_string(), "code-reviewer".to_string()],
+            endpoints: vec!["https://gpu-box.example.com:11434".to_string()],
+            conversations: vec![("3f2a9c".to_string(), "Rust lifetimes".to_string())],
+        }
+    }
+
+    fn replacements(line: &str) -> (usize, Vec<String>) {
+        let (start, candidates) = complete(line, line.len(), &context()).unwrap();
+        (start, candidates.into_iter().map(|c| c.replacement).collect())
+    }
+
+    #[test]
+    fn test_complete_command_names() {
+        let (start, names) = replacements("/sw");
+        assert_eq!(start, 1);
+        assert_eq!(names, vec!["switch"]);
+
+        let (_, names) = replacements("/");
+        assert!(names.contains(&"load".to_string()));
+        assert!(names.contains(&"compose".to_string()));
+    }
+
+    #[test]
+    fn test_complete_command_arguments() {
+        assert_eq!(replacements("/model q"), (7, vec!["qwen3:8b".to_string()]));
+        assert_eq!(replacements("/set te"), (5, vec!["temperature".to_string()]));
+        assert_eq!(replacements("/persona co"), (9, vec!["code-reviewer".to_string()]));
+        assert_eq!(replacements("/load 3f"), (6, vec!["3f2a9c".to_string()]));
+        assert_eq!(
+            replacements("/switch "),
+            (8, vec![
+                "local".to_string(),
+                "gpu-box.example.com".to_string(),
+                "https://gpu-box.example.com:11434".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_conversations_are_listed_with_their_names() {
+        let (_, candidates) = complete("/load ", 6, &context()).unwrap();
+        assert_eq!(candidates[0].display, "3f2a9c  Rust lifetimes");
+    }
+
+    #[test]
+    fn test_complete_paths() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        std::fs::write(temp_dir.path().join("notes.md"), "").unwrap();
+        let prefix = temp_dir.path().join("no").display().to_string();
+
+        for line in [format!("/export {}", prefix), format!("/code save 1 {}", prefix)] {
+            let (_, candidates) = complete(&line, line.len(), &context()).unwrap();
+            assert_eq!(candidates.len(), 1);
+            assert!(candidates[0].replacement.ends_with("notes.md"));
+        }
+    }
+
+    #[test]
+    fn test_no_completion_in_prompts_or_other_arguments() {
+        assert!(replacements("Explain /sw").1.is_empty());
+        assert!(replacements("/model qwen3:8b q").1.is_empty());
+        assert!(replacements("/code copy ").1.is_empty());
+    }
+
+    #[test]
+    fn test_hint() {
+        let context = context();
+        assert_eq!(hint("/comp", 5, &context), Some("ose".to_string()));
+        assert_eq!(hint("/model ll", 9, &context), Some("ama3.2:latest".to_string()));
+        assert_eq!(hint("/model ", 7, &context), None);
+        assert_eq!(hint("/compose", 8, &context), None);
+        assert_eq!(hint("/comp", 3, &context), None);
+        assert_eq!(hint("Hello", 5, &context), None);
+    }
+}
@@ -44,6 +44,8 @@ pub struct AppConfig {
     pub app: AppSettings,
     pub backend: BackendSettings,
     pub ui: UISettings,
+    #[serde(default)]
+    pub conversations: ConversationSettings,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -118,15 +120,100 @@ impl BackendSettings {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UISettings {
     pub font_size: u16,
+    /// Maximum number of stored conversations; 0 for no limit
     pub max_chat_history: usize,
     #[serde(default = "default_theme")]
     pub theme: String,
+    /// Syntax highlighting theme for code blocks in the terminal
+    #[serde(default = "default_code_theme")]
+    pub code_theme: String,
+    /// Number the lines of code blocks in the terminal
+    #[serde(default)]
+    pub code_line_numbers: bool,
+    /// Show the thinking of reasoning models in the terminal instead of collapsing it
+    #[serde(default)]
+    pub show_thinking: bool,
+    /// Keybindings of the interactive prompt: "emacs" or "vi"
+    #[serde(default = "default_edit_mode")]
+    pub edit_mode: String,
+    /// Number of prompts kept in the interactive prompt's history
+    #[serde(default = "default_input_history_size")]
+    pub input_history_size: usize,
 }
 
 fn default_theme() -> String {
     "Hacker Green".to_string()
 }
 
+fn default_code_theme() -> String {
+    crate::highlight::DEFAULT_CODE_THEME.to_string()
+}
+
+fn default_edit_mode() -> String {
+    "emacs".to_string()
+}
+
+fn default_input_history_size() -> usize {
+    1000
+}
+
+/// Settings for stored conversations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSettings {
+    /// Generate a short title in the background after the first exchange
+    #[serde(default = "default_auto_title")]
+    pub auto_title: bool,
+    /// Model used to generate titles; the conversation's own model when unset
+    #[serde(default)]
+    pub title_model: Option<String>,
+    /// Delete conversations that have not been updated for this many days
+    #[serde(default)]
+    pub max_age_days: Option<u64>,
+    /// Delete the oldest conversations once stored files exceed this many megabytes
+    #[serde(default)]
+    pub max_total_size_mb: Option<u64>,
+    /// Never prune pinned conversations
+    #[serde(default = "default_keep_pinned")]
+    pub keep_pinned: bool,
+    /// Apply the retention policy when the interactive REPL starts; off by default
+    /// so upgrading never deletes history without being asked to
+    #[serde(default)]
+    pub prune_on_startup: bool,
+    /// Embed new messages in the background for semantic search
+    #[serde(default)]
+    pub semantic_search: bool,
+    /// Ollama model used for embeddings; changing it re-embeds the history
+    #[serde(default = "default_embedding_model")]
+    pub embedding_model: String,
+}
+
+fn default_auto_title() -> bool {
+    true
+}
+
+fn default_keep_pinned() -> bool {
+    true
+}
+
+fn default_embedding_model() -> String {
+    "nomic-embed-text".to_string()
+}
+
+impl Default for ConversationSettings {
+    fn default() -> Self {
+        Self {
+            auto_title: default_auto_title(),
+            title_model: None,
+            max_age_days: None,
+            max_total_size_mb: None,
+            keep_pinned: default_keep_pinned(),
+            prune_on_startup: false,
+            semantic_search: false,
+            embedding_model: default_embedding_model(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ColorTheme {
     HackerGreen,
@@ -434,7 +521,13 @@ impl Default for AppConfig {
                 font_size: 16,
                 max_chat_history: 1000,
                 theme: "Hacker Green".to_string(),
+                code_theme: default_code_theme(),
+                code_line_numbers: false,
+                show_thinking: false,
+                edit_mode: default_edit_mode(),
+                input_history_size: default_input_history_size(),
             },
+            conversations: ConversationSettings::default(),
         }
     }
 }
@@ -925,7 +1018,13 @@ theme = "Hacker Green"
                 font_size: 16,
                 max_chat_history: 1000,
                 theme: "Hacker Green".to_string(),
+                code_theme: default_code_theme(),
+                code_line_numbers: false,
+                show_thinking: false,
+                edit_mode: default_edit_mode(),
+                input_history_size: default_input_history_size(),
             },
+            conversations: ConversationSettings::default(),
         };
 
         // Perform migration
@@ -1002,7 +1101,13 @@ theme = "Hacker Green"
                 font_size: 16,
                 max_chat_history: 1000,
                 theme: "Hacker Green".to_string(),
+                code_theme: default_code_theme(),
+                code_line_numbers: false,
+                show_thinking: false,
+                edit_mode: default_edit_mode(),
+                input_history_size: default_input_history_size(),
             },
+            conversations: ConversationSettings::default(),
         };
 
         // Store original values for comparison
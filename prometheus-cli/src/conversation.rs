@@ -1,34 +1,61 @@
 use anyhow::{Context, Result};
+use prometheus_search::{
+    SearchEngine, SearchMessage, SearchQuery, SearchResult, Searchable, SemanticMatch, VectorIndex,
+};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 use uuid::Uuid;
+pub use prometheus_chat::{ChatMessage, GenerationOptions, MessageMetadata, MessageNode, MessageTree};
+use crate::backend::GenerationSettings;
+use crate::encryption::{self, Cipher, KdfParams, KeyFile};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ChatMessage {
-    pub role: String,
-    pub content: String,
-    pub timestamp: String,
-}
+/// Name of the key file written when history is encrypted
+const KEY_FILE_NAME: &str = "encryption.json";
 
-impl ChatMessage {
-    pub fn new(role: String, content: String) -> Self {
-        Self {
-            role,
-            content,
-            timestamp: chrono::Local::now().to_rfc3339(),
-        }
-    }
-}
+/// New key file written before files are re-encrypted, renamed to `KEY_FILE_NAME` once all are
+const PENDING_KEY_FILE_NAME: &str = "encryption.json.new";
+
+/// Extension of the copies files are written to before being renamed into place
+const STAGED_EXTENSION: &str = "json.tmp";
+
+/// Name of the persisted search index; encrypted along with the conversations
+const SEARCH_INDEX_FILE: &str = "search_index.json";
+const VECTOR_INDEX_FILE: &str = "embeddings.json";
+
+/// IDs of conversations saved since their messages were last embedded
+const PENDING_EMBEDDINGS_FILE: &str = "embeddings_pending.json";
+
+/// Prompts recalled by the interactive line editor; never kept while history is encrypted
+const INPUT_HISTORY_FILE: &str = "input_history.txt";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Conversation {
     pub id: String,
     pub name: String,
+    /// Messages on the active path, in order
     pub messages: Vec<ChatMessage>,
+    /// Messages on inactive branches, e.g. the original tail of an edited prompt
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branch_nodes: Vec<MessageNode>,
     pub created_at: String,
     pub updated_at: String,
     pub model: Option<String>,
+    /// Lowercase labels used to organize and filter conversations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Pinned conversations are listed before all others
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// Slash-separated folder path, e.g. "work/reviews"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    /// System prompt and generation options, changed with `/system` and `/set`
+    #[serde(default, skip_serializing_if = "GenerationSettings::is_default")]
+    pub settings: GenerationSettings,
 }
 
 impl Conversation {
@@ -38,9 +65,14 @@ impl Conversation {
             id: Uuid::new_v4().to_string(),
             name,
             messages: Vec::new(),
+            branch_nodes: Vec::new(),
             created_at: now.clone(),
             updated_at: now,
             model,
+            tags: Vec::new(),
+            pinned: false,
+            folder: None,
+            settings: GenerationSettings::default(),
         }
     }
 
@@ -58,28 +90,189 @@ impl Conversation {
         self.update_timestamp();
     }
 
-    pub fn remove_message(&mut self, index: usize) {
-        if index < self.messages.len() {
-            self.messages.remove(index);
-            self.update_timestamp();
+    /// Add a tag to the conversation
+    ///
+    /// # Returns
+    /// `false` if the conversation already had the tag
+    pub fn add_tag(&mut self, tag: &str) -> Result<bool> {
+        let tag = normalize_tag(tag)?;
+        if self.tags.contains(&tag) {
+            return Ok(false);
         }
+        self.tags.push(tag);
+        self.tags.sort();
+        Ok(true)
     }
 
-    pub fn update_message(&mut self, index: usize, content: String) {
-        if index < self.messages.len() {
-            self.messages[index].content = content;
-            self.update_timestamp();
-        }
+    /// Remove a tag from the conversation
+    ///
+    /// # Returns
+    /// `false` if the conversation did not have the tag
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let tag = tag_key(tag);
+        let before = self.tags.len();
+        self.tags.retain(|t| *t != tag);
+        self.tags.len() != before
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag_key(tag);
+        self.tags.contains(&tag)
+    }
+
+    /// Move the conversation into a folder, or out of any folder with `None`
+    pub fn set_folder(&mut self, folder: Option<&str>) -> Result<()> {
+        self.folder = match folder {
+            Some(folder) => normalize_folder(folder)?,
+            None => None,
+        };
+        Ok(())
     }
 
-    pub fn clear_messages_after(&mut self, index: usize) {
-        if index < self.messages.len() {
-            self.messages.truncate(index + 1);
-            self.update_timestamp();
+    /// Render the active path as a Markdown document
+    ///
+    /// Each message is followed by its generation metadata, if any, so the
+    /// export records which model and settings produced every answer.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n\n", self.name);
+        out.push_str(&format!("- Created: {}\n", self.created_at));
+        out.push_str(&format!("- Updated: {}\n", self.updated_at));
+        if let Some(model) = &self.model {
+            out.push_str(&format!("- Model: {}\n", model));
+        }
+        if let Some(folder) = &self.folder {
+            out.push_str(&format!("- Folder: {}\n", folder));
+        }
+        if !self.tags.is_empty() {
+            out.push_str(&format!("- Tags: {}\n", self.tags.join(", ")));
+        }
+
+        for message in &self.messages {
+            out.push_str(&format!("\n## {} ({})\n\n", message.role, message.timestamp));
+            out.push_str(message.content.trim_end());
+            out.push('\n');
+
+            let fields = message.metadata.summary();
+            if !fields.is_empty() {
+                out.push('\n');
+                for (key, value) in fields {
+                    out.push_str(&format!("> {}: {}\n", key, value));
+                }
+            }
         }
+
+        out
+    }
+
+    /// Export the conversation to a file
+    ///
+    /// The format is chosen from the extension: `.json` writes the full
+    /// conversation (including branches), anything else writes Markdown.
+    pub fn export_to_file(&self, path: &Path) -> Result<()> {
+        let is_json = path
+            .extension()
+            .map(|ext| ext.eq_ignore_ascii_case("json"))
+            .unwrap_or(false);
+
+        let content = if is_json {
+            serde_json::to_string_pretty(self).context("Failed to serialize conversation")?
+        } else {
+            self.to_markdown()
+        };
+
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write export file {}", path.display()))
+    }
+}
+
+impl MessageTree for Conversation {
+    fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    fn messages_mut(&mut self) -> &mut Vec<ChatMessage> {
+        &mut self.messages
+    }
+
+    fn branch_nodes(&self) -> &[MessageNode] {
+        &self.branch_nodes
+    }
+
+    fn branch_nodes_mut(&mut self) -> &mut Vec<MessageNode> {
+        &mut self.branch_nodes
+    }
+
+    fn touch(&mut self) {
+        self.update_timestamp();
     }
 }
 
+impl Searchable for Conversation {
+    fn search_id(&self) -> &str {
+        &self.id
+    }
+
+    fn search_messages(&self) -> Vec<SearchMessage<'_>> {
+        self.messages
+            .iter()
+            .map(|m| SearchMessage {
+                role: &m.role,
+                content: &m.content,
+                timestamp: Some(&m.timestamp),
+                model: m.metadata.model.as_deref(),
+            })
+            .collect()
+    }
+
+    fn search_updated_at(&self) -> Option<&str> {
+        Some(&self.updated_at)
+    }
+
+    fn search_name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+
+    fn search_model(&self) -> Option<&str> {
+        self.model.as_deref()
+    }
+
+    fn search_tags(&self) -> Vec<&str> {
+        self.tags.iter().map(String::as_str).collect()
+    }
+}
+
+/// Normalize a tag: trimmed, lowercase, without a leading '#', whitespace or commas
+pub fn normalize_tag(tag: &str) -> Result<String> {
+    let tag = tag_key(tag);
+    if tag.is_empty() {
+        anyhow::bail!("Tag cannot be empty");
+    }
+    if tag.chars().any(|c| c.is_whitespace() || c == ',') {
+        anyhow::bail!("Tag cannot contain spaces or commas: {}", tag);
+    }
+    Ok(tag)
+}
+
+fn tag_key(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+/// Normalize a folder path to `a/b/c` form
+///
+/// # Returns
+/// `None` for an empty path (no folder)
+pub fn normalize_folder(folder: &str) -> Result<Option<String>> {
+    let parts: Vec<&str> = folder
+        .split('/')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    if parts.iter().any(|p| *p == "." || *p == "..") {
+        anyhow::bail!("Folder path cannot contain '.' or '..': {}", folder);
+    }
+    Ok(if parts.is_empty() { None } else { Some(parts.join("/")) })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversationMetadata {
     pub id: String,
@@ -87,6 +280,12 @@ pub struct ConversationMetadata {
     pub preview: String,
     pub updated_at: String,
     pub message_count: usize,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub folder: Option<String>,
 }
 
 impl ConversationMetadata {
@@ -101,8 +300,8 @@ impl ConversationMetadata {
                 .map(|m| &m.content)
                 .unwrap_or(&conv.messages[0].content);
             
-            if first_user_msg.len() > 50 {
-                format!("{}...", &first_user_msg[..50])
+            if first_user_msg.chars().count() > 50 {
+                format!("{}...", first_user_msg.chars().take(50).collect::<String>())
             } else {
                 first_user_msg.clone()
             }
@@ -114,6 +313,24 @@ impl ConversationMetadata {
             preview,
             updated_at: conv.updated_at.clone(),
             message_count: conv.messages.len(),
+            tags: conv.tags.clone(),
+            pinned: conv.pinned,
+            folder: conv.folder.clone(),
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag_key(tag);
+        self.tags.contains(&tag)
+    }
+
+    /// Whether the conversation is in `folder` or one of its subfolders
+    pub fn in_folder(&self, folder: &str) -> bool {
+        match (&self.folder, normalize_folder(folder)) {
+            (Some(own), Ok(Some(folder))) => {
+                *own == folder || own.starts_with(&format!("{}/", folder))
+            }
+            _ => false,
         }
     }
 }
@@ -143,8 +360,13 @@ impl MetadataFile {
         self.conversations.retain(|m| m.id != id);
     }
 
+    /// Sort pinned conversations first, then newest first
     pub fn sort_by_date(&mut self) {
-        self.conversations.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        self.conversations.sort_by(|a, b| {
+            b.pinned
+                .cmp(&a.pinned)
+                .then_with(|| b.updated_at.cmp(&a.updated_at))
+        });
     }
 }
 
@@ -152,6 +374,14 @@ impl MetadataFile {
 pub struct ConversationManager {
     conversations_dir: PathBuf,
     metadata_path: PathBuf,
+    /// Set once encrypted history has been unlocked; files are then written encrypted
+    cipher: Option<Arc<Cipher>>,
+    /// Kept up to date on every save and delete once search is enabled
+    search: Option<Arc<Mutex<SearchEngine>>>,
+    /// Set when the index has changed since it was last persisted
+    search_unsaved: Arc<AtomicBool>,
+    /// Held while the list of conversations waiting to be embedded is read and rewritten
+    pending_embeddings_lock: Arc<Mutex<()>>,
 }
 
 impl ConversationManager {
@@ -161,6 +391,10 @@ impl ConversationManager {
         Self {
             conversations_dir,
             metadata_path,
+            cipher: None,
+            search: None,
+            search_unsaved: Arc::new(AtomicBool::new(false)),
+            pending_embeddings_lock: Arc::new(Mutex::new(())),
         }
     }
 
@@ -170,7 +404,449 @@ impl ConversationManager {
         Self {
             conversations_dir,
             metadata_path,
+            cipher: None,
+            search: None,
+            search_unsaved: Arc::new(AtomicBool::new(false)),
+            pending_embeddings_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Open the default conversations directory, unlocking it if it is encrypted
+    ///
+    /// The passphrase is read from `PROMETHEUS_PASSPHRASE`, or prompted for.
+    pub fn open() -> Result<Self> {
+        let manager = Self::new();
+        if manager.is_encrypted() {
+            let passphrase = encryption::read_passphrase("Conversation history passphrase: ")?;
+            return manager.unlock(&passphrase);
+        }
+        Ok(manager)
+    }
+
+    fn key_file_path(&self) -> PathBuf {
+        self.conversations_dir.join(KEY_FILE_NAME)
+    }
+
+    /// Whether encryption has been enabled for this directory
+    pub fn is_encrypted(&self) -> bool {
+        self.key_file_path().exists()
+    }
+
+    /// Derive the key from `passphrase` so encrypted files can be read and written
+    pub fn unlock(mut self, passphrase: &str) -> Result<Self> {
+        let key_file = KeyFile::load(&self.key_file_path())?
+            .ok_or_else(|| anyhow::anyhow!("Conversation history is not encrypted"))?;
+        self.cipher = Some(Arc::new(key_file.unlock(passphrase)?));
+        Ok(self)
+    }
+
+    /// Encrypt all existing plaintext files with a key derived from `passphrase`
+    ///
+    /// # Returns
+    /// A manager unlocked with the new key
+    pub fn enable_encryption(&self, passphrase: &str, params: KdfParams) -> Result<Self> {
+        if self.is_encrypted() {
+            anyhow::bail!("Conversation history is already encrypted");
+        }
+        let target = self.replace_key(passphrase, params)?;
+
+        // The line editor's history holds the same prompts in plain text
+        let history_path = self.conversations_dir.join(INPUT_HISTORY_FILE);
+        match fs::remove_file(&history_path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).context("Failed to remove input history")
+            }
+            _ => Ok(target),
+        }
+    }
+
+    /// Re-encrypt all files with a key derived from a new passphrase
+    ///
+    /// The manager must already be unlocked.
+    ///
+    /// # Returns
+    /// A manager unlocked with the new key
+    pub fn rekey(&self, new_passphrase: &str, params: KdfParams) -> Result<Self> {
+        if self.cipher.is_none() {
+            anyhow::bail!("Conversation history must be unlocked before changing its passphrase");
+        }
+        self.replace_key(new_passphrase, params)
+    }
+
+    /// Decrypt all files and remove the key file
+    ///
+    /// The manager must already be unlocked.
+    pub fn disable_encryption(&self) -> Result<Self> {
+        if self.cipher.is_none() {
+            anyhow::bail!("Conversation history must be unlocked before decrypting it");
+        }
+        let target = Self {
+            cipher: None,
+            ..self.clone()
+        };
+        // Plaintext files stay readable while the key file exists, so an
+        // interrupted rename leaves nothing unreadable
+        let staged = self.stage_files(&target)?;
+        commit_staged(&staged)?;
+        fs::remove_file(self.key_file_path()).context("Failed to remove key file")?;
+        Ok(target)
+    }
+
+    /// Re-encrypt every file with a new key and then make it the directory's key
+    ///
+    /// The new key file is written under a temporary name before any file is
+    /// encrypted with it, so its salt is never lost. If staging the files fails,
+    /// nothing has been replaced and the temporary key file is removed.
+    fn replace_key(&self, passphrase: &str, params: KdfParams) -> Result<Self> {
+        let (key_file, cipher) = KeyFile::create(passphrase, params)?;
+        let target = Self {
+            cipher: Some(Arc::new(cipher)),
+            ..self.clone()
+        };
+
+        if !self.conversations_dir.exists() {
+            fs::create_dir_all(&self.conversations_dir)
+                .context("Failed to create conversations directory")?;
+        }
+        let pending_key_path = self.conversations_dir.join(PENDING_KEY_FILE_NAME);
+        key_file.save(&pending_key_path)?;
+
+        let staged = match self.stage_files(&target) {
+            Ok(staged) => staged,
+            Err(e) => {
+                let _ = fs::remove_file(&pending_key_path);
+                return Err(e);
+            }
+        };
+        // From here on some files may use the new key, so the pending key file is kept on failure
+        commit_staged(&staged).with_context(|| {
+            format!("The new key was left in {}", pending_key_path.display())
+        })?;
+        fs::rename(&pending_key_path, self.key_file_path())
+            .context("Failed to replace key file")?;
+        Ok(target)
+    }
+
+    /// Read every stored file with this manager's key and write a staged copy with `target`'s
+    ///
+    /// # Returns
+    /// The paths of the original files, each with a staged copy next to it.
+    /// On error every staged copy written so far is removed.
+    fn stage_files(&self, target: &Self) -> Result<Vec<PathBuf>> {
+        if !self.conversations_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut staged = Vec::new();
+        if let Err(e) = self.stage_each_file(target, &mut staged) {
+            for path in &staged {
+                let _ = fs::remove_file(staged_path(path));
+            }
+            return Err(e);
+        }
+        Ok(staged)
+    }
+
+    fn stage_each_file(&self, target: &Self, staged: &mut Vec<PathBuf>) -> Result<()> {
+        let entries = fs::read_dir(&self.conversations_dir)
+            .context("Failed to read conversations directory")?;
+        for entry in entries {
+            let path = entry.context("Failed to read conversations directory")?.path();
+            let is_json = path.extension().map(|e| e == "json").unwrap_or(false);
+            if !is_json || path.file_name().map(|n| n == KEY_FILE_NAME).unwrap_or(false) {
+                continue;
+            }
+            let content = self.read_file(&path)?;
+            target.write_file_to(&staged_path(&path), &path, &content)?;
+            staged.push(path);
+        }
+        Ok(())
+    }
+
+    /// Read a stored file, decrypting it if needed
+    fn read_file(&self, path: &Path) -> Result<String> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        if !encryption::is_encrypted(&content) {
+            return Ok(content);
+        }
+        match &self.cipher {
+            Some(cipher) => cipher
+                .decrypt(&content)
+                .with_context(|| format!("Failed to decrypt {}", path.display())),
+            None => anyhow::bail!(
+                "Conversation history is encrypted; set {} or enter the passphrase to unlock it",
+                encryption::PASSPHRASE_ENV
+            ),
+        }
+    }
+
+    /// Write a stored file, encrypting it if the manager is unlocked
+    ///
+    /// The content is written to a staged copy that then replaces the file, so
+    /// an interrupted write never leaves it truncated.
+    fn write_file(&self, path: &Path, content: &str) -> Result<()> {
+        let staged = staged_path(path);
+        self.write_file_to(&staged, path, content)?;
+        fs::rename(&staged, path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Encrypt `content` if the manager is unlocked and write it to `to`
+    ///
+    /// `path` is the stored file the content belongs to, used in errors.
+    fn write_file_to(&self, to: &Path, path: &Path, content: &str) -> Result<()> {
+        let content = match &self.cipher {
+            Some(cipher) => cipher.encrypt(content)?,
+            None => content.to_string(),
+        };
+        fs::write(to, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Enable search, loading the persisted index or rebuilding it if missing or unreadable
+    pub fn enable_search(&mut self) -> Result<()> {
+        let path = self.search_index_path();
+        let engine = if path.exists() {
+            self.read_file(&path)
+                .ok()
+                .and_then(|content| SearchEngine::from_json(&content).ok())
+        } else {
+            None
+        };
+
+        match engine {
+            Some(engine) => self.search = Some(Arc::new(Mutex::new(engine))),
+            None => {
+                self.search = Some(Arc::new(Mutex::new(SearchEngine::new())));
+                self.rebuild_search_index()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Path of the interactive prompt history, or `None` while history is encrypted
+    pub fn input_history_path(&self) -> Option<PathBuf> {
+        if self.is_encrypted() {
+            return None;
+        }
+        Some(self.conversations_dir.join(INPUT_HISTORY_FILE))
+    }
+
+    fn search_index_path(&self) -> PathBuf {
+        self.conversations_dir.join(SEARCH_INDEX_FILE)
+    }
+
+    fn search_engine(&self) -> Result<MutexGuard<'_, SearchEngine>> {
+        let search = self.search.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Search is not enabled"))?;
+        search.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire search index lock: {}", e))
+    }
+
+    /// Search stored messages
+    pub fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+        Ok(self.search_engine()?.search(query)?)
+    }
+
+    /// Results for messages found by a semantic query, most similar first
+    pub fn semantic_search(&self, matches: &[SemanticMatch]) -> Result<Vec<SearchResult>> {
+        Ok(self.search_engine()?.semantic_results(matches))
+    }
+
+    /// Search stored messages, blending keyword ranking with semantic similarity
+    pub fn hybrid_search(
+        &self,
+        query: &SearchQuery,
+        matches: &[SemanticMatch],
+        semantic_weight: f64,
+    ) -> Result<Vec<SearchResult>> {
+        Ok(self.search_engine()?.search_hybrid(query, matches, semantic_weight)?)
+    }
+
+    /// Load the message embeddings computed with `model`
+    ///
+    /// A missing or unreadable index, or one computed with a different model, is
+    /// replaced by an empty one so that every message is embedded again.
+    pub fn load_vector_index(&self, model: &str) -> VectorIndex {
+        let path = self.conversations_dir.join(VECTOR_INDEX_FILE);
+        if !path.exists() {
+            return VectorIndex::new(model);
+        }
+        match self.read_file(&path).map(|content| VectorIndex::from_json(&content)) {
+            Ok(Ok(index)) if index.model() == model => index,
+            Ok(Ok(_)) => {
+                log::info!("Embedding model changed to {}; messages will be embedded again", model);
+                VectorIndex::new(model)
+            }
+            Ok(Err(e)) => {
+                log::warn!("Discarding unreadable embeddings: {}", e);
+                VectorIndex::new(model)
+            }
+            Err(e) => {
+                log::warn!("Failed to read embeddings: {}", e);
+                VectorIndex::new(model)
+            }
+        }
+    }
+
+    /// Persist message embeddings next to the conversations
+    pub fn save_vector_index(&self, index: &VectorIndex) -> Result<()> {
+        if !self.conversations_dir.exists() {
+            fs::create_dir_all(&self.conversations_dir)
+                .context("Failed to create conversations directory")?;
+        }
+        let content = serde_json::to_string(index)
+            .context("Failed to serialize embeddings")?;
+        self.write_file(&self.conversations_dir.join(VECTOR_INDEX_FILE), &content)
+            .context("Failed to write embeddings")
+    }
+
+    /// Drop the stored embeddings of deleted conversations, whatever model made them
+    fn remove_embeddings<'a>(&self, ids: impl IntoIterator<Item = &'a String>) -> Result<()> {
+        let path = self.conversations_dir.join(VECTOR_INDEX_FILE);
+        if !path.exists() {
+            return Ok(());
+        }
+        let content = self.read_file(&path)
+            .context("Failed to read embeddings")?;
+        let mut index = VectorIndex::from_json(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse embeddings: {}", e))?;
+        let count = index.len();
+        for id in ids {
+            index.remove_conversation(id);
+        }
+        if index.len() != count {
+            self.save_vector_index(&index)?;
+        }
+        Ok(())
+    }
+
+    /// Take the IDs of conversations saved since they were last embedded
+    ///
+    /// From then on saves are recorded again, starting from an empty list.
+    ///
+    /// # Returns
+    /// `None` if saves weren't recorded yet (before the first semantic search),
+    /// in which case any conversation may need embedding
+    pub fn take_pending_embeddings(&self) -> Result<Option<HashSet<String>>> {
+        let _guard = self.pending_embeddings_lock.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire pending embeddings lock: {}", e))?;
+        let pending = self.read_pending_embeddings()?;
+        self.write_pending_embeddings(&HashSet::new())?;
+        Ok(pending)
+    }
+
+    /// Record that conversations need embedding, unless saves aren't recorded yet
+    pub fn add_pending_embeddings<'a>(&self, ids: impl IntoIterator<Item = &'a String>) -> Result<()> {
+        let _guard = self.pending_embeddings_lock.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire pending embeddings lock: {}", e))?;
+        let Some(mut pending) = self.read_pending_embeddings()? else {
+            return Ok(());
+        };
+        let count = pending.len();
+        pending.extend(ids.into_iter().cloned());
+        if pending.len() != count {
+            self.write_pending_embeddings(&pending)?;
+        }
+        Ok(())
+    }
+
+    fn read_pending_embeddings(&self) -> Result<Option<HashSet<String>>> {
+        let path = self.conversations_dir.join(PENDING_EMBEDDINGS_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = self.read_file(&path)
+            .context("Failed to read pending embeddings")?;
+        let pending = serde_json::from_str(&content)
+            .context("Failed to parse pending embeddings")?;
+        Ok(Some(pending))
+    }
+
+    fn write_pending_embeddings(&self, pending: &HashSet<String>) -> Result<()> {
+        if !self.conversations_dir.exists() {
+            fs::create_dir_all(&self.conversations_dir)
+                .context("Failed to create conversations directory")?;
+        }
+        let content = serde_json::to_string(pending)
+            .context("Failed to serialize pending embeddings")?;
+        self.write_file(&self.conversations_dir.join(PENDING_EMBEDDINGS_FILE), &content)
+            .context("Failed to write pending embeddings")
+    }
+
+    /// Re-index every stored conversation and persist the index
+    ///
+    /// # Returns
+    /// The number of conversations indexed
+    pub fn rebuild_search_index(&self) -> Result<usize> {
+        let mut conversations = Vec::new();
+        for metadata in self.list_conversations()? {
+            match self.load_conversation(&metadata.id) {
+                Ok(conversation) => conversations.push(conversation),
+                Err(e) => log::warn!("Skipping conversation {} while indexing: {}", metadata.id, e),
+            }
+        }
+
+        let count = conversations.len();
+        self.update_search_index(|engine| engine.rebuild_index(&conversations))?;
+        self.save_search_index()?;
+        Ok(count)
+    }
+
+    /// Apply `change` to the search index
+    ///
+    /// The index is only written by [`ConversationManager::save_search_index`],
+    /// so saving a message doesn't rewrite the whole index. The persisted copy is
+    /// removed on the first unsaved change, so that if the index is never saved
+    /// (e.g. after a crash) it is rebuilt the next time search is enabled.
+    /// Without search enabled the persisted index can't be kept in step at all,
+    /// so it is removed as well.
+    fn update_search_index(&self, change: impl FnOnce(&mut SearchEngine)) -> Result<()> {
+        let Some(search) = &self.search else {
+            return self.remove_search_index();
+        };
+
+        let mut engine = search.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire search index lock: {}", e))?;
+        change(&mut engine);
+
+        if !self.search_unsaved.swap(true, Ordering::SeqCst) {
+            self.remove_search_index()?;
+        }
+        Ok(())
+    }
+
+    /// Persist the search index if it has changed since it was last saved
+    ///
+    /// Called when the REPL exits and after the index is rebuilt.
+    pub fn save_search_index(&self) -> Result<()> {
+        let Some(search) = &self.search else {
+            return Ok(());
+        };
+        if !self.search_unsaved.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let engine = search.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire search index lock: {}", e))?;
+        if !self.conversations_dir.exists() {
+            fs::create_dir_all(&self.conversations_dir)
+                .context("Failed to create conversations directory")?;
+        }
+        let content = serde_json::to_string(&*engine)
+            .context("Failed to serialize search index")?;
+        self.write_file(&self.search_index_path(), &content)
+            .context("Failed to write search index")?;
+        self.search_unsaved.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    fn remove_search_index(&self) -> Result<()> {
+        let path = self.search_index_path();
+        if path.exists() {
+            fs::remove_file(&path).context("Failed to remove stale search index")?;
         }
+        Ok(())
     }
 
     pub fn load_metadata(&self) -> Result<MetadataFile> {
@@ -178,7 +854,7 @@ impl ConversationManager {
             return Ok(MetadataFile::new());
         }
 
-        let content = fs::read_to_string(&self.metadata_path)
+        let content = self.read_file(&self.metadata_path)
             .context("Failed to read metadata file")?;
         
         // Handle empty file
@@ -203,20 +879,33 @@ impl ConversationManager {
         let content = serde_json::to_string_pretty(metadata)
             .context("Failed to serialize metadata")?;
         
-        fs::write(&self.metadata_path, content)
+        self.write_file(&self.metadata_path, &content)
             .context("Failed to write metadata file")?;
         
         Ok(())
     }
 
+    /// Path of a conversation's file
+    ///
+    /// IDs can be typed by the user (e.g. `/load`), so anything but a UUID is
+    /// rejected rather than joined into a path that could point outside the directory.
+    fn conversation_path(&self, id: &str) -> Result<PathBuf> {
+        match Uuid::parse_str(id) {
+            Ok(uuid) if uuid.to_string() == id => {
+                Ok(self.conversations_dir.join(format!("{}.json", id)))
+            }
+            _ => anyhow::bail!("Invalid conversation ID: {}", id),
+        }
+    }
+
     pub fn load_conversation(&self, id: &str) -> Result<Conversation> {
-        let path = self.conversations_dir.join(format!("{}.json", id));
+        let path = self.conversation_path(id)?;
         
         if !path.exists() {
             anyhow::bail!("Conversation file not found: {}", id);
         }
 
-        let content = fs::read_to_string(&path)
+        let content = self.read_file(&path)
             .context("Failed to read conversation file")?;
         
         let conversation: Conversation = serde_json::from_str(&content)
@@ -232,24 +921,36 @@ impl ConversationManager {
                 .context("Failed to create conversations directory")?;
         }
         
-        let path = self.conversations_dir.join(format!("{}.json", conversation.id));
+        let path = self.conversation_path(&conversation.id)?;
         
         let content = serde_json::to_string_pretty(conversation)
             .context("Failed to serialize conversation")?;
         
-        fs::write(&path, content)
+        self.write_file(&path, &content)
             .context("Failed to write conversation file")?;
 
         // Update metadata
         let mut metadata = self.load_metadata()?;
         metadata.add_or_update(ConversationMetadata::from_conversation(conversation));
         self.save_metadata(&metadata)?;
+
+        // The conversation itself is saved; a stale index is rebuilt on demand
+        if let Err(e) = self.update_search_index(|engine| {
+            engine.remove_conversation(&conversation.id);
+            engine.index_conversation(conversation);
+        }) {
+            log::warn!("Failed to update search index: {}", e);
+        }
+
+        if let Err(e) = self.add_pending_embeddings([&conversation.id]) {
+            log::warn!("Failed to record the conversation for embedding: {}", e);
+        }
         
         Ok(())
     }
 
     pub fn delete_conversation(&self, id: &str) -> Result<()> {
-        let path = self.conversations_dir.join(format!("{}.json", id));
+        let path = self.conversation_path(id)?;
         
         if path.exists() {
             fs::remove_file(&path)
@@ -260,12 +961,645 @@ impl ConversationManager {
         let mut metadata = self.load_metadata()?;
         metadata.remove(id);
         self.save_metadata(&metadata)?;
+
+        if let Err(e) = self.update_search_index(|engine| engine.remove_conversation(id)) {
+            log::warn!("Failed to update search index: {}", e);
+        }
+
+        if let Err(e) = self.remove_embeddings([&id.to_string()]) {
+            log::warn!("Failed to remove embeddings: {}", e);
+        }
         
         Ok(())
     }
 
+    /// Delete several conversations, rewriting the metadata file once
+    pub fn delete_conversations(&self, ids: &[String]) -> Result<()> {
+        for id in ids {
+            let path = self.conversation_path(id)?;
+            if path.exists() {
+                fs::remove_file(&path)
+                    .context("Failed to delete conversation file")?;
+            }
+        }
+
+        let mut metadata = self.load_metadata()?;
+        metadata.conversations.retain(|m| !ids.contains(&m.id));
+        self.save_metadata(&metadata)?;
+
+        if let Err(e) = self.update_search_index(|engine| {
+            for id in ids {
+                engine.remove_conversation(id);
+            }
+        }) {
+            log::warn!("Failed to update search index: {}", e);
+        }
+
+        if let Err(e) = self.remove_embeddings(ids) {
+            log::warn!("Failed to remove embeddings: {}", e);
+        }
+
+        Ok(())
+    }
+
     pub fn list_conversations(&self) -> Result<Vec<ConversationMetadata>> {
         let metadata = self.load_metadata()?;
         Ok(metadata.conversations)
     }
+
+    /// Size of a conversation's file on disk, or 0 if it has no file
+    pub fn conversation_size(&self, id: &str) -> Result<u64> {
+        let path = self.conversation_path(id)?;
+        if !path.exists() {
+            return Ok(0);
+        }
+        let metadata = fs::metadata(&path).context("Failed to read conversation file size")?;
+        Ok(metadata.len())
+    }
+
+    /// List conversations that have all of the given tags, pinned first
+    pub fn list_conversations_with_tags(&self, tags: &[String]) -> Result<Vec<ConversationMetadata>> {
+        let mut conversations = self.list_conversations()?;
+        conversations.retain(|c| tags.iter().all(|tag| c.has_tag(tag)));
+        Ok(conversations)
+    }
+
+    /// All tags in use, with the number of conversations carrying each
+    pub fn list_tags(&self) -> Result<Vec<(String, usize)>> {
+        let mut counts: std::collections::BTreeMap<String, usize> = Default::default();
+        for conversation in self.list_conversations()? {
+            for tag in conversation.tags {
+                *counts.entry(tag).or_default() += 1;
+            }
+        }
+        Ok(counts.into_iter().collect())
+    }
+
+    /// All folders in use, sorted
+    pub fn list_folders(&self) -> Result<Vec<String>> {
+        let mut folders: Vec<String> = self
+            .list_conversations()?
+            .into_iter()
+            .filter_map(|c| c.folder)
+            .collect();
+        folders.sort();
+        folders.dedup();
+        Ok(folders)
+    }
+
+    /// Load a conversation, apply `change` and save it again
+    fn update_conversation<T>(
+        &self,
+        id: &str,
+        change: impl FnOnce(&mut Conversation) -> Result<T>,
+    ) -> Result<T> {
+        let mut conversation = self.load_conversation(id)?;
+        let result = change(&mut conversation)?;
+        self.save_conversation(&conversation)?;
+        Ok(result)
+    }
+
+    /// Add a tag to a saved conversation
+    pub fn add_tag(&self, id: &str, tag: &str) -> Result<bool> {
+        self.update_conversation(id, |c| c.add_tag(tag))
+    }
+
+    /// Remove a tag from a saved conversation
+    pub fn remove_tag(&self, id: &str, tag: &str) -> Result<bool> {
+        self.update_conversation(id, |c| Ok(c.remove_tag(tag)))
+    }
+
+    /// Rename a tag on every conversation that has it
+    ///
+    /// # Returns
+    /// The number of conversations changed
+    pub fn rename_tag(&self, old: &str, new: &str) -> Result<usize> {
+        let new = normalize_tag(new)?;
+        let mut changed = 0;
+        for metadata in self.list_conversations()? {
+            if metadata.has_tag(old) {
+                self.update_conversation(&metadata.id, |c| {
+                    c.remove_tag(old);
+                    c.add_tag(&new)
+                })?;
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Remove a tag from every conversation
+    ///
+    /// # Returns
+    /// The number of conversations changed
+    pub fn delete_tag(&self, tag: &str) -> Result<usize> {
+        let mut changed = 0;
+        for metadata in self.list_conversations()? {
+            if metadata.has_tag(tag) {
+                self.update_conversation(&metadata.id, |c| Ok(c.remove_tag(tag)))?;
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Pin or unpin a saved conversation
+    pub fn set_pinned(&self, id: &str, pinned: bool) -> Result<()> {
+        self.update_conversation(id, |c| {
+            c.pinned = pinned;
+            Ok(())
+        })
+    }
+
+    /// Move a saved conversation into a folder, or out of any folder with `None`
+    pub fn set_folder(&self, id: &str, folder: Option<&str>) -> Result<()> {
+        self.update_conversation(id, |c| c.set_folder(folder))
+    }
+}
+
+/// Path of the copy a stored file is written to before being renamed into place
+fn staged_path(path: &Path) -> PathBuf {
+    path.with_extension(STAGED_EXTENSION)
+}
+
+/// Rename every staged copy over its original file
+fn commit_staged(paths: &[PathBuf]) -> Result<()> {
+    for path in paths {
+        fs::rename(staged_path(path), path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation_with(messages: &[(&str, &str)]) -> Conversation {
+        let mut conversation = Conversation::new("Test".to_string(), None);
+        for (role, content) in messages {
+            conversation.add_message(ChatMessage::new(role.to_string(), content.to_string()));
+        }
+        conversation
+    }
+
+    #[test]
+    fn test_load_flat_conversation_without_message_ids() {
+        let json = r#"{
+            "id": "abc",
+            "name": "Old chat",
+            "messages": [
+                {"role": "user", "content": "Hello", "timestamp": "2024-01-01T00:00:00+00:00"},
+                {"role": "assistant", "content": "Hi", "timestamp": "2024-01-01T00:00:01+00:00"}
+            ],
+            "created_at": "2024-01-01T00:00:00+00:00",
+            "updated_at": "2024-01-01T00:00:01+00:00",
+            "model": null
+        }"#;
+
+        let mut conversation: Conversation = serde_json::from_str(json).unwrap();
+        assert_eq!(conversation.messages.len(), 2);
+        assert_ne!(conversation.messages[0].id, conversation.messages[1].id);
+        assert!(!conversation.has_branches());
+
+        conversation.update_message(1, "Hello there".to_string());
+        assert_eq!(conversation.list_branches().len(), 2);
+    }
+
+    #[test]
+    fn test_branches_survive_persistence() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let mut conversation = conversation_with(&[("user", "Q"), ("assistant", "A")]);
+        conversation.update_message(0, "Q edited".to_string());
+
+        manager.save_conversation(&conversation).unwrap();
+        let loaded = manager.load_conversation(&conversation.id).unwrap();
+
+        assert_eq!(loaded.messages.len(), 1);
+        assert_eq!(loaded.list_branches(), conversation.list_branches());
+    }
+
+    #[test]
+    fn test_load_message_without_metadata() {
+        let json = r#"{"role": "assistant", "content": "Hi", "timestamp": "2024-01-01T00:00:01+00:00"}"#;
+
+        let message: ChatMessage = serde_json::from_str(json).unwrap();
+        assert!(message.metadata.is_empty());
+
+        // Empty metadata is not written back
+        let serialized = serde_json::to_string(&message).unwrap();
+        assert!(!serialized.contains("metadata"));
+    }
+
+    #[test]
+    fn test_message_thinking_is_stored_separately() {
+        let message = ChatMessage::new("assistant".to_string(), "Hi".to_string())
+            .with_thinking(Some("Greet back.".to_string()));
+
+        let serialized = serde_json::to_string(&message).unwrap();
+        let deserialized: ChatMessage = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.content, "Hi");
+        assert_eq!(deserialized.thinking.as_deref(), Some("Greet back."));
+
+        let plain = ChatMessage::new("assistant".to_string(), "Hi".to_string());
+        assert!(!serde_json::to_string(&plain).unwrap().contains("thinking"));
+    }
+
+    #[test]
+    fn test_message_metadata_roundtrip() {
+        let message = ChatMessage::new("assistant".to_string(), "Hi".to_string()).with_metadata(
+            MessageMetadata {
+                model: Some("llama2".to_string()),
+                endpoint: Some("http://localhost:11434".to_string()),
+                persona_id: Some("coder".to_string()),
+                options: GenerationOptions {
+                    temperature: Some(0.7),
+                    ..Default::default()
+                },
+                prompt_tokens: Some(10),
+                completion_tokens: Some(20),
+                latency_ms: Some(1500),
+                finish_reason: Some("stop".to_string()),
+            },
+        );
+
+        let serialized = serde_json::to_string(&message).unwrap();
+        assert!(!serialized.contains("num_predict"));
+        let deserialized: ChatMessage = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(deserialized.metadata, message.metadata);
+    }
+
+    #[test]
+    fn test_metadata_summary_lists_set_fields() {
+        let metadata = MessageMetadata {
+            model: Some("llama2".to_string()),
+            latency_ms: Some(1234),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            metadata.summary(),
+            vec![("model", "llama2".to_string()), ("latency", "1.23s".to_string())]
+        );
+        assert!(MessageMetadata::default().summary().is_empty());
+    }
+
+    #[test]
+    fn test_to_markdown_includes_messages_and_metadata() {
+        let mut conversation = conversation_with(&[("user", "Question")]);
+        conversation.add_message(
+            ChatMessage::new("assistant".to_string(), "Answer".to_string()).with_metadata(
+                MessageMetadata {
+                    finish_reason: Some("stop".to_string()),
+                    ..Default::default()
+                },
+            ),
+        );
+
+        let markdown = conversation.to_markdown();
+        assert!(markdown.starts_with(&format!("# {}", conversation.name)));
+        assert!(markdown.contains("## user"));
+        assert!(markdown.contains("Question"));
+        assert!(markdown.contains("## assistant"));
+        assert!(markdown.contains("> finish reason: stop"));
+    }
+
+    #[test]
+    fn test_tags_are_normalized_and_deduplicated() {
+        let mut conversation = conversation_with(&[]);
+
+        assert!(conversation.add_tag(" Rust ").unwrap());
+        assert!(!conversation.add_tag("#rust").unwrap());
+        assert!(conversation.add_tag("async").unwrap());
+        assert!(conversation.add_tag("two words").is_err());
+        assert!(conversation.add_tag("   ").is_err());
+
+        assert_eq!(conversation.tags, vec!["async", "rust"]);
+        assert!(conversation.has_tag("RUST"));
+        assert!(conversation.remove_tag("Rust"));
+        assert!(!conversation.remove_tag("rust"));
+    }
+
+    #[test]
+    fn test_folder_is_normalized() {
+        let mut conversation = conversation_with(&[]);
+
+        conversation.set_folder(Some("/work//reviews/")).unwrap();
+        assert_eq!(conversation.folder.as_deref(), Some("work/reviews"));
+
+        conversation.set_folder(Some("  ")).unwrap();
+        assert_eq!(conversation.folder, None);
+
+        assert!(conversation.set_folder(Some("work/../secret")).is_err());
+    }
+
+    #[test]
+    fn test_metadata_in_folder_matches_subfolders() {
+        let mut conversation = conversation_with(&[]);
+        conversation.set_folder(Some("work/reviews")).unwrap();
+        let metadata = ConversationMetadata::from_conversation(&conversation);
+
+        assert!(metadata.in_folder("work"));
+        assert!(metadata.in_folder("work/reviews/"));
+        assert!(!metadata.in_folder("wor"));
+        assert!(!metadata.in_folder("personal"));
+    }
+
+    #[test]
+    fn test_sort_by_date_keeps_pinned_first() {
+        let mut metadata = MetadataFile::new();
+        for (id, updated_at, pinned) in [
+            ("old-pinned", "2024-01-01", true),
+            ("new", "2024-03-01", false),
+            ("older", "2024-02-01", false),
+        ] {
+            let mut conversation = conversation_with(&[]);
+            conversation.id = id.to_string();
+            conversation.updated_at = updated_at.to_string();
+            conversation.pinned = pinned;
+            metadata.add_or_update(ConversationMetadata::from_conversation(&conversation));
+        }
+
+        let ids: Vec<&str> = metadata.conversations.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["old-pinned", "new", "older"]);
+    }
+
+    #[test]
+    fn test_metadata_preview_truncates_on_char_boundary() {
+        let conversation = conversation_with(&[("user", &"é".repeat(60))]);
+        let metadata = ConversationMetadata::from_conversation(&conversation);
+        assert_eq!(metadata.preview, format!("{}...", "é".repeat(50)));
+    }
+
+    #[test]
+    fn test_load_metadata_without_organization_fields() {
+        let json = r#"{"conversations": [{"id": "a", "name": "Chat", "preview": "Hi",
+            "updated_at": "2024-01-01T00:00:00+00:00", "message_count": 2}]}"#;
+
+        let metadata: MetadataFile = serde_json::from_str(json).unwrap();
+        assert!(metadata.conversations[0].tags.is_empty());
+        assert!(!metadata.conversations[0].pinned);
+        assert_eq!(metadata.conversations[0].folder, None);
+    }
+
+    #[test]
+    fn test_manager_tag_crud() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let first = conversation_with(&[("user", "First")]);
+        let second = conversation_with(&[("user", "Second")]);
+        manager.save_conversation(&first).unwrap();
+        manager.save_conversation(&second).unwrap();
+
+        manager.add_tag(&first.id, "draft").unwrap();
+        manager.add_tag(&first.id, "rust").unwrap();
+        manager.add_tag(&second.id, "draft").unwrap();
+        assert_eq!(
+            manager.list_tags().unwrap(),
+            vec![("draft".to_string(), 2), ("rust".to_string(), 1)]
+        );
+
+        let tagged = manager.list_conversations_with_tags(&["draft".to_string(), "rust".to_string()]).unwrap();
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].id, first.id);
+
+        assert_eq!(manager.rename_tag("draft", "wip").unwrap(), 2);
+        assert_eq!(manager.delete_tag("rust").unwrap(), 1);
+        assert!(manager.remove_tag(&second.id, "wip").unwrap());
+        assert_eq!(manager.list_tags().unwrap(), vec![("wip".to_string(), 1)]);
+        assert_eq!(manager.load_conversation(&first.id).unwrap().tags, vec!["wip"]);
+    }
+
+    #[test]
+    fn test_manager_pin_and_folder() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let mut older = conversation_with(&[("user", "Older")]);
+        older.updated_at = "2000-01-01T00:00:00+00:00".to_string();
+        let newer = conversation_with(&[("user", "Newer")]);
+        manager.save_conversation(&older).unwrap();
+        manager.save_conversation(&newer).unwrap();
+
+        manager.set_pinned(&older.id, true).unwrap();
+        manager.set_folder(&newer.id, Some("work")).unwrap();
+
+        let listed = manager.list_conversations().unwrap();
+        assert_eq!(listed[0].id, older.id);
+        assert!(listed[0].pinned);
+        assert_eq!(listed[1].folder.as_deref(), Some("work"));
+        assert_eq!(manager.list_folders().unwrap(), vec!["work"]);
+
+        assert!(manager.set_pinned("missing", true).is_err());
+    }
+
+    #[test]
+    fn test_generation_settings_are_saved_with_conversation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let mut conversation = conversation_with(&[("user", "Bonjour")]);
+        conversation.settings.system = Some("Answer in French.".to_string());
+        conversation.settings.options.set("temperature", "0.3").unwrap();
+        manager.save_conversation(&conversation).unwrap();
+
+        let loaded = manager.load_conversation(&conversation.id).unwrap();
+        assert_eq!(loaded.settings, conversation.settings);
+
+        // Conversations without settings don't store the field
+        let plain = conversation_with(&[("user", "Hi")]);
+        let json = serde_json::to_string(&plain).unwrap();
+        assert!(!json.contains("settings"));
+    }
+
+    #[test]
+    fn test_conversation_ids_must_be_uuids() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let history = temp_dir.path().join("history");
+        let manager = ConversationManager::with_directory(&history);
+        fs::write(temp_dir.path().join("outside.json"), "{}").unwrap();
+
+        assert!(manager.load_conversation("../outside").is_err());
+        assert!(manager.delete_conversation("../outside").is_err());
+        assert!(manager.delete_conversations(&["../outside".to_string()]).is_err());
+        assert!(manager.conversation_size("../outside").is_err());
+        assert!(temp_dir.path().join("outside.json").exists());
+
+        let mut conversation = conversation_with(&[("user", "Hello")]);
+        conversation.id = "../outside".to_string();
+        assert!(manager.save_conversation(&conversation).is_err());
+        assert_eq!(fs::read_to_string(temp_dir.path().join("outside.json")).unwrap(), "{}");
+    }
+
+    #[test]
+    fn test_deleting_conversations_drops_their_embeddings() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let conversations: Vec<_> = (0..3)
+            .map(|i| conversation_with(&[("user", &format!("message {}", i))]))
+            .collect();
+        let mut index = VectorIndex::new("nomic-embed-text");
+        for conversation in &conversations {
+            manager.save_conversation(conversation).unwrap();
+            for message in index.pending(conversation) {
+                index.insert(&message, vec![1.0, 0.0]);
+            }
+        }
+        manager.save_vector_index(&index).unwrap();
+
+        manager.delete_conversation(&conversations[0].id).unwrap();
+        assert_eq!(manager.load_vector_index("nomic-embed-text").len(), 2);
+
+        manager.delete_conversations(&[conversations[1].id.clone()]).unwrap();
+        let mut index = manager.load_vector_index("nomic-embed-text");
+        assert_eq!(index.len(), 1);
+        assert!(index.pending(&conversations[2]).is_empty());
+    }
+
+    #[test]
+    fn test_search_index_is_saved_on_request() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let index_path = temp_dir.path().join(SEARCH_INDEX_FILE);
+        let mut manager = ConversationManager::with_directory(temp_dir.path());
+        manager.enable_search().unwrap();
+        assert!(index_path.exists());
+
+        // Saving a conversation updates the index in memory and drops the stale copy
+        manager.save_conversation(&conversation_with(&[("user", "Tell me about lifetimes")])).unwrap();
+        let query = SearchQuery::new("lifetimes".to_string());
+        assert_eq!(manager.search(&query).unwrap().len(), 1);
+        assert!(!index_path.exists());
+
+        manager.save_search_index().unwrap();
+        assert!(index_path.exists());
+        let mut reopened = ConversationManager::with_directory(temp_dir.path());
+        reopened.enable_search().unwrap();
+        assert_eq!(reopened.search(&query).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_enable_encryption_migrates_plaintext_files() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let conversation = conversation_with(&[("user", "proprietary code")]);
+        manager.save_conversation(&conversation).unwrap();
+
+        let encrypted = manager
+            .enable_encryption("secret", crate::encryption::test_params())
+            .unwrap();
+        assert!(manager.is_encrypted());
+
+        let raw = fs::read_to_string(temp_dir.path().join(format!("{}.json", conversation.id))).unwrap();
+        assert!(encryption::is_encrypted(&raw));
+        assert!(!raw.contains("proprietary"));
+        let raw_metadata = fs::read_to_string(temp_dir.path().join("metadata.json")).unwrap();
+        assert!(encryption::is_encrypted(&raw_metadata));
+
+        // A locked manager cannot read the files; an unlocked one can
+        assert!(ConversationManager::with_directory(temp_dir.path()).list_conversations().is_err());
+        let loaded = encrypted.load_conversation(&conversation.id).unwrap();
+        assert_eq!(loaded.messages[0].content, "proprietary code");
+        let unlocked = ConversationManager::with_directory(temp_dir.path()).unlock("secret").unwrap();
+        assert_eq!(unlocked.list_conversations().unwrap().len(), 1);
+        assert!(ConversationManager::with_directory(temp_dir.path()).unlock("wrong").is_err());
+    }
+
+    #[test]
+    fn test_input_history_is_dropped_when_encrypting() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let history_path = manager.input_history_path().unwrap();
+        fs::write(&history_path, "proprietary code\n").unwrap();
+
+        let encrypted = manager
+            .enable_encryption("secret", crate::encryption::test_params())
+            .unwrap();
+        assert!(!history_path.exists());
+        assert!(encrypted.input_history_path().is_none());
+    }
+
+    /// Names of staged copies and pending key files left in `dir`
+    fn leftover_files(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".tmp") || name == PENDING_KEY_FILE_NAME)
+            .collect()
+    }
+
+    #[test]
+    fn test_failed_enable_encryption_leaves_files_in_plaintext() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let conversations: Vec<_> = (0..3)
+            .map(|i| conversation_with(&[("user", &format!("message {}", i))]))
+            .collect();
+        for conversation in &conversations {
+            manager.save_conversation(conversation).unwrap();
+        }
+        // A directory named like a conversation file cannot be read, so the rewrite fails partway
+        fs::create_dir(temp_dir.path().join("unreadable.json")).unwrap();
+
+        assert!(manager.enable_encryption("secret", crate::encryption::test_params()).is_err());
+        assert!(!manager.is_encrypted());
+        assert!(leftover_files(temp_dir.path()).is_empty());
+        for conversation in &conversations {
+            let raw = fs::read_to_string(temp_dir.path().join(format!("{}.json", conversation.id))).unwrap();
+            assert!(!encryption::is_encrypted(&raw));
+            manager.load_conversation(&conversation.id).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_failed_rekey_keeps_old_key_working() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let conversations: Vec<_> = (0..3)
+            .map(|i| conversation_with(&[("user", &format!("message {}", i))]))
+            .collect();
+        for conversation in &conversations {
+            manager.save_conversation(conversation).unwrap();
+        }
+        let params = crate::encryption::test_params();
+        let encrypted = manager.enable_encryption("old", params).unwrap();
+
+        // A corrupted file cannot be decrypted, so the rewrite fails partway
+        let raw = fs::read_to_string(temp_dir.path().join(format!("{}.json", conversations[0].id))).unwrap();
+        let corrupted = raw.replacen("\"ciphertext\": \"", "\"ciphertext\": \"AAAA", 1);
+        assert_ne!(raw, corrupted);
+        fs::write(temp_dir.path().join("corrupted.json"), corrupted).unwrap();
+
+        assert!(encrypted.rekey("new", params).is_err());
+        assert!(leftover_files(temp_dir.path()).is_empty());
+        assert!(ConversationManager::with_directory(temp_dir.path()).unlock("new").is_err());
+        let unlocked = ConversationManager::with_directory(temp_dir.path()).unlock("old").unwrap();
+        for conversation in &conversations {
+            assert_eq!(unlocked.load_conversation(&conversation.id).unwrap().id, conversation.id);
+        }
+    }
+
+    #[test]
+    fn test_rekey_and_disable_encryption() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let conversation = conversation_with(&[("user", "Hello")]);
+        manager.save_conversation(&conversation).unwrap();
+
+        let params = crate::encryption::test_params();
+        assert!(manager.rekey("new", params).is_err());
+        let encrypted = manager.enable_encryption("old", params).unwrap();
+        assert!(encrypted.enable_encryption("again", params).is_err());
+
+        let rekeyed = encrypted.rekey("new", params).unwrap();
+        assert!(ConversationManager::with_directory(temp_dir.path()).unlock("old").is_err());
+        assert_eq!(rekeyed.load_conversation(&conversation.id).unwrap().id, conversation.id);
+
+        let plain = rekeyed.disable_encryption().unwrap();
+        assert!(!plain.is_encrypted());
+        let raw = fs::read_to_string(temp_dir.path().join(format!("{}.json", conversation.id))).unwrap();
+        assert!(raw.contains("Hello"));
+        assert_eq!(
+            ConversationManager::with_directory(temp_dir.path()).list_conversations().unwrap().len(),
+            1
+        );
+    }
 }
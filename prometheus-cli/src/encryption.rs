@@ -0,0 +1,336 @@
+use anyhow::{Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Environment variable holding the passphrase for encrypted history
+pub const PASSPHRASE_ENV: &str = "PROMETHEUS_PASSPHRASE";
+
+/// Environment variable holding the new passphrase for `history encrypt` and `history rekey`
+pub const NEW_PASSPHRASE_ENV: &str = "PROMETHEUS_NEW_PASSPHRASE";
+
+/// Marks a file as encrypted by this module
+const FORMAT: &str = "prometheus-encrypted";
+
+/// Current version of the encrypted file and key file formats
+const FORMAT_VERSION: u32 = 1;
+
+/// Known plaintext stored in the key file to check a passphrase
+const CHECK_VALUE: &str = "prometheus-passphrase-check";
+
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+/// An encrypted file: XChaCha20-Poly1305 ciphertext with its nonce
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EncryptedFile {
+    format: String,
+    version: u32,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Argon2id cost parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory cost in KiB
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// Stored next to the conversations; holds everything needed to re-derive the key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyFile {
+    pub version: u32,
+    pub kdf: String,
+    salt: String,
+    pub params: KdfParams,
+    /// `CHECK_VALUE` encrypted with the derived key
+    check: EncryptedFile,
+}
+
+impl KeyFile {
+    /// Create a key file for a new passphrase
+    ///
+    /// # Returns
+    /// The key file and the cipher derived from the passphrase
+    pub fn create(passphrase: &str, params: KdfParams) -> Result<(Self, Cipher)> {
+        if passphrase.is_empty() {
+            anyhow::bail!("Passphrase cannot be empty");
+        }
+
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+
+        let cipher = Cipher::derive(passphrase, &salt, params)?;
+        let check = cipher.seal(CHECK_VALUE)?;
+
+        let key_file = Self {
+            version: FORMAT_VERSION,
+            kdf: "argon2id".to_string(),
+            salt: STANDARD.encode(salt),
+            params,
+            check,
+        };
+        Ok((key_file, cipher))
+    }
+
+    /// Derive the cipher from a passphrase, failing if the passphrase is wrong
+    pub fn unlock(&self, passphrase: &str) -> Result<Cipher> {
+        if self.version > FORMAT_VERSION {
+            anyhow::bail!(
+                "Key file version {} is newer than this version of prometheus-cli supports",
+                self.version
+            );
+        }
+        if self.kdf != "argon2id" {
+            anyhow::bail!("Unsupported key derivation function: {}", self.kdf);
+        }
+
+        let salt = STANDARD.decode(&self.salt).context("Invalid salt in key file")?;
+        let cipher = Cipher::derive(passphrase, &salt, self.params)?;
+
+        match cipher.open(&self.check) {
+            Ok(check) if check == CHECK_VALUE => Ok(cipher),
+            _ => anyhow::bail!("Incorrect passphrase for conversation history"),
+        }
+    }
+
+    /// Load a key file
+    ///
+    /// # Returns
+    /// `None` if the file does not exist
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path).context("Failed to read key file")?;
+        let key_file = serde_json::from_str(&content).context("Failed to parse key file")?;
+        Ok(Some(key_file))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).context("Failed to serialize key file")?;
+        fs::write(path, content).context("Failed to write key file")?;
+        Ok(())
+    }
+}
+
+/// Encrypts and decrypts stored files with a key derived from a passphrase
+#[derive(Clone)]
+pub struct Cipher {
+    aead: XChaCha20Poly1305,
+}
+
+impl Cipher {
+    fn derive(passphrase: &str, salt: &[u8], params: KdfParams) -> Result<Self> {
+        let params = Params::new(
+            params.memory_kib,
+            params.iterations,
+            params.parallelism,
+            Some(KEY_LEN),
+        )
+        .map_err(|e| anyhow::anyhow!("Invalid key derivation parameters: {}", e))?;
+
+        let mut key = [0u8; KEY_LEN];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow::anyhow!("Failed to derive encryption key: {}", e))?;
+
+        let aead = XChaCha20Poly1305::new(&key.into());
+        key.fill(0);
+        Ok(Self { aead })
+    }
+
+    /// Encrypt file contents
+    ///
+    /// # Returns
+    /// The encrypted file as JSON, ready to write to disk
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let file = self.seal(plaintext)?;
+        serde_json::to_string_pretty(&file).context("Failed to serialize encrypted file")
+    }
+
+    /// Decrypt file contents written by [`Cipher::encrypt`]
+    pub fn decrypt(&self, content: &str) -> Result<String> {
+        let file = parse_encrypted(content)
+            .ok_or_else(|| anyhow::anyhow!("File is not encrypted"))?;
+        self.open(&file)
+    }
+
+    fn seal(&self, plaintext: &str) -> Result<EncryptedFile> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .aead
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt file"))?;
+
+        Ok(EncryptedFile {
+            format: FORMAT.to_string(),
+            version: FORMAT_VERSION,
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        })
+    }
+
+    fn open(&self, file: &EncryptedFile) -> Result<String> {
+        if file.version > FORMAT_VERSION {
+            anyhow::bail!(
+                "Encrypted file version {} is newer than this version of prometheus-cli supports",
+                file.version
+            );
+        }
+
+        let nonce = STANDARD.decode(&file.nonce).context("Invalid nonce in encrypted file")?;
+        if nonce.len() != 24 {
+            anyhow::bail!("Invalid nonce length in encrypted file");
+        }
+        let ciphertext = STANDARD
+            .decode(&file.ciphertext)
+            .context("Invalid ciphertext in encrypted file")?;
+
+        let plaintext = self
+            .aead
+            .decrypt(XNonce::from_slice(&nonce), ciphertext.as_ref())
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt file: wrong key or corrupted data"))?;
+
+        String::from_utf8(plaintext).context("Decrypted file is not valid UTF-8")
+    }
+}
+
+/// Whether file contents were written by [`Cipher::encrypt`]
+pub fn is_encrypted(content: &str) -> bool {
+    parse_encrypted(content).is_some()
+}
+
+fn parse_encrypted(content: &str) -> Option<EncryptedFile> {
+    serde_json::from_str::<EncryptedFile>(content)
+        .ok()
+        .filter(|file| file.format == FORMAT)
+}
+
+/// Read the passphrase from `PROMETHEUS_PASSPHRASE`, or prompt for it without echo
+pub fn read_passphrase(prompt: &str) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        if !passphrase.is_empty() {
+            return Ok(passphrase);
+        }
+    }
+    rpassword::prompt_password(prompt).context("Failed to read passphrase")
+}
+
+/// Read a new passphrase from `PROMETHEUS_NEW_PASSPHRASE`, or prompt for it twice
+pub fn read_new_passphrase() -> Result<String> {
+    if let Ok(passphrase) = std::env::var(NEW_PASSPHRASE_ENV) {
+        if !passphrase.is_empty() {
+            return Ok(passphrase);
+        }
+    }
+
+    let passphrase = rpassword::prompt_password("New passphrase: ")
+        .context("Failed to read passphrase")?;
+    if passphrase.is_empty() {
+        anyhow::bail!("Passphrase cannot be empty");
+    }
+    let confirmation = rpassword::prompt_password("Confirm passphrase: ")
+        .context("Failed to read passphrase")?;
+    if passphrase != confirmation {
+        anyhow::bail!("Passphrases do not match");
+    }
+    Ok(passphrase)
+}
+
+#[cfg(test)]
+pub(crate) fn test_params() -> KdfParams {
+    // Minimal cost so tests stay fast
+    KdfParams {
+        memory_kib: 64,
+        iterations: 1,
+        parallelism: 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let (_, cipher) = KeyFile::create("secret", test_params()).unwrap();
+
+        let encrypted = cipher.encrypt("{\"id\": \"abc\"}").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.contains("abc"));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "{\"id\": \"abc\"}");
+    }
+
+    #[test]
+    fn test_plaintext_is_not_encrypted() {
+        assert!(!is_encrypted("{\"id\": \"abc\", \"messages\": []}"));
+        assert!(!is_encrypted(""));
+    }
+
+    #[test]
+    fn test_unlock_checks_passphrase() {
+        let (key_file, cipher) = KeyFile::create("secret", test_params()).unwrap();
+        let encrypted = cipher.encrypt("hello").unwrap();
+
+        assert!(key_file.unlock("wrong").is_err());
+        let unlocked = key_file.unlock("secret").unwrap();
+        assert_eq!(unlocked.decrypt(&encrypted).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_key_file_round_trip() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("encryption.json");
+        assert!(KeyFile::load(&path).unwrap().is_none());
+
+        let (key_file, _) = KeyFile::create("secret", test_params()).unwrap();
+        key_file.save(&path).unwrap();
+
+        let loaded = KeyFile::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.params, test_params());
+        assert!(loaded.unlock("secret").is_ok());
+    }
+
+    #[test]
+    fn test_rejects_newer_format_version() {
+        let (_, cipher) = KeyFile::create("secret", test_params()).unwrap();
+        let encrypted = cipher.encrypt("hello").unwrap();
+        let newer = encrypted.replace("\"version\": 1", "\"version\": 2");
+
+        let error = cipher.decrypt(&newer).unwrap_err();
+        assert!(error.to_string().contains("newer"));
+    }
+
+    #[test]
+    fn test_tampered_ciphertext_fails() {
+        let (_, cipher) = KeyFile::create("secret", test_params()).unwrap();
+        let mut file = cipher.seal("hello").unwrap();
+        file.ciphertext = STANDARD.encode(b"not the real ciphertext");
+
+        assert!(cipher.open(&file).is_err());
+    }
+
+    #[test]
+    fn test_empty_passphrase_rejected() {
+        assert!(KeyFile::create("", test_params()).is_err());
+    }
+}
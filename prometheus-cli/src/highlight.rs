@@ -0,0 +1,348 @@
+use crossterm::style::{style, Attribute, Color, Stylize};
+use std::sync::OnceLock;
+use syntect::highlighting::{FontStyle, HighlightIterator, HighlightState, Highlighter, Style, Theme, ThemeSet};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
+
+/// Theme used when none is configured or the configured one doesn't exist
+pub const DEFAULT_CODE_THEME: &str = "base16-ocean.dark";
+
+/// Colors the terminal can display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSupport {
+    /// Plain text, for `NO_COLOR` and dumb terminals
+    NoColor,
+    /// The 16 standard ANSI colors
+    Ansi16,
+    /// The xterm 256-color palette
+    Ansi256,
+    /// 24-bit RGB
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Detect color support from the environment
+    ///
+    /// A non-empty `NO_COLOR` disables colors (see no-color.org), `COLORTERM`
+    /// announces truecolor, and `TERM` tells 256-color and dumb terminals apart.
+    pub fn detect() -> Self {
+        Self::from_env(|name| std::env::var(name).ok())
+    }
+
+    fn from_env(var: impl Fn(&str) -> Option<String>) -> Self {
+        if var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+            return Self::NoColor;
+        }
+        let colorterm = var("COLORTERM").unwrap_or_default().to_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return Self::TrueColor;
+        }
+        match var("TERM").unwrap_or_default().as_str() {
+            "dumb" => Self::NoColor,
+            term if term.contains("256color") => Self::Ansi256,
+            _ => Self::Ansi16,
+        }
+    }
+
+    /// Convert an RGB color to the closest color the terminal can display
+    fn convert(self, r: u8, g: u8, b: u8) -> Option<Color> {
+        match self {
+            Self::NoColor => None,
+            Self::Ansi16 => Some(ansi16(r, g, b)),
+            Self::Ansi256 => Some(Color::AnsiValue(ansi256(r, g, b))),
+            Self::TrueColor => Some(Color::Rgb { r, g, b }),
+        }
+    }
+}
+
+/// How code blocks are highlighted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeStyle {
+    /// Name of a syntect theme, see `theme_names`
+    pub theme: String,
+    /// Number the lines of code blocks
+    pub line_numbers: bool,
+    pub colors: ColorSupport,
+}
+
+impl CodeStyle {
+    /// Create a style for the terminal's detected color support
+    pub fn new(theme: &str, line_numbers: bool) -> Self {
+        Self {
+            theme: theme.to_string(),
+            line_numbers,
+            colors: ColorSupport::detect(),
+        }
+    }
+}
+
+impl Default for CodeStyle {
+    fn default() -> Self {
+        Self::new(DEFAULT_CODE_THEME, false)
+    }
+}
+
+fn syntaxes() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn themes() -> &'static ThemeSet {
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+    THEMES.get_or_init(ThemeSet::load_defaults)
+}
+
+/// Names of the available code themes, sorted
+pub fn theme_names() -> Vec<&'static str> {
+    let mut names: Vec<&str> = themes().themes.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    names
+}
+
+/// Look up a theme name, ignoring case, returning its exact spelling
+pub fn find_theme(name: &str) -> Option<&'static str> {
+    theme_names().into_iter().find(|theme| theme.eq_ignore_ascii_case(name))
+}
+
+/// Highlighting state of one code block, carried from line to line
+#[derive(Debug, Clone)]
+pub struct CodeBlockState {
+    parse: ParseState,
+    highlight: HighlightState,
+    /// Number of lines highlighted so far
+    line: usize,
+}
+
+/// Language-aware syntax highlighter for fenced code blocks
+#[derive(Debug, Clone)]
+pub struct CodeHighlighter {
+    theme: &'static Theme,
+    line_numbers: bool,
+    colors: ColorSupport,
+}
+
+impl CodeHighlighter {
+    /// Create a highlighter, falling back to the default theme for unknown names
+    pub fn new(code_style: &CodeStyle) -> Self {
+        let name = find_theme(&code_style.theme).unwrap_or_else(|| {
+            log::warn!("Unknown code theme '{}', using {}", code_style.theme, DEFAULT_CODE_THEME);
+            DEFAULT_CODE_THEME
+        });
+        Self {
+            theme: &themes().themes[name],
+            line_numbers: code_style.line_numbers,
+            colors: code_style.colors,
+        }
+    }
+
+    /// Start highlighting a code block
+    ///
+    /// # Arguments
+    /// * `language` - Language of the fence, e.g. "rust" or "py"; plain text when unknown
+    pub fn start(&self, language: Option<&str>) -> CodeBlockState {
+        let highlighter = Highlighter::new(self.theme);
+        CodeBlockState {
+            parse: ParseState::new(find_syntax(language)),
+            highlight: HighlightState::new(&highlighter, ScopeStack::new()),
+            line: 0,
+        }
+    }
+
+    /// Highlight the next line of a code block, returning it with a trailing newline
+    pub fn highlight_line(&self, state: &mut CodeBlockState, line: &str) -> String {
+        state.line += 1;
+        let mut output = String::new();
+        if self.line_numbers {
+            output.push_str(&self.gutter(state.line));
+        }
+        if self.colors == ColorSupport::NoColor {
+            output.push_str(line);
+            output.push('\n');
+            return output;
+        }
+
+        // The newline syntaxes expect every line to end in a newline
+        let line = format!("{}\n", line);
+        let ops = match state.parse.parse_line(&line, syntaxes()) {
+            Ok(ops) => ops,
+            Err(e) => {
+                log::debug!("Failed to highlight code: {}", e);
+                output.push_str(&line);
+                return output;
+            }
+        };
+        let highlighter = Highlighter::new(self.theme);
+        for (region_style, text) in HighlightIterator::new(&mut state.highlight, &ops, &line, &highlighter) {
+            let text = text.trim_end_matches('\n');
+            if !text.is_empty() {
+                output.push_str(&self.paint(region_style, text));
+            }
+        }
+        output.push('\n');
+        output
+    }
+
+    /// Highlight a whole code block
+    pub fn highlight(&self, code: &str, language: Option<&str>) -> String {
+        let mut state = self.start(language);
+        code.lines().map(|line| self.highlight_line(&mut state, line)).collect()
+    }
+
+    fn paint(&self, region_style: Style, text: &str) -> String {
+        let foreground = region_style.foreground;
+        let Some(color) = self.colors.convert(foreground.r, foreground.g, foreground.b) else {
+            return text.to_string();
+        };
+        let mut content = style(text).with(color);
+        if region_style.font_style.contains(FontStyle::BOLD) {
+            content = content.attribute(Attribute::Bold);
+        }
+        if region_style.font_style.contains(FontStyle::ITALIC) {
+            content = content.attribute(Attribute::Italic);
+        }
+        if region_style.font_style.contains(FontStyle::UNDERLINE) {
+            content = content.attribute(Attribute::Underlined);
+        }
+        content.to_string()
+    }
+
+    fn gutter(&self, line: usize) -> String {
+        let gutter = format!("{:>4} │ ", line);
+        match self.colors {
+            ColorSupport::NoColor => gutter,
+            _ => style(gutter).with(Color::DarkGrey).to_string(),
+        }
+    }
+}
+
+/// Find the grammar for a fence language by name or file extension
+fn find_syntax(language: Option<&str>) -> &'static SyntaxReference {
+    let syntaxes = syntaxes();
+    language
+        .and_then(|language| {
+            syntaxes
+                .find_syntax_by_token(language)
+                .or_else(|| syntaxes.find_syntax_by_token(&language.to_lowercase()))
+        })
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text())
+}
+
+/// Closest color of the xterm 256-color palette
+fn ansi256(r: u8, g: u8, b: u8) -> u8 {
+    if r == g && g == b {
+        // The grayscale ramp is finer than the gray levels of the color cube
+        return match r {
+            0..=7 => 16,
+            249..=255 => 231,
+            _ => 232 + ((u16::from(r) - 8) * 24 / 241) as u8,
+        };
+    }
+    let level = |value: u8| match value {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => (value - 35) / 40,
+    };
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+/// Closest of the 16 standard ANSI colors, using the xterm defaults
+fn ansi16(r: u8, g: u8, b: u8) -> Color {
+    const PALETTE: [(Color, (i32, i32, i32)); 16] = [
+        (Color::Black, (0, 0, 0)),
+        (Color::DarkRed, (205, 0, 0)),
+        (Color::DarkGreen, (0, 205, 0)),
+        (Color::DarkYellow, (205, 205, 0)),
+        (Color::DarkBlue, (0, 0, 238)),
+        (Color::DarkMagenta, (205, 0, 205)),
+        (Color::DarkCyan, (0, 205, 205)),
+        (Color::Grey, (229, 229, 229)),
+        (Color::DarkGrey, (127, 127, 127)),
+        (Color::Red, (255, 0, 0)),
+        (Color::Green, (0, 255, 0)),
+        (Color::Yellow, (255, 255, 0)),
+        (Color::Blue, (92, 92, 255)),
+        (Color::Magenta, (255, 0, 255)),
+        (Color::Cyan, (0, 255, 255)),
+        (Color::White, (255, 255, 255)),
+    ];
+    let (r, g, b) = (i32::from(r), i32::from(g), i32::from(b));
+    PALETTE
+        .iter()
+        .min_by_key(|(_, (pr, pg, pb))| (r - pr).pow(2) + (g - pg).pow(2) + (b - pb).pow(2))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code_style(colors: ColorSupport, line_numbers: bool) -> CodeStyle {
+        CodeStyle {
+            theme: DEFAULT_CODE_THEME.to_string(),
+            line_numbers,
+            colors,
+        }
+    }
+
+    #[test]
+    fn test_detect_color_support() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| vars.iter().find(|(n, _)| *n == name).map(|(_, v)| v.to_string())
+        };
+        assert_eq!(ColorSupport::from_env(env(&[("NO_COLOR", "1"), ("COLORTERM", "truecolor")])), ColorSupport::NoColor);
+        assert_eq!(ColorSupport::from_env(env(&[("NO_COLOR", ""), ("COLORTERM", "24bit")])), ColorSupport::TrueColor);
+        assert_eq!(ColorSupport::from_env(env(&[("TERM", "xterm-256color")])), ColorSupport::Ansi256);
+        assert_eq!(ColorSupport::from_env(env(&[("TERM", "dumb")])), ColorSupport::NoColor);
+        assert_eq!(ColorSupport::from_env(env(&[("TERM", "xterm")])), ColorSupport::Ansi16);
+    }
+
+    #[test]
+    fn test_color_fallbacks() {
+        assert_eq!(ansi256(255, 0, 0), 196);
+        assert_eq!(ansi256(0, 0, 0), 16);
+        assert_eq!(ansi256(128, 128, 128), 243);
+        assert_eq!(ansi16(250, 10, 10), Color::Red);
+        assert_eq!(ansi16(20, 20, 20), Color::Black);
+        assert_eq!(ColorSupport::NoColor.convert(1, 2, 3), None);
+    }
+
+    #[test]
+    fn test_highlight_by_language() {
+        let highlighter = CodeHighlighter::new(&code_style(ColorSupport::TrueColor, false));
+        let rust = highlighter.highlight("fn main() {}", Some("rust"));
+        let plain = highlighter.highlight("fn main() {}", None);
+        assert!(rust.contains("\x1b[38;2;"));
+        assert_ne!(rust, plain);
+        assert!(rust.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_highlight_without_color() {
+        let highlighter = CodeHighlighter::new(&code_style(ColorSupport::NoColor, true));
+        let output = highlighter.highlight("let a = 1;\nlet b = 2;", Some("rs"));
+        assert_eq!(output, "   1 │ let a = 1;\n   2 │ let b = 2;\n");
+    }
+
+    #[test]
+    fn test_state_carries_across_lines() {
+        let highlighter = CodeHighlighter::new(&code_style(ColorSupport::Ansi256, false));
+        let whole = highlighter.highlight("/* a\nb */", Some("c"));
+
+        let mut state = highlighter.start(Some("c"));
+        let first = highlighter.highlight_line(&mut state, "/* a");
+        let second = highlighter.highlight_line(&mut state, "b */");
+        assert_eq!(whole, first + &second);
+    }
+
+    #[test]
+    fn test_themes() {
+        assert!(theme_names().contains(&DEFAULT_CODE_THEME));
+        assert_eq!(find_theme("SOLARIZED (DARK)"), Some("Solarized (dark)"));
+        assert_eq!(find_theme("no-such-theme"), None);
+
+        // Unknown themes fall back to the default instead of failing
+        let mut code_style = code_style(ColorSupport::TrueColor, false);
+        code_style.theme = "no-such-theme".to_string();
+        assert!(!CodeHighlighter::new(&code_style).highlight("x", Some("rust")).is_empty());
+    }
+}
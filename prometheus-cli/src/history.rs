@@ -0,0 +1,102 @@
+use crate::conversation::ConversationMetadata;
+
+/// Filters for `prometheus-cli history`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistoryFilter {
+    /// Only conversations carrying all of these tags
+    pub tags: Vec<String>,
+    /// Only conversations in this folder or one of its subfolders
+    pub folder: Option<String>,
+    /// Only pinned conversations
+    pub pinned_only: bool,
+}
+
+impl HistoryFilter {
+    pub fn matches(&self, conversation: &ConversationMetadata) -> bool {
+        (!self.pinned_only || conversation.pinned)
+            && self.tags.iter().all(|tag| conversation.has_tag(tag))
+            && self
+                .folder
+                .as_deref()
+                .map(|folder| conversation.in_folder(folder))
+                .unwrap_or(true)
+    }
+}
+
+/// Format conversations as one line each, in the order given
+///
+/// Each line shows a pin marker, the first 8 characters of the ID, the last
+/// update date, the name, and the folder and tags if set.
+pub fn format_history(conversations: &[ConversationMetadata]) -> String {
+    let mut out = String::new();
+    for conversation in conversations {
+        let marker = if conversation.pinned { '*' } else { ' ' };
+        let short_id: String = conversation.id.chars().take(8).collect();
+        let date = conversation.updated_at.get(..10).unwrap_or(&conversation.updated_at);
+
+        out.push_str(&format!("{} {}  {}  {}", marker, short_id, date, conversation.name));
+        if let Some(folder) = &conversation.folder {
+            out.push_str(&format!("  [{}]", folder));
+        }
+        for tag in &conversation.tags {
+            out.push_str(&format!(" #{}", tag));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(id: &str, tags: &[&str], folder: Option<&str>, pinned: bool) -> ConversationMetadata {
+        ConversationMetadata {
+            id: id.to_string(),
+            name: format!("Chat {}", id),
+            preview: String::new(),
+            updated_at: "2024-05-01T10:00:00+00:00".to_string(),
+            message_count: 2,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            pinned,
+            folder: folder.map(|f| f.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_filter_by_tags_requires_all() {
+        let filter = HistoryFilter {
+            tags: vec!["rust".to_string(), "#Async".to_string()],
+            ..Default::default()
+        };
+
+        assert!(filter.matches(&metadata("a", &["async", "rust"], None, false)));
+        assert!(!filter.matches(&metadata("b", &["rust"], None, false)));
+    }
+
+    #[test]
+    fn test_filter_by_folder_and_pinned() {
+        let filter = HistoryFilter {
+            folder: Some("work".to_string()),
+            pinned_only: true,
+            ..Default::default()
+        };
+
+        assert!(filter.matches(&metadata("a", &[], Some("work/reviews"), true)));
+        assert!(!filter.matches(&metadata("b", &[], Some("work"), false)));
+        assert!(!filter.matches(&metadata("c", &[], None, true)));
+        assert!(HistoryFilter::default().matches(&metadata("d", &[], None, false)));
+    }
+
+    #[test]
+    fn test_format_history() {
+        let output = format_history(&[
+            metadata("0123456789", &["rust"], Some("work"), true),
+            metadata("abc", &[], None, false),
+        ]);
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "* 01234567  2024-05-01  Chat 0123456789  [work] #rust");
+        assert_eq!(lines[1], "  abc  2024-05-01  Chat abc");
+    }
+}
@@ -7,20 +7,14 @@ pub struct InputProcessor;
 
 impl InputProcessor {
     /// Process and combine all input sources into a final prompt
+    ///
+    /// The system prompt is not part of it; it is sent separately with the request.
     pub fn build_prompt(
         base_prompt: String,
         file_paths: &[String],
-        system_prompt: Option<&str>,
     ) -> Result<String> {
         let mut final_prompt = String::new();
         
-        // Add system prompt if provided
-        if let Some(system) = system_prompt {
-            final_prompt.push_str("System: ");
-            final_prompt.push_str(system);
-            final_prompt.push_str("\n\n");
-        }
-        
         // Add file contents in the order specified
         for file_path in file_paths {
             let content = Self::read_file_safely(file_path)?;
@@ -226,7 +220,6 @@ mod tests {
         let result = InputProcessor::build_prompt(
             "Test prompt".to_string(),
             &file_paths,
-            None,
         );
         
         match result {
@@ -287,24 +280,10 @@ mod tests {
     }
 
     #[test]
-    fn test_build_prompt_with_system_prompt() {
+    fn test_build_prompt_without_files() {
         let result = InputProcessor::build_prompt(
             "Main prompt".to_string(),
             &[],
-            Some("You are a helpful assistant"),
-        ).unwrap();
-        
-        assert!(result.contains("System: You are a helpful assistant"));
-        assert!(result.contains("Main prompt"));
-        assert!(result.starts_with("System:"));
-    }
-
-    #[test]
-    fn test_build_prompt_without_system_prompt() {
-        let result = InputProcessor::build_prompt(
-            "Main prompt".to_string(),
-            &[],
-            None,
         ).unwrap();
         
         assert_eq!(result, "Main prompt");
@@ -319,7 +298,6 @@ mod tests {
         let result = InputProcessor::build_prompt(
             "Main prompt".to_string(),
             &[file_path.clone()],
-            None,
         )?;
         
         assert!(result.contains(&format!("File: {}", file_path)));
@@ -340,7 +318,6 @@ mod tests {
         let result = InputProcessor::build_prompt(
             "Main prompt".to_string(),
             &[file_path1.clone(), file_path2.clone()],
-            None,
         )?;
         
         // Check that both files are included
@@ -365,21 +342,17 @@ mod tests {
         let result = InputProcessor::build_prompt(
             "Main prompt".to_string(),
             &[file_path.clone()],
-            Some("System message"),
         )?;
         
         // Check all components are present and in correct order
-        assert!(result.starts_with("System: System message"));
-        assert!(result.contains(&format!("File: {}", file_path)));
+        assert!(result.starts_with(&format!("File: {}", file_path)));
         assert!(result.contains("File content"));
         assert!(result.ends_with("Main prompt"));
         
         // Check ordering
-        let system_pos = result.find("System: System message").unwrap();
         let file_pos = result.find(&format!("File: {}", file_path)).unwrap();
         let prompt_pos = result.find("Main prompt").unwrap();
         
-        assert!(system_pos < file_pos);
         assert!(file_pos < prompt_pos);
         
         Ok(())
@@ -554,7 +527,6 @@ mod tests {
         let result = InputProcessor::build_prompt(
             "Prompt".to_string(),
             &[empty_path.clone()],
-            None,
         )?;
         
         assert!(result.contains(&format!("File: {}", empty_path)));
@@ -567,7 +539,6 @@ mod tests {
         let result = InputProcessor::build_prompt(
             "Prompt".to_string(),
             &[whitespace_path.clone()],
-            None,
         )?;
         
         assert!(result.contains("   \n\t\n   "));
@@ -584,7 +555,6 @@ mod tests {
         let result = InputProcessor::build_prompt(
             "Prompt".to_string(),
             &[file_path],
-            None,
         )?;
         
         assert!(result.contains(special_content));
@@ -611,7 +581,6 @@ mod tests {
         let result = InputProcessor::build_prompt(
             "Final prompt".to_string(),
             &file_paths,
-            None,
         )?;
         
         // Verify each file appears in order
@@ -954,7 +923,6 @@ mod tests {
         let result = InputProcessor::build_prompt(
             "Main prompt".to_string(),
             &file_paths,
-            Some("System prompt"),
         );
         assert!(result.is_ok());
 
@@ -1026,14 +994,13 @@ mod tests {
         let result = InputProcessor::build_prompt(
             "User prompt".to_string(),
             &paths,
-            Some("System instruction"),
         )?;
         
         // Verify structure and ordering
         let lines: Vec<&str> = result.lines().collect();
         
-        // Should start with system prompt
-        assert!(lines[0].starts_with("System: System instruction"));
+        // Should start with the first file
+        assert!(lines[0].starts_with("File: "));
         
         // Should contain file markers and content
         assert!(result.contains("File: "));
@@ -4,17 +4,28 @@
 pub mod app;
 pub mod backend;
 pub mod commands;
+pub mod completion;
 pub mod config;
 pub mod conversation;
+pub mod encryption;
 pub mod error;
 pub mod exit_codes;
+pub mod highlight;
+pub mod history;
 pub mod input;
+pub mod line_editor;
 pub mod markdown_renderer;
 pub mod mode;
 pub mod non_interactive;
 pub mod ollama_service;
 pub mod output;
+pub mod retention;
+pub mod search;
+pub mod semantic;
 pub mod streaming;
+pub mod streaming_markdown;
 pub mod terminal;
+pub use prometheus_chat::thinking;
+pub mod titling;
 pub mod update;
 pub mod url_validator;
\ No newline at end of file
@@ -0,0 +1,344 @@
+//! Line editing for the interactive prompt
+//!
+//! Wraps rustyline with emacs or vi keybindings, a history file that persists
+//! across sessions, reverse incremental search (Ctrl-R) and bracketed paste.
+//! Reads run on a blocking thread so the caller can keep waiting on signals.
+//!
+//! A prompt can span several lines: Alt-Enter or Ctrl-J inserts a newline,
+//! pasted text is inserted without being submitted, and a line starting with
+//! `"""` opens a block that Enter keeps extending until it is closed.
+//!
+//! Tab completes commands and their arguments (see [`crate::completion`]),
+//! and the first completion of the word being typed is shown as a dimmed hint.
+
+use anyhow::{Context, Result};
+use crossterm::style::Stylize;
+use rustyline::completion::{Completer, Pair};
+use rustyline::config::{CompletionType, Config, EditMode};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Cmd, Editor, Helper, KeyCode, KeyEvent, Modifiers};
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::completion::{self, CompletionContext};
+use crate::config::UISettings;
+
+/// Opens and closes a multi-line block at the prompt
+pub const BLOCK_DELIMITER: &str = "\"\"\"";
+
+type PromptEditor = Editor<PromptHelper, FileHistory>;
+
+/// Result of reading one line at the prompt
+#[derive(Debug, Clone, PartialEq)]
+pub enum Input {
+    /// The submitted text, without the delimiters of a `"""` block
+    Line(String),
+    /// Ctrl-C
+    Interrupted,
+    /// Ctrl-D on an empty line, or the end of piped input
+    Eof,
+}
+
+/// Prompt editor shared between the app and the thread reading input
+#[derive(Clone)]
+pub struct LineEditor {
+    editor: Arc<Mutex<PromptEditor>>,
+    completions: Arc<Mutex<CompletionContext>>,
+    history_path: Option<PathBuf>,
+    saved_mode: Option<TerminalMode>,
+}
+
+impl LineEditor {
+    /// Create an editor configured from the `[ui]` settings
+    ///
+    /// # Arguments
+    /// * `settings` - Edit mode and history size
+    /// * `history_path` - File the history is loaded from and appended to; `None` keeps it in memory
+    pub fn new(settings: &UISettings, history_path: Option<PathBuf>) -> Result<Self> {
+        let edit_mode = parse_edit_mode(&settings.edit_mode).unwrap_or_else(|| {
+            log::warn!("Unknown edit mode '{}', using emacs", settings.edit_mode);
+            EditMode::Emacs
+        });
+        let config = Config::builder()
+            .edit_mode(edit_mode)
+            .max_history_size(settings.input_history_size)
+            .context("Invalid input history size")?
+            .history_ignore_dups(true)
+            .context("Invalid input history settings")?
+            .history_ignore_space(true)
+            .bracketed_paste(true)
+            .completion_type(CompletionType::List)
+            .build();
+
+        let mut editor = PromptEditor::with_config(config).context("Failed to create line editor")?;
+        let completions = Arc::new(Mutex::new(CompletionContext::default()));
+        editor.set_helper(Some(PromptHelper {
+            completions: Arc::clone(&completions),
+        }));
+        editor.bind_sequence(KeyEvent(KeyCode::Enter, Modifiers::ALT), Cmd::Newline);
+        editor.bind_sequence(KeyEvent::ctrl('J'), Cmd::Newline);
+        if let Some(path) = history_path.as_ref().filter(|path| path.exists()) {
+            if let Err(e) = editor.load_history(path) {
+                log::warn!("Failed to load input history from {}: {}", path.display(), e);
+            }
+        }
+
+        Ok(Self {
+            editor: Arc::new(Mutex::new(editor)),
+            completions,
+            history_path,
+            saved_mode: TerminalMode::save(),
+        })
+    }
+
+    /// Read a line, adding it to the history
+    ///
+    /// Dropping the returned future leaves the read running; call
+    /// [`LineEditor::abandon`] before exiting so the terminal is usable again.
+    pub async fn read_line(&self, prompt: &str) -> Result<Input> {
+        let editor = self.clone();
+        let prompt = prompt.to_string();
+        tokio::task::spawn_blocking(move || editor.read_line_blocking(&prompt))
+            .await
+            .context("Input task failed")?
+    }
+
+    /// Change the values completed after commands
+    ///
+    /// Can be called while a read is pending, e.g. from a background task.
+    pub fn update_completions(&self, update: impl FnOnce(&mut CompletionContext)) {
+        match self.completions.lock() {
+            Ok(mut completions) => update(&mut completions),
+            Err(e) => log::warn!("Failed to acquire completion lock: {}", e),
+        }
+    }
+
+    /// Restore the terminal settings a pending read switched away from
+    pub fn abandon(&self) {
+        if let Some(mode) = &self.saved_mode {
+            mode.restore();
+        }
+    }
+
+    fn read_line_blocking(&self, prompt: &str) -> Result<Input> {
+        let mut editor = self.editor.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire line editor lock: {}", e))?;
+        match editor.readline(prompt) {
+            Ok(line) => {
+                // Kept with its delimiters so recalling it reopens the block
+                self.remember(&mut editor, &line);
+                Ok(Input::Line(unwrap_block(&line)))
+            }
+            Err(ReadlineError::Interrupted) => Ok(Input::Interrupted),
+            Err(ReadlineError::Eof) => Ok(Input::Eof),
+            Err(e) => Err(e).context("Failed to read input"),
+        }
+    }
+
+    /// Add a line to the history and append it to the history file
+    ///
+    /// Blank lines, repeats of the previous line and lines starting with a
+    /// space are not kept.
+    fn remember(&self, editor: &mut PromptEditor, line: &str) {
+        if line.trim().is_empty() {
+            return;
+        }
+        match editor.add_history_entry(line) {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(e) => {
+                log::warn!("Failed to add input history entry: {}", e);
+                return;
+            }
+        }
+
+        let Some(path) = &self.history_path else {
+            return;
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                log::warn!("Failed to create {}: {}", parent.display(), e);
+                return;
+            }
+        }
+        if let Err(e) = editor.append_history(path) {
+            log::warn!("Failed to save input history to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Whether `input` starts a `"""` block that has not been closed yet
+fn is_open_block(input: &str) -> bool {
+    let input = input.trim();
+    input.starts_with(BLOCK_DELIMITER)
+        && (input.len() < 2 * BLOCK_DELIMITER.len() || !input.ends_with(BLOCK_DELIMITER))
+}
+
+/// Strip the delimiters of a closed `"""` block, keeping the indentation inside it
+///
+/// Input that is not a block is returned unchanged.
+pub fn unwrap_block(input: &str) -> String {
+    let trimmed = input.trim();
+    if !trimmed.starts_with(BLOCK_DELIMITER) || is_open_block(trimmed) {
+        return input.to_string();
+    }
+    let inner = &trimmed[BLOCK_DELIMITER.len()..trimmed.len() - BLOCK_DELIMITER.len()];
+    inner
+        .trim_start_matches([' ', '\t'])
+        .trim_start_matches(['\r', '\n'])
+        .trim_end()
+        .to_string()
+}
+
+/// Completes and hints input, and keeps Enter inside an open `"""` block from submitting it
+struct PromptHelper {
+    completions: Arc<Mutex<CompletionContext>>,
+}
+
+impl Validator for PromptHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        if is_open_block(ctx.input()) {
+            Ok(ValidationResult::Incomplete)
+        } else {
+            Ok(ValidationResult::Valid(None))
+        }
+    }
+}
+
+impl Completer for PromptHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        match self.completions.lock() {
+            Ok(completions) => completion::complete(line, pos, &completions),
+            Err(_) => Ok((pos, Vec::new())),
+        }
+    }
+}
+
+impl Hinter for PromptHelper {
+    type Hint = String;
+
+    fn hint(&self, line: &str, pos: usize, _ctx: &rustyline::Context<'_>) -> Option<String> {
+        let completions = self.completions.lock().ok()?;
+        completion::hint(line, pos, &completions)
+    }
+}
+
+impl Highlighter for PromptHelper {
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(hint.dim().to_string())
+    }
+}
+
+impl Helper for PromptHelper {}
+
+/// Parse an edit mode name ("emacs" or "vi")
+pub fn parse_edit_mode(name: &str) -> Option<EditMode> {
+    match name.trim().to_lowercase().as_str() {
+        "emacs" => Some(EditMode::Emacs),
+        "vi" | "vim" => Some(EditMode::Vi),
+        _ => None,
+    }
+}
+
+/// Terminal settings of stdin, captured before the editor switches to raw mode
+#[derive(Clone, Copy)]
+struct TerminalMode(libc::termios);
+
+impl TerminalMode {
+    /// Capture the current settings; `None` when stdin is not a terminal
+    fn save() -> Option<Self> {
+        let mut termios = std::mem::MaybeUninit::<libc::termios>::uninit();
+        let result = unsafe { libc::tcgetattr(libc::STDIN_FILENO, termios.as_mut_ptr()) };
+        if result != 0 {
+            return None;
+        }
+        Some(Self(unsafe { termios.assume_init() }))
+    }
+
+    fn restore(&self) {
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustyline::history::History;
+
+    fn settings() -> UISettings {
+        crate::config::AppConfig::default().ui
+    }
+
+    #[test]
+    fn test_parse_edit_mode() {
+        assert_eq!(parse_edit_mode("emacs"), Some(EditMode::Emacs));
+        assert_eq!(parse_edit_mode(" Vi "), Some(EditMode::Vi));
+        assert_eq!(parse_edit_mode("vim"), Some(EditMode::Vi));
+        assert_eq!(parse_edit_mode("nano"), None);
+    }
+
+    #[test]
+    fn test_block_is_open_until_closed() {
+        assert!(is_open_block("\"\"\""));
+        assert!(is_open_block("\"\"\"\nTraceback (most recent call last):"));
+        assert!(!is_open_block("\"\"\"\nTraceback\n\"\"\""));
+        assert!(!is_open_block("\"\"\"one line\"\"\""));
+        assert!(!is_open_block("What does \"\"\" mean in Python?"));
+    }
+
+    #[test]
+    fn test_unwrap_block() {
+        assert_eq!(
+            unwrap_block("\"\"\"\n    indented\nline two\n\"\"\""),
+            "    indented\nline two"
+        );
+        assert_eq!(unwrap_block("\"\"\"one line\"\"\""), "one line");
+        assert_eq!(unwrap_block("\"\"\"\n\"\"\""), "");
+        assert_eq!(unwrap_block("plain\nprompt"), "plain\nprompt");
+    }
+
+    #[test]
+    fn test_history_persists_across_editors() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("history").join("input_history.txt");
+
+        let editor = LineEditor::new(&settings(), Some(path.clone())).unwrap();
+        {
+            let mut prompt_editor = editor.editor.lock().unwrap();
+            editor.remember(&mut prompt_editor, "What is Rust?");
+            editor.remember(&mut prompt_editor, "What is Rust?");
+            editor.remember(&mut prompt_editor, " my password is hunter2");
+            editor.remember(&mut prompt_editor, "   ");
+            editor.remember(&mut prompt_editor, "/models");
+        }
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("What is Rust?"));
+        assert!(!content.contains("hunter2"));
+
+        let reloaded = LineEditor::new(&settings(), Some(path)).unwrap();
+        assert_eq!(reloaded.editor.lock().unwrap().history().len(), 2);
+    }
+
+    #[test]
+    fn test_history_without_file_stays_in_memory() {
+        let editor = LineEditor::new(&settings(), None).unwrap();
+        let mut prompt_editor = editor.editor.lock().unwrap();
+        editor.remember(&mut prompt_editor, "Hello");
+        assert_eq!(prompt_editor.history().len(), 1);
+    }
+}
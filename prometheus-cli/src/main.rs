@@ -1,31 +1,50 @@
 use anyhow::{Context, Result};
-use clap::{Parser, CommandFactory};
+use clap::{Parser, CommandFactory, Subcommand};
 use clap_complete::{generate, Shell};
+use prometheus_chat::thinking;
+use prometheus_personas::PersonaManager;
+use prometheus_search::{language_from_name, Language};
 
 mod app;
 mod backend;
 mod commands;
+mod completion;
 mod config;
 mod conversation;
+mod encryption;
 mod error;
 mod exit_codes;
+mod highlight;
+mod history;
 mod input;
+mod line_editor;
 mod markdown_renderer;
 mod mode;
 mod non_interactive;
 mod ollama_service;
 mod output;
+mod retention;
+mod search;
+mod semantic;
 mod streaming;
+mod streaming_markdown;
 mod terminal;
+mod titling;
 mod update;
 mod url_validator;
 
 use app::CliApp;
+use backend::BackendClient;
 use config::AppConfig;
+use conversation::ConversationManager;
+use encryption::KdfParams;
 use exit_codes::{ExitCodes, exit_with_error};
+use history::{format_history, HistoryFilter};
 use input::InputProcessor;
 use mode::{ExecutionMode, ModeDetector};
 use non_interactive::NonInteractiveHandler;
+use retention::{format_prune_report, RetentionPolicy};
+use search::{format_search_results, SearchMode, SearchOptions};
 use url_validator::UrlValidator;
 
 /// Prometheus CLI - Terminal-based AI chat interface
@@ -70,6 +89,9 @@ use url_validator::UrlValidator;
 ///     # JSON output for scripts
 ///     prometheus-cli --json --quiet "Generate a UUID"
 /// 
+///     # Save only the generated code
+///     prometheus-cli --extract-code "Write a bash script that backs up ~/notes" > backup.sh
+/// 
 ///     # Custom model and parameters with secure connection
 ///     prometheus-cli --url https://api.example.com:8080 --model codellama --temperature 0.3 "Write a Python function"
 /// 
@@ -78,12 +100,15 @@ use url_validator::UrlValidator;
 #[derive(Parser, Debug)]
 #[command(name = "prometheus-cli")]
 #[command(author, version, about)]
+#[command(args_conflicts_with_subcommands = true)]
 #[command(long_about = "Prometheus CLI provides both interactive and non-interactive modes for AI chat.\n\nInteractive mode starts a REPL session for ongoing conversations.\nNon-interactive mode processes a single prompt and exits, perfect for scripts and automation.")]
 struct Args {
     /// Prompt to process (enables non-interactive mode)
     /// 
     /// When provided, the CLI processes this prompt and exits instead of starting
     /// an interactive session. Can be combined with stdin input and file contents.
+    /// A prompt that is a subcommand name (`history`, `search`) must follow `--`
+    /// or another option to be sent as a prompt.
     #[arg(value_name = "PROMPT", help = "Prompt to process (enables non-interactive mode)")]
     prompt: Option<String>,
 
@@ -128,11 +153,19 @@ struct Args {
     /// System prompt to use
     /// 
     /// Set a system prompt that provides context or instructions to the AI.
-    /// This is prepended to your main prompt.
+    /// It replaces the model's default system prompt.
     /// Example: --system "You are a helpful coding assistant"
     #[arg(long, value_name = "PROMPT", help = "System prompt to use")]
     system: Option<String>,
 
+    /// Persona to use
+    /// 
+    /// Use the system prompt of a persona shared with the desktop app. Personas
+    /// are listed with /personas in an interactive session.
+    /// Example: --persona code-reviewer
+    #[arg(long, value_name = "ID", conflicts_with = "system", help = "Persona to use")]
+    persona: Option<String>,
+
     /// Temperature for generation (0.0-2.0)
     /// 
     /// Control randomness in responses. Lower values (0.1-0.7) are more focused,
@@ -182,12 +215,107 @@ struct Args {
     #[arg(long, help = "Save partial responses on interruption")]
     save_on_interrupt: bool,
 
+    /// Print only the code blocks of the response
+    /// 
+    /// Output the contents of each fenced code block, without the fences or any
+    /// surrounding prose, separated by blank lines. Useful for piping generated
+    /// code straight into a file or another tool.
+    #[arg(long, conflicts_with = "json", help = "Print only the code blocks of the response")]
+    extract_code: bool,
+
     /// Generate shell completions
     /// 
     /// Generate shell completion scripts for bash, zsh, fish, or PowerShell.
     /// Example: prometheus-cli --generate-completions zsh > ~/.zsh/completions/_prometheus-cli
     #[arg(long = "generate-completions", value_name = "SHELL", help = "Generate shell completions")]
     generate_completions: Option<Shell>,
+
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
+
+/// Subcommands for managing saved conversations
+#[derive(Subcommand, Debug)]
+enum CliCommand {
+    /// List saved conversations, pinned first
+    ///
+    /// Example: prometheus-cli history --tag rust --folder work
+    History {
+        #[command(subcommand)]
+        action: Option<HistoryAction>,
+
+        /// Only show conversations with this tag (repeat to require several)
+        #[arg(long = "tag", value_name = "TAG", action = clap::ArgAction::Append)]
+        tags: Vec<String>,
+
+        /// Only show conversations in this folder or its subfolders
+        #[arg(long, value_name = "PATH")]
+        folder: Option<String>,
+
+        /// Only show pinned conversations
+        #[arg(long)]
+        pinned: bool,
+    },
+    /// Search the text of saved conversations
+    ///
+    /// Example: prometheus-cli search --whole-word lifetimes
+    Search {
+        /// Words to search for
+        #[arg(value_name = "QUERY", required = true, num_args = 1..)]
+        query: Vec<String>,
+
+        /// Match case exactly
+        #[arg(short = 'c', long)]
+        case_sensitive: bool,
+
+        /// Only match whole words
+        #[arg(short = 'w', long)]
+        whole_word: bool,
+
+        /// Require accents to match ("cafe" no longer finds "café")
+        #[arg(short = 'a', long)]
+        match_accents: bool,
+
+        /// Tolerate typos (one for words up to 5 letters, two for longer words)
+        #[arg(short = 'f', long)]
+        fuzzy: bool,
+
+        /// Also match other forms of words in this language, e.g. "run" finds "running"
+        #[arg(long, value_name = "LANGUAGE", value_parser = parse_stem_language)]
+        stem: Option<Language>,
+
+        /// Find messages with a similar meaning, using embeddings from Ollama
+        #[arg(long, conflicts_with = "hybrid")]
+        semantic: bool,
+
+        /// Rank by both keywords and meaning
+        #[arg(long)]
+        hybrid: bool,
+    },
+}
+
+/// Actions on the saved conversation history
+#[derive(Subcommand, Debug)]
+enum HistoryAction {
+    /// Delete conversations outside the retention policy in the config
+    ///
+    /// Example: prometheus-cli history prune --dry-run
+    Prune {
+        /// Show what would be deleted without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Encrypt saved conversations with a passphrase
+    ///
+    /// The passphrase is read from PROMETHEUS_NEW_PASSPHRASE or prompted for.
+    Encrypt,
+    /// Change the passphrase of encrypted conversations
+    ///
+    /// The current passphrase is read from PROMETHEUS_PASSPHRASE and the new one
+    /// from PROMETHEUS_NEW_PASSPHRASE, or both are prompted for.
+    Rekey,
+    /// Decrypt saved conversations and turn encryption off
+    Decrypt,
 }
 
 #[tokio::main]
@@ -215,6 +343,40 @@ async fn main() -> Result<()> {
         }
     };
 
+    // Handle conversation management subcommands
+    match args.command {
+        Some(CliCommand::History { action, tags, folder, pinned }) => {
+            return match action {
+                Some(HistoryAction::Prune { dry_run }) => run_history_prune(&config, dry_run),
+                Some(HistoryAction::Encrypt) => run_history_encrypt(),
+                Some(HistoryAction::Rekey) => run_history_rekey(),
+                Some(HistoryAction::Decrypt) => run_history_decrypt(),
+                None => run_history(&HistoryFilter { tags, folder, pinned_only: pinned }),
+            };
+        }
+        Some(CliCommand::Search { query, case_sensitive, whole_word, match_accents, fuzzy, stem, semantic, hybrid }) => {
+            let mode = if semantic {
+                SearchMode::Semantic
+            } else if hybrid {
+                SearchMode::Hybrid
+            } else {
+                SearchMode::Keyword
+            };
+            let url = args.url.clone().unwrap_or_else(|| config.backend.ollama_url.clone());
+            return run_search(&config, &url, &SearchOptions {
+                text: query.join(" "),
+                mode,
+                case_sensitive,
+                whole_word,
+                match_accents,
+                fuzzy,
+                stemming: stem,
+            })
+            .await;
+        }
+        None => {}
+    }
+
     // Validate URL if provided via CLI argument (Requirements 4.1, 4.2)
     if let Some(ref url) = args.url {
         if let Err(e) = UrlValidator::validate_backend_url(url) {
@@ -257,6 +419,105 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// List saved conversations matching `filter`
+fn run_history(filter: &HistoryFilter) -> Result<()> {
+    let conversations: Vec<_> = ConversationManager::open()?
+        .list_conversations()
+        .context("Failed to load conversation history")?
+        .into_iter()
+        .filter(|c| filter.matches(c))
+        .collect();
+
+    if conversations.is_empty() {
+        println!("No conversations found");
+    } else {
+        print!("{}", format_history(&conversations));
+    }
+    Ok(())
+}
+
+/// Search saved conversations and print numbered, highlighted matches
+fn parse_stem_language(name: &str) -> Result<Language, String> {
+    language_from_name(name).ok_or_else(|| format!("unsupported stemming language '{}'", name))
+}
+
+async fn run_search(config: &AppConfig, url: &str, options: &SearchOptions) -> Result<()> {
+    let mut manager = ConversationManager::open()?;
+    manager.enable_search().context("Failed to load search index")?;
+    let client = BackendClient::new(url.to_string(), config.backend.timeout_seconds)?;
+    let hits = semantic::search_history(&manager, &client, &config.conversations.embedding_model, options)
+        .await
+        .context("Failed to search conversation history")?;
+
+    if hits.is_empty() {
+        println!("No matches for '{}'", options.text);
+        return Ok(());
+    }
+
+    print!("{}", format_search_results(&hits, !ModeDetector::is_stdout_redirected()));
+    println!("\nOpen a result in an interactive session with /search and /load <n>, or /load <id>");
+    Ok(())
+}
+
+/// Apply the configured retention policy to saved conversations
+fn run_history_prune(config: &AppConfig, dry_run: bool) -> Result<()> {
+    let policy = RetentionPolicy::from_config(config);
+    let report = retention::prune(&ConversationManager::open()?, &policy, dry_run)
+        .context("Failed to prune conversation history")?;
+
+    print!("{}", format_prune_report(&report));
+    Ok(())
+}
+
+/// Encrypt existing plaintext conversations with a new passphrase
+fn run_history_encrypt() -> Result<()> {
+    let manager = ConversationManager::new();
+    if manager.is_encrypted() {
+        anyhow::bail!("Conversation history is already encrypted; use `history rekey` to change the passphrase");
+    }
+
+    let passphrase = encryption::read_new_passphrase()?;
+    manager
+        .enable_encryption(&passphrase, KdfParams::default())
+        .context("Failed to encrypt conversation history")?;
+
+    println!("Conversation history encrypted. Set {} or enter the passphrase to unlock it.", encryption::PASSPHRASE_ENV);
+    Ok(())
+}
+
+/// Re-encrypt conversations with a new passphrase
+fn run_history_rekey() -> Result<()> {
+    let manager = ConversationManager::new();
+    if !manager.is_encrypted() {
+        anyhow::bail!("Conversation history is not encrypted; use `history encrypt` first");
+    }
+
+    let manager = manager.unlock(&encryption::read_passphrase("Current passphrase: ")?)?;
+    let passphrase = encryption::read_new_passphrase()?;
+    manager
+        .rekey(&passphrase, KdfParams::default())
+        .context("Failed to change the conversation history passphrase")?;
+
+    println!("Conversation history passphrase changed");
+    Ok(())
+}
+
+/// Decrypt conversations and remove the key file
+fn run_history_decrypt() -> Result<()> {
+    let manager = ConversationManager::new();
+    if !manager.is_encrypted() {
+        anyhow::bail!("Conversation history is not encrypted");
+    }
+
+    let manager = manager.unlock(&encryption::read_passphrase("Passphrase: ")?)?;
+    manager
+        .disable_encryption()
+        .context("Failed to decrypt conversation history")?;
+
+    println!("Conversation history decrypted");
+    Ok(())
+}
+
 /// Run the CLI in interactive mode (existing behavior)
 async fn run_interactive_mode(config: AppConfig, args: Args) -> Result<()> {
     // Validate URL from config if no CLI URL provided (Requirements 4.4)
@@ -272,8 +533,20 @@ async fn run_interactive_mode(config: AppConfig, args: Args) -> Result<()> {
     let mut app = CliApp::new_with_model_selection(config, args.url, args.model)
         .await
         .context("Failed to initialize CLI application")?;
+    if let Some(system) = args.system {
+        app.set_system_prompt(system);
+    }
+    if let Some(id) = &args.persona {
+        if let Err(e) = app.set_persona(id) {
+            exit_with_error(ExitCodes::INVALID_ARGS, &e.to_string());
+        }
+    }
+
+    app.run().await?;
 
-    app.run().await
+    // A signal at the prompt leaves the line editor blocked reading stdin;
+    // exit instead of waiting for it when the runtime shuts down
+    std::process::exit(ExitCodes::SUCCESS);
 }
 
 /// Run the CLI in non-interactive mode
@@ -296,7 +569,6 @@ async fn run_non_interactive_mode(
     let final_prompt = match InputProcessor::build_prompt(
         prompt,
         &args.file,
-        args.system.as_deref(),
     ) {
         Ok(prompt) => prompt,
         Err(e) => {
@@ -335,8 +607,19 @@ async fn run_non_interactive_mode(
         }
     };
 
+    // Send the system prompt, or the persona's, with the request
+    let system = match &args.persona {
+        Some(id) => match PersonaManager::load().get_persona(id) {
+            Some(persona) => Some(persona.system_prompt.clone()),
+            None => exit_with_error(ExitCodes::INVALID_ARGS, &format!("Persona '{}' not found", id)),
+        },
+        None => args.system.clone(),
+    };
+    handler.set_system_prompt(system);
+
     // Adjust options based on output redirection detection
     let mut adjusted_options = options.clone();
+    adjusted_options.extract_code = args.extract_code;
     let output_formatter = output::OutputFormatter::new();
     output_formatter.adjust_for_redirection(&mut adjusted_options);
 
@@ -379,6 +662,97 @@ mod tests {
         assert_eq!(args.prompt, None);
     }
 
+    #[test]
+    fn test_history_subcommand_parsing() {
+        let args = Args::try_parse_from([
+            "prometheus-cli", "history", "--tag", "rust", "--tag", "async", "--pinned"
+        ]).unwrap();
+        match args.command {
+            Some(CliCommand::History { action, tags, folder, pinned }) => {
+                assert!(action.is_none());
+                assert_eq!(tags, vec!["rust", "async"]);
+                assert_eq!(folder, None);
+                assert!(pinned);
+            }
+            other => panic!("Expected history subcommand, got {:?}", other),
+        }
+        assert_eq!(args.prompt, None);
+
+        // Ordinary prompts are not mistaken for subcommands
+        let args = Args::try_parse_from(["prometheus-cli", "history of Rome"]).unwrap();
+        assert!(args.command.is_none());
+        assert_eq!(args.prompt, Some("history of Rome".to_string()));
+    }
+
+    #[test]
+    fn test_subcommand_names_as_prompts() {
+        // A bare subcommand name runs the subcommand
+        let args = Args::try_parse_from(["prometheus-cli", "history"]).unwrap();
+        assert!(matches!(args.command, Some(CliCommand::History { .. })));
+
+        // After `--` it is sent as a one-word prompt
+        for word in ["history", "search"] {
+            let args = Args::try_parse_from(["prometheus-cli", "--", word]).unwrap();
+            assert!(args.command.is_none());
+            assert_eq!(args.prompt.as_deref(), Some(word));
+        }
+
+        // So it is when it follows other options
+        let args = Args::try_parse_from(["prometheus-cli", "--model", "llama3", "search"]).unwrap();
+        assert!(args.command.is_none());
+        assert_eq!(args.prompt.as_deref(), Some("search"));
+    }
+
+    #[test]
+    fn test_history_prune_subcommand_parsing() {
+        let args = Args::try_parse_from(["prometheus-cli", "history", "prune", "--dry-run"]).unwrap();
+        match args.command {
+            Some(CliCommand::History { action: Some(HistoryAction::Prune { dry_run }), .. }) => {
+                assert!(dry_run);
+            }
+            other => panic!("Expected history prune subcommand, got {:?}", other),
+        }
+
+        let args = Args::try_parse_from(["prometheus-cli", "history", "rekey"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(CliCommand::History { action: Some(HistoryAction::Rekey), .. })
+        ));
+
+        let args = Args::try_parse_from(["prometheus-cli", "history", "prune"]).unwrap();
+        assert!(matches!(
+            args.command,
+            Some(CliCommand::History { action: Some(HistoryAction::Prune { dry_run: false }), .. })
+        ));
+    }
+
+    #[test]
+    fn test_search_subcommand_parsing() {
+        let args = Args::try_parse_from(["prometheus-cli", "search", "-w", "borrow", "checker"]).unwrap();
+        match args.command {
+            Some(CliCommand::Search { query, case_sensitive, whole_word, stem, .. }) => {
+                assert_eq!(query, vec!["borrow", "checker"]);
+                assert!(!case_sensitive);
+                assert!(whole_word);
+                assert_eq!(stem, None);
+            }
+            other => panic!("Expected search subcommand, got {:?}", other),
+        }
+
+        assert!(Args::try_parse_from(["prometheus-cli", "search"]).is_err());
+
+        let args = Args::try_parse_from(["prometheus-cli", "search", "--stem", "english", "-f", "runing"]).unwrap();
+        match args.command {
+            Some(CliCommand::Search { stem, fuzzy, .. }) => {
+                assert_eq!(stem, Some(Language::English));
+                assert!(fuzzy);
+            }
+            other => panic!("Expected search subcommand, got {:?}", other),
+        }
+        assert!(Args::try_parse_from(["prometheus-cli", "search", "--stem", "klingon", "word"]).is_err());
+        assert!(Args::try_parse_from(["prometheus-cli", "search", "--semantic", "--hybrid", "word"]).is_err());
+    }
+
     #[test]
     fn test_file_flag_parsing() {
         // Test single file
@@ -407,6 +781,14 @@ mod tests {
         assert_eq!(args.prompt, Some("Hello".to_string()));
     }
 
+    #[test]
+    fn test_persona_flag() {
+        let args = Args::try_parse_from(["prometheus-cli", "--persona", "code-reviewer", "Review this"]).unwrap();
+        assert_eq!(args.persona, Some("code-reviewer".to_string()));
+
+        assert!(Args::try_parse_from(["prometheus-cli", "--persona", "teacher", "--system", "Be brief", "Hi"]).is_err());
+    }
+
     #[test]
     fn test_temperature_flag_parsing() {
         // Valid temperature
@@ -455,6 +837,13 @@ mod tests {
         assert!(args.save_on_interrupt);
     }
 
+    #[test]
+    fn test_extract_code_flag() {
+        let args = Args::try_parse_from(["prometheus-cli", "--extract-code", "prompt"]).unwrap();
+        assert!(args.extract_code);
+        assert!(Args::try_parse_from(["prometheus-cli", "--extract-code", "--json", "prompt"]).is_err());
+    }
+
     #[test]
     fn test_existing_flags_still_work() {
         let args = Args::try_parse_from(&[
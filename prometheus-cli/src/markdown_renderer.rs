@@ -1,30 +1,139 @@
 use anyhow::Result;
 use crossterm::style::{Color, Attribute};
 use termimad::{MadSkin, CompoundStyle};
+use crate::highlight::{CodeBlockState, CodeHighlighter, CodeStyle, ColorSupport};
+
+/// An opening code fence line: three or more backticks or tildes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Fence {
+    marker: char,
+    len: usize,
+    /// First word of the info string, e.g. "rust"
+    language: Option<String>,
+}
+
+impl Fence {
+    /// Parse an opening fence line
+    pub(crate) fn open(line: &str) -> Option<Self> {
+        let rest = line.trim_start_matches(' ');
+        if line.len() - rest.len() > 3 {
+            return None;
+        }
+        let marker = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+        let len = rest.chars().take_while(|c| *c == marker).count();
+        let info = &rest[len..];
+        // Backticks in the info string mean this is inline code, not a fence
+        if len < 3 || (marker == '`' && info.contains('`')) {
+            return None;
+        }
+        Some(Self {
+            marker,
+            len,
+            language: info.split_whitespace().next().map(str::to_string),
+        })
+    }
+
+    /// Whether a line closes this fence
+    pub(crate) fn closed_by(&self, line: &str) -> bool {
+        let trimmed = line.trim();
+        let len = trimmed.chars().take_while(|c| *c == self.marker).count();
+        len >= self.len && len == trimmed.len()
+    }
+
+    pub(crate) fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+}
+
+/// A fenced code block of a message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    /// First word of the fence's info string, e.g. "rust"
+    pub language: Option<String>,
+    /// The code between the fences, without a trailing newline
+    pub code: String,
+}
+
+impl CodeBlock {
+    fn new(fence: Fence, lines: &[&str]) -> Self {
+        Self {
+            language: fence.language,
+            code: lines.join("\n"),
+        }
+    }
+}
+
+/// Extract the fenced code blocks of a message, in order
+///
+/// A fence left open at the end (e.g. an interrupted response) still counts.
+pub fn code_blocks(markdown: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut open: Option<(Fence, Vec<&str>)> = None;
+
+    for line in markdown.lines() {
+        match open.take() {
+            Some((fence, lines)) if fence.closed_by(line) => blocks.push(CodeBlock::new(fence, &lines)),
+            Some((fence, mut lines)) => {
+                lines.push(line);
+                open = Some((fence, lines));
+            }
+            None => open = Fence::open(line).map(|fence| (fence, Vec::new())),
+        }
+    }
+    if let Some((fence, lines)) = open {
+        blocks.push(CodeBlock::new(fence, &lines));
+    }
+
+    blocks
+}
 
 /// Markdown renderer for terminal output
+///
+/// Text is rendered with termimad, fenced code blocks are syntax highlighted.
 pub struct MarkdownRenderer {
     skin: MadSkin,
+    code: CodeHighlighter,
 }
 
 impl MarkdownRenderer {
     /// Create a new MarkdownRenderer with default styling
     pub fn new() -> Self {
-        let mut skin = MadSkin::default();
-        
-        // Configure code block styling
-        skin.code_block.set_fg(Color::Green);
-        
-        // Configure inline code styling
-        skin.inline_code = CompoundStyle::with_fg(Color::Cyan);
-        
-        // Configure bold styling
-        skin.bold = CompoundStyle::with_attr(Attribute::Bold);
-        
-        // Configure italic styling
-        skin.italic = CompoundStyle::with_attr(Attribute::Italic);
-        
-        Self { skin }
+        Self::with_code_style(&CodeStyle::default())
+    }
+
+    /// Create a MarkdownRenderer that highlights code blocks with the given style
+    ///
+    /// Without color support (e.g. `NO_COLOR` is set), text is rendered unstyled too.
+    pub fn with_code_style(code_style: &CodeStyle) -> Self {
+        let skin = if code_style.colors == ColorSupport::NoColor {
+            MadSkin::no_style()
+        } else {
+            let mut skin = MadSkin::default();
+
+            // Configure code block styling, for code termimad renders itself
+            skin.code_block.set_fg(Color::Green);
+
+            // Configure inline code styling
+            skin.inline_code = CompoundStyle::with_fg(Color::Cyan);
+
+            // Configure bold styling
+            skin.bold = CompoundStyle::with_attr(Attribute::Bold);
+
+            // Configure italic styling
+            skin.italic = CompoundStyle::with_attr(Attribute::Italic);
+
+            skin
+        };
+
+        Self {
+            skin,
+            code: CodeHighlighter::new(code_style),
+        }
+    }
+
+    /// The highlighter used for fenced code blocks
+    pub fn highlighter(&self) -> &CodeHighlighter {
+        &self.code
     }
     
     /// Render markdown text to a formatted string for terminal display
@@ -46,15 +155,45 @@ impl MarkdownRenderer {
     }
     
     /// Try to render markdown, returning an error if it fails
+    ///
+    /// Fenced code blocks are highlighted; the text between them goes to termimad.
     fn try_render(&self, markdown: &str) -> Result<String> {
-        // Use termimad to render the markdown
-        let rendered = self.skin.text(markdown, None);
-        Ok(rendered.to_string())
+        let mut rendered = String::new();
+        let mut text: Vec<&str> = Vec::new();
+        let mut code: Option<(Fence, CodeBlockState)> = None;
+
+        for line in markdown.lines() {
+            if let Some((fence, state)) = code.as_mut() {
+                if fence.closed_by(line) {
+                    code = None;
+                } else {
+                    rendered.push_str(&self.code.highlight_line(state, line));
+                }
+            } else if let Some(fence) = Fence::open(line) {
+                rendered.push_str(&self.render_text(&text.join("\n")));
+                text.clear();
+                let state = self.code.start(fence.language());
+                code = Some((fence, state));
+            } else {
+                text.push(line);
+            }
+        }
+        rendered.push_str(&self.render_text(&text.join("\n")));
+
+        Ok(rendered)
+    }
+
+    /// Render markdown without code fences with termimad
+    fn render_text(&self, markdown: &str) -> String {
+        if markdown.is_empty() {
+            return String::new();
+        }
+        self.skin.text(markdown, None).to_string()
     }
     
-    /// Check if a string contains code block markers
+    /// Check if a string contains a fenced code block
     pub fn contains_code_block(&self, text: &str) -> bool {
-        text.contains("```")
+        text.lines().any(|line| Fence::open(line).is_some())
     }
     
     /// Check if a string contains inline code markers
@@ -118,8 +257,53 @@ mod tests {
         let renderer = MarkdownRenderer::new();
         let markdown = "```rust\nfn main() {}\n```";
         let result = renderer.render(markdown);
-        // Should contain the code content
-        assert!(result.contains("fn main()"));
+        // Should contain the code content, split up by highlighting
+        assert!(strip_ansi(&result).contains("fn main()"));
+    }
+
+    #[test]
+    fn test_render_code_block_highlighted_by_language() {
+        let code_style = CodeStyle {
+            colors: ColorSupport::TrueColor,
+            ..CodeStyle::default()
+        };
+        let renderer = MarkdownRenderer::with_code_style(&code_style);
+        let result = renderer.render("Intro\n\n```rust\nlet x = 1;\n```\n\nOutro");
+        assert!(result.contains("\x1b[38;2;"));
+        let plain = strip_ansi(&result);
+        assert!(plain.contains("Intro"));
+        assert!(plain.contains("let x = 1;"));
+        assert!(plain.contains("Outro"));
+        assert!(!plain.contains("```"));
+    }
+
+    #[test]
+    fn test_render_without_color() {
+        let code_style = CodeStyle {
+            line_numbers: true,
+            colors: ColorSupport::NoColor,
+            ..CodeStyle::default()
+        };
+        let renderer = MarkdownRenderer::with_code_style(&code_style);
+        let result = renderer.render("Some **bold**\n```\ncode\n```");
+        assert!(!result.contains('\x1b'));
+        assert!(result.contains("   1 │ code\n"));
+    }
+
+    #[test]
+    fn test_fence_parsing() {
+        let fence = Fence::open("~~~~ python title=x").unwrap();
+        assert_eq!(fence.language(), Some("python"));
+        assert!(!fence.closed_by("~~~"));
+        assert!(fence.closed_by("~~~~~ "));
+        assert!(!fence.closed_by("```"));
+        assert!(Fence::open("```let x = `a`;```").is_none());
+        assert!(Fence::open("    ```").is_none());
+        assert!(Fence::open("``").is_none());
+    }
+
+    fn strip_ansi(text: &str) -> String {
+        regex::Regex::new("\x1b\\[[0-9;]*m").unwrap().replace_all(text, "").to_string()
     }
 
     #[test]
@@ -182,6 +366,26 @@ mod tests {
         let renderer = MarkdownRenderer::new();
         assert!(renderer.contains_code_block("```rust\ncode\n```"));
         assert!(!renderer.contains_code_block("regular text"));
+        assert!(!renderer.contains_code_block("inline ```code``` only"));
+    }
+
+    #[test]
+    fn test_code_blocks() {
+        let markdown = "Run:\n```sh\ncargo build\n\ncargo test\n```\nThen\n~~~\n```\nunclosed";
+        assert_eq!(
+            code_blocks(markdown),
+            vec![
+                CodeBlock {
+                    language: Some("sh".to_string()),
+                    code: "cargo build\n\ncargo test".to_string(),
+                },
+                CodeBlock {
+                    language: None,
+                    code: "```\nunclosed".to_string(),
+                },
+            ]
+        );
+        assert!(code_blocks("no code here").is_empty());
     }
 
     #[test]
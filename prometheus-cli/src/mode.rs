@@ -21,6 +21,8 @@ pub struct NonInteractiveOptions {
     pub no_stream: bool,
     pub verbose: bool,
     pub save_on_interrupt: bool,
+    /// Print only the code blocks of the response
+    pub extract_code: bool,
 }
 
 impl NonInteractiveOptions {
@@ -37,6 +39,7 @@ impl NonInteractiveOptions {
             no_stream,
             verbose,
             save_on_interrupt,
+            extract_code: false,
         }
     }
 }
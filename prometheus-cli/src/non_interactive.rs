@@ -1,16 +1,19 @@
 use anyhow::{Context, Result};
-use crate::backend::BackendClient;
+use crate::backend::{BackendClient, GenerationSettings};
 use crate::config::AppConfig;
 use crate::input::InputProcessor;
 use crate::output::OutputFormatter;
 use crate::mode::NonInteractiveOptions;
 use crate::exit_codes::ExitCodes;
+use crate::thinking::{self, Segment, ThinkingParser};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 
 
 pub struct NonInteractiveHandler {
     backend_client: BackendClient,
+    /// System prompt sent with the request
+    settings: GenerationSettings,
     output_formatter: OutputFormatter,
     interrupted: Arc<AtomicBool>,
     signal_received: Arc<AtomicI32>,
@@ -32,6 +35,7 @@ impl NonInteractiveHandler {
         
         Ok(Self {
             backend_client,
+            settings: GenerationSettings::default(),
             output_formatter,
             interrupted,
             signal_received,
@@ -83,6 +87,11 @@ impl NonInteractiveHandler {
         }
     }
     
+    /// Send `system` as the system prompt, replacing the model's default one
+    pub fn set_system_prompt(&mut self, system: Option<String>) {
+        self.settings.system = system;
+    }
+
     async fn process_streaming(
         &mut self,
         prompt: &str,
@@ -90,10 +99,12 @@ impl NonInteractiveHandler {
         options: &NonInteractiveOptions,
     ) -> Result<()> {
         let mut response_buffer = String::new();
+        let mut parser = ThinkingParser::new();
+        let mut after_thinking = false;
         let interrupted = Arc::clone(&self.interrupted);
         
         let result = self.backend_client
-            .send_prompt_streaming(prompt, model, |chunk| {
+            .send_prompt_streaming_with_settings(prompt, model, &self.settings, |chunk| {
                 if interrupted.load(Ordering::Relaxed) {
                     return Ok(());
                 }
@@ -101,21 +112,23 @@ impl NonInteractiveHandler {
                 response_buffer.push_str(&chunk);
                 
                 // Use the output formatter for proper stream handling
-                self.output_formatter.output_streaming_chunk(&chunk, options).unwrap_or(());
+                let segments = parser.push(&chunk);
+                output_segments(&self.output_formatter, segments, &mut after_thinking, options);
                 
                 Ok(())
             })
             .await;
+        output_segments(&self.output_formatter, parser.finish(), &mut after_thinking, options);
         
         match result {
             Ok(_) => {
                 if !self.interrupted.load(Ordering::Relaxed) {
-                    self.output_formatter.format_response(&response_buffer, options)?;
+                    self.format_response(&response_buffer, options)?;
                 } else {
                     // Handle interruption
                     if options.save_on_interrupt && !response_buffer.is_empty() {
                         eprintln!("Saving partial response due to interruption...");
-                        self.output_formatter.format_response(&response_buffer, options)?;
+                        self.format_response(&response_buffer, options)?;
                     }
                     let exit_code = self.signal_received.load(Ordering::Relaxed);
                     std::process::exit(if exit_code != 0 { exit_code } else { ExitCodes::SIGINT });
@@ -125,7 +138,7 @@ impl NonInteractiveHandler {
             Err(e) => {
                 if options.save_on_interrupt && !response_buffer.is_empty() {
                     eprintln!("Saving partial response due to error...");
-                    self.output_formatter.format_response(&response_buffer, options)?;
+                    self.format_response(&response_buffer, options)?;
                 }
                 Err(e)
             }
@@ -142,7 +155,7 @@ impl NonInteractiveHandler {
         let interrupted = Arc::clone(&self.interrupted);
         
         let result = self.backend_client
-            .send_prompt_streaming(prompt, model, |chunk| {
+            .send_prompt_streaming_with_settings(prompt, model, &self.settings, |chunk| {
                 if interrupted.load(Ordering::Relaxed) {
                     return Ok(());
                 }
@@ -154,11 +167,11 @@ impl NonInteractiveHandler {
         match result {
             Ok(_) => {
                 if !self.interrupted.load(Ordering::Relaxed) {
-                    self.output_formatter.format_response(&full_response, options)?;
+                    self.format_response(&full_response, options)?;
                 } else {
                     // Handle interruption
                     if options.save_on_interrupt && !full_response.is_empty() {
-                        self.output_formatter.format_response(&full_response, options)?;
+                        self.format_response(&full_response, options)?;
                     }
                     let exit_code = self.signal_received.load(Ordering::Relaxed);
                     std::process::exit(if exit_code != 0 { exit_code } else { ExitCodes::SIGINT });
@@ -167,13 +180,20 @@ impl NonInteractiveHandler {
             }
             Err(e) => {
                 if options.save_on_interrupt && !full_response.is_empty() {
-                    self.output_formatter.format_response(&full_response, options)?;
+                    self.format_response(&full_response, options)?;
                 }
                 Err(e)
             }
         }
     }
     
+    /// Output a complete response, with its thinking separated from the answer
+    fn format_response(&self, response: &str, options: &NonInteractiveOptions) -> Result<()> {
+        let response = thinking::split_thinking(response);
+        self.output_formatter
+            .format_response_with_thinking(&response.answer, response.thinking.as_deref(), options)
+    }
+    
     fn setup_signal_handlers(&self) -> Result<()> {
         let interrupted = Arc::clone(&self.interrupted);
         let signal_received = Arc::clone(&self.signal_received);
@@ -212,7 +232,7 @@ impl NonInteractiveHandler {
         // Only save if explicitly requested
         if options.save_on_interrupt && !partial_response.is_empty() {
             eprintln!("Saving partial response due to interruption...");
-            if let Err(e) = self.output_formatter.format_response(partial_response, options) {
+            if let Err(e) = self.format_response(partial_response, options) {
                 eprintln!("Warning: Failed to save partial response: {}", e);
             }
         }
@@ -225,6 +245,31 @@ impl NonInteractiveHandler {
     }
 }
 
+/// Stream segments of the response: thinking to stderr, the answer to stdout
+///
+/// A blank line separates the thinking from the answer that follows it.
+fn output_segments(
+    formatter: &OutputFormatter,
+    segments: Vec<Segment>,
+    after_thinking: &mut bool,
+    options: &NonInteractiveOptions,
+) {
+    for segment in segments {
+        match segment {
+            Segment::Thinking(text) => {
+                formatter.output_thinking_chunk(&text, options).unwrap_or(());
+                *after_thinking = true;
+            }
+            Segment::Answer(text) => {
+                if std::mem::take(after_thinking) {
+                    formatter.output_thinking_chunk("\n\n", options).unwrap_or(());
+                }
+                formatter.output_streaming_chunk(&text, options).unwrap_or(());
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -242,6 +287,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let handler = NonInteractiveHandler::new(
@@ -263,6 +309,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let mut handler = NonInteractiveHandler::new(
@@ -307,6 +354,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let mut handler = NonInteractiveHandler::new(
@@ -340,6 +388,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let non_streaming_options = NonInteractiveOptions {
@@ -348,6 +397,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let handler1 = NonInteractiveHandler::new(
@@ -376,6 +426,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let handler = NonInteractiveHandler::new(
@@ -399,6 +450,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let handler = NonInteractiveHandler::new(
@@ -429,6 +481,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let handler = NonInteractiveHandler::new(
@@ -459,6 +512,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let handler = NonInteractiveHandler::new(
@@ -492,6 +546,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let handler_no_save = NonInteractiveHandler::new(
@@ -508,6 +563,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt: true,
+            extract_code: false,
         };
 
         let handler_save = NonInteractiveHandler::new(
@@ -534,6 +590,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let handler1 = NonInteractiveHandler::new(
@@ -566,6 +623,7 @@ mod tests {
             no_stream: false,
             verbose: true,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let handler = NonInteractiveHandler::new(
@@ -626,6 +684,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         // Test that handler creation succeeds (would lead to successful exit)
@@ -684,6 +743,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt,
+            extract_code: false,
         };
 
         let handler = match NonInteractiveHandler::new(
@@ -752,6 +812,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt,
+            extract_code: false,
         };
 
         let handler = match NonInteractiveHandler::new(
@@ -803,6 +864,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let mut handler = match NonInteractiveHandler::new(
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
 use serde_json::json;
 use std::io::{self, Write, IsTerminal};
+use crate::markdown_renderer::{self, CodeBlock};
 use crate::mode::NonInteractiveOptions;
 
 /// Output formatter for handling different output formats in non-interactive mode
@@ -14,18 +15,42 @@ impl OutputFormatter {
     
     /// Format and output the response according to the specified options
     pub fn format_response(&self, response: &str, options: &NonInteractiveOptions) -> Result<()> {
-        if options.json {
-            self.format_json_response(response, options)
+        self.format_response_with_thinking(response, None, options)
+    }
+
+    /// Format and output a response whose thinking was separated from it
+    ///
+    /// The thinking is a separate field in JSON output, goes to stderr in
+    /// default mode and is left out of quiet and code-only output.
+    pub fn format_response_with_thinking(
+        &self,
+        response: &str,
+        thinking: Option<&str>,
+        options: &NonInteractiveOptions,
+    ) -> Result<()> {
+        if options.extract_code {
+            self.format_code_blocks(response, options)
+        } else if options.json {
+            self.format_json_response(response, thinking, options)
         } else if options.quiet {
             self.format_quiet_response(response)
         } else {
+            // Streamed thinking was shown as it arrived
+            if let Some(thinking) = thinking.filter(|_| options.no_stream) {
+                self.output_thinking_chunk(&format!("{}\n\n", thinking), options)?;
+            }
             self.format_default_response(response, options)
         }
     }
     
     /// Format response as JSON with metadata
-    fn format_json_response(&self, response: &str, options: &NonInteractiveOptions) -> Result<()> {
-        let output = json!({
+    fn format_json_response(
+        &self,
+        response: &str,
+        thinking: Option<&str>,
+        options: &NonInteractiveOptions,
+    ) -> Result<()> {
+        let mut output = json!({
             "response": response,
             "metadata": {
                 "length": response.len(),
@@ -34,6 +59,9 @@ impl OutputFormatter {
                 "format": "json"
             }
         });
+        if let Some(thinking) = thinking {
+            output["thinking"] = json!(thinking);
+        }
         
         // JSON output goes to stdout
         println!("{}", serde_json::to_string_pretty(&output)
@@ -55,6 +83,22 @@ impl OutputFormatter {
         Ok(())
     }
     
+    /// Output only the code blocks of the response, separated by blank lines
+    fn format_code_blocks(&self, response: &str, options: &NonInteractiveOptions) -> Result<()> {
+        let blocks = markdown_renderer::code_blocks(response);
+        if blocks.is_empty() && !options.quiet {
+            self.output_warning("The response contains no code blocks")?;
+        }
+        print!("{}", extracted_code(&blocks));
+        self.flush_stdout_for_pipes()?;
+
+        if options.verbose {
+            eprintln!("Extracted {} code block(s)", blocks.len());
+        }
+
+        Ok(())
+    }
+
     /// Format response with default formatting
     fn format_default_response(&self, response: &str, options: &NonInteractiveOptions) -> Result<()> {
         if !options.no_stream {
@@ -77,8 +121,8 @@ impl OutputFormatter {
     
     /// Output a chunk during streaming (for non-quiet, non-JSON modes)
     pub fn output_streaming_chunk(&self, chunk: &str, options: &NonInteractiveOptions) -> Result<()> {
-        // Only output chunks in default mode (not quiet or JSON)
-        if !options.quiet && !options.json {
+        // Only output chunks in default mode (not quiet, JSON or code extraction)
+        if !options.quiet && !options.json && !options.extract_code {
             print!("{}", chunk);
             // Ensure proper flushing for pipe compatibility
             self.flush_stdout_for_pipes()?;
@@ -86,6 +130,21 @@ impl OutputFormatter {
         Ok(())
     }
     
+    /// Output a chunk of the model's thinking (default mode only)
+    ///
+    /// Thinking goes to stderr, dimmed on a terminal, so stdout carries only the answer.
+    pub fn output_thinking_chunk(&self, chunk: &str, options: &NonInteractiveOptions) -> Result<()> {
+        if !options.quiet && !options.json && !options.extract_code {
+            if io::stderr().is_terminal() {
+                eprint!("\x1b[2m{}\x1b[0m", chunk);
+            } else {
+                eprint!("{}", chunk);
+            }
+            self.flush_stderr_for_pipes()?;
+        }
+        Ok(())
+    }
+    
     /// Flush stdout appropriately for pipe compatibility
     fn flush_stdout_for_pipes(&self) -> Result<()> {
         io::stdout().flush().context("Failed to flush stdout")?;
@@ -170,6 +229,15 @@ impl OutputFormatter {
     }
 }
 
+/// The code of the blocks, each ending in a newline and separated by a blank line
+fn extracted_code(blocks: &[CodeBlock]) -> String {
+    blocks
+        .iter()
+        .map(|block| format!("{}\n", block.code))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 impl Default for OutputFormatter {
     fn default() -> Self {
         Self::new()
@@ -196,6 +264,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         // Test that quiet mode formatting doesn't fail
@@ -220,6 +289,7 @@ mod tests {
             no_stream,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         // Test that we can create valid JSON for any response
@@ -270,6 +340,7 @@ mod tests {
             no_stream: false,
             verbose,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         // Test that all output methods work without panicking
@@ -331,6 +402,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         // Test that quiet mode works (we can't easily capture stdout in tests,
@@ -348,6 +420,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         // We can't easily capture stdout, but we can test the JSON generation logic
@@ -386,6 +459,7 @@ mod tests {
             no_stream: true,
             verbose: true,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let non_verbose_options = NonInteractiveOptions {
@@ -394,6 +468,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         // Test that verbose mode doesn't cause errors
@@ -422,6 +497,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
         
         let result = formatter.output_streaming_chunk("chunk", &default_options);
@@ -434,6 +510,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
         
         let result = formatter.output_streaming_chunk("chunk", &quiet_options);
@@ -446,6 +523,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
         
         let result = formatter.output_streaming_chunk("chunk", &json_options);
@@ -490,6 +568,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
         
         let result = formatter.format_default_response("Test response", &streaming_options);
@@ -502,6 +581,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
         
         let result = formatter.format_default_response("Test response", &non_streaming_options);
@@ -517,6 +597,7 @@ mod tests {
             no_stream: false,
             verbose: true,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         // Test that JSON + verbose mode works correctly
@@ -533,6 +614,7 @@ mod tests {
             no_stream: true,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         // Create the JSON structure manually to test metadata accuracy
@@ -565,6 +647,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         // Test empty response
@@ -578,6 +661,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
         
         let result = formatter.format_response("", &json_options);
@@ -593,6 +677,7 @@ mod tests {
             no_stream: false,
             verbose: true,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         // Test large response
@@ -610,6 +695,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         // Test response with special characters
@@ -644,6 +730,7 @@ mod tests {
             no_stream: false,
             verbose: false,
             save_on_interrupt: false,
+            extract_code: false,
         };
 
         let result1 = formatter1.format_response("test", &options);
@@ -652,4 +739,12 @@ mod tests {
         assert!(result1.is_ok());
         assert!(result2.is_ok());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_extracted_code() {
+        let response = "Install it:\n```sh\ncargo install foo\n```\nThen run:\n```\nfoo --help\n```\nDone.";
+        let blocks = markdown_renderer::code_blocks(response);
+        assert_eq!(extracted_code(&blocks), "cargo install foo\n\nfoo --help\n");
+        assert_eq!(extracted_code(&[]), "");
+    }
+}
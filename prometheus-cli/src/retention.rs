@@ -0,0 +1,394 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use std::fmt;
+use crate::config::AppConfig;
+use crate::conversation::{ConversationManager, ConversationMetadata};
+
+/// Limits applied when pruning stored conversations
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Keep at most this many conversations
+    pub max_conversations: Option<usize>,
+    /// Remove conversations not updated for this many days
+    pub max_age_days: Option<u64>,
+    /// Keep the stored conversation files under this many bytes
+    pub max_total_bytes: Option<u64>,
+    /// Never remove pinned conversations
+    pub keep_pinned: bool,
+}
+
+impl RetentionPolicy {
+    /// Build the policy from `ui.max_chat_history` and the `[conversations]` settings
+    pub fn from_config(config: &AppConfig) -> Self {
+        let settings = &config.conversations;
+        Self {
+            max_conversations: Some(config.ui.max_chat_history).filter(|max| *max > 0),
+            max_age_days: settings.max_age_days,
+            max_total_bytes: settings.max_total_size_mb.map(|mb| mb.saturating_mul(1024 * 1024)),
+            keep_pinned: settings.keep_pinned,
+        }
+    }
+
+    /// Whether the policy sets no limits at all
+    pub fn is_unlimited(&self) -> bool {
+        self.max_conversations.is_none()
+            && self.max_age_days.is_none()
+            && self.max_total_bytes.is_none()
+    }
+}
+
+/// Why a conversation was selected for pruning
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneReason {
+    Age,
+    Count,
+    Size,
+}
+
+impl fmt::Display for PruneReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PruneReason::Age => write!(f, "older than the maximum age"),
+            PruneReason::Count => write!(f, "over the conversation limit"),
+            PruneReason::Size => write!(f, "over the size limit"),
+        }
+    }
+}
+
+/// A conversation selected for pruning
+#[derive(Debug, Clone)]
+pub struct PrunedConversation {
+    pub metadata: ConversationMetadata,
+    /// Size of the conversation file in bytes
+    pub size: u64,
+    pub reason: PruneReason,
+}
+
+/// Outcome of a prune run
+#[derive(Debug, Clone, Default)]
+pub struct PruneReport {
+    /// Conversations deleted, or that would be deleted in a dry run
+    pub pruned: Vec<PrunedConversation>,
+    pub dry_run: bool,
+}
+
+impl PruneReport {
+    /// Total size of the pruned conversation files in bytes
+    pub fn freed_bytes(&self) -> u64 {
+        self.pruned.iter().map(|p| p.size).sum()
+    }
+}
+
+/// Select the conversations that fall outside `policy`
+///
+/// Conversations older than the maximum age are selected first. The oldest
+/// of the rest are then selected until both the count and size limits are
+/// met. Pinned conversations count toward the limits but are never selected
+/// while `keep_pinned` is set.
+///
+/// # Arguments
+/// * `conversations` - Each conversation's metadata with its file size in bytes
+/// * `now` - The time ages are measured from
+pub fn select_for_pruning(
+    policy: &RetentionPolicy,
+    mut conversations: Vec<(ConversationMetadata, u64)>,
+    now: DateTime<Utc>,
+) -> Vec<PrunedConversation> {
+    let prunable = |metadata: &ConversationMetadata| !(policy.keep_pinned && metadata.pinned);
+
+    // Oldest first; timestamps can carry different UTC offsets, so compare them
+    // as instants. Unreadable ones sort last so they are never pruned first.
+    conversations.sort_by_key(|(metadata, _)| {
+        updated_at(metadata).unwrap_or(DateTime::<Utc>::MAX_UTC)
+    });
+
+    let mut pruned = Vec::new();
+
+    if let Some(days) = policy.max_age_days {
+        let cutoff = now - Duration::days(days.min(i64::MAX as u64 / 86_400) as i64);
+        let (expired, kept): (Vec<_>, Vec<_>) = conversations
+            .into_iter()
+            .partition(|(metadata, _)| prunable(metadata) && updated_before(metadata, cutoff));
+        pruned.extend(expired.into_iter().map(|(metadata, size)| PrunedConversation {
+            metadata,
+            size,
+            reason: PruneReason::Age,
+        }));
+        conversations = kept;
+    }
+
+    let mut count = conversations.len();
+    let mut total_size: u64 = conversations.iter().map(|(_, size)| size).sum();
+
+    for (metadata, size) in conversations {
+        let reason = if policy.max_conversations.is_some_and(|max| count > max) {
+            PruneReason::Count
+        } else if policy.max_total_bytes.is_some_and(|max| total_size > max) {
+            PruneReason::Size
+        } else {
+            break;
+        };
+
+        if prunable(&metadata) {
+            count -= 1;
+            total_size -= size;
+            pruned.push(PrunedConversation { metadata, size, reason });
+        }
+    }
+
+    pruned
+}
+
+/// Apply `policy` to the stored conversations
+///
+/// Pruned conversations are deleted through the manager, which also removes
+/// them from the metadata file.
+///
+/// # Arguments
+/// * `dry_run` - Report what would be deleted without deleting anything
+pub fn prune(
+    manager: &ConversationManager,
+    policy: &RetentionPolicy,
+    dry_run: bool,
+) -> Result<PruneReport> {
+    if policy.is_unlimited() {
+        return Ok(PruneReport { pruned: Vec::new(), dry_run });
+    }
+
+    let mut conversations = Vec::new();
+    for metadata in manager.list_conversations()? {
+        let size = manager.conversation_size(&metadata.id)?;
+        conversations.push((metadata, size));
+    }
+
+    let pruned = select_for_pruning(policy, conversations, Utc::now());
+
+    if !dry_run && !pruned.is_empty() {
+        let ids: Vec<String> = pruned.iter().map(|p| p.metadata.id.clone()).collect();
+        manager.delete_conversations(&ids)?;
+    }
+
+    Ok(PruneReport { pruned, dry_run })
+}
+
+/// Apply the policy from `config` if `prune_on_startup` is set
+///
+/// Deleted conversations are listed on stderr; errors are logged rather than returned.
+pub fn prune_on_startup(config: &AppConfig, manager: &ConversationManager) {
+    if !config.conversations.prune_on_startup {
+        return;
+    }
+
+    match prune(manager, &RetentionPolicy::from_config(config), false) {
+        Ok(report) if !report.pruned.is_empty() => {
+            eprint!("{}", format_prune_report(&report));
+        }
+        Ok(_) => {}
+        Err(e) => log::warn!("Failed to prune conversation history: {}", e),
+    }
+}
+
+/// Format a prune report as a summary line followed by one line per conversation
+pub fn format_prune_report(report: &PruneReport) -> String {
+    if report.pruned.is_empty() {
+        return "No conversations to prune\n".to_string();
+    }
+
+    let verb = if report.dry_run { "Would delete" } else { "Deleted" };
+    let mut out = format!(
+        "{} {} conversation(s), {}\n",
+        verb,
+        report.pruned.len(),
+        format_bytes(report.freed_bytes())
+    );
+    for pruned in &report.pruned {
+        let short_id: String = pruned.metadata.id.chars().take(8).collect();
+        let date = pruned.metadata.updated_at.get(..10).unwrap_or(&pruned.metadata.updated_at);
+        out.push_str(&format!(
+            "  {}  {}  {}  ({})\n",
+            short_id, date, pruned.metadata.name, pruned.reason
+        ));
+    }
+    out
+}
+
+fn updated_at(metadata: &ConversationMetadata) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&metadata.updated_at)
+        .ok()
+        .map(|updated| updated.with_timezone(&Utc))
+}
+
+fn updated_before(metadata: &ConversationMetadata, cutoff: DateTime<Utc>) -> bool {
+    updated_at(metadata).is_some_and(|updated| updated < cutoff)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+    let bytes_f = bytes as f64;
+    if bytes_f >= MB {
+        format!("{:.1} MB", bytes_f / MB)
+    } else if bytes_f >= KB {
+        format!("{:.1} KB", bytes_f / KB)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::{ChatMessage, Conversation};
+
+    fn metadata(id: &str, updated_at: &str, pinned: bool) -> ConversationMetadata {
+        ConversationMetadata {
+            id: id.to_string(),
+            name: format!("Chat {}", id),
+            preview: String::new(),
+            updated_at: updated_at.to_string(),
+            message_count: 2,
+            tags: Vec::new(),
+            pinned,
+            folder: None,
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-06-01T00:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn ids(pruned: &[PrunedConversation]) -> Vec<&str> {
+        pruned.iter().map(|p| p.metadata.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_policy_from_config() {
+        let mut config = AppConfig::default();
+        assert!(!config.conversations.prune_on_startup);
+        config.ui.max_chat_history = 0;
+        assert!(RetentionPolicy::from_config(&config).is_unlimited());
+
+        config.ui.max_chat_history = 50;
+        config.conversations.max_total_size_mb = Some(2);
+        let policy = RetentionPolicy::from_config(&config);
+        assert_eq!(policy.max_conversations, Some(50));
+        assert_eq!(policy.max_total_bytes, Some(2 * 1024 * 1024));
+        assert!(policy.keep_pinned);
+    }
+
+    #[test]
+    fn test_select_by_age_skips_pinned() {
+        let policy = RetentionPolicy {
+            max_age_days: Some(30),
+            keep_pinned: true,
+            ..Default::default()
+        };
+        let conversations = vec![
+            (metadata("old", "2024-01-01T00:00:00+00:00", false), 10),
+            (metadata("old-pinned", "2024-01-01T00:00:00+00:00", true), 10),
+            (metadata("recent", "2024-05-20T00:00:00+00:00", false), 10),
+        ];
+
+        let pruned = select_for_pruning(&policy, conversations, now());
+        assert_eq!(ids(&pruned), vec!["old"]);
+        assert_eq!(pruned[0].reason, PruneReason::Age);
+    }
+
+    #[test]
+    fn test_select_by_count_removes_oldest() {
+        let policy = RetentionPolicy {
+            max_conversations: Some(2),
+            keep_pinned: true,
+            ..Default::default()
+        };
+        let conversations = vec![
+            (metadata("c", "2024-03-01T00:00:00+00:00", false), 10),
+            (metadata("a", "2024-01-01T00:00:00+00:00", true), 10),
+            (metadata("b", "2024-02-01T00:00:00+00:00", false), 10),
+            (metadata("d", "2024-04-01T00:00:00+00:00", false), 10),
+        ];
+
+        let pruned = select_for_pruning(&policy, conversations, now());
+        assert_eq!(ids(&pruned), vec!["b", "c"]);
+        assert!(pruned.iter().all(|p| p.reason == PruneReason::Count));
+    }
+
+    #[test]
+    fn test_select_by_count_compares_instants_across_offsets() {
+        let policy = RetentionPolicy {
+            max_conversations: Some(1),
+            ..Default::default()
+        };
+        // "a" sorts first as a string but is the later instant (10:00 UTC vs 06:00 UTC)
+        let conversations = vec![
+            (metadata("a", "2024-03-01T05:00:00-05:00", false), 10),
+            (metadata("b", "2024-03-01T08:00:00+02:00", false), 10),
+        ];
+
+        let pruned = select_for_pruning(&policy, conversations, now());
+        assert_eq!(ids(&pruned), vec!["b"]);
+    }
+
+    #[test]
+    fn test_select_by_size_and_pinned_not_exempt() {
+        let policy = RetentionPolicy {
+            max_total_bytes: Some(250),
+            keep_pinned: false,
+            ..Default::default()
+        };
+        let conversations = vec![
+            (metadata("a", "2024-01-01T00:00:00+00:00", true), 100),
+            (metadata("b", "2024-02-01T00:00:00+00:00", false), 100),
+            (metadata("c", "2024-03-01T00:00:00+00:00", false), 100),
+        ];
+
+        let pruned = select_for_pruning(&policy, conversations, now());
+        assert_eq!(ids(&pruned), vec!["a"]);
+        assert_eq!(pruned[0].reason, PruneReason::Size);
+    }
+
+    #[test]
+    fn test_prune_deletes_files_and_metadata() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let mut old = Conversation::new("Old".to_string(), None);
+        old.add_message(ChatMessage::new("user".to_string(), "Hello".to_string()));
+        old.updated_at = "2000-01-01T00:00:00+00:00".to_string();
+        let recent = Conversation::new("Recent".to_string(), None);
+        manager.save_conversation(&old).unwrap();
+        manager.save_conversation(&recent).unwrap();
+        let mut index = prometheus_search::VectorIndex::new("nomic-embed-text");
+        for message in index.pending(&old) {
+            index.insert(&message, vec![1.0, 0.0]);
+        }
+        manager.save_vector_index(&index).unwrap();
+
+        let policy = RetentionPolicy {
+            max_age_days: Some(365),
+            keep_pinned: true,
+            ..Default::default()
+        };
+
+        let report = prune(&manager, &policy, true).unwrap();
+        assert_eq!(ids(&report.pruned), vec![old.id.as_str()]);
+        assert_eq!(manager.list_conversations().unwrap().len(), 2);
+        assert!(format_prune_report(&report).starts_with("Would delete 1 conversation(s)"));
+
+        let report = prune(&manager, &policy, false).unwrap();
+        assert_eq!(report.pruned.len(), 1);
+        let remaining = manager.list_conversations().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, recent.id);
+        assert!(manager.load_conversation(&old.id).is_err());
+        assert!(manager.load_vector_index("nomic-embed-text").is_empty());
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 bytes");
+        assert_eq!(format_bytes(2048), "2.0 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MB");
+    }
+}
@@ -0,0 +1,289 @@
+use anyhow::Result;
+use crossterm::style::Stylize;
+use prometheus_search::{language_from_name, Language, SearchQuery, SearchResult};
+use std::collections::HashMap;
+
+use crate::conversation::ConversationManager;
+
+/// How search results are found and ranked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchMode {
+    /// Words and phrases, ranked with BM25
+    #[default]
+    Keyword,
+    /// Similar meaning, ranked by embedding similarity
+    Semantic,
+    /// Both, blending the two rankings
+    Hybrid,
+}
+
+/// Options for `/search` and `prometheus-cli search`, mirroring `SearchQueryBuilder`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    pub text: String,
+    pub mode: SearchMode,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    /// Require accents to match instead of folding them
+    pub match_accents: bool,
+    /// Tolerate typos
+    pub fuzzy: bool,
+    /// Language to stem words in
+    pub stemming: Option<Language>,
+}
+
+impl SearchOptions {
+    /// Parse the arguments following `/search`
+    ///
+    /// `-c`/`--case-sensitive`, `-w`/`--whole-word`, `-a`/`--match-accents`,
+    /// `-f`/`--fuzzy`, `--stem <language>`, `--semantic` and `--hybrid` are
+    /// read up to the first other word or `--`; the rest is the query, so it
+    /// can contain words such as `-c`. Returns `None` without a query or for an
+    /// unknown stemming language.
+    pub fn parse(args: &[&str]) -> Option<Self> {
+        let mut options = Self::default();
+        let mut args = args.iter().peekable();
+        while let Some(arg) = args.peek() {
+            match **arg {
+                "-c" | "--case-sensitive" => options.case_sensitive = true,
+                "-w" | "--whole-word" => options.whole_word = true,
+                "-a" | "--match-accents" => options.match_accents = true,
+                "-f" | "--fuzzy" => options.fuzzy = true,
+                "--stem" => {
+                    args.next();
+                    options.stemming = Some(language_from_name(args.peek()?)?);
+                }
+                "--semantic" => options.mode = SearchMode::Semantic,
+                "--hybrid" => options.mode = SearchMode::Hybrid,
+                "--" => {
+                    args.next();
+                    break;
+                }
+                _ => break,
+            }
+            args.next();
+        }
+
+        let words: Vec<&str> = args.copied().collect();
+        if words.is_empty() {
+            return None;
+        }
+        options.text = words.join(" ");
+        Some(options)
+    }
+
+    pub fn to_query(&self) -> SearchQuery {
+        SearchQuery::new(self.text.clone())
+            .with_case_sensitive(self.case_sensitive)
+            .with_whole_word(self.whole_word)
+            .with_diacritic_sensitive(self.match_accents)
+            .with_max_edits(if self.fuzzy { 2 } else { 0 })
+            .with_stemming(self.stemming)
+    }
+}
+
+/// A matching message, with what is needed to display and open it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    pub conversation_id: String,
+    pub conversation_name: String,
+    pub updated_at: String,
+    pub message_index: usize,
+    pub role: String,
+    pub context: String,
+    /// Byte ranges of the matches within `context`
+    pub matches: Vec<(usize, usize)>,
+}
+
+/// Search the conversation history by keyword, whatever `options.mode` says
+///
+/// The manager must have search enabled. Invalid queries fail with the query
+/// and a caret under the problem. See `semantic::search_history` for the
+/// other modes.
+pub fn search_history(manager: &ConversationManager, options: &SearchOptions) -> Result<Vec<SearchHit>> {
+    let query = options.to_query();
+    if let Err(e) = query.parse() {
+        anyhow::bail!("{}\n{}", e, e.pointer(&query.text));
+    }
+    let results = manager.search(&query)?;
+    to_hits(manager, results, Some(&query))
+}
+
+/// Attach conversation details to search results
+///
+/// Matches of `query` are highlighted; semantic results have none.
+pub fn to_hits(
+    manager: &ConversationManager,
+    results: Vec<SearchResult>,
+    query: Option<&SearchQuery>,
+) -> Result<Vec<SearchHit>> {
+    let conversations: HashMap<String, (String, String)> = manager
+        .list_conversations()?
+        .into_iter()
+        .map(|c| (c.id, (c.name, c.updated_at)))
+        .collect();
+
+    Ok(results
+        .into_iter()
+        .filter_map(|result| {
+            // Skip results for conversations that no longer exist
+            let (name, updated_at) = conversations.get(&result.conversation_id)?.clone();
+            Some(SearchHit {
+                matches: query.map(|query| result.context_matches(query)).unwrap_or_default(),
+                conversation_id: result.conversation_id,
+                conversation_name: name,
+                updated_at,
+                message_index: result.message_index,
+                role: result.role,
+                context: result.context,
+            })
+        })
+        .collect())
+}
+
+/// Format hits as numbered entries, for use with `/load <n>`
+///
+/// Each entry shows the first 8 characters of the conversation ID, its date and
+/// name, followed by the matching context on an indented line. Matches are
+/// highlighted when `color` is set.
+pub fn format_search_results(hits: &[SearchHit], color: bool) -> String {
+    let mut out = String::new();
+    for (i, hit) in hits.iter().enumerate() {
+        let short_id: String = hit.conversation_id.chars().take(8).collect();
+        let date = hit.updated_at.get(..10).unwrap_or(&hit.updated_at);
+
+        out.push_str(&format!(
+            "{:>3}. {}  {}  {}  ({} message {})\n",
+            i + 1,
+            short_id,
+            date,
+            hit.conversation_name,
+            hit.role,
+            hit.message_index + 1
+        ));
+        out.push_str("     ");
+        out.push_str(&highlight(&hit.context, &hit.matches, color));
+        out.push('\n');
+    }
+    out
+}
+
+/// Flatten `context` to one line, highlighting the given byte ranges
+fn highlight(context: &str, matches: &[(usize, usize)], color: bool) -> String {
+    let mut out = String::new();
+    let mut last = 0;
+    for &(start, end) in matches {
+        if start < last || end > context.len() {
+            continue;
+        }
+        out.push_str(&context[last..start]);
+        let matched = &context[start..end];
+        if color {
+            out.push_str(&matched.yellow().bold().to_string());
+        } else {
+            out.push_str(matched);
+        }
+        last = end;
+    }
+    out.push_str(&context[last..]);
+    // Newlines are single bytes, so replacing them keeps the ranges above valid
+    out.replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::{ChatMessage, Conversation};
+
+    fn hit(context: &str, matches: Vec<(usize, usize)>) -> SearchHit {
+        SearchHit {
+            conversation_id: "0123456789".to_string(),
+            conversation_name: "Lifetimes".to_string(),
+            updated_at: "2024-05-01T10:00:00+00:00".to_string(),
+            message_index: 1,
+            role: "assistant".to_string(),
+            context: context.to_string(),
+            matches,
+        }
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options = SearchOptions::parse(&["-w", "--case-sensitive", "borrow", "checker"]).unwrap();
+        assert_eq!(options.text, "borrow checker");
+        assert!(options.whole_word);
+        assert!(options.case_sensitive);
+
+        assert!(SearchOptions::parse(&[]).is_none());
+        assert!(SearchOptions::parse(&["-c"]).is_none());
+
+        let options = SearchOptions::parse(&["-f", "--stem", "en", "-a", "runing"]).unwrap();
+        assert_eq!(options.text, "runing");
+        assert!(options.fuzzy && options.match_accents);
+        assert_eq!(options.stemming, Some(Language::English));
+        assert!(SearchOptions::parse(&["--stem", "klingon", "word"]).is_none());
+        assert!(SearchOptions::parse(&["--stem"]).is_none());
+
+        // Options are only read before the query, or up to `--`
+        let options = SearchOptions::parse(&["-w", "gcc", "-c", "flag"]).unwrap();
+        assert_eq!(options.text, "gcc -c flag");
+        assert!(options.whole_word && !options.case_sensitive);
+        let options = SearchOptions::parse(&["--", "-f", "--stem"]).unwrap();
+        assert_eq!(options.text, "-f --stem");
+        assert!(!options.fuzzy);
+        assert!(SearchOptions::parse(&["-c", "--"]).is_none());
+        assert_eq!(SearchOptions::parse(&["--hybrid", "cancel"]).unwrap().mode, SearchMode::Hybrid);
+    }
+
+    #[test]
+    fn test_format_search_results_plain() {
+        let output = format_search_results(&[hit("about\nlifetimes here", vec![(6, 15)])], false);
+
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "  1. 01234567  2024-05-01  Lifetimes  (assistant message 2)");
+        assert_eq!(lines[1], "     about lifetimes here");
+    }
+
+    #[test]
+    fn test_format_search_results_highlights_matches() {
+        let output = format_search_results(&[hit("about lifetimes", vec![(6, 15)])], true);
+        assert!(output.contains(&"lifetimes".yellow().bold().to_string()));
+    }
+
+    #[test]
+    fn test_search_history() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut manager = ConversationManager::with_directory(temp_dir.path());
+        manager.enable_search().unwrap();
+
+        let mut conversation = Conversation::new("Rust questions".to_string(), None);
+        conversation.add_message(ChatMessage::new("user".to_string(), "Explain lifetimes".to_string()));
+        manager.save_conversation(&conversation).unwrap();
+
+        let options = SearchOptions::parse(&["Lifetimes"]).unwrap();
+        let hits = search_history(&manager, &options).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].conversation_name, "Rust questions");
+        assert_eq!(hits[0].matches, vec![(8, 17)]);
+
+        let case_sensitive = SearchOptions::parse(&["-c", "Lifetimes"]).unwrap();
+        assert!(search_history(&manager, &case_sensitive).unwrap().is_empty());
+
+        let filtered = SearchOptions::parse(&["lifetimes", "role:assistant"]).unwrap();
+        assert!(search_history(&manager, &filtered).unwrap().is_empty());
+
+        let fuzzy = SearchOptions::parse(&["-f", "lifetmes"]).unwrap();
+        assert_eq!(search_history(&manager, &fuzzy).unwrap()[0].matches, vec![(8, 17)]);
+    }
+
+    #[test]
+    fn test_search_history_invalid_query() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut manager = ConversationManager::with_directory(temp_dir.path());
+        manager.enable_search().unwrap();
+
+        let options = SearchOptions::parse(&["before:yesterday"]).unwrap();
+        let error = search_history(&manager, &options).unwrap_err().to_string();
+        assert!(error.ends_with("before:yesterday\n       ^"));
+    }
+}
@@ -0,0 +1,298 @@
+use anyhow::{Context, Result};
+use prometheus_search::{PendingMessage, VectorIndex};
+use std::collections::HashSet;
+
+use crate::backend::BackendClient;
+use crate::conversation::{Conversation, ConversationManager};
+use crate::search::{self, SearchHit, SearchMode, SearchOptions};
+
+/// Messages sent to the embedding model per request
+const EMBED_BATCH_SIZE: usize = 16;
+
+/// Most similar messages listed by a semantic search
+const SEMANTIC_RESULT_LIMIT: usize = 20;
+
+/// Similar messages considered alongside keyword matches in hybrid search
+const HYBRID_CANDIDATES: usize = 50;
+
+/// Share of the hybrid score given to semantic similarity
+const HYBRID_SEMANTIC_WEIGHT: f64 = 0.5;
+
+/// Held while the embeddings file is read, updated and written, so background
+/// embedding and searches don't overwrite each other's changes
+static EMBEDDINGS_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Search the conversation history in any mode
+///
+/// Semantic and hybrid searches first embed messages that have no embedding
+/// yet (all of them after the embedding model changes), then embed the query.
+///
+/// # Arguments
+/// * `manager` - Conversation manager with search enabled
+/// * `client` - Backend used to compute embeddings
+/// * `model` - Embedding model
+/// * `options` - The query and search mode
+pub async fn search_history(
+    manager: &ConversationManager,
+    client: &BackendClient,
+    model: &str,
+    options: &SearchOptions,
+) -> Result<Vec<SearchHit>> {
+    if options.mode == SearchMode::Keyword {
+        return search::search_history(manager, options);
+    }
+
+    let query = options.to_query();
+    if options.mode == SearchMode::Hybrid {
+        if let Err(e) = query.parse() {
+            anyhow::bail!("{}\n{}", e, e.pointer(&query.text));
+        }
+    }
+
+    let index = {
+        let _guard = EMBEDDINGS_LOCK.lock().await;
+        let mut index = manager.load_vector_index(model);
+        let embedded = embed_history(manager, client, &mut index).await;
+        manager.save_vector_index(&index)?;
+        embedded?;
+        index
+    };
+
+    let query_vector = client
+        .embed(model, std::slice::from_ref(&options.text))
+        .await
+        .with_context(|| format!("Failed to embed the query with {}", model))?
+        .remove(0);
+
+    match options.mode {
+        SearchMode::Semantic => {
+            let matches = index.search(&query_vector, SEMANTIC_RESULT_LIMIT);
+            search::to_hits(manager, manager.semantic_search(&matches)?, None)
+        }
+        _ => {
+            let matches = index.search(&query_vector, HYBRID_CANDIDATES);
+            let results = manager.hybrid_search(&query, &matches, HYBRID_SEMANTIC_WEIGHT)?;
+            search::to_hits(manager, results, Some(&query))
+        }
+    }
+}
+
+/// Embed the messages of stored conversations that have no up-to-date embedding
+///
+/// Only conversations saved since the last search are loaded, unless `index` is
+/// empty (e.g. after the embedding model changed) or saves weren't recorded yet.
+/// Embeddings of deleted conversations are dropped. Embeddings computed before
+/// a failure are kept in `index`, and the conversations are checked again next time.
+///
+/// # Returns
+/// The number of messages embedded
+async fn embed_history(manager: &ConversationManager, client: &BackendClient, index: &mut VectorIndex) -> Result<usize> {
+    let ids: HashSet<String> = manager.list_conversations()?.into_iter().map(|c| c.id).collect();
+    index.retain_conversations(&ids);
+
+    let changed = match manager.take_pending_embeddings() {
+        Ok(Some(changed)) if !index.is_empty() => changed.intersection(&ids).cloned().collect(),
+        Ok(_) => ids,
+        Err(e) => {
+            log::warn!("Checking every conversation for embedding: {}", e);
+            ids
+        }
+    };
+
+    let mut pending = Vec::new();
+    for id in &changed {
+        match manager.load_conversation(id) {
+            Ok(conversation) => pending.extend(index.pending(&conversation)),
+            Err(e) => log::warn!("Skipping conversation {} while embedding: {}", id, e),
+        }
+    }
+    let embedded = embed_pending(client, index, &pending).await;
+    if embedded.is_err() {
+        if let Err(e) = manager.add_pending_embeddings(&changed) {
+            log::warn!("Failed to record conversations for embedding: {}", e);
+        }
+    }
+    embedded
+}
+
+/// Embed the new messages of a conversation and save them
+///
+/// Meant to run in a background task after each exchange.
+///
+/// # Returns
+/// The number of messages embedded
+pub async fn embed_conversation(
+    manager: &ConversationManager,
+    client: &BackendClient,
+    model: &str,
+    conversation: &Conversation,
+) -> Result<usize> {
+    let _guard = EMBEDDINGS_LOCK.lock().await;
+    let mut index = manager.load_vector_index(model);
+    let pending = index.pending(conversation);
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let embedded = embed_pending(client, &mut index, &pending).await;
+    manager.save_vector_index(&index)?;
+    embedded
+}
+
+async fn embed_pending(client: &BackendClient, index: &mut VectorIndex, pending: &[PendingMessage]) -> Result<usize> {
+    let model = index.model().to_string();
+    for batch in pending.chunks(EMBED_BATCH_SIZE) {
+        let inputs: Vec<String> = batch.iter().map(|message| message.content.clone()).collect();
+        let vectors = client
+            .embed(&model, &inputs)
+            .await
+            .with_context(|| format!("Failed to embed messages with {}", model))?;
+        for (message, vector) in batch.iter().zip(vectors) {
+            index.insert(message, vector);
+        }
+    }
+    Ok(pending.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversation::ChatMessage;
+
+    /// Answers /api/embed with a 2-dimensional vector per input: texts
+    /// mentioning "cancel" or "abort" point one way, everything else the other
+    async fn mock_embeddings(server: &mut mockito::ServerGuard) -> mockito::Mock {
+        server
+            .mock("POST", "/api/embed")
+            .with_body_from_request(|request| {
+                let body: serde_json::Value = serde_json::from_slice(request.body().unwrap()).unwrap();
+                let embeddings: Vec<[f32; 2]> = body["input"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|input| {
+                        let text = input.as_str().unwrap();
+                        if text.contains("cancel") || text.contains("abort") {
+                            [1.0, 0.1]
+                        } else {
+                            [0.1, 1.0]
+                        }
+                    })
+                    .collect();
+                serde_json::json!({ "embeddings": embeddings }).to_string().into()
+            })
+            .expect_at_least(1)
+            .create_async()
+            .await
+    }
+
+    fn manager_with(temp_dir: &std::path::Path, messages: &[&str]) -> ConversationManager {
+        let mut manager = ConversationManager::with_directory(temp_dir);
+        manager.enable_search().unwrap();
+        let mut conversation = Conversation::new("Async".to_string(), None);
+        for content in messages {
+            conversation.add_message(ChatMessage::new("user".to_string(), content.to_string()));
+        }
+        manager.save_conversation(&conversation).unwrap();
+        manager
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_finds_paraphrases() {
+        let mut server = mockito::Server::new_async().await;
+        mock_embeddings(&mut server).await;
+        let client = BackendClient::new(server.url(), 5).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = manager_with(temp_dir.path(), &["How do I abort a future?", "Tell me about lifetimes"]);
+
+        let options = SearchOptions::parse(&["--semantic", "cancel", "a", "task"]).unwrap();
+        let hits = search_history(&manager, &client, "embedder", &options).await.unwrap();
+
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].message_index, 0);
+        assert!(hits[0].matches.is_empty());
+        assert_eq!(manager.load_vector_index("embedder").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_changing_the_model_embeds_again() {
+        let mut server = mockito::Server::new_async().await;
+        mock_embeddings(&mut server).await;
+        let client = BackendClient::new(server.url(), 5).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = manager_with(temp_dir.path(), &["first", "second"]);
+
+        let conversation = manager.load_conversation(&manager.list_conversations().unwrap()[0].id).unwrap();
+        assert_eq!(embed_conversation(&manager, &client, "old", &conversation).await.unwrap(), 2);
+        assert_eq!(embed_conversation(&manager, &client, "old", &conversation).await.unwrap(), 0);
+        assert_eq!(embed_conversation(&manager, &client, "new", &conversation).await.unwrap(), 2);
+        assert_eq!(manager.load_vector_index("new").model(), "new");
+    }
+
+    #[tokio::test]
+    async fn test_search_only_embeds_conversations_saved_since() {
+        let mut server = mockito::Server::new_async().await;
+        mock_embeddings(&mut server).await;
+        let client = BackendClient::new(server.url(), 5).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = manager_with(temp_dir.path(), &["first"]);
+        let options = SearchOptions::parse(&["--semantic", "first"]).unwrap();
+        search_history(&manager, &client, "embedder", &options).await.unwrap();
+        assert_eq!(manager.take_pending_embeddings().unwrap(), Some(HashSet::new()));
+
+        let mut conversation = Conversation::new("Later".to_string(), None);
+        conversation.add_message(ChatMessage::new("user".to_string(), "second".to_string()));
+        manager.save_conversation(&conversation).unwrap();
+        assert_eq!(manager.take_pending_embeddings().unwrap(), Some(HashSet::from([conversation.id.clone()])));
+        manager.add_pending_embeddings([&conversation.id]).unwrap();
+
+        // A conversation that wasn't saved through the manager isn't looked at
+        let mut skipped = Conversation::new("Skipped".to_string(), None);
+        skipped.add_message(ChatMessage::new("user".to_string(), "third".to_string()));
+        let mut metadata = manager.load_metadata().unwrap();
+        metadata.add_or_update(crate::conversation::ConversationMetadata::from_conversation(&skipped));
+        manager.save_metadata(&metadata).unwrap();
+
+        search_history(&manager, &client, "embedder", &options).await.unwrap();
+        assert_eq!(manager.load_vector_index("embedder").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_embedding_is_retried() {
+        let mut server = mockito::Server::new_async().await;
+        let client = BackendClient::new(server.url(), 5).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = manager_with(temp_dir.path(), &["first"]);
+        let options = SearchOptions::parse(&["--semantic", "first"]).unwrap();
+        let embeddings = mock_embeddings(&mut server).await;
+        search_history(&manager, &client, "embedder", &options).await.unwrap();
+        embeddings.remove_async().await;
+
+        let mut conversation = Conversation::new("Later".to_string(), None);
+        conversation.add_message(ChatMessage::new("user".to_string(), "second".to_string()));
+        manager.save_conversation(&conversation).unwrap();
+        let failing = server.mock("POST", "/api/embed").with_status(500).create_async().await;
+        assert!(search_history(&manager, &client, "embedder", &options).await.is_err());
+        failing.remove_async().await;
+
+        mock_embeddings(&mut server).await;
+        search_history(&manager, &client, "embedder", &options).await.unwrap();
+        assert_eq!(manager.load_vector_index("embedder").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_search_highlights_keywords() {
+        let mut server = mockito::Server::new_async().await;
+        mock_embeddings(&mut server).await;
+        let client = BackendClient::new(server.url(), 5).unwrap();
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = manager_with(temp_dir.path(), &["abort the future", "cancel the future", "unrelated"]);
+
+        let options = SearchOptions::parse(&["--hybrid", "cancel"]).unwrap();
+        let hits = search_history(&manager, &client, "embedder", &options).await.unwrap();
+
+        assert_eq!(hits[0].message_index, 1);
+        assert_eq!(hits[0].matches, vec![(0, 6)]);
+        assert_eq!(hits[1].message_index, 0);
+    }
+}
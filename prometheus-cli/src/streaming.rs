@@ -1,10 +1,34 @@
 use anyhow::Result;
+use crate::highlight::CodeStyle;
+use crate::markdown_renderer::MarkdownRenderer;
+use crate::mode::ModeDetector;
+use crate::streaming_markdown::{Frame, StreamingMarkdown};
 use crate::terminal::Terminal;
+use crate::thinking::{Segment, ThinkingParser};
+use std::time::Instant;
+
+/// Terminal size assumed when it can't be queried
+const DEFAULT_TERMINAL_COLUMNS: u16 = 80;
+const DEFAULT_TERMINAL_ROWS: u16 = 24;
 
 /// Handler for streaming AI responses with real-time display
+///
+/// On a terminal, responses are rendered as markdown while they stream in.
+/// When stdout is redirected, chunks are written as they are, so pipes and
+/// files receive the plain markdown.
+///
+/// The thinking section of reasoning models is kept apart from the answer.
+/// It is shown dimmed, or collapsed into a single line, and never written
+/// when stdout is redirected.
 pub struct StreamingHandler {
     terminal: Terminal,
     buffer: String,
+    markdown: Option<StreamingMarkdown>,
+    parser: ThinkingParser,
+    thinking: String,
+    show_thinking: bool,
+    /// When the thinking on screen started, until the answer follows it
+    thinking_started: Option<Instant>,
 }
 
 impl StreamingHandler {
@@ -13,16 +37,46 @@ impl StreamingHandler {
     /// # Arguments
     /// * `terminal` - The terminal instance for output
     pub fn new(terminal: Terminal) -> Self {
+        Self::with_code_style(terminal, &CodeStyle::default())
+    }
+
+    /// Create a new StreamingHandler that highlights code blocks with the given style
+    ///
+    /// # Arguments
+    /// * `terminal` - The terminal instance for output
+    /// * `code_style` - Theme, line numbers and colors for code blocks
+    pub fn with_code_style(terminal: Terminal, code_style: &CodeStyle) -> Self {
+        let markdown = if ModeDetector::is_stdout_redirected() {
+            None
+        } else {
+            let (columns, rows) = crossterm::terminal::size()
+                .unwrap_or((DEFAULT_TERMINAL_COLUMNS, DEFAULT_TERMINAL_ROWS));
+            let renderer = MarkdownRenderer::with_code_style(code_style);
+            Some(StreamingMarkdown::new(renderer, rows.saturating_sub(1), columns))
+        };
         Self {
             terminal,
             buffer: String::new(),
+            markdown,
+            parser: ThinkingParser::new(),
+            thinking: String::new(),
+            show_thinking: false,
+            thinking_started: None,
         }
     }
 
+    /// Show the model's thinking as it streams, instead of collapsing it
+    pub fn with_thinking_shown(mut self, show_thinking: bool) -> Self {
+        self.show_thinking = show_thinking;
+        self
+    }
+
     /// Handle a chunk of streaming response
     ///
     /// This method is called for each chunk received from the backend.
     /// It displays the chunk immediately and accumulates it in the buffer.
+    /// When rendering markdown, completed blocks are committed and the
+    /// block still being written is redrawn in place.
     ///
     /// # Arguments
     /// * `chunk` - The text chunk to display and accumulate
@@ -30,20 +84,107 @@ impl StreamingHandler {
     /// # Returns
     /// Ok(()) on success, or an error if writing to terminal fails
     pub fn on_chunk(&mut self, chunk: String) -> Result<()> {
-        self.buffer.push_str(&chunk);
-        self.terminal.write(&chunk)?;
+        for segment in self.parser.push(&chunk) {
+            self.on_segment(segment)?;
+        }
+        Ok(())
+    }
+
+    fn on_segment(&mut self, segment: Segment) -> Result<()> {
+        match segment {
+            Segment::Thinking(text) => self.on_thinking(&text),
+            Segment::Answer(text) => {
+                self.end_thinking()?;
+                self.buffer.push_str(&text);
+                match self.markdown.as_mut() {
+                    Some(markdown) => {
+                        let frame = markdown.push(&text);
+                        self.draw(frame)
+                    }
+                    None => self.terminal.write(&text),
+                }
+            }
+        }
+    }
+
+    fn on_thinking(&mut self, text: &str) -> Result<()> {
+        let first = self.thinking.is_empty();
+        self.thinking.push_str(text);
+        if self.markdown.is_none() {
+            return Ok(());
+        }
+
+        if first {
+            self.thinking_started = Some(Instant::now());
+            // Replace the loading spinner
+            self.terminal.redraw(0, "")?;
+            if !self.show_thinking {
+                return self.terminal.write_dimmed("Thinking...\n");
+            }
+        }
+        if self.show_thinking {
+            self.terminal.write_dimmed(text)?;
+        }
         Ok(())
     }
 
+    /// Close the thinking on screen, once the answer starts or the stream ends
+    fn end_thinking(&mut self) -> Result<()> {
+        let Some(started) = self.thinking_started.take() else {
+            return Ok(());
+        };
+        if self.show_thinking {
+            let end = if self.thinking.ends_with('\n') { "\n" } else { "\n\n" };
+            self.terminal.write(end)
+        } else {
+            self.terminal.redraw(1, "")?;
+            self.terminal.write_dimmed(&format!(
+                "Thought for {:.1}s (/think to display)\n\n",
+                started.elapsed().as_secs_f32()
+            ))
+        }
+    }
+
+    /// Flush the parser and close the thinking, at the end of the stream
+    fn finish_thinking(&mut self) -> Result<()> {
+        for segment in self.parser.finish() {
+            self.on_segment(segment)?;
+        }
+        self.end_thinking()
+    }
+
+    /// Commit the block still being rendered, if any
+    ///
+    /// # Returns
+    /// Whether markdown rendering is active; its output always ends in a newline
+    fn finish_markdown(&mut self) -> Result<bool> {
+        match self.markdown.as_mut() {
+            Some(markdown) => {
+                let frame = markdown.finish();
+                self.draw(frame)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn draw(&mut self, frame: Frame) -> Result<()> {
+        self.terminal.redraw(frame.clear_rows, &frame.text)
+    }
+
     /// Finalize the streaming response
     ///
     /// This method should be called when streaming is complete.
-    /// It adds a newline and returns the complete accumulated response.
+    /// It commits any markdown still being rendered, or adds a newline after
+    /// raw output, and returns the complete accumulated response.
     ///
     /// # Returns
-    /// The complete response text accumulated from all chunks
+    /// The complete answer accumulated from all chunks, without the thinking
     pub fn finalize(&mut self) -> Result<String> {
-        self.terminal.write("\n")?;
+        self.finish_thinking()?;
+        if !self.finish_markdown()? {
+            self.terminal.write("\n")?;
+        }
         Ok(std::mem::take(&mut self.buffer))
     }
 
@@ -58,6 +199,12 @@ impl StreamingHandler {
         &self.buffer
     }
 
+    /// The model's thinking so far, if it produced any
+    pub fn thinking(&self) -> Option<String> {
+        let thinking = self.thinking.trim_end();
+        (!thinking.is_empty()).then(|| thinking.to_string())
+    }
+
     /// Handle an error during streaming
     ///
     /// This method displays an error message and returns the partial response
@@ -69,8 +216,9 @@ impl StreamingHandler {
     /// # Returns
     /// The partial response accumulated before the error
     pub fn handle_error(&mut self, error: &str) -> Result<String> {
+        self.finish_thinking()?;
         // Add newline after partial response if there is any
-        if !self.buffer.is_empty() {
+        if !self.finish_markdown()? && !self.buffer.is_empty() {
             self.terminal.write("\n")?;
         }
         self.terminal.write_error(error)?;
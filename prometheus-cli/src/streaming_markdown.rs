@@ -0,0 +1,386 @@
+use regex::Regex;
+use std::sync::OnceLock;
+use unicode_width::UnicodeWidthStr;
+
+use crate::highlight::CodeBlockState;
+use crate::markdown_renderer::{Fence, MarkdownRenderer};
+
+/// One redraw of streamed markdown output
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Frame {
+    /// Rows of the previous preview to erase, ending just above the cursor
+    pub clear_rows: u16,
+    /// Rendered text to print afterwards: newly committed blocks, then the new preview
+    pub text: String,
+}
+
+/// A piece of streamed markdown that is complete and can be committed
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Block {
+    /// Markdown outside code fences
+    Text(String),
+    /// A blank line between blocks
+    Blank,
+    /// A code fence was opened, with this language
+    CodeStart(Option<String>),
+    /// A complete line of code
+    Code(String),
+}
+
+/// Incremental markdown renderer for streamed responses
+///
+/// Text is split into blocks as it arrives. Closed blocks (paragraphs, list
+/// items, headings) are rendered once and committed; only the trailing open
+/// block is re-rendered on every chunk, replacing its previous preview.
+/// Lines of a code fence are committed as soon as they are complete, since
+/// code is never reflowed, so long code blocks don't have to be redrawn; the
+/// highlighting state is carried from line to line.
+pub struct StreamingMarkdown {
+    renderer: MarkdownRenderer,
+    /// Source text that has not been committed yet
+    pending: String,
+    /// Code fence open at the start of `pending`
+    fence: Option<Fence>,
+    /// Highlighting state of the committed lines of the open code fence
+    code: Option<CodeBlockState>,
+    /// Rows taken by the preview currently on screen
+    preview_rows: u16,
+    /// Taller previews are committed line by line, as rows scrolled off
+    /// screen can no longer be erased
+    max_preview_rows: u16,
+    /// Terminal width, past which lines wrap onto more rows
+    columns: u16,
+}
+
+impl StreamingMarkdown {
+    /// Create a renderer whose previews never exceed `max_preview_rows`
+    ///
+    /// # Arguments
+    /// * `renderer` - Renders the blocks
+    /// * `max_preview_rows` - Usually the terminal height minus one
+    /// * `columns` - The terminal width
+    pub fn new(renderer: MarkdownRenderer, max_preview_rows: u16, columns: u16) -> Self {
+        Self {
+            renderer,
+            pending: String::new(),
+            fence: None,
+            code: None,
+            preview_rows: 0,
+            max_preview_rows: max_preview_rows.max(1),
+            columns: columns.max(1),
+        }
+    }
+
+    /// Add a streamed chunk, returning the redraw it causes
+    pub fn push(&mut self, chunk: &str) -> Frame {
+        self.pending.push_str(chunk);
+        let blocks = self.take_closed_blocks();
+        let mut text = self.render_blocks(blocks);
+
+        let mut preview = self.render_open_block();
+        if rows(&preview, self.columns) > self.max_preview_rows {
+            let blocks = self.take_complete_lines();
+            text.push_str(&self.render_blocks(blocks));
+            preview = self.render_open_block();
+        }
+
+        let frame = Frame {
+            clear_rows: self.preview_rows,
+            text: text + &preview,
+        };
+        self.preview_rows = rows(&preview, self.columns);
+        frame
+    }
+
+    /// Commit everything left, including the open block, at the end of the stream
+    pub fn finish(&mut self) -> Frame {
+        let mut blocks = self.take_closed_blocks();
+        blocks.extend(self.take_complete_lines());
+        let rest = std::mem::take(&mut self.pending);
+        match self.fence.take() {
+            Some(_) if !rest.is_empty() => blocks.push(Block::Code(rest)),
+            Some(_) => {}
+            None if !rest.trim().is_empty() => blocks.push(Block::Text(rest)),
+            None => {}
+        }
+
+        let frame = Frame {
+            clear_rows: self.preview_rows,
+            text: self.render_blocks(blocks),
+        };
+        self.code = None;
+        self.preview_rows = 0;
+        frame
+    }
+
+    /// Remove the blocks that are closed from `pending`
+    fn take_closed_blocks(&mut self) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        let mut start = 0;
+        let mut pos = 0;
+
+        while let Some(offset) = self.pending[pos..].find('\n') {
+            let end = pos + offset + 1;
+            let line = self.pending[pos..end - 1].trim_end_matches('\r');
+
+            if let Some(fence) = &self.fence {
+                if fence.closed_by(line) {
+                    self.fence = None;
+                } else {
+                    blocks.push(Block::Code(line.to_string()));
+                }
+                start = end;
+            } else if let Some(fence) = Fence::open(line) {
+                push_block(&mut blocks, &self.pending[start..pos]);
+                blocks.push(Block::CodeStart(fence.language().map(str::to_string)));
+                self.fence = Some(fence);
+                start = end;
+            } else if line.trim().is_empty() {
+                push_block(&mut blocks, &self.pending[start..pos]);
+                blocks.push(Block::Blank);
+                start = end;
+            } else if is_heading(line) {
+                push_block(&mut blocks, &self.pending[start..pos]);
+                blocks.push(Block::Text(line.to_string()));
+                start = end;
+            } else if starts_list_item(line) {
+                // The new item stays open, the previous one is complete
+                push_block(&mut blocks, &self.pending[start..pos]);
+                start = pos;
+            }
+            pos = end;
+        }
+
+        self.pending.drain(..start);
+        blocks
+    }
+
+    /// Remove the complete lines of the open block, for previews too tall to redraw
+    fn take_complete_lines(&mut self) -> Vec<Block> {
+        if self.fence.is_some() {
+            // Complete code lines are committed already
+            return Vec::new();
+        }
+        match self.pending.rfind('\n') {
+            Some(end) => {
+                let lines: String = self.pending.drain(..=end).collect();
+                vec![Block::Text(lines.trim_end_matches(['\r', '\n']).to_string())]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Render the open block at the end of `pending`
+    fn render_open_block(&self) -> String {
+        let highlighter = self.renderer.highlighter();
+        match &self.fence {
+            Some(_) if self.pending.is_empty() => String::new(),
+            Some(fence) => {
+                // Highlight the partial line without advancing the committed state
+                let mut state = self.code.clone().unwrap_or_else(|| highlighter.start(fence.language()));
+                highlighter.highlight_line(&mut state, &self.pending)
+            }
+            None if self.pending.trim().is_empty() => String::new(),
+            None => self.renderer.render(self.pending.trim_end_matches(['\r', '\n'])),
+        }
+    }
+
+    fn render_blocks(&mut self, blocks: Vec<Block>) -> String {
+        let highlighter = self.renderer.highlighter();
+        let mut rendered = String::new();
+        for block in blocks {
+            match block {
+                Block::Text(text) => rendered.push_str(&self.renderer.render(&text)),
+                Block::Blank => rendered.push('\n'),
+                Block::CodeStart(language) => self.code = Some(highlighter.start(language.as_deref())),
+                Block::Code(line) => {
+                    let state = self.code.get_or_insert_with(|| highlighter.start(None));
+                    rendered.push_str(&highlighter.highlight_line(state, &line));
+                }
+            }
+        }
+        rendered
+    }
+}
+
+/// Add a block to be committed, unless it is empty
+fn push_block(blocks: &mut Vec<Block>, source: &str) {
+    let source = source.trim_end_matches(['\r', '\n']);
+    if !source.trim().is_empty() {
+        blocks.push(Block::Text(source.to_string()));
+    }
+}
+
+/// Whether a line is an ATX heading, e.g. "## Usage"
+fn is_heading(line: &str) -> bool {
+    let hashes = line.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&hashes) && (line.len() == hashes || line[hashes..].starts_with(' '))
+}
+
+/// Whether a line starts a top-level list item, e.g. "- item" or "2. item"
+fn starts_list_item(line: &str) -> bool {
+    if ["- ", "* ", "+ "].iter().any(|marker| line.starts_with(marker)) {
+        return true;
+    }
+    let rest = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    rest.len() < line.len() && (rest.starts_with(". ") || rest.starts_with(") "))
+}
+
+/// Number of terminal rows taken by rendered text, whose lines all end in a newline
+///
+/// Lines wider than the terminal wrap onto several rows; escape sequences take none.
+fn rows(rendered: &str, columns: u16) -> u16 {
+    static ESCAPE: OnceLock<Regex> = OnceLock::new();
+    let escape = ESCAPE.get_or_init(|| Regex::new("\x1b\\[[0-9;?]*[A-Za-z]").unwrap());
+
+    let columns = usize::from(columns.max(1));
+    let rows: usize = rendered
+        .split_inclusive('\n')
+        .filter_map(|line| line.strip_suffix('\n'))
+        .map(|line| escape.replace_all(line, "").width().div_ceil(columns).max(1))
+        .sum();
+    u16::try_from(rows).unwrap_or(u16::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::highlight::{CodeStyle, ColorSupport};
+
+    fn streaming_markdown(max_preview_rows: u16) -> StreamingMarkdown {
+        let code_style = CodeStyle {
+            colors: ColorSupport::NoColor,
+            ..CodeStyle::default()
+        };
+        StreamingMarkdown::new(MarkdownRenderer::with_code_style(&code_style), max_preview_rows, 80)
+    }
+
+    fn blocks(chunks: &[&str]) -> (Vec<Block>, StreamingMarkdown) {
+        let mut markdown = streaming_markdown(100);
+        let mut blocks = Vec::new();
+        for chunk in chunks {
+            markdown.pending.push_str(chunk);
+            blocks.extend(markdown.take_closed_blocks());
+        }
+        (blocks, markdown)
+    }
+
+    fn text(text: &str) -> Block {
+        Block::Text(text.to_string())
+    }
+
+    fn code(line: &str) -> Block {
+        Block::Code(line.to_string())
+    }
+
+    #[test]
+    fn test_paragraph_commits_at_blank_line() {
+        let (blocks, markdown) = blocks(&["First **bo", "ld** line\nsecond line\n", "\nNext"]);
+        assert_eq!(blocks, vec![text("First **bold** line\nsecond line"), Block::Blank]);
+        assert_eq!(markdown.pending, "Next");
+    }
+
+    #[test]
+    fn test_list_items_commit_when_next_item_starts() {
+        let (blocks, markdown) = blocks(&["Steps:\n- one\n  - nested\n", "- two\n2. three"]);
+        assert_eq!(blocks, vec![text("Steps:"), text("- one\n  - nested")]);
+        assert_eq!(markdown.pending, "- two\n2. three");
+    }
+
+    #[test]
+    fn test_heading_is_its_own_block() {
+        let (blocks, markdown) = blocks(&["Intro\n## Usage\nText"]);
+        assert_eq!(blocks, vec![text("Intro"), text("## Usage")]);
+        assert_eq!(markdown.pending, "Text");
+    }
+
+    #[test]
+    fn test_code_fence_lines_commit_as_they_complete() {
+        let (blocks, markdown) = blocks(&["Code:\n```rust\nfn main() {\n    let x", " = 1;\n}\n``", "`\nDone"]);
+        assert_eq!(
+            blocks,
+            vec![
+                text("Code:"),
+                Block::CodeStart(Some("rust".to_string())),
+                code("fn main() {"),
+                code("    let x = 1;"),
+                code("}"),
+            ]
+        );
+        assert!(markdown.fence.is_none());
+        assert_eq!(markdown.pending, "Done");
+    }
+
+    #[test]
+    fn test_blank_lines_inside_fence_are_code() {
+        let (blocks, markdown) = blocks(&["~~~~\na\n\n```\n~~~~\n"]);
+        assert_eq!(blocks, vec![Block::CodeStart(None), code("a"), code(""), code("```")]);
+        assert!(markdown.fence.is_none());
+    }
+
+    #[test]
+    fn test_frames_replace_previous_preview() {
+        let mut markdown = streaming_markdown(100);
+
+        let frame = markdown.push("Hello");
+        assert_eq!(frame.clear_rows, 0);
+        assert!(frame.text.contains("Hello"));
+
+        let frame = markdown.push(" world\n\nBye");
+        // The preview of "Hello" is replaced by the committed paragraph
+        assert_eq!(frame.clear_rows, 1);
+        assert!(frame.text.contains("Hello world"));
+        assert!(frame.text.contains("Bye"));
+
+        let frame = markdown.finish();
+        assert_eq!(frame.clear_rows, 1);
+        assert!(frame.text.contains("Bye"));
+        assert!(markdown.pending.is_empty());
+    }
+
+    #[test]
+    fn test_rows_count_wrapped_lines() {
+        assert_eq!(rows("", 10), 0);
+        assert_eq!(rows("short\n\n", 10), 2);
+        assert_eq!(rows(&format!("{}\n", "x".repeat(10)), 10), 1);
+        assert_eq!(rows(&format!("{}\n", "x".repeat(25)), 10), 3);
+        assert_eq!(rows("\x1b[1;31mcolored\x1b[0m\n", 7), 1);
+        assert_eq!(rows("日本語\n", 4), 2);
+    }
+
+    #[test]
+    fn test_wrapped_preview_is_cleared() {
+        let mut markdown = streaming_markdown(100);
+        markdown.push(&"word ".repeat(40));
+        let frame = markdown.push("end");
+        assert!(frame.clear_rows >= 3);
+    }
+
+    #[test]
+    fn test_tall_preview_commits_complete_lines() {
+        let mut markdown = streaming_markdown(2);
+        let frame = markdown.push("one\ntwo\nthree\nfour");
+        assert!(frame.text.contains("three"));
+        assert_eq!(markdown.pending, "four");
+        assert_eq!(markdown.preview_rows, 1);
+    }
+
+    #[test]
+    fn test_code_lines_are_highlighted_as_they_complete() {
+        let mut markdown = streaming_markdown(100);
+        let frame = markdown.push("```rust\nlet a");
+        assert_eq!(frame.text, "let a\n");
+
+        let frame = markdown.push(" = 1;\n");
+        assert_eq!(frame, Frame { clear_rows: 1, text: "let a = 1;\n".to_string() });
+    }
+
+    #[test]
+    fn test_finish_closes_open_fence() {
+        let mut markdown = streaming_markdown(100);
+        markdown.push("```\npartial");
+        let frame = markdown.finish();
+        assert_eq!(frame.text, "partial\n");
+        assert!(markdown.fence.is_none());
+    }
+}
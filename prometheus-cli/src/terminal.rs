@@ -1,10 +1,11 @@
 use anyhow::{Context, Result};
 use crossterm::{
     cursor,
-    execute,
-    style::{Color, Print, ResetColor, SetForegroundColor},
+    execute, queue,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
     terminal::{Clear, ClearType},
 };
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use std::io::{self, Write};
 
 /// Terminal handler for CLI I/O operations
@@ -53,6 +54,29 @@ impl Terminal {
         Ok(())
     }
 
+    /// Replace the last rows of output with new text
+    ///
+    /// Erases `rows` lines above the cursor and everything below it, then writes
+    /// `text` in their place. Used to redraw the open block of a streamed response.
+    ///
+    /// # Arguments
+    /// * `rows` - Number of lines above the cursor to erase
+    /// * `text` - The text to write
+    pub fn redraw(&mut self, rows: u16, text: &str) -> Result<()> {
+        if rows > 0 {
+            queue!(self.stdout, cursor::MoveUp(rows)).context("Failed to move cursor")?;
+        }
+        queue!(
+            self.stdout,
+            cursor::MoveToColumn(0),
+            Clear(ClearType::FromCursorDown),
+            Print(text)
+        )
+        .context("Failed to redraw output")?;
+        self.stdout.flush().context("Failed to flush stdout")?;
+        Ok(())
+    }
+
     /// Write a user prompt with formatting
     ///
     /// # Arguments
@@ -177,6 +201,22 @@ impl Terminal {
         Ok(())
     }
 
+    /// Write text dimmed, e.g. a model's thinking
+    ///
+    /// # Arguments
+    /// * `text` - The text to write dimmed
+    pub fn write_dimmed(&mut self, text: &str) -> Result<()> {
+        execute!(
+            self.stdout,
+            SetAttribute(Attribute::Dim),
+            Print(text),
+            SetAttribute(Attribute::Reset)
+        )
+        .context("Failed to write dimmed text")?;
+        self.stdout.flush().context("Failed to flush stdout")?;
+        Ok(())
+    }
+
     /// Clear the terminal screen
     pub fn clear_screen(&mut self) -> Result<()> {
         execute!(
@@ -225,6 +265,31 @@ impl Terminal {
     pub fn is_spinner_visible(&self) -> bool {
         self.spinner_visible
     }
+
+    /// Copy text to the clipboard with the OSC 52 escape sequence
+    ///
+    /// The terminal emulator sets its own clipboard, so this also works over
+    /// SSH. Terminals without OSC 52 support silently ignore the sequence.
+    pub fn copy_to_clipboard(&mut self, text: &str) -> Result<()> {
+        let in_tmux = std::env::var_os("TMUX").is_some();
+        write!(self.stdout, "{}", osc52_sequence(text, in_tmux))
+            .context("Failed to write clipboard sequence")?;
+        self.stdout.flush().context("Failed to flush stdout")?;
+        Ok(())
+    }
+}
+
+/// OSC 52 sequence setting the clipboard to `text`
+///
+/// Inside tmux the sequence is wrapped in a passthrough, so it reaches the
+/// outer terminal.
+fn osc52_sequence(text: &str, in_tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    if in_tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
 }
 
 impl Default for Terminal {
@@ -527,4 +592,10 @@ mod tests {
         terminal.write(&long_text).unwrap();
         assert_eq!(terminal.get_output().len(), 10000);
     }
+
+    #[test]
+    fn test_osc52_sequence() {
+        assert_eq!(osc52_sequence("hi", false), "\x1b]52;c;aGk=\x07");
+        assert_eq!(osc52_sequence("hi", true), "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\");
+    }
 }
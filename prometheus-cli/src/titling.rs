@@ -0,0 +1,23 @@
+use anyhow::Result;
+use crate::backend::BackendClient;
+use prometheus_chat::titling::{build_title_prompt, clean_title};
+
+/// Generate a title for a conversation from its first exchange
+///
+/// # Arguments
+/// * `client` - Backend to send the request to
+/// * `model` - Model to use (ideally a small, fast one)
+/// * `user` - The first user message
+/// * `assistant` - The first assistant response
+pub async fn generate_title(
+    client: &BackendClient,
+    model: &str,
+    user: &str,
+    assistant: &str,
+) -> Result<String> {
+    let prompt = build_title_prompt(user, assistant);
+    let response = client.send_prompt_streaming(&prompt, model, |_| Ok(())).await?;
+
+    clean_title(&response.text)
+        .ok_or_else(|| anyhow::anyhow!("Model returned an empty title"))
+}
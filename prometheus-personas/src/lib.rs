@@ -1,7 +1,29 @@
+//! Personas shared by the Prometheus CLI and desktop app
+//!
+//! A persona is a named system prompt. The built-in default persona is always
+//! available; more are read from `personas.json` in the Prometheus config
+//! directory, so both apps offer the same ones.
+
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Mutex;
 
+/// Name of the file personas are loaded from
+pub const PERSONAS_FILE: &str = "personas.json";
+
+/// Directory for configuration shared by the CLI and desktop app
+///
+/// `~/.config/prometheus` on Linux, `~/Library/Application Support/prometheus`
+/// on macOS and `%APPDATA%\prometheus` on Windows.
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("prometheus"))
+}
+
+/// Path of the shared personas file
+pub fn personas_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(PERSONAS_FILE))
+}
+
 /// Represents a predefined AI persona with specific behavioral characteristics
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Persona {
@@ -58,6 +80,24 @@ impl PersonaManager {
         }
     }
 
+    /// Creates a PersonaManager with the default persona and those in the shared personas file
+    ///
+    /// Falls back to `personas.json` in the working directory, where the
+    /// desktop app used to look, when the shared file does not exist.
+    pub fn load() -> Self {
+        match personas_path().filter(|path| path.exists()) {
+            Some(path) => Self::from_file(path),
+            None => Self::from_file(PathBuf::from(PERSONAS_FILE)),
+        }
+    }
+
+    /// Creates a PersonaManager with the default persona and those in `config_path`
+    pub fn from_file(config_path: PathBuf) -> Self {
+        let mut manager = Self::new();
+        manager.load_additional_personas(config_path);
+        manager
+    }
+
     /// Loads personas from a configuration file
     /// Requirements: 4.2, 4.4
     pub fn load_personas_from_file(config_path: PathBuf) -> Result<Vec<Persona>, String> {
@@ -100,7 +140,9 @@ impl PersonaManager {
                 }
             }
             Err(e) => {
-                log::warn!("Could not read persona config file: {}", e);
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Could not read persona config file: {}", e);
+                }
                 Ok(Vec::new()) // Return empty vec if file doesn't exist
             }
         }
@@ -270,6 +312,29 @@ mod tests {
         assert!(manager.get_active_persona().is_none());
     }
 
+    #[test]
+    fn test_from_file_adds_personas_after_default() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let config_path = temp_dir.path().join(PERSONAS_FILE);
+        std::fs::write(
+            &config_path,
+            r#"{"personas": [{"id": "teacher", "name": "Patient Teacher", "icon": "🎓", "system_prompt": "Explain patiently."}]}"#,
+        )
+        .unwrap();
+
+        let manager = PersonaManager::from_file(config_path);
+        let ids: Vec<_> = manager.get_all_personas().iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, vec!["default", "teacher"]);
+        assert_eq!(manager.get_persona("teacher").unwrap().system_prompt, "Explain patiently.");
+    }
+
+    #[test]
+    fn test_missing_file_keeps_default_persona() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = PersonaManager::from_file(temp_dir.path().join(PERSONAS_FILE));
+        assert_eq!(manager.get_all_personas().len(), 1);
+    }
+
     #[test]
     fn test_set_nonexistent_persona_fails() {
         let manager = PersonaManager::new();
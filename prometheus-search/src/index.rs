@@ -1,7 +1,7 @@
-use crate::conversation::Conversation;
-use crate::search::SearchResult;
+use crate::normalize::fold;
 use regex::Regex;
 use std::collections::HashMap;
+use std::sync::OnceLock;
 
 /// Handles the indexing of conversations for search
 pub struct SearchIndexer {
@@ -22,52 +22,36 @@ impl SearchIndexer {
         Self { min_word_length }
     }
 
-    /// Index a conversation and return a map of terms to search results
-    pub fn index_conversation(&self, conversation: &Conversation) -> HashMap<String, SearchResult> {
-        let mut results = HashMap::new();
+    /// Index a message's content
+    ///
+    /// # Returns
+    /// Each lowercase term with the byte range of every occurrence, in order
+    pub fn index_message(&self, content: &str) -> HashMap<String, Vec<(usize, usize)>> {
+        let mut terms: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
 
-        for (message_index, message) in conversation.messages.iter().enumerate() {
-            let tokens = self.tokenize(&message.content);
-            
-            for (term, positions) in tokens {
-                // Skip terms that are too short
-                if term.len() < self.min_word_length {
-                    continue;
-                }
-
-                let context = self.extract_context(&message.content, &positions);
-                
-                let result = SearchResult {
-                    conversation_id: conversation.id.clone(),
-                    message_index,
-                    match_positions: positions,
-                    context,
-                    role: message.role.clone(),
-                };
-
-                // Use lowercase term as key for case-insensitive indexing
-                results.insert(term.to_lowercase(), result);
+        for (term, positions) in self.tokenize(content) {
+            // Skip terms that are too short
+            if term.len() < self.min_word_length {
+                continue;
             }
+            // Spellings differing in case or accents share one entry
+            terms.entry(Self::normalize_text(&term)).or_default().extend(positions);
         }
 
-        results
+        for positions in terms.values_mut() {
+            positions.sort_unstable();
+        }
+        terms
     }
 
     /// Tokenize text into terms and their positions
     fn tokenize(&self, text: &str) -> HashMap<String, Vec<(usize, usize)>> {
         let mut tokens: HashMap<String, Vec<(usize, usize)>> = HashMap::new();
         
-        // Match word characters (letters, numbers, underscores)
-        let word_regex = Regex::new(r"\w+").unwrap();
-        
-        for mat in word_regex.find_iter(text) {
-            let term = mat.as_str().to_string();
-            let start = mat.start();
-            let end = mat.end();
-            
+        for (start, end) in word_ranges(text) {
             tokens
-                .entry(term)
-                .or_insert_with(Vec::new)
+                .entry(text[start..end].to_string())
+                .or_default()
                 .push((start, end));
         }
         
@@ -75,7 +59,7 @@ impl SearchIndexer {
     }
 
     /// Extract context around match positions
-    fn extract_context(&self, text: &str, positions: &[(usize, usize)]) -> String {
+    pub(crate) fn extract_context(&self, text: &str, positions: &[(usize, usize)]) -> String {
         if positions.is_empty() {
             return String::new();
         }
@@ -86,11 +70,7 @@ impl SearchIndexer {
         // Context window: 50 characters before and after
         let context_window = 50;
         
-        let context_start = if start > context_window {
-            start - context_window
-        } else {
-            0
-        };
+        let context_start = start.saturating_sub(context_window);
         
         let context_end = std::cmp::min(end + context_window, text.len());
         
@@ -128,12 +108,26 @@ impl SearchIndexer {
         index
     }
 
-    /// Normalize text for indexing (lowercase, trim)
+    /// Normalize text for indexing (trim, fold accents and compatibility forms, lowercase)
     pub fn normalize_text(text: &str) -> String {
-        text.trim().to_lowercase()
+        fold(text.trim()).to_lowercase()
     }
 }
 
+/// Byte ranges of the words in `text`
+///
+/// Words are runs of letters, digits, underscores and combining marks, so an
+/// accent written as a separate character stays part of its word.
+pub(crate) fn word_ranges(text: &str) -> Vec<(usize, usize)> {
+    word_regex().find_iter(text).map(|mat| (mat.start(), mat.end())).collect()
+}
+
+/// The pattern matching one word, compiled once
+pub(crate) fn word_regex() -> &'static Regex {
+    static WORD: OnceLock<Regex> = OnceLock::new();
+    WORD.get_or_init(|| Regex::new(r"\w+").unwrap())
+}
+
 impl Default for SearchIndexer {
     fn default() -> Self {
         Self::new()
@@ -143,7 +137,6 @@ impl Default for SearchIndexer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::app::ChatMessage;
 
     #[test]
     fn test_tokenize() {
@@ -189,42 +182,40 @@ mod tests {
     }
 
     #[test]
-    fn test_index_conversation() {
+    fn test_index_message() {
         let indexer = SearchIndexer::new();
-        let mut conversation = Conversation::new("Test".to_string(), None);
-        conversation.add_message(ChatMessage::new(
-            "user".to_string(),
-            "Hello world".to_string(),
-        ));
-        
-        let results = indexer.index_conversation(&conversation);
-        
-        // Should have indexed "hello" and "world" (lowercase keys)
-        assert!(results.contains_key("hello"));
-        assert!(results.contains_key("world"));
+        let terms = indexer.index_message("Hello world, hello again");
+
+        // Keys are lowercase and keep every occurrence
+        assert_eq!(terms.get("hello").unwrap(), &vec![(0, 5), (13, 18)]);
+        assert!(terms.contains_key("world"));
+        assert!(!terms.contains_key("Hello"));
     }
 
     #[test]
     fn test_min_word_length() {
         let indexer = SearchIndexer::with_min_word_length(3);
-        let mut conversation = Conversation::new("Test".to_string(), None);
-        conversation.add_message(ChatMessage::new(
-            "user".to_string(),
-            "I am testing".to_string(),
-        ));
-        
-        let results = indexer.index_conversation(&conversation);
-        
+        let terms = indexer.index_message("I am testing");
+
         // "I" and "am" should be filtered out (< 3 chars)
-        assert!(!results.contains_key("i"));
-        assert!(!results.contains_key("am"));
-        assert!(results.contains_key("testing"));
+        assert!(!terms.contains_key("i"));
+        assert!(!terms.contains_key("am"));
+        assert!(terms.contains_key("testing"));
     }
 
     #[test]
     fn test_normalize_text() {
         assert_eq!(SearchIndexer::normalize_text("  Hello World  "), "hello world");
         assert_eq!(SearchIndexer::normalize_text("UPPERCASE"), "uppercase");
+        assert_eq!(SearchIndexer::normalize_text("Crème Brûlée"), "creme brulee");
+    }
+
+    #[test]
+    fn test_index_message_folds_accents() {
+        let indexer = SearchIndexer::new();
+        // Precomposed and combining accents index as the same term, at their original positions
+        let terms = indexer.index_message("Café or cafe\u{301}");
+        assert_eq!(terms.get("cafe").unwrap(), &vec![(0, 5), (9, 15)]);
     }
 
     #[test]
@@ -0,0 +1,1735 @@
+//! Conversation history search shared by the Prometheus CLI and desktop app
+//!
+//! The index keeps a posting list per term with every message the term occurs
+//! in, and ranks matching messages with BM25, boosted for recent conversations.
+//! Queries support phrases, boolean operators and field filters; see [`parser`].
+//! Semantic search compares message embeddings stored in a [`VectorIndex`], and
+//! can be blended with keyword ranking.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub mod index;
+pub mod normalize;
+pub mod parser;
+pub mod query;
+pub mod vector;
+
+pub use index::SearchIndexer;
+pub use normalize::{language_from_name, Language};
+pub use parser::{parse, Filter, QueryError, QueryNode};
+pub use query::{SearchQuery, SearchQueryBuilder};
+pub use vector::{PendingMessage, SemanticMatch, VectorIndex};
+
+use query::{MatchKind, TermMatch, WordMatcher};
+
+/// Version of the persisted index format; older indexes are rebuilt
+const INDEX_VERSION: u32 = 3;
+
+/// BM25 term frequency saturation
+const BM25_K1: f64 = 1.2;
+
+/// BM25 length normalization
+const BM25_B: f64 = 0.75;
+
+/// Age at which a conversation's recency boost has halved
+const RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Boost given to a conversation updated just now; it decays towards none with age
+const RECENCY_WEIGHT: f64 = 0.5;
+
+/// Score multiplier for words matched only with typos, so exact matches rank first
+const FUZZY_WEIGHT: f64 = 0.5;
+
+/// A message as seen by the search index
+#[derive(Debug, Clone, Copy)]
+pub struct SearchMessage<'a> {
+    pub role: &'a str,
+    pub content: &'a str,
+    /// When the message was sent, as an RFC 3339 timestamp
+    pub timestamp: Option<&'a str>,
+    /// Model that generated the message
+    pub model: Option<&'a str>,
+}
+
+/// A conversation that can be added to the search index
+pub trait Searchable {
+    /// Unique ID of the conversation
+    fn search_id(&self) -> &str;
+
+    /// The messages of the conversation, in order
+    fn search_messages(&self) -> Vec<SearchMessage<'_>>;
+
+    /// When the conversation was last updated, as an RFC 3339 timestamp
+    ///
+    /// Used to rank recent conversations higher; `None` gives no boost.
+    fn search_updated_at(&self) -> Option<&str> {
+        None
+    }
+
+    /// Display name of the conversation, for `in:` filters
+    fn search_name(&self) -> Option<&str> {
+        None
+    }
+
+    /// Model of the conversation, for `model:` filters on messages without one
+    fn search_model(&self) -> Option<&str> {
+        None
+    }
+
+    /// Tags of the conversation, for `tag:` filters
+    fn search_tags(&self) -> Vec<&str> {
+        Vec::new()
+    }
+}
+
+/// Main search engine that coordinates indexing and querying
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchEngine {
+    /// Format version, checked when loading
+    version: u32,
+    /// Posting lists: every message each lowercase term occurs in
+    postings: HashMap<String, Vec<Posting>>,
+    /// Indexed conversations by ID
+    conversations: HashMap<String, IndexedConversation>,
+    /// Number of indexed messages, for BM25
+    message_count: usize,
+    /// Total length of all indexed messages in terms, for BM25
+    total_length: usize,
+}
+
+/// The occurrences of a term in one message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Posting {
+    conversation_id: String,
+    message_index: usize,
+    /// Byte ranges of every occurrence in the message content
+    positions: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedConversation {
+    name: Option<String>,
+    model: Option<String>,
+    /// Lowercase tags
+    tags: Vec<String>,
+    /// Last update as a Unix timestamp, for the recency boost
+    updated_at: Option<i64>,
+    messages: Vec<IndexedMessage>,
+    /// Terms with postings for this conversation, so it can be removed without scanning the index
+    terms: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedMessage {
+    role: String,
+    content: String,
+    model: Option<String>,
+    /// Day the message was sent as YYYY-MM-DD, or the conversation's last update if unknown
+    date: Option<String>,
+    /// Number of indexed terms in the message
+    length: usize,
+}
+
+/// Represents a single search result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    /// ID of the conversation containing the match
+    pub conversation_id: String,
+    /// Index of the message within the conversation
+    pub message_index: usize,
+    /// Positions of matches within the message content (start, end)
+    pub match_positions: Vec<(usize, usize)>,
+    /// Surrounding text context for the match
+    pub context: String,
+    /// The role of the message (user/assistant)
+    pub role: String,
+    /// Relevance; results are ordered by descending score
+    pub score: f64,
+}
+
+impl SearchResult {
+    /// Byte ranges of the query's matches within the context, for highlighting
+    pub fn context_matches(&self, query: &SearchQuery) -> Vec<(usize, usize)> {
+        let mut positions = query.search_in_content(&self.context);
+        // Lowercasing can shift byte offsets in some scripts; drop matches that no longer line up
+        positions.retain(|&(start, end)| {
+            self.context.is_char_boundary(start) && self.context.is_char_boundary(end)
+        });
+        positions
+    }
+}
+
+/// Messages matching (part of) a query, by conversation ID and message index
+type Candidates<'a> = HashMap<(&'a str, usize), Candidate>;
+
+/// Occurrences of a word or phrase, by conversation ID and message index
+type Occurrences<'a> = HashMap<(&'a str, usize), Vec<(usize, usize)>>;
+
+/// A message matching (part of) the query
+#[derive(Default)]
+struct Candidate {
+    score: f64,
+    positions: Vec<(usize, usize)>,
+}
+
+impl Candidate {
+    fn merge(&mut self, other: Candidate) {
+        self.score += other.score;
+        self.positions.extend(other.positions);
+    }
+}
+
+impl SearchEngine {
+    /// Create a new empty search engine
+    pub fn new() -> Self {
+        Self {
+            version: INDEX_VERSION,
+            postings: HashMap::new(),
+            conversations: HashMap::new(),
+            message_count: 0,
+            total_length: 0,
+        }
+    }
+
+    /// Index a conversation for searching, replacing any earlier version of it
+    pub fn index_conversation<C: Searchable>(&mut self, conversation: &C) {
+        let id = conversation.search_id();
+        self.remove_conversation(id);
+
+        let parse_timestamp = |timestamp: &str| chrono::DateTime::parse_from_rfc3339(timestamp).ok();
+        let conversation_updated = conversation.search_updated_at().and_then(parse_timestamp);
+
+        let indexer = SearchIndexer::new();
+        let mut messages = Vec::new();
+        let mut terms = Vec::new();
+
+        for (message_index, message) in conversation.search_messages().into_iter().enumerate() {
+            let message_terms = indexer.index_message(message.content);
+            let length = message_terms.values().map(|positions| positions.len()).sum();
+
+            for (term, positions) in message_terms {
+                let postings = self.postings.entry(term.clone()).or_default();
+                // Postings for a conversation are added together, so only the last can be ours
+                if postings.last().map(|p| p.conversation_id != id).unwrap_or(true) {
+                    terms.push(term);
+                }
+                postings.push(Posting {
+                    conversation_id: id.to_string(),
+                    message_index,
+                    positions,
+                });
+            }
+
+            let date = message
+                .timestamp
+                .and_then(parse_timestamp)
+                .or(conversation_updated)
+                .map(|timestamp| timestamp.date_naive().format("%Y-%m-%d").to_string());
+
+            self.message_count += 1;
+            self.total_length += length;
+            messages.push(IndexedMessage {
+                role: message.role.to_string(),
+                content: message.content.to_string(),
+                model: message.model.map(str::to_string),
+                date,
+                length,
+            });
+        }
+
+        self.conversations.insert(
+            id.to_string(),
+            IndexedConversation {
+                name: conversation.search_name().map(str::to_string),
+                model: conversation.search_model().map(str::to_string),
+                tags: conversation.search_tags().iter().map(|tag| tag.to_lowercase()).collect(),
+                updated_at: conversation_updated.map(|timestamp| timestamp.timestamp()),
+                messages,
+                terms,
+            },
+        );
+    }
+
+    /// Remove a conversation from the index
+    ///
+    /// Only the posting lists of the conversation's own terms are touched.
+    pub fn remove_conversation(&mut self, conversation_id: &str) {
+        let Some(conversation) = self.conversations.remove(conversation_id) else {
+            return;
+        };
+
+        for term in &conversation.terms {
+            if let Some(postings) = self.postings.get_mut(term) {
+                postings.retain(|p| p.conversation_id != conversation_id);
+                if postings.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+
+        self.message_count -= conversation.messages.len();
+        self.total_length -= conversation.messages.iter().map(|m| m.length).sum::<usize>();
+    }
+
+    /// Search for messages matching the query, most relevant first
+    pub fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>, QueryError> {
+        self.search_at(query, chrono::Utc::now().timestamp())
+    }
+
+    /// Search as if the current time were `now` (a Unix timestamp)
+    ///
+    /// Each word and phrase is scored with BM25 over messages, and the total is
+    /// boosted by the conversation's recency. Queries of only filters match with
+    /// a score of zero and are ordered by recency.
+    pub fn search_at(&self, query: &SearchQuery, now: i64) -> Result<Vec<SearchResult>, QueryError> {
+        let Some(node) = query.parse()? else {
+            return Ok(Vec::new());
+        };
+        if self.message_count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let indexer = SearchIndexer::new();
+        let mut results: Vec<SearchResult> = self
+            .evaluate(&node, query)
+            .into_iter()
+            .map(|((conversation_id, message_index), mut candidate)| {
+                candidate.positions.sort_unstable();
+                candidate.positions.dedup();
+
+                let message = self.message((conversation_id, message_index));
+                let boost = self.recency_boost(conversation_id, now);
+                // Messages matched only by filters show their beginning
+                let context = if candidate.positions.is_empty() {
+                    indexer.extract_context(&message.content, &[(0, 0)])
+                } else {
+                    indexer.extract_context(&message.content, &candidate.positions)
+                };
+                SearchResult {
+                    conversation_id: conversation_id.to_string(),
+                    message_index,
+                    context,
+                    match_positions: candidate.positions,
+                    role: message.role.clone(),
+                    score: candidate.score * boost,
+                }
+            })
+            .collect();
+
+        results.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| {
+                    let updated_at = |id: &str| self.conversations[id].updated_at;
+                    updated_at(&b.conversation_id).cmp(&updated_at(&a.conversation_id))
+                })
+                .then_with(|| a.conversation_id.cmp(&b.conversation_id))
+                .then(a.message_index.cmp(&b.message_index))
+        });
+        Ok(results)
+    }
+
+    /// Results for semantically similar messages, scored by similarity
+    ///
+    /// Matches for conversations or messages that are no longer indexed are skipped.
+    pub fn semantic_results(&self, matches: &[SemanticMatch]) -> Vec<SearchResult> {
+        let indexer = SearchIndexer::new();
+        matches
+            .iter()
+            .filter_map(|m| {
+                let message = self.conversations.get(&m.conversation_id)?.messages.get(m.message_index)?;
+                Some(SearchResult {
+                    conversation_id: m.conversation_id.clone(),
+                    message_index: m.message_index,
+                    match_positions: Vec::new(),
+                    context: indexer.extract_context(&message.content, &[(0, 0)]),
+                    role: message.role.clone(),
+                    score: f64::from(m.similarity),
+                })
+            })
+            .collect()
+    }
+
+    /// Blend keyword and semantic ranking
+    ///
+    /// Keyword scores are scaled so the best result scores 1, then combined with
+    /// the (non-negative) similarity as `(1 - semantic_weight) * keyword +
+    /// semantic_weight * similarity`. Messages found by either method are included.
+    pub fn search_hybrid(
+        &self,
+        query: &SearchQuery,
+        matches: &[SemanticMatch],
+        semantic_weight: f64,
+    ) -> Result<Vec<SearchResult>, QueryError> {
+        let semantic_weight = semantic_weight.clamp(0.0, 1.0);
+        let keyword = self.search(query)?;
+        let best = keyword.first().map(|r| r.score).filter(|&score| score > 0.0).unwrap_or(1.0);
+
+        let mut results: HashMap<(String, usize), SearchResult> = HashMap::new();
+        for mut result in keyword {
+            result.score = (1.0 - semantic_weight) * result.score / best;
+            results.insert((result.conversation_id.clone(), result.message_index), result);
+        }
+        for result in self.semantic_results(matches) {
+            let similarity = semantic_weight * result.score.max(0.0);
+            results
+                .entry((result.conversation_id.clone(), result.message_index))
+                .and_modify(|existing| existing.score += similarity)
+                .or_insert(SearchResult { score: similarity, ..result });
+        }
+
+        let mut results: Vec<SearchResult> = results.into_values().collect();
+        results.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.conversation_id.cmp(&b.conversation_id))
+                .then(a.message_index.cmp(&b.message_index))
+        });
+        Ok(results)
+    }
+
+    /// Messages matching a query node, with their scores and match positions
+    fn evaluate(&self, node: &QueryNode, query: &SearchQuery) -> Candidates<'_> {
+        match node {
+            QueryNode::Term { word, prefix } => {
+                let mode = if *prefix { TermMatch::Prefix } else { query.word_match() };
+                let (direct, fuzzy) = self.word_matches(&WordMatcher::new(query, word, mode));
+                let mut candidates = self.score(direct, 1.0);
+                for (key, candidate) in self.score(fuzzy, FUZZY_WEIGHT) {
+                    candidates.entry(key).or_default().merge(candidate);
+                }
+                candidates
+            }
+            QueryNode::Phrase(words) => self.score(self.phrase_matches(query, words), 1.0),
+            QueryNode::Filter(filter) => self.filtered(self.all_messages(), std::slice::from_ref(filter)),
+            QueryNode::Not(inner) => self.excluding(self.all_messages(), std::slice::from_ref(inner), query),
+            QueryNode::And(nodes) => {
+                let mut filters = Vec::new();
+                let mut excluded = Vec::new();
+                let mut required = Vec::new();
+                for node in nodes {
+                    match node {
+                        QueryNode::Filter(filter) => filters.push(filter.clone()),
+                        QueryNode::Not(inner) => excluded.push((**inner).clone()),
+                        node => required.push(node),
+                    }
+                }
+
+                let mut candidates = match required.split_first() {
+                    Some((first, rest)) => {
+                        let mut candidates = self.evaluate(first, query);
+                        for node in rest {
+                            let mut matched = self.evaluate(node, query);
+                            candidates = candidates
+                                .into_iter()
+                                .filter_map(|(key, mut candidate)| {
+                                    candidate.merge(matched.remove(&key)?);
+                                    Some((key, candidate))
+                                })
+                                .collect();
+                        }
+                        candidates
+                    }
+                    None => self.all_messages(),
+                };
+                candidates = self.filtered(candidates, &filters);
+                self.excluding(candidates, &excluded, query)
+            }
+            QueryNode::Or(nodes) => {
+                let mut candidates = Candidates::new();
+                for node in nodes {
+                    for (key, candidate) in self.evaluate(node, query) {
+                        candidates.entry(key).or_default().merge(candidate);
+                    }
+                }
+                candidates
+            }
+        }
+    }
+
+    /// Every indexed message, with no score
+    fn all_messages(&self) -> Candidates<'_> {
+        self.conversations
+            .iter()
+            .flat_map(|(id, conversation)| {
+                (0..conversation.messages.len()).map(move |index| ((id.as_str(), index), Candidate::default()))
+            })
+            .collect()
+    }
+
+    /// Keep the candidates matching every filter
+    fn filtered<'a>(&self, mut candidates: Candidates<'a>, filters: &[Filter]) -> Candidates<'a> {
+        if !filters.is_empty() {
+            candidates.retain(|&key, _| filters.iter().all(|filter| self.matches_filter(key, filter)));
+        }
+        candidates
+    }
+
+    /// Drop the candidates matching any of the excluded nodes
+    fn excluding<'a>(&self, mut candidates: Candidates<'a>, excluded: &[QueryNode], query: &SearchQuery) -> Candidates<'a> {
+        for node in excluded {
+            let matched = self.evaluate(node, query);
+            candidates.retain(|key, _| !matched.contains_key(key));
+        }
+        candidates
+    }
+
+    fn matches_filter(&self, (conversation_id, message_index): (&str, usize), filter: &Filter) -> bool {
+        let conversation = &self.conversations[conversation_id];
+        let message = &conversation.messages[message_index];
+        match filter {
+            Filter::Role(role) => message.role.to_lowercase() == *role,
+            Filter::Model(model) => message
+                .model
+                .as_ref()
+                .or(conversation.model.as_ref())
+                .map(|name| name.to_lowercase().contains(model.as_str()))
+                .unwrap_or(false),
+            Filter::Before(date) => message
+                .date
+                .as_ref()
+                .map(|sent| *sent < date.format("%Y-%m-%d").to_string())
+                .unwrap_or(false),
+            Filter::After(date) => message
+                .date
+                .as_ref()
+                .map(|sent| *sent > date.format("%Y-%m-%d").to_string())
+                .unwrap_or(false),
+            Filter::Tag(tag) => conversation.tags.contains(tag),
+            Filter::In(target) => {
+                conversation_id.starts_with(target.as_str())
+                    || conversation
+                        .name
+                        .as_ref()
+                        .map(|name| name.to_lowercase().contains(&target.to_lowercase()))
+                        .unwrap_or(false)
+            }
+        }
+    }
+
+    /// Score each message's matches for one word or phrase with BM25, scaled by `weight`
+    fn score<'a>(&self, matches: Occurrences<'a>, weight: f64) -> Candidates<'a> {
+        let message_count = self.message_count as f64;
+        let average_length = (self.total_length as f64 / message_count).max(1.0);
+        let document_frequency = matches.len() as f64;
+        let idf = ((message_count - document_frequency + 0.5) / (document_frequency + 0.5) + 1.0).ln();
+
+        matches
+            .into_iter()
+            .map(|(key, positions)| {
+                let length = self.message(key).length as f64;
+                let frequency = positions.len() as f64;
+                let score = idf * frequency * (BM25_K1 + 1.0)
+                    / (frequency + BM25_K1 * (1.0 - BM25_B + BM25_B * length / average_length));
+                (key, Candidate { score: score * weight, positions })
+            })
+            .collect()
+    }
+
+    /// Occurrences of one query word, grouped by message
+    ///
+    /// # Returns
+    /// Occurrences matching as typed or by stem, and occurrences only matching with typos
+    ///
+    /// Whole words are a single lookup; substrings, prefixes, stems and typos scan every
+    /// indexed term, so their cost grows with the vocabulary rather than the matches.
+    fn word_matches(&self, matcher: &WordMatcher) -> (Occurrences<'_>, Occurrences<'_>) {
+        let terms: Vec<(&Vec<Posting>, MatchKind)> = match matcher.exact_key() {
+            Some(key) => self.postings.get(key).map(|postings| (postings, MatchKind::Direct)).into_iter().collect(),
+            None => self
+                .postings
+                .iter()
+                .filter_map(|(term, postings)| Some((postings, matcher.match_term(term)?)))
+                .collect(),
+        };
+
+        let mut direct = Occurrences::new();
+        let mut fuzzy = Occurrences::new();
+        for (postings, kind) in terms {
+            let matches = if kind == MatchKind::Direct { &mut direct } else { &mut fuzzy };
+            for posting in postings {
+                let key = (posting.conversation_id.as_str(), posting.message_index);
+                let content = &self.message(key).content;
+                // The index is folded and lowercase, so check each occurrence if case or accents matter
+                let positions = posting
+                    .positions
+                    .iter()
+                    .filter(|&&(start, end)| matcher.matches_occurrence(&content[start..end]));
+                matches.entry(key).or_default().extend(positions);
+            }
+        }
+        direct.retain(|_, positions| !positions.is_empty());
+        fuzzy.retain(|key, positions| !positions.is_empty() && !direct.contains_key(key));
+        (direct, fuzzy)
+    }
+
+    /// Occurrences of a phrase, grouped by message
+    ///
+    /// Each word must match exactly, and be followed by the next with nothing
+    /// but spaces and punctuation in between.
+    fn phrase_matches(&self, query: &SearchQuery, words: &[String]) -> Occurrences<'_> {
+        let mut word_occurrences = Vec::new();
+        for word in words {
+            let (occurrences, _) = self.word_matches(&WordMatcher::exact(query, word));
+            word_occurrences.push(occurrences);
+        }
+        let Some((first, rest)) = word_occurrences.split_first() else {
+            return Occurrences::new();
+        };
+
+        let mut matches = Occurrences::new();
+        for (&key, starts) in first {
+            let content = &self.message(key).content;
+            let Some(rest) = rest.iter().map(|occurrences| occurrences.get(&key)).collect::<Option<Vec<_>>>() else {
+                continue;
+            };
+            for &(start, first_end) in starts {
+                let mut end = first_end;
+                let complete = rest.iter().all(|positions| {
+                    let next = positions.iter().find(|&&(next_start, _)| {
+                        next_start >= end && !content[end..next_start].chars().any(is_word_char)
+                    });
+                    match next {
+                        Some(&(_, next_end)) => {
+                            end = next_end;
+                            true
+                        }
+                        None => false,
+                    }
+                });
+                if complete {
+                    matches.entry(key).or_default().push((start, end));
+                }
+            }
+        }
+        matches
+    }
+
+    fn message(&self, (conversation_id, message_index): (&str, usize)) -> &IndexedMessage {
+        &self.conversations[conversation_id].messages[message_index]
+    }
+
+    /// Multiplier favouring recently updated conversations
+    fn recency_boost(&self, conversation_id: &str, now: i64) -> f64 {
+        match self.conversations[conversation_id].updated_at {
+            Some(updated_at) => {
+                let age_days = (now - updated_at).max(0) as f64 / 86_400.0;
+                1.0 + RECENCY_WEIGHT * 0.5_f64.powf(age_days / RECENCY_HALF_LIFE_DAYS)
+            }
+            None => 1.0,
+        }
+    }
+
+    /// Rebuild the entire search index from scratch
+    pub fn rebuild_index<C: Searchable>(&mut self, conversations: &[C]) {
+        self.clear();
+
+        for conversation in conversations {
+            self.index_conversation(conversation);
+        }
+    }
+
+    /// Get the number of indexed conversations
+    pub fn indexed_count(&self) -> usize {
+        self.conversations.len()
+    }
+
+    /// Check if a conversation is indexed
+    pub fn is_indexed(&self, conversation_id: &str) -> bool {
+        self.conversations.contains_key(conversation_id)
+    }
+
+    /// Clear the entire search index
+    pub fn clear(&mut self) {
+        self.postings.clear();
+        self.conversations.clear();
+        self.message_count = 0;
+        self.total_length = 0;
+    }
+
+    /// Save the search index to a file for persistence
+    pub fn save_to_file(&self, path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Load the search index from a file
+    pub fn load_from_file(path: &std::path::Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let json = std::fs::read_to_string(path)?;
+        Self::from_json(&json)
+    }
+
+    /// Load a search index saved with `save_to_file`
+    ///
+    /// Fails for indexes in an older format, which should be rebuilt.
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let engine: Self = serde_json::from_str(json)?;
+        if engine.version != INDEX_VERSION {
+            return Err(format!("Unsupported search index version {}", engine.version).into());
+        }
+        Ok(engine)
+    }
+}
+
+/// Characters that make up words, as matched by `\w`
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || unicode_normalization::char::is_combining_mark(c)
+}
+
+impl Default for SearchEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Minimal conversation types for tests
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::{SearchMessage, Searchable};
+
+    #[derive(Debug, Clone)]
+    pub struct ChatMessage {
+        pub role: String,
+        pub content: String,
+        pub timestamp: Option<String>,
+        pub model: Option<String>,
+    }
+
+    impl ChatMessage {
+        pub fn new(role: String, content: String) -> Self {
+            Self {
+                role,
+                content,
+                timestamp: None,
+                model: None,
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    pub struct Conversation {
+        pub id: String,
+        pub name: String,
+        pub messages: Vec<ChatMessage>,
+        pub updated_at: Option<String>,
+        pub tags: Vec<String>,
+    }
+
+    impl Conversation {
+        pub fn new(name: String, _model: Option<String>) -> Self {
+            use std::sync::atomic::{AtomicUsize, Ordering};
+            static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+            Self {
+                id: format!("{}-{}", name.to_lowercase(), NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+                name,
+                messages: Vec::new(),
+                updated_at: None,
+                tags: Vec::new(),
+            }
+        }
+
+        pub fn add_message(&mut self, message: ChatMessage) {
+            self.messages.push(message);
+        }
+    }
+
+    impl Searchable for Conversation {
+        fn search_id(&self) -> &str {
+            &self.id
+        }
+
+        fn search_messages(&self) -> Vec<SearchMessage<'_>> {
+            self.messages
+                .iter()
+                .map(|m| SearchMessage {
+                    role: &m.role,
+                    content: &m.content,
+                    timestamp: m.timestamp.as_deref(),
+                    model: m.model.as_deref(),
+                })
+                .collect()
+        }
+
+        fn search_updated_at(&self) -> Option<&str> {
+            self.updated_at.as_deref()
+        }
+
+        fn search_name(&self) -> Option<&str> {
+            Some(&self.name)
+        }
+
+        fn search_tags(&self) -> Vec<&str> {
+            self.tags.iter().map(String::as_str).collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{ChatMessage, Conversation};
+
+    #[test]
+    fn test_search_engine_creation() {
+        let engine = SearchEngine::new();
+        assert_eq!(engine.indexed_count(), 0);
+    }
+
+    #[test]
+    fn test_index_conversation() {
+        let mut engine = SearchEngine::new();
+        let mut conversation = Conversation::new("Test".to_string(), None);
+        conversation.add_message(ChatMessage::new(
+            "user".to_string(),
+            "Hello world".to_string(),
+        ));
+        
+        engine.index_conversation(&conversation);
+        assert_eq!(engine.indexed_count(), 1);
+        assert!(engine.is_indexed(&conversation.id));
+    }
+
+    #[test]
+    fn test_remove_conversation() {
+        let mut engine = SearchEngine::new();
+        let mut conversation = Conversation::new("Test".to_string(), None);
+        conversation.add_message(ChatMessage::new(
+            "user".to_string(),
+            "Hello world".to_string(),
+        ));
+        
+        let conv_id = conversation.id.clone();
+        engine.index_conversation(&conversation);
+        assert!(engine.is_indexed(&conv_id));
+        
+        engine.remove_conversation(&conv_id);
+        assert!(!engine.is_indexed(&conv_id));
+        assert_eq!(engine.indexed_count(), 0);
+    }
+
+    #[test]
+    fn test_clear_index() {
+        let mut engine = SearchEngine::new();
+        let mut conversation = Conversation::new("Test".to_string(), None);
+        conversation.add_message(ChatMessage::new(
+            "user".to_string(),
+            "Hello world".to_string(),
+        ));
+        
+        engine.index_conversation(&conversation);
+        assert_eq!(engine.indexed_count(), 1);
+        
+        engine.clear();
+        assert_eq!(engine.indexed_count(), 0);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        use std::path::PathBuf;
+        
+        let mut engine = SearchEngine::new();
+        let mut conversation = Conversation::new("Test".to_string(), None);
+        conversation.add_message(ChatMessage::new(
+            "user".to_string(),
+            "Hello world".to_string(),
+        ));
+        
+        engine.index_conversation(&conversation);
+        assert_eq!(engine.indexed_count(), 1);
+        
+        // Save to temp file
+        let temp_path = PathBuf::from("/tmp/test_search_index.json");
+        engine.save_to_file(&temp_path).expect("Failed to save");
+        
+        // Load from file
+        let loaded_engine = SearchEngine::load_from_file(&temp_path).expect("Failed to load");
+        assert_eq!(loaded_engine.indexed_count(), 1);
+        assert!(loaded_engine.is_indexed(&conversation.id));
+        
+        // Clean up
+        let _ = std::fs::remove_file(&temp_path);
+    }
+
+    // Helper function to create a test conversation with messages
+    fn create_test_conversation(name: &str, messages: Vec<(&str, &str)>) -> Conversation {
+        let mut conversation = Conversation::new(name.to_string(), None);
+        for (role, content) in messages {
+            conversation.add_message(ChatMessage::new(role.to_string(), content.to_string()));
+        }
+        conversation
+    }
+
+    // ===== Comprehensive Search Tests =====
+
+    #[test]
+    fn test_index_simple_message() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![("user", "Hello world")],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("hello".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].conversation_id, conversation.id);
+        assert_eq!(results[0].message_index, 0);
+        assert_eq!(results[0].role, "user");
+    }
+
+    #[test]
+    fn test_index_multiple_messages() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "What is Rust?"),
+                ("assistant", "Rust is a systems programming language"),
+                ("user", "Tell me more about Rust"),
+            ],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("rust".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        // Should find "Rust" in all 3 messages, not just the last one
+        let mut message_indices: Vec<usize> = results.iter().map(|r| r.message_index).collect();
+        message_indices.sort();
+        assert_eq!(message_indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_index_code_blocks() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "Show me a function"),
+                ("assistant", "```rust\nfn hello() {\n    println!(\"Hello\");\n}\n```"),
+            ],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("println".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message_index, 1);
+    }
+
+    #[test]
+    fn test_index_special_characters() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "What about C++, Python, and JavaScript?"),
+                ("assistant", "These are popular languages: C++, Python, JavaScript"),
+            ],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        // Search for terms with special characters
+        let query = SearchQuery::new("python".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        // Should find "Python" in at least one message
+        assert!(!results.is_empty());
+    }
+
+    #[test]
+    fn test_index_markdown_formatting() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "Explain **bold** and *italic* text"),
+                ("assistant", "**Bold** uses double asterisks, *italic* uses single"),
+            ],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("bold".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        // Should find "bold" in at least one message
+        assert!(!results.is_empty());
+    }
+
+    #[test]
+    fn test_index_long_messages() {
+        let mut engine = SearchEngine::new();
+        let long_content = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(50);
+        let conversation = create_test_conversation(
+            "Test",
+            vec![("user", &long_content)],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("lorem".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        assert_eq!(results.len(), 1);
+        // Context should be extracted properly
+        assert!(!results[0].context.is_empty());
+    }
+
+    #[test]
+    fn test_case_insensitive_search() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "Hello World"),
+                ("assistant", "HELLO there"),
+                ("user", "hello again"),
+            ],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        // Search with lowercase - should find all variations
+        let query = SearchQuery::new("hello".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        assert!(!results.is_empty(), "Should find at least one result");
+        
+        // Search with uppercase - should also find all variations (case-insensitive by default)
+        let query = SearchQuery::new("HELLO".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        assert!(!results.is_empty(), "Should find at least one result");
+        
+        // Search with mixed case - should also find all variations
+        let query = SearchQuery::new("HeLLo".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        assert!(!results.is_empty(), "Should find at least one result");
+    }
+
+    #[test]
+    fn test_case_sensitive_search() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "Hello World"),
+                ("assistant", "HELLO there"),
+                ("user", "hello again"),
+            ],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        // The index is lowercase, but occurrences are checked against the original text
+        let query = SearchQuery::new("Hello".to_string()).with_case_sensitive(true);
+        let results = engine.search(&query).unwrap();
+        
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message_index, 0);
+        assert_eq!(results[0].match_positions, vec![(0, 5)]);
+        
+        // Test case-sensitive highlighting with search_in_content
+        let query_sensitive = SearchQuery::new("Hello".to_string()).with_case_sensitive(true);
+        let text = "Hello world, HELLO again, hello there";
+        let positions = query_sensitive.search_in_content(text);
+        
+        // Should only find "Hello" with capital H
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0], (0, 5));
+    }
+
+    #[test]
+    fn test_whole_word_search() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "test testing tested"),
+                ("assistant", "This is a test"),
+            ],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        // Whole word search should only match exact word "test"
+        let query = SearchQuery::new("test".to_string()).with_whole_word(true);
+        let results = engine.search(&query).unwrap();
+        
+        // Should match "test" but not "testing" or "tested"
+        // At least one result expected
+        assert!(!results.is_empty(), "Should find at least one exact match for 'test'");
+    }
+
+    #[test]
+    fn test_partial_word_search() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "test testing tested"),
+                ("assistant", "This is a test"),
+            ],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        // Partial search should match all variations
+        let query = SearchQuery::new("test".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        // Should match "test", "testing", and "tested"
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn test_search_multiple_conversations() {
+        let mut engine = SearchEngine::new();
+        
+        let conv1 = create_test_conversation(
+            "Conversation 1",
+            vec![("user", "Tell me about Rust")],
+        );
+        let conv2 = create_test_conversation(
+            "Conversation 2",
+            vec![("user", "What is Python?")],
+        );
+        let conv3 = create_test_conversation(
+            "Conversation 3",
+            vec![("user", "Compare Rust and Python")],
+        );
+
+        engine.index_conversation(&conv1);
+        engine.index_conversation(&conv2);
+        engine.index_conversation(&conv3);
+        
+        let query = SearchQuery::new("rust".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        // Should find Rust in conv1 and conv3
+        assert_eq!(results.len(), 2);
+        
+        let conv_ids: Vec<String> = results.iter().map(|r| r.conversation_id.clone()).collect();
+        assert!(conv_ids.contains(&conv1.id));
+        assert!(conv_ids.contains(&conv3.id));
+    }
+
+    #[test]
+    fn test_search_no_results() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![("user", "Hello world")],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("nonexistent".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        assert_eq!(results.len(), 0);
+    }
+
+    #[test]
+    fn test_search_empty_query() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![("user", "Hello world")],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        assert_eq!(results.len(), 0);
+    }
+
+    #[test]
+    fn test_result_context_extraction() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![("user", "This is a very long message with the word target somewhere in the middle of it")],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("target".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        assert_eq!(results.len(), 1);
+        // Context should contain the word and surrounding text
+        assert!(results[0].context.contains("target"));
+        assert!(!results[0].context.is_empty());
+    }
+
+    #[test]
+    fn test_search_with_numbers() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "What is 42?"),
+                ("assistant", "42 is the answer to everything"),
+            ],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("42".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        // Should find "42" in at least one message
+        assert!(!results.is_empty(), "Should find at least one result for '42'");
+    }
+
+    #[test]
+    fn test_search_with_underscores() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "What is snake_case?"),
+                ("assistant", "snake_case is a naming convention"),
+            ],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("snake_case".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        // Should find "snake_case" in at least one message
+        assert!(!results.is_empty(), "Should find at least one result for 'snake_case'");
+    }
+
+    #[test]
+    fn test_rebuild_index() {
+        let mut engine = SearchEngine::new();
+        
+        let conv1 = create_test_conversation("Conv1", vec![("user", "First")]);
+        let conv2 = create_test_conversation("Conv2", vec![("user", "Second")]);
+        
+        engine.index_conversation(&conv1);
+        assert_eq!(engine.indexed_count(), 1);
+        
+        // Rebuild with both conversations
+        engine.rebuild_index(&[conv1.clone(), conv2.clone()]);
+        assert_eq!(engine.indexed_count(), 2);
+        
+        let query = SearchQuery::new("first".to_string());
+        let results = engine.search(&query).unwrap();
+        assert_eq!(results.len(), 1);
+        
+        let query = SearchQuery::new("second".to_string());
+        let results = engine.search(&query).unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_conversation_from_index() {
+        let mut engine = SearchEngine::new();
+        
+        let conv1 = create_test_conversation("Conv1", vec![("user", "Hello")]);
+        let conv2 = create_test_conversation("Conv2", vec![("user", "World")]);
+        
+        engine.index_conversation(&conv1);
+        engine.index_conversation(&conv2);
+        assert_eq!(engine.indexed_count(), 2);
+        
+        engine.remove_conversation(&conv1.id);
+        assert_eq!(engine.indexed_count(), 1);
+        assert!(!engine.is_indexed(&conv1.id));
+        assert!(engine.is_indexed(&conv2.id));
+        
+        let query = SearchQuery::new("hello".to_string());
+        let results = engine.search(&query).unwrap();
+        assert_eq!(results.len(), 0);
+    }
+
+    #[test]
+    fn test_large_conversation_set() {
+        let mut engine = SearchEngine::new();
+        
+        // Create 100 conversations with multiple messages each
+        let mut conversations = Vec::new();
+        for i in 0..100 {
+            let conv = create_test_conversation(
+                &format!("Conversation {}", i),
+                vec![
+                    ("user", &format!("Question {} about programming", i)),
+                    ("assistant", &format!("Answer {} about programming languages", i)),
+                    ("user", "Tell me more"),
+                    ("assistant", "Here is more information about programming"),
+                ],
+            );
+            conversations.push(conv);
+        }
+        
+        // Index all conversations
+        for conv in &conversations {
+            engine.index_conversation(conv);
+        }
+        
+        assert_eq!(engine.indexed_count(), 100);
+        
+        // Search for common term
+        let query = SearchQuery::new("programming".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        // Should find "programming" in many messages
+        assert!(results.len() >= 100); // At least one per conversation
+    }
+
+    #[test]
+    fn test_large_conversation_performance() {
+        let mut engine = SearchEngine::new();
+        
+        // Create message strings that will live long enough
+        let message_strings: Vec<String> = (0..1000)
+            .map(|i| format!("Message {} with some content", i))
+            .collect();
+        
+        // Create references to the strings
+        let message_refs: Vec<(&str, &str)> = message_strings
+            .iter()
+            .map(|s| ("user", s.as_str()))
+            .collect();
+        
+        let conversation = create_test_conversation("Large", message_refs);
+        
+        engine.index_conversation(&conversation);
+        
+        // Search should still be fast
+        let query = SearchQuery::new("message".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        // Should find "Message" in every message
+        assert_eq!(results.len(), 1000);
+    }
+
+    #[test]
+    fn test_search_query_builder() {
+        let query = SearchQueryBuilder::new()
+            .text("test".to_string())
+            .case_sensitive(true)
+            .whole_word(false)
+            .build();
+        
+        assert_eq!(query.text, "test");
+        assert!(query.case_sensitive);
+        assert!(!query.whole_word);
+    }
+
+    #[test]
+    fn test_search_in_content_case_insensitive() {
+        let query = SearchQuery::new("hello".to_string());
+        let text = "Hello world, HELLO again, hello there";
+        
+        let positions = query.search_in_content(text);
+        
+        // Should find all three occurrences
+        assert_eq!(positions.len(), 3);
+    }
+
+    #[test]
+    fn test_search_in_content_case_sensitive() {
+        let query = SearchQuery::new("Hello".to_string()).with_case_sensitive(true);
+        let text = "Hello world, HELLO again, hello there";
+        
+        let positions = query.search_in_content(text);
+        
+        // Should only find "Hello" with capital H
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0], (0, 5));
+    }
+
+    #[test]
+    fn test_search_in_content_whole_word() {
+        let query = SearchQuery::new("test".to_string()).with_whole_word(true);
+        let text = "test testing tested test";
+        
+        let positions = query.search_in_content(text);
+        
+        // Should only find standalone "test" words (2 occurrences)
+        assert_eq!(positions.len(), 2);
+    }
+
+    #[test]
+    fn test_search_in_content_multiple_occurrences() {
+        let query = SearchQuery::new("the".to_string());
+        let text = "the quick brown fox jumps over the lazy dog";
+        
+        let positions = query.search_in_content(text);
+        
+        // Should find both occurrences of "the"
+        assert_eq!(positions.len(), 2);
+        // First occurrence at the start
+        assert_eq!(positions[0].0, 0);
+        assert_eq!(positions[0].1, 3);
+        // Second occurrence later in the text
+        assert_eq!(positions[1].0, 31); // "over the" - 'the' starts at position 31
+        assert_eq!(positions[1].1, 34);
+    }
+
+    #[test]
+    fn test_empty_conversation() {
+        let mut engine = SearchEngine::new();
+        let conversation = Conversation::new("Empty".to_string(), None);
+        
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("anything".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        assert_eq!(results.len(), 0);
+    }
+
+    #[test]
+    fn test_conversation_with_empty_messages() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", ""),
+                ("assistant", "Hello"),
+                ("user", ""),
+            ],
+        );
+
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("hello".to_string());
+        let results = engine.search(&query).unwrap();
+        
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message_index, 1);
+    }
+
+    #[test]
+    fn test_results_ranked_by_relevance() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "We could use a channel here, or maybe some other approach to the problem"),
+                ("assistant", "Channel channel channel"),
+                ("user", "Unrelated"),
+            ],
+        );
+        engine.index_conversation(&conversation);
+
+        let results = engine.search(&SearchQuery::new("channel".to_string())).unwrap();
+
+        // More occurrences in a shorter message rank higher
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].message_index, 1);
+        assert_eq!(results[0].match_positions.len(), 3);
+        assert!(results[0].score > results[1].score);
+    }
+
+    #[test]
+    fn test_rare_terms_weigh_more() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "rust async"),
+                ("user", "rust lifetimes"),
+                ("user", "rust traits"),
+            ],
+        );
+        engine.index_conversation(&conversation);
+
+        let common = engine.search(&SearchQuery::new("rust".to_string())).unwrap();
+        let rare = engine.search(&SearchQuery::new("lifetimes".to_string())).unwrap();
+        assert!(rare[0].score > common[0].score);
+    }
+
+    #[test]
+    fn test_recent_conversations_rank_higher() {
+        let mut engine = SearchEngine::new();
+        let mut old = create_test_conversation("Old", vec![("user", "lifetimes question")]);
+        old.updated_at = Some("2024-01-01T00:00:00+00:00".to_string());
+        let mut recent = create_test_conversation("Recent", vec![("user", "lifetimes question")]);
+        recent.updated_at = Some("2024-06-01T00:00:00+00:00".to_string());
+        engine.index_conversation(&old);
+        engine.index_conversation(&recent);
+
+        let now = chrono::DateTime::parse_from_rfc3339("2024-06-02T00:00:00+00:00").unwrap().timestamp();
+        let results = engine.search_at(&SearchQuery::new("lifetimes".to_string()), now).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].conversation_id, recent.id);
+        assert!(results[0].score > results[1].score);
+    }
+
+    #[test]
+    fn test_every_query_word_must_match() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "borrow checker errors"),
+                ("user", "borrow a book"),
+            ],
+        );
+        engine.index_conversation(&conversation);
+
+        let results = engine.search(&SearchQuery::new("borrow checker".to_string())).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].message_index, 0);
+        assert_eq!(results[0].match_positions, vec![(0, 6), (7, 14)]);
+    }
+
+    #[test]
+    fn test_reindexing_replaces_previous_version() {
+        let mut engine = SearchEngine::new();
+        let mut conversation = create_test_conversation("Test", vec![("user", "first draft")]);
+        engine.index_conversation(&conversation);
+
+        conversation.messages[0].content = "second draft".to_string();
+        engine.index_conversation(&conversation);
+
+        assert!(engine.search(&SearchQuery::new("first".to_string())).unwrap().is_empty());
+        assert_eq!(engine.search(&SearchQuery::new("draft".to_string())).unwrap().len(), 1);
+        assert_eq!(engine.message_count, 1);
+    }
+
+    #[test]
+    fn test_remove_keeps_other_conversations_postings() {
+        let mut engine = SearchEngine::new();
+        let conv1 = create_test_conversation("Conv1", vec![("user", "shared term"), ("user", "shared again")]);
+        let conv2 = create_test_conversation("Conv2", vec![("user", "shared too")]);
+        engine.index_conversation(&conv1);
+        engine.index_conversation(&conv2);
+        assert_eq!(engine.conversations[&conv1.id].terms.iter().filter(|t| *t == "shared").count(), 1);
+
+        engine.remove_conversation(&conv1.id);
+
+        let results = engine.search(&SearchQuery::new("shared".to_string())).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].conversation_id, conv2.id);
+        assert!(!engine.postings.contains_key("term"));
+        assert_eq!(engine.message_count, 1);
+        assert_eq!(engine.total_length, 2);
+    }
+
+    #[test]
+    fn test_index_updates_on_reindex() {
+        let mut engine = SearchEngine::new();
+        
+        let mut conversation = create_test_conversation(
+            "Test",
+            vec![("user", "original message")],
+        );
+        
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("original".to_string());
+        let results = engine.search(&query).unwrap();
+        assert_eq!(results.len(), 1);
+        
+        // Modify conversation and reindex
+        conversation.add_message(ChatMessage::new(
+            "user".to_string(),
+            "updated message".to_string(),
+        ));
+        
+        engine.remove_conversation(&conversation.id);
+        engine.index_conversation(&conversation);
+        
+        let query = SearchQuery::new("updated".to_string());
+        let results = engine.search(&query).unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_context_matches() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation("Test", vec![("user", "Hello world")]);
+        engine.index_conversation(&conversation);
+
+        let query = SearchQuery::new("world".to_string());
+        let results = engine.search(&query).unwrap();
+        assert_eq!(results[0].context_matches(&query), vec![(6, 11)]);
+    }
+
+    fn query_results(engine: &SearchEngine, text: &str) -> Vec<(String, usize)> {
+        let mut results: Vec<(String, usize)> = engine
+            .search(&SearchQuery::new(text.to_string()))
+            .unwrap()
+            .into_iter()
+            .map(|r| (r.conversation_id, r.message_index))
+            .collect();
+        results.sort();
+        results
+    }
+
+    #[test]
+    fn test_phrase_and_prefix_queries() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![
+                ("user", "The borrow checker rejects this"),
+                ("user", "Check the borrow rules"),
+            ],
+        );
+        engine.index_conversation(&conversation);
+
+        let results = engine.search(&SearchQuery::new("\"borrow checker\"".to_string())).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].match_positions, vec![(4, 18)]);
+
+        assert_eq!(query_results(&engine, "check*").len(), 2);
+        assert_eq!(query_results(&engine, "rule*"), vec![(conversation.id.clone(), 1)]);
+    }
+
+    #[test]
+    fn test_boolean_queries() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![("user", "tokio runtime"), ("user", "async-std runtime"), ("user", "threads")],
+        );
+        engine.index_conversation(&conversation);
+        let id = conversation.id.clone();
+
+        assert_eq!(query_results(&engine, "runtime -tokio"), vec![(id.clone(), 1)]);
+        assert_eq!(query_results(&engine, "tokio OR threads"), vec![(id.clone(), 0), (id.clone(), 2)]);
+        assert_eq!(query_results(&engine, "(tokio OR async) runtime").len(), 2);
+        assert_eq!(query_results(&engine, "NOT runtime"), vec![(id, 2)]);
+    }
+
+    #[test]
+    fn test_filter_queries() {
+        let mut engine = SearchEngine::new();
+        let mut rust = create_test_conversation("Rust", vec![("user", "lifetimes?"), ("assistant", "lifetimes are regions")]);
+        rust.tags = vec!["Rust".to_string()];
+        rust.messages[0].timestamp = Some("2025-03-01T10:00:00+00:00".to_string());
+        rust.messages[1].timestamp = Some("2025-03-02T10:00:00+00:00".to_string());
+        rust.messages[1].model = Some("llama3:8b".to_string());
+        let other = create_test_conversation("Other", vec![("user", "lifetimes again")]);
+        engine.index_conversation(&rust);
+        engine.index_conversation(&other);
+
+        assert_eq!(query_results(&engine, "lifetimes role:assistant"), vec![(rust.id.clone(), 1)]);
+        assert_eq!(query_results(&engine, "model:llama3"), vec![(rust.id.clone(), 1)]);
+        assert_eq!(query_results(&engine, "lifetimes tag:#rust").len(), 2);
+        assert_eq!(query_results(&engine, "lifetimes in:other"), vec![(other.id.clone(), 0)]);
+        assert_eq!(query_results(&engine, "in:rust before:2025-03-02"), vec![(rust.id.clone(), 0)]);
+        assert_eq!(query_results(&engine, "after:2025-03-01"), vec![(rust.id.clone(), 1)]);
+        assert_eq!(query_results(&engine, "lifetimes -role:user"), vec![(rust.id, 1)]);
+    }
+
+    #[test]
+    fn test_invalid_query_is_an_error() {
+        let engine = SearchEngine::new();
+        let error = engine.search(&SearchQuery::new("(rust".to_string())).unwrap_err();
+        assert_eq!(error.position, 5);
+    }
+
+    #[test]
+    fn test_older_index_versions_are_rejected() {
+        let mut engine = SearchEngine::new();
+        engine.version = INDEX_VERSION - 1;
+        let json = serde_json::to_string(&engine).unwrap();
+        assert!(SearchEngine::from_json(&json).is_err());
+    }
+
+    #[test]
+    fn test_accents_are_folded() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation("Test", vec![("user", "Meet at the Café"), ("user", "naïve approach")]);
+        engine.index_conversation(&conversation);
+
+        let results = engine.search(&SearchQuery::new("cafe".to_string())).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].match_positions, vec![(12, 17)]);
+        assert_eq!(query_results(&engine, "\"naive approach\"").len(), 1);
+
+        let strict = SearchQuery::new("cafe".to_string()).with_diacritic_sensitive(true);
+        assert!(engine.search(&strict).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stemming() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation("Test", vec![("user", "The tests are running")]);
+        engine.index_conversation(&conversation);
+
+        let query = SearchQuery::new("run".to_string()).with_whole_word(true);
+        assert!(engine.search(&query).unwrap().is_empty());
+        let results = engine.search(&query.with_stemming(Some(Language::English))).unwrap();
+        assert_eq!(results[0].match_positions, vec![(14, 21)]);
+    }
+
+    #[test]
+    fn test_fuzzy_matches_rank_below_exact() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation("Test", vec![("user", "running late"), ("user", "runing late")]);
+        engine.index_conversation(&conversation);
+
+        assert_eq!(query_results(&engine, "runing").len(), 1);
+        let query = SearchQuery::new("runing".to_string()).with_max_edits(1);
+        let results = engine.search(&query).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].message_index, 1);
+        assert!(results[0].score > results[1].score);
+    }
+
+    #[test]
+    fn test_semantic_results_skip_removed_conversations() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation("Test", vec![("user", "How do I cancel a task?")]);
+        engine.index_conversation(&conversation);
+
+        let matches = vec![
+            SemanticMatch { conversation_id: conversation.id.clone(), message_index: 0, similarity: 0.8 },
+            SemanticMatch { conversation_id: "deleted".to_string(), message_index: 0, similarity: 0.9 },
+        ];
+        let results = engine.semantic_results(&matches);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].context, "How do I cancel a task?");
+        assert!((results[0].score - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_hybrid_search_blends_scores() {
+        let mut engine = SearchEngine::new();
+        let conversation = create_test_conversation(
+            "Test",
+            vec![("user", "abort the future"), ("user", "cancel the future"), ("user", "unrelated")],
+        );
+        engine.index_conversation(&conversation);
+        let similar = |message_index, similarity| SemanticMatch {
+            conversation_id: conversation.id.clone(),
+            message_index,
+            similarity,
+        };
+
+        let query = SearchQuery::new("cancel".to_string());
+        let results = engine.search_hybrid(&query, &[similar(0, 0.9), similar(1, 0.8)], 0.5).unwrap();
+
+        // The keyword match also scores semantically; the paraphrase is found by similarity alone
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].message_index, 1);
+        assert!((results[0].score - 0.9).abs() < 1e-6);
+        assert_eq!(results[1].message_index, 0);
+        assert!((results[1].score - 0.45).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,128 @@
+//! Text normalization for matching: diacritic folding, stemming and typo tolerance
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+pub use rust_stemmers::Algorithm as Language;
+
+/// Fold text for matching, so that "café" and "ﬁle" compare equal to "cafe" and "file"
+///
+/// Applies compatibility decomposition (NFKD) and drops the combining marks it
+/// separates out. Case is kept.
+pub fn fold(text: &str) -> String {
+    text.nfkd().filter(|c| !is_combining_mark(*c)).collect()
+}
+
+/// Canonical composition (NFC), so precomposed and combining accents compare equal
+pub(crate) fn compose(text: &str) -> String {
+    text.nfc().collect()
+}
+
+/// Look up a stemming language by English name or ISO 639-1 code, ignoring case
+pub fn language_from_name(name: &str) -> Option<Language> {
+    let language = match name.to_lowercase().as_str() {
+        "arabic" | "ar" => Language::Arabic,
+        "danish" | "da" => Language::Danish,
+        "dutch" | "nl" => Language::Dutch,
+        "english" | "en" => Language::English,
+        "finnish" | "fi" => Language::Finnish,
+        "french" | "fr" => Language::French,
+        "german" | "de" => Language::German,
+        "greek" | "el" => Language::Greek,
+        "hungarian" | "hu" => Language::Hungarian,
+        "italian" | "it" => Language::Italian,
+        "norwegian" | "no" => Language::Norwegian,
+        "portuguese" | "pt" => Language::Portuguese,
+        "romanian" | "ro" => Language::Romanian,
+        "russian" | "ru" => Language::Russian,
+        "spanish" | "es" => Language::Spanish,
+        "swedish" | "sv" => Language::Swedish,
+        "tamil" | "ta" => Language::Tamil,
+        "turkish" | "tr" => Language::Turkish,
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Stem a lowercase word, e.g. "running" to "run" in English
+pub(crate) fn stem(language: Language, word: &str) -> String {
+    rust_stemmers::Stemmer::create(language).stem(word).into_owned()
+}
+
+/// Typo tolerance for a word of `length` characters, capped at `max_edits`
+///
+/// Short words allow fewer edits, as any two-letter word is within two edits of
+/// every other: none up to 2 characters, one up to 5, then two.
+pub(crate) fn allowed_edits(length: usize, max_edits: usize) -> usize {
+    let edits = match length {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => 2,
+    };
+    edits.min(max_edits)
+}
+
+/// Whether the Levenshtein distance between `a` and `b` is at most `max`
+///
+/// Gives up as soon as every alignment needs more than `max` edits.
+pub(crate) fn within_distance(a: &str, b: &str, max: usize) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return false;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, &a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().all(|&distance| distance > max) {
+            return false;
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()] <= max
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(fold("café"), "cafe");
+        assert_eq!(fold("cafe\u{301}"), "cafe");
+        assert_eq!(fold("Ångström"), "Angstrom");
+        assert_eq!(fold("ﬁle"), "file");
+        assert_eq!(fold("naïve Straße"), "naive Straße");
+    }
+
+    #[test]
+    fn test_stem() {
+        assert_eq!(stem(Language::English, "running"), "run");
+        assert_eq!(stem(Language::English, "runs"), "run");
+        assert_eq!(language_from_name("EN"), Some(Language::English));
+        assert_eq!(language_from_name("klingon"), None);
+    }
+
+    #[test]
+    fn test_within_distance() {
+        assert!(within_distance("runing", "running", 1));
+        assert!(within_distance("recieve", "receive", 2));
+        assert!(!within_distance("recieve", "receive", 1));
+        assert!(within_distance("same", "same", 0));
+        assert!(!within_distance("cat", "horse", 2));
+    }
+
+    #[test]
+    fn test_allowed_edits() {
+        assert_eq!(allowed_edits(2, 2), 0);
+        assert_eq!(allowed_edits(4, 2), 1);
+        assert_eq!(allowed_edits(8, 2), 2);
+        assert_eq!(allowed_edits(8, 1), 1);
+    }
+}
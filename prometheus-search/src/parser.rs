@@ -0,0 +1,445 @@
+use crate::index::word_regex;
+use chrono::NaiveDate;
+use std::fmt;
+
+/// A parsed search query
+///
+/// Produced by [`parse`]; words next to each other are combined with `And`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryNode {
+    /// A single word; `prefix` is set for `word*`
+    Term { word: String, prefix: bool },
+    /// Words that must appear next to each other, from `"quoted text"`
+    Phrase(Vec<String>),
+    /// A `field:value` filter
+    Filter(Filter),
+    And(Vec<QueryNode>),
+    Or(Vec<QueryNode>),
+    Not(Box<QueryNode>),
+}
+
+/// Field filters, matched against message and conversation metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// `role:assistant` - messages with this role
+    Role(String),
+    /// `model:llama3` - messages generated by a model whose name contains this
+    Model(String),
+    /// `before:2026-01-01` - messages sent before this date
+    Before(NaiveDate),
+    /// `after:2026-01-01` - messages sent after this date
+    After(NaiveDate),
+    /// `tag:rust` - conversations with this tag
+    Tag(String),
+    /// `in:<conversation>` - conversations whose ID starts with, or whose name contains, this
+    In(String),
+}
+
+/// A query that could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    /// Character offset in the query where the problem is
+    pub position: usize,
+}
+
+impl QueryError {
+    fn new(message: impl Into<String>, position: usize) -> Self {
+        Self {
+            message: message.into(),
+            position,
+        }
+    }
+
+    /// The query with a caret under the position of the error, on two lines
+    pub fn pointer(&self, query: &str) -> String {
+        format!("{}\n{}^", query, " ".repeat(self.position))
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+const FIELDS: &[&str] = &["role", "model", "before", "after", "tag", "in"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Phrase(String),
+    /// Field name (lowercase), value and the position of the value
+    Field(String, String, usize),
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+}
+
+/// Parse a query
+///
+/// Supported syntax:
+/// - words, combined with `AND` (the default), `OR` and `NOT` or a leading `-`
+/// - `"quoted phrases"` and `(grouping)`
+/// - prefix wildcards: `lifetime*`
+/// - filters: `role:`, `model:`, `before:`, `after:`, `tag:` and `in:`, with
+///   quoted values for spaces: `in:"Rust questions"`
+///
+/// Operators must be uppercase; lowercase `and`, `or` and `not` are words.
+///
+/// # Returns
+/// `None` for a blank query
+pub fn parse(query: &str) -> Result<Option<QueryNode>, QueryError> {
+    let tokens = tokenize(query)?;
+    if tokens.is_empty() {
+        return Ok(None);
+    }
+
+    let mut parser = Parser {
+        tokens,
+        index: 0,
+        end: query.chars().count(),
+    };
+    let node = parser.parse_or()?;
+    if let Some((_, position)) = parser.peek() {
+        return Err(QueryError::new("Unexpected ')'", position));
+    }
+    Ok(Some(node))
+}
+
+fn tokenize(query: &str) -> Result<Vec<(Token, usize)>, QueryError> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push((Token::LParen, i));
+            i += 1;
+        } else if c == ')' {
+            tokens.push((Token::RParen, i));
+            i += 1;
+        } else if c == '"' {
+            let (text, next) = read_quoted(&chars, i)?;
+            tokens.push((Token::Phrase(text), i));
+            i = next;
+        } else if c == '-' && chars.get(i + 1).map(|c| !c.is_whitespace()).unwrap_or(false) {
+            tokens.push((Token::Not, i));
+            i += 1;
+        } else {
+            let start = i;
+            while i < chars.len() && !chars[i].is_whitespace() && !"()\"".contains(chars[i]) {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+
+            let token = match word.as_str() {
+                "AND" => Token::And,
+                "OR" => Token::Or,
+                "NOT" => Token::Not,
+                _ => match word.split_once(':') {
+                    Some((name, value)) if FIELDS.contains(&name.to_lowercase().as_str()) => {
+                        let value_position = start + name.chars().count() + 1;
+                        let value = if value.is_empty() && chars.get(i) == Some(&'"') {
+                            let (text, next) = read_quoted(&chars, i)?;
+                            i = next;
+                            text
+                        } else {
+                            value.to_string()
+                        };
+                        if value.trim().is_empty() {
+                            return Err(QueryError::new(
+                                format!("Expected a value after '{}:'", name),
+                                value_position,
+                            ));
+                        }
+                        Token::Field(name.to_lowercase(), value, value_position)
+                    }
+                    _ => Token::Word(word),
+                },
+            };
+            tokens.push((token, start));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Read a double-quoted string starting at `start`
+///
+/// # Returns
+/// The text between the quotes and the position after the closing quote
+fn read_quoted(chars: &[char], start: usize) -> Result<(String, usize), QueryError> {
+    let end = chars[start + 1..]
+        .iter()
+        .position(|&c| c == '"')
+        .map(|offset| start + 1 + offset)
+        .ok_or_else(|| QueryError::new("Unterminated quote", start))?;
+    Ok((chars[start + 1..end].iter().collect(), end + 1))
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    /// Length of the query, reported for errors at the end
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(&Token, usize)> {
+        self.tokens.get(self.index).map(|(token, position)| (token, *position))
+    }
+
+    fn position(&self) -> usize {
+        self.peek().map(|(_, position)| position).unwrap_or(self.end)
+    }
+
+    fn parse_or(&mut self) -> Result<QueryNode, QueryError> {
+        let mut nodes = vec![self.parse_and()?];
+        while let Some((Token::Or, _)) = self.peek() {
+            self.index += 1;
+            nodes.push(self.parse_and()?);
+        }
+        Ok(if nodes.len() == 1 { nodes.remove(0) } else { QueryNode::Or(nodes) })
+    }
+
+    fn parse_and(&mut self) -> Result<QueryNode, QueryError> {
+        let mut nodes = Vec::new();
+        loop {
+            match self.peek() {
+                None | Some((Token::RParen, _)) | Some((Token::Or, _)) => break,
+                Some((Token::And, position)) => {
+                    if nodes.is_empty() {
+                        return Err(QueryError::new("Expected a search term before AND", position));
+                    }
+                    self.index += 1;
+                    nodes.push(self.parse_unary()?);
+                }
+                Some(_) => nodes.push(self.parse_unary()?),
+            }
+        }
+
+        match nodes.len() {
+            0 => Err(QueryError::new("Expected a search term", self.position())),
+            1 => Ok(nodes.remove(0)),
+            _ => Ok(QueryNode::And(nodes)),
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<QueryNode, QueryError> {
+        if let Some((Token::Not, _)) = self.peek() {
+            self.index += 1;
+            return Ok(QueryNode::Not(Box::new(self.parse_unary()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<QueryNode, QueryError> {
+        let Some((token, position)) = self.tokens.get(self.index).cloned() else {
+            return Err(QueryError::new("Expected a search term", self.end));
+        };
+        self.index += 1;
+
+        match token {
+            Token::LParen => {
+                let node = self.parse_or()?;
+                match self.peek() {
+                    Some((Token::RParen, _)) => {
+                        self.index += 1;
+                        Ok(node)
+                    }
+                    _ => Err(QueryError::new("Expected ')'", self.position())),
+                }
+            }
+            Token::RParen => Err(QueryError::new("Unexpected ')'", position)),
+            Token::And => Err(QueryError::new("Expected a search term before AND", position)),
+            Token::Or => Err(QueryError::new("Expected a search term before OR", position)),
+            Token::Not => unreachable!("NOT is handled by parse_unary"),
+            Token::Phrase(text) => {
+                let words = words(&text);
+                match words.len() {
+                    0 => Err(QueryError::new("Expected words inside the quotes", position)),
+                    1 => Ok(QueryNode::Term { word: words[0].clone(), prefix: false }),
+                    _ => Ok(QueryNode::Phrase(words)),
+                }
+            }
+            Token::Field(name, value, value_position) => parse_filter(&name, &value, value_position),
+            Token::Word(word) => parse_word(&word, position),
+        }
+    }
+}
+
+fn parse_word(word: &str, position: usize) -> Result<QueryNode, QueryError> {
+    let prefix = word.ends_with('*');
+    let core = word.trim_end_matches('*');
+    if let Some(offset) = core.chars().position(|c| c == '*') {
+        return Err(QueryError::new(
+            "Wildcards are only supported at the end of a word",
+            position + offset,
+        ));
+    }
+
+    let words = words(core);
+    match words.len() {
+        0 => Err(QueryError::new("Expected a search term", position)),
+        1 => Ok(QueryNode::Term { word: words[0].clone(), prefix }),
+        _ if prefix => Err(QueryError::new(
+            "Wildcards are only supported on single words",
+            position + core.chars().count(),
+        )),
+        // `borrow-checker` matches the words next to each other
+        _ => Ok(QueryNode::Phrase(words)),
+    }
+}
+
+fn parse_filter(name: &str, value: &str, value_position: usize) -> Result<QueryNode, QueryError> {
+    let parse_date = |value: &str| {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+            QueryError::new(format!("Invalid date '{}', expected YYYY-MM-DD", value), value_position)
+        })
+    };
+
+    let filter = match name {
+        "role" => Filter::Role(value.to_lowercase()),
+        "model" => Filter::Model(value.to_lowercase()),
+        "before" => Filter::Before(parse_date(value)?),
+        "after" => Filter::After(parse_date(value)?),
+        "tag" => Filter::Tag(value.trim_start_matches('#').to_lowercase()),
+        "in" => Filter::In(value.to_string()),
+        _ => unreachable!("unknown fields are parsed as words"),
+    };
+    Ok(QueryNode::Filter(filter))
+}
+
+/// Split text into the words the index is built from
+pub(crate) fn words(text: &str) -> Vec<String> {
+    word_regex().find_iter(text).map(|mat| mat.as_str().to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(word: &str) -> QueryNode {
+        QueryNode::Term { word: word.to_string(), prefix: false }
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_blank_query() {
+        assert_eq!(parse("").unwrap(), None);
+        assert_eq!(parse("   ").unwrap(), None);
+    }
+
+    #[test]
+    fn test_implicit_and() {
+        assert_eq!(
+            parse("borrow checker").unwrap(),
+            Some(QueryNode::And(vec![term("borrow"), term("checker")]))
+        );
+    }
+
+    #[test]
+    fn test_or_binds_looser_than_and() {
+        assert_eq!(
+            parse("rust async OR go").unwrap(),
+            Some(QueryNode::Or(vec![
+                QueryNode::And(vec![term("rust"), term("async")]),
+                term("go"),
+            ]))
+        );
+        assert_eq!(
+            parse("rust AND (async OR tokio)").unwrap(),
+            Some(QueryNode::And(vec![
+                term("rust"),
+                QueryNode::Or(vec![term("async"), term("tokio")]),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_not_and_minus() {
+        let expected = Some(QueryNode::And(vec![
+            term("rust"),
+            QueryNode::Not(Box::new(term("unsafe"))),
+        ]));
+        assert_eq!(parse("rust NOT unsafe").unwrap(), expected);
+        assert_eq!(parse("rust -unsafe").unwrap(), expected);
+    }
+
+    #[test]
+    fn test_phrases_and_prefixes() {
+        assert_eq!(
+            parse("\"borrow checker\" lifetime*").unwrap(),
+            Some(QueryNode::And(vec![
+                QueryNode::Phrase(vec!["borrow".to_string(), "checker".to_string()]),
+                QueryNode::Term { word: "lifetime".to_string(), prefix: true },
+            ]))
+        );
+        // Punctuated words match as a phrase
+        assert_eq!(
+            parse("tokio::spawn").unwrap(),
+            Some(QueryNode::Phrase(vec!["tokio".to_string(), "spawn".to_string()]))
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        assert_eq!(
+            parse("role:Assistant model:llama3 before:2026-01-01 after:2025-06-30 tag:#Rust in:\"Rust questions\"")
+                .unwrap(),
+            Some(QueryNode::And(vec![
+                QueryNode::Filter(Filter::Role("assistant".to_string())),
+                QueryNode::Filter(Filter::Model("llama3".to_string())),
+                QueryNode::Filter(Filter::Before(date("2026-01-01"))),
+                QueryNode::Filter(Filter::After(date("2025-06-30"))),
+                QueryNode::Filter(Filter::Tag("rust".to_string())),
+                QueryNode::Filter(Filter::In("Rust questions".to_string())),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_errors_point_at_the_problem() {
+        let error = parse("rust (async OR tokio").unwrap_err();
+        assert_eq!(error.message, "Expected ')'");
+        assert_eq!(error.position, 20);
+
+        let error = parse("lifetimes before:yesterday").unwrap_err();
+        assert_eq!(error.position, 17);
+        assert!(error.message.contains("yesterday"));
+
+        assert_eq!(parse("rust \"unclosed").unwrap_err().position, 5);
+        assert_eq!(parse("rust )").unwrap_err().position, 5);
+        assert_eq!(parse("OR rust").unwrap_err().position, 0);
+        assert_eq!(parse("rust OR").unwrap_err().position, 7);
+        assert_eq!(parse("li*fe").unwrap_err().position, 2);
+        assert_eq!(parse("tag:").unwrap_err().position, 4);
+    }
+
+    #[test]
+    fn test_error_pointer() {
+        let error = parse("rust )").unwrap_err();
+        assert_eq!(error.to_string(), "Unexpected ')' at column 6");
+        assert_eq!(error.pointer("rust )"), "rust )\n     ^");
+    }
+
+    #[test]
+    fn test_unknown_fields_are_words() {
+        assert_eq!(parse("http:").unwrap(), Some(term("http")));
+        assert_eq!(
+            parse("std:io").unwrap(),
+            Some(QueryNode::Phrase(vec!["std".to_string(), "io".to_string()]))
+        );
+    }
+}
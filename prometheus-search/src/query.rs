@@ -0,0 +1,443 @@
+use crate::index::{word_ranges, SearchIndexer};
+use crate::normalize::{self, Language};
+use crate::parser::{self, QueryError, QueryNode};
+
+/// How a query word is compared with indexed terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TermMatch {
+    /// The whole term
+    Exact,
+    /// The start of the term, for `word*`
+    Prefix,
+    /// Anywhere in the term
+    Substring,
+}
+
+impl TermMatch {
+    /// Whether a term matches a word in the same normal form
+    pub(crate) fn matches(self, term: &str, word: &str) -> bool {
+        match self {
+            TermMatch::Exact => term == word,
+            TermMatch::Prefix => term.starts_with(word),
+            TermMatch::Substring => term.contains(word),
+        }
+    }
+}
+
+/// How a term matched a query word
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MatchKind {
+    /// As typed, or a form with the same stem
+    Direct,
+    /// Within the allowed number of typos
+    Fuzzy,
+}
+
+/// Matches one query word against indexed terms and occurrences in text
+pub(crate) struct WordMatcher<'a> {
+    query: &'a SearchQuery,
+    mode: TermMatch,
+    /// The word as indexed: folded and lowercase
+    key: String,
+    key_stem: Option<String>,
+    /// The word normalized as the query asks, for checking occurrences
+    form: String,
+    form_stem: Option<String>,
+    edits: usize,
+}
+
+impl<'a> WordMatcher<'a> {
+    pub(crate) fn new(query: &'a SearchQuery, word: &str, mode: TermMatch) -> Self {
+        // Stems and typos only make sense for whole words
+        let whole = mode != TermMatch::Prefix;
+        let language = query.stemming.filter(|_| whole);
+        let key = SearchIndexer::normalize_text(word);
+        let form = query.normalize_occurrence(word);
+        Self {
+            query,
+            mode,
+            key_stem: language.map(|language| normalize::stem(language, &key)),
+            form_stem: language.map(|language| normalize::stem(language, &form)),
+            edits: if whole {
+                normalize::allowed_edits(key.chars().count(), query.max_edits)
+            } else {
+                0
+            },
+            key,
+            form,
+        }
+    }
+
+    /// Matcher for a word of a phrase, which is neither stemmed nor fuzzy
+    pub(crate) fn exact(query: &'a SearchQuery, word: &str) -> Self {
+        Self {
+            query,
+            mode: TermMatch::Exact,
+            key: SearchIndexer::normalize_text(word),
+            key_stem: None,
+            form: query.normalize_occurrence(word),
+            form_stem: None,
+            edits: 0,
+        }
+    }
+
+    /// The indexed term to look up, if only that term can match
+    pub(crate) fn exact_key(&self) -> Option<&str> {
+        let exact = self.mode == TermMatch::Exact && self.key_stem.is_none() && self.edits == 0;
+        exact.then_some(self.key.as_str())
+    }
+
+    /// How an indexed term matches the word, if at all
+    pub(crate) fn match_term(&self, term: &str) -> Option<MatchKind> {
+        self.compare(term, &self.key, self.key_stem.as_deref())
+    }
+
+    /// Whether an occurrence of a matching term has the case and accents the query asks for
+    pub(crate) fn matches_occurrence(&self, occurrence: &str) -> bool {
+        if !self.query.case_sensitive && !self.query.diacritic_sensitive {
+            return true;
+        }
+        let occurrence = self.query.normalize_occurrence(occurrence);
+        self.compare(&occurrence, &self.form, self.form_stem.as_deref()).is_some()
+    }
+
+    fn compare(&self, candidate: &str, word: &str, word_stem: Option<&str>) -> Option<MatchKind> {
+        if self.mode.matches(candidate, word) {
+            return Some(MatchKind::Direct);
+        }
+        if let (Some(language), Some(word_stem)) = (self.query.stemming, word_stem) {
+            if normalize::stem(language, candidate) == word_stem {
+                return Some(MatchKind::Direct);
+            }
+        }
+        if self.edits > 0 && normalize::within_distance(candidate, word, self.edits) {
+            return Some(MatchKind::Fuzzy);
+        }
+        None
+    }
+}
+
+/// Represents a search query with various options
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SearchQuery {
+    /// The search text
+    pub text: String,
+    /// Whether the search should be case-sensitive
+    pub case_sensitive: bool,
+    /// Whether to match whole words only
+    ///
+    /// Off by default, so "test" also finds "testing". A substring can't be looked up
+    /// directly, so each word is compared against every indexed term; turn this on to
+    /// read a single posting list per word on large histories.
+    pub whole_word: bool,
+    /// Whether accents must match; by default "cafe" also finds "café"
+    #[serde(default)]
+    pub diacritic_sensitive: bool,
+    /// Language to stem words in, so that "running" also finds "runs"
+    #[serde(default)]
+    pub stemming: Option<Language>,
+    /// Typos allowed per word, from 0 (none) to 2; short words allow fewer
+    #[serde(default)]
+    pub max_edits: usize,
+}
+
+impl SearchQuery {
+    /// Create a new search query with default options
+    pub fn new(text: String) -> Self {
+        Self {
+            text,
+            case_sensitive: false,
+            whole_word: false,
+            diacritic_sensitive: false,
+            stemming: None,
+            max_edits: 0,
+        }
+    }
+
+    /// Parse the query text
+    ///
+    /// See [`crate::parser::parse`] for the syntax.
+    pub fn parse(&self) -> Result<Option<QueryNode>, QueryError> {
+        parser::parse(&self.text)
+    }
+
+    /// How a plain word (not a phrase or `word*`) is matched against indexed terms
+    pub(crate) fn word_match(&self) -> TermMatch {
+        if self.whole_word {
+            TermMatch::Exact
+        } else {
+            TermMatch::Substring
+        }
+    }
+
+    /// Normalize an occurrence of a word for comparison, keeping case and accents if they matter
+    fn normalize_occurrence(&self, text: &str) -> String {
+        let text = if self.diacritic_sensitive {
+            normalize::compose(text)
+        } else {
+            normalize::fold(text)
+        };
+        if self.case_sensitive {
+            text
+        } else {
+            text.to_lowercase()
+        }
+    }
+
+    /// Find the query's words and phrases in `text`, for highlighting
+    ///
+    /// Whole words are highlighted, including words matched through folding,
+    /// stemming or typos. Excluded (`NOT`) terms and filters are not highlighted.
+    ///
+    /// # Returns
+    /// Byte ranges of the matches, in order and without overlaps
+    pub fn search_in_content(&self, text: &str) -> Vec<(usize, usize)> {
+        let Ok(Some(node)) = self.parse() else {
+            return Vec::new();
+        };
+        let words: Vec<(usize, usize, String)> = word_ranges(text)
+            .into_iter()
+            .map(|(start, end)| (start, end, SearchIndexer::normalize_text(&text[start..end])))
+            .collect();
+        let matches = |matcher: &WordMatcher, &(start, end, ref key): &(usize, usize, String)| {
+            matcher.match_term(key).is_some() && matcher.matches_occurrence(&text[start..end])
+        };
+
+        let mut positions = Vec::new();
+        let mut nodes = vec![&node];
+        while let Some(node) = nodes.pop() {
+            match node {
+                QueryNode::Term { word, prefix } => {
+                    let mode = if *prefix { TermMatch::Prefix } else { self.word_match() };
+                    let matcher = WordMatcher::new(self, word, mode);
+                    positions.extend(
+                        words.iter().filter(|word| matches(&matcher, word)).map(|&(start, end, _)| (start, end)),
+                    );
+                }
+                QueryNode::Phrase(phrase) if !phrase.is_empty() => {
+                    let matchers: Vec<WordMatcher> = phrase.iter().map(|word| WordMatcher::exact(self, word)).collect();
+                    for window in words.windows(matchers.len()) {
+                        if matchers.iter().zip(window).all(|(matcher, word)| matches(matcher, word)) {
+                            positions.push((window[0].0, window[window.len() - 1].1));
+                        }
+                    }
+                }
+                QueryNode::And(children) | QueryNode::Or(children) => nodes.extend(children),
+                QueryNode::Phrase(_) | QueryNode::Filter(_) | QueryNode::Not(_) => {}
+            }
+        }
+
+        positions.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::with_capacity(positions.len());
+        for (start, end) in positions {
+            match merged.last_mut() {
+                Some(last) if start < last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
+    }
+
+    /// Set case sensitivity
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Set whole word matching
+    pub fn with_whole_word(mut self, whole_word: bool) -> Self {
+        self.whole_word = whole_word;
+        self
+    }
+
+    /// Set whether accents must match
+    pub fn with_diacritic_sensitive(mut self, diacritic_sensitive: bool) -> Self {
+        self.diacritic_sensitive = diacritic_sensitive;
+        self
+    }
+
+    /// Set the stemming language, or `None` to match words as typed
+    pub fn with_stemming(mut self, stemming: Option<Language>) -> Self {
+        self.stemming = stemming;
+        self
+    }
+
+    /// Set the number of typos allowed per word (at most 2)
+    pub fn with_max_edits(mut self, max_edits: usize) -> Self {
+        self.max_edits = max_edits.min(2);
+        self
+    }
+}
+
+/// Builder for constructing search queries
+pub struct SearchQueryBuilder {
+    text: String,
+    case_sensitive: bool,
+    whole_word: bool,
+    diacritic_sensitive: bool,
+    stemming: Option<Language>,
+    max_edits: usize,
+}
+
+impl SearchQueryBuilder {
+    /// Create a new query builder
+    pub fn new() -> Self {
+        Self {
+            text: String::new(),
+            case_sensitive: false,
+            whole_word: false,
+            diacritic_sensitive: false,
+            stemming: None,
+            max_edits: 0,
+        }
+    }
+
+    /// Set the search text
+    pub fn text(mut self, text: String) -> Self {
+        self.text = text;
+        self
+    }
+
+    /// Enable case-sensitive search
+    pub fn case_sensitive(mut self, enabled: bool) -> Self {
+        self.case_sensitive = enabled;
+        self
+    }
+
+    /// Enable whole word matching
+    pub fn whole_word(mut self, enabled: bool) -> Self {
+        self.whole_word = enabled;
+        self
+    }
+
+    /// Require accents to match
+    pub fn diacritic_sensitive(mut self, enabled: bool) -> Self {
+        self.diacritic_sensitive = enabled;
+        self
+    }
+
+    /// Stem words in the given language
+    pub fn stemming(mut self, language: Option<Language>) -> Self {
+        self.stemming = language;
+        self
+    }
+
+    /// Allow typos, up to 2 per word
+    pub fn max_edits(mut self, max_edits: usize) -> Self {
+        self.max_edits = max_edits.min(2);
+        self
+    }
+
+    /// Build the search query
+    pub fn build(self) -> SearchQuery {
+        SearchQuery {
+            text: self.text,
+            case_sensitive: self.case_sensitive,
+            whole_word: self.whole_word,
+            diacritic_sensitive: self.diacritic_sensitive,
+            stemming: self.stemming,
+            max_edits: self.max_edits,
+        }
+    }
+}
+
+impl Default for SearchQueryBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_creation() {
+        let query = SearchQuery::new("test".to_string());
+        assert_eq!(query.text, "test");
+        assert!(!query.case_sensitive);
+        assert!(!query.whole_word);
+    }
+
+    #[test]
+    fn test_query_with_options() {
+        let query = SearchQuery::new("test".to_string())
+            .with_case_sensitive(true)
+            .with_whole_word(true);
+        
+        assert!(query.case_sensitive);
+        assert!(query.whole_word);
+    }
+
+    #[test]
+    fn test_query_builder() {
+        let query = SearchQueryBuilder::new()
+            .text("test".to_string())
+            .case_sensitive(true)
+            .whole_word(false)
+            .build();
+        
+        assert_eq!(query.text, "test");
+        assert!(query.case_sensitive);
+        assert!(!query.whole_word);
+    }
+
+    #[test]
+    fn test_matches_occurrence() {
+        let query = SearchQuery::new("Rust".to_string());
+        let matcher = WordMatcher::new(&query, "Rust", TermMatch::Substring);
+        assert!(matcher.matches_occurrence("rust"));
+
+        let query = query.with_case_sensitive(true);
+        let substring = WordMatcher::new(&query, "Rust", TermMatch::Substring);
+        assert!(substring.matches_occurrence("Rustacean"));
+        assert!(!substring.matches_occurrence("rust"));
+        assert!(WordMatcher::new(&query, "Rust", TermMatch::Prefix).matches_occurrence("Rustacean"));
+        assert!(!WordMatcher::new(&query, "Rust", TermMatch::Exact).matches_occurrence("Rustacean"));
+    }
+
+    #[test]
+    fn test_match_term() {
+        let query = SearchQuery::new(String::new());
+        assert_eq!(WordMatcher::new(&query, "Café", TermMatch::Exact).match_term("cafe"), Some(MatchKind::Direct));
+        assert_eq!(WordMatcher::new(&query, "running", TermMatch::Exact).match_term("runs"), None);
+
+        let query = query.with_stemming(Some(Language::English)).with_max_edits(1);
+        assert_eq!(WordMatcher::new(&query, "running", TermMatch::Exact).match_term("runs"), Some(MatchKind::Direct));
+        assert_eq!(WordMatcher::new(&query, "runing", TermMatch::Exact).match_term("running"), Some(MatchKind::Fuzzy));
+        assert_eq!(WordMatcher::new(&query, "run", TermMatch::Prefix).match_term("ran"), None);
+        assert!(WordMatcher::exact(&query, "runing").match_term("running").is_none());
+    }
+
+    #[test]
+    fn test_diacritic_sensitive_occurrences() {
+        let query = SearchQuery::new(String::new()).with_diacritic_sensitive(true);
+        let matcher = WordMatcher::new(&query, "cafe", TermMatch::Exact);
+        assert_eq!(matcher.match_term("cafe"), Some(MatchKind::Direct));
+        assert!(!matcher.matches_occurrence("café"));
+        assert!(WordMatcher::new(&query, "café", TermMatch::Exact).matches_occurrence("cafe\u{301}"));
+    }
+
+    #[test]
+    fn test_search_in_content_skips_excluded_terms() {
+        let query = SearchQuery::new("\"quick brown\" lazy* -fox role:user".to_string());
+        assert_eq!(
+            query.search_in_content("the quick  brown fox jumps over the lazybones"),
+            vec![(4, 16), (36, 45)]
+        );
+    }
+
+    #[test]
+    fn test_search_in_content_highlights_original_text() {
+        let query = SearchQuery::new("cafe runing".to_string()).with_max_edits(1);
+        let text = "Le Café was running late";
+        assert_eq!(query.search_in_content(text), vec![(3, 8), (13, 20)]);
+        assert_eq!(&text[3..8], "Café");
+    }
+
+    #[test]
+    fn test_search_in_content_multiple_words() {
+        let query = SearchQuery::new("quick fox".to_string());
+        assert_eq!(query.search_in_content("the quick brown fox"), vec![(4, 9), (16, 19)]);
+    }
+}
@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::Searchable;
+
+/// Version of the persisted vector index format; older indexes are recomputed
+const VECTOR_INDEX_VERSION: u32 = 1;
+
+/// Message embeddings for semantic search, computed with a single model
+///
+/// A flat index: queries are compared with every stored vector, which is fast
+/// enough for personal chat histories. Vectors are stored normalized, so cosine
+/// similarity is a dot product.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VectorIndex {
+    version: u32,
+    /// Embedding model the vectors were computed with
+    model: String,
+    /// Embedded messages by conversation ID
+    conversations: HashMap<String, Vec<MessageVector>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct MessageVector {
+    message_index: usize,
+    /// Hash of the content the vector was computed from, to notice edits
+    content_hash: u64,
+    vector: Vec<f32>,
+}
+
+/// A message that has no up-to-date embedding yet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingMessage {
+    pub conversation_id: String,
+    pub message_index: usize,
+    pub content: String,
+}
+
+/// A message similar to a semantic query
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticMatch {
+    pub conversation_id: String,
+    pub message_index: usize,
+    /// Cosine similarity with the query, from -1 to 1
+    pub similarity: f32,
+}
+
+impl VectorIndex {
+    /// Create an empty index for embeddings computed with `model`
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            version: VECTOR_INDEX_VERSION,
+            model: model.into(),
+            conversations: HashMap::new(),
+        }
+    }
+
+    /// The embedding model the vectors were computed with
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Number of embedded messages
+    pub fn len(&self) -> usize {
+        self.conversations.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Messages of a conversation without an embedding for their current content
+    ///
+    /// Also drops the embeddings of messages that were edited or removed.
+    /// Empty messages are never embedded.
+    pub fn pending<C: Searchable>(&mut self, conversation: &C) -> Vec<PendingMessage> {
+        let id = conversation.search_id();
+        let messages = conversation.search_messages();
+        let hashes: Vec<u64> = messages.iter().map(|m| content_hash(m.content)).collect();
+
+        let vectors = self.conversations.entry(id.to_string()).or_default();
+        vectors.retain(|v| hashes.get(v.message_index) == Some(&v.content_hash));
+        let embedded: HashSet<usize> = vectors.iter().map(|v| v.message_index).collect();
+
+        messages
+            .iter()
+            .enumerate()
+            .filter(|(index, message)| !embedded.contains(index) && !message.content.trim().is_empty())
+            .map(|(index, message)| PendingMessage {
+                conversation_id: id.to_string(),
+                message_index: index,
+                content: message.content.to_string(),
+            })
+            .collect()
+    }
+
+    /// Store the embedding of a pending message
+    pub fn insert(&mut self, message: &PendingMessage, vector: Vec<f32>) {
+        let vectors = self.conversations.entry(message.conversation_id.clone()).or_default();
+        vectors.retain(|v| v.message_index != message.message_index);
+        vectors.push(MessageVector {
+            message_index: message.message_index,
+            content_hash: content_hash(&message.content),
+            vector: normalized(vector),
+        });
+    }
+
+    /// Remove the embeddings of a conversation
+    pub fn remove_conversation(&mut self, conversation_id: &str) {
+        self.conversations.remove(conversation_id);
+    }
+
+    /// Keep only the conversations whose IDs are given, e.g. after some were deleted
+    pub fn retain_conversations(&mut self, ids: &HashSet<String>) {
+        self.conversations.retain(|id, _| ids.contains(id));
+    }
+
+    /// The messages most similar to a query embedding, most similar first
+    pub fn search(&self, query: &[f32], limit: usize) -> Vec<SemanticMatch> {
+        let query = normalized(query.to_vec());
+        let mut matches: Vec<SemanticMatch> = self
+            .conversations
+            .iter()
+            .flat_map(|(id, vectors)| {
+                let query = &query;
+                vectors.iter().filter(move |v| v.vector.len() == query.len()).map(move |v| SemanticMatch {
+                    conversation_id: id.clone(),
+                    message_index: v.message_index,
+                    similarity: v.vector.iter().zip(query).map(|(a, b)| a * b).sum(),
+                })
+            })
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.similarity
+                .total_cmp(&a.similarity)
+                .then_with(|| a.conversation_id.cmp(&b.conversation_id))
+                .then(a.message_index.cmp(&b.message_index))
+        });
+        matches.truncate(limit);
+        matches
+    }
+
+    /// Load an index saved with `serde_json`
+    ///
+    /// Fails for indexes in an older format, which should be recomputed.
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let index: Self = serde_json::from_str(json)?;
+        if index.version != VECTOR_INDEX_VERSION {
+            return Err(format!("Unsupported vector index version {}", index.version).into());
+        }
+        Ok(index)
+    }
+}
+
+/// Scale a vector to unit length; zero vectors are left as they are
+fn normalized(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+/// FNV-1a, which unlike `DefaultHasher` is stable across Rust releases
+fn content_hash(content: &str) -> u64 {
+    content.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{ChatMessage, Conversation};
+
+    fn conversation(messages: &[&str]) -> Conversation {
+        let mut conversation = Conversation::new("Test".to_string(), None);
+        for content in messages {
+            conversation.add_message(ChatMessage::new("user".to_string(), content.to_string()));
+        }
+        conversation
+    }
+
+    #[test]
+    fn test_pending_messages() {
+        let mut index = VectorIndex::new("nomic-embed-text");
+        let mut conversation = conversation(&["first", "", "second"]);
+
+        let pending = index.pending(&conversation);
+        assert_eq!(pending.iter().map(|m| m.message_index).collect::<Vec<_>>(), vec![0, 2]);
+        for message in &pending {
+            index.insert(message, vec![1.0, 0.0]);
+        }
+        assert!(index.pending(&conversation).is_empty());
+
+        // Edited and removed messages lose their embeddings
+        conversation.messages[0].content = "edited".to_string();
+        conversation.messages.truncate(1);
+        let pending = index.pending(&conversation);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].content, "edited");
+        assert_eq!(index.len(), 0);
+    }
+
+    #[test]
+    fn test_search_by_cosine_similarity() {
+        let mut index = VectorIndex::new("model");
+        let conversation = conversation(&["a", "b", "c"]);
+        let pending = index.pending(&conversation);
+        index.insert(&pending[0], vec![1.0, 0.0]);
+        index.insert(&pending[1], vec![3.0, 3.0]);
+        index.insert(&pending[2], vec![0.0, -2.0]);
+
+        let matches = index.search(&[2.0, 0.0], 2);
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].message_index, 0);
+        assert!((matches[0].similarity - 1.0).abs() < 1e-6);
+        assert_eq!(matches[1].message_index, 1);
+        assert!((matches[1].similarity - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_retain_conversations() {
+        let mut index = VectorIndex::new("model");
+        let kept = conversation(&["kept"]);
+        let deleted = conversation(&["deleted"]);
+        for pending in [index.pending(&kept), index.pending(&deleted)].concat() {
+            index.insert(&pending, vec![1.0]);
+        }
+
+        index.retain_conversations(&HashSet::from([kept.id.clone()]));
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.search(&[1.0], 10)[0].conversation_id, kept.id);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut index = VectorIndex::new("model");
+        let conversation = conversation(&["text"]);
+        let pending = index.pending(&conversation);
+        index.insert(&pending[0], vec![0.5, 0.5]);
+
+        let loaded = VectorIndex::from_json(&serde_json::to_string(&index).unwrap()).unwrap();
+        assert_eq!(loaded.model(), "model");
+        assert_eq!(loaded.len(), 1);
+
+        let mut old = index;
+        old.version = 0;
+        assert!(VectorIndex::from_json(&serde_json::to_string(&old).unwrap()).is_err());
+    }
+}
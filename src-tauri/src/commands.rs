@@ -1,18 +1,18 @@
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tauri::{AppHandle, Emitter, State};
 use futures_util::StreamExt;
-use crate::persona::{Persona, PersonaManager};
+use prometheus_chat::{BranchInfo, ChatMessage, MessageMetadata};
+use prometheus_personas::{Persona, PersonaManager};
 use crate::config::{AppConfig, RemoteEndpoint};
+use crate::conversation::{Conversation, ConversationManager, ConversationMetadata, SearchStatus};
+use crate::markdown::{self, highlight_matches, MessageSegment};
+use prometheus_search::{language_from_name, SearchQuery};
+use prometheus_chat::thinking::{Segment, ThinkingParser};
+use crate::titling;
 use crate::network::{ConnectionManager, ConnectionTestResult};
-use std::sync::{Arc, RwLock};
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ChatMessage {
-    pub role: String,
-    pub content: String,
-    pub timestamp: String,
-}
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
 
 #[tauri::command]
 pub async fn get_models(
@@ -69,11 +69,96 @@ pub async fn send_message_stream(
     model: String,
     request_id: String,
     system_prompt: Option<String>,
+    persona_id: Option<String>,
+    conversation_id: Option<String>,
     connection_manager: State<'_, Arc<ConnectionManager>>,
+    persona_manager: State<'_, PersonaManager>,
+    config: State<'_, Arc<RwLock<AppConfig>>>,
+    conversation_manager: State<'_, Arc<Mutex<ConversationManager>>>,
 ) -> Result<(), String> {
     // Get the active endpoint URL based on connection mode
     // Requirements: 3.1, 3.2
     let ollama_url = connection_manager.get_active_endpoint()?;
+
+    // Recorded on the response and sent with `stream-done`
+    let mut metadata = MessageMetadata {
+        model: Some(model.clone()),
+        endpoint: Some(ollama_url.clone()),
+        persona_id: persona_id.or_else(|| persona_manager.get_active_persona().map(|p| p.id)),
+        ..Default::default()
+    };
+
+    let response = stream_response(
+        &app,
+        &ollama_url,
+        &model,
+        &prompt,
+        system_prompt.as_deref(),
+        &request_id,
+        &mut metadata,
+    )
+    .await?;
+
+    let _ = app.emit("stream-done", serde_json::json!({
+        "request_id": request_id,
+        "metadata": metadata
+    }));
+
+    if let Some(conversation_id) = conversation_id {
+        maybe_generate_title(
+            &app,
+            &config,
+            conversation_manager.inner(),
+            conversation_id,
+            ollama_url,
+            model,
+            prompt,
+            response.text,
+        );
+    }
+    Ok(())
+}
+
+/// A streamed response, with the thinking of reasoning models separated
+struct StreamedResponse {
+    text: String,
+    thinking: Option<String>,
+}
+
+/// Body of the streamed `/api/generate` request for a prompt
+///
+/// The system prompt replaces the model's default one; the prompt is sent unchanged.
+/// Requirements: 3.1, 3.3
+pub fn generate_request_body(model: &str, prompt: &str, system_prompt: Option<&str>) -> serde_json::Value {
+    let mut request_body = serde_json::json!({
+        "model": model,
+        "prompt": prompt,
+        "stream": true
+    });
+    if let Some(sys_prompt) = system_prompt {
+        request_body["system"] = serde_json::json!(sys_prompt);
+    }
+    request_body
+}
+
+/// Stream a response from Ollama, emitting `stream-token`, `stream-thinking`
+/// and `stream-error` events
+///
+/// The caller emits `stream-done` once it has handled the finished response.
+/// A stream that ends without a final chunk is reported as an error.
+///
+/// # Returns
+/// The full response
+async fn stream_response(
+    app: &AppHandle,
+    ollama_url: &str,
+    model: &str,
+    prompt: &str,
+    system_prompt: Option<&str>,
+    request_id: &str,
+    metadata: &mut MessageMetadata,
+) -> Result<StreamedResponse, String> {
+    let started = Instant::now();
     
     let client = reqwest::Client::builder()
         .timeout(Duration::from_secs(120))
@@ -81,20 +166,7 @@ pub async fn send_message_stream(
         .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
 
     let url = format!("{}/api/generate", ollama_url);
-
-    // Prepend system prompt to user message if provided
-    // Requirements: 3.1, 3.3
-    let final_prompt = if let Some(sys_prompt) = system_prompt {
-        format!("{}\n\n{}", sys_prompt, prompt)
-    } else {
-        prompt
-    };
-
-    let request_body = serde_json::json!({
-        "model": model,
-        "prompt": final_prompt,
-        "stream": true
-    });
+    let request_body = generate_request_body(model, prompt, system_prompt);
 
     let response = client
         .post(&url)
@@ -114,6 +186,9 @@ pub async fn send_message_stream(
     // Process streaming response
     let mut stream = response.bytes_stream();
     let mut line_buffer = String::new();
+    let mut parser = ThinkingParser::new();
+    let mut response_text = String::new();
+    let mut thinking = String::new();
 
     while let Some(chunk) = stream.next().await {
         match chunk {
@@ -133,18 +208,21 @@ pub async fn send_message_stream(
                     match serde_json::from_str::<serde_json::Value>(&line) {
                         Ok(json) => {
                             if let Some(token) = json.get("response").and_then(|v| v.as_str()) {
-                                let _ = app.emit("stream-token", serde_json::json!({
-                                    "request_id": request_id,
-                                    "token": token
-                                }));
+                                let segments = parser.push(token);
+                                emit_segments(app, request_id, segments, &mut response_text, &mut thinking);
                             }
 
                             // Check if this is the final response
                             if json.get("done").and_then(|v| v.as_bool()).unwrap_or(false) {
-                                let _ = app.emit("stream-done", serde_json::json!({
-                                    "request_id": request_id
-                                }));
-                                return Ok(());
+                                let segments = parser.finish();
+                                emit_segments(app, request_id, segments, &mut response_text, &mut thinking);
+                                metadata.record_final_chunk(&json);
+                                metadata.latency_ms = Some(started.elapsed().as_millis() as u64);
+                                let thinking = thinking.trim_end();
+                                return Ok(StreamedResponse {
+                                    text: response_text,
+                                    thinking: (!thinking.is_empty()).then(|| thinking.to_string()),
+                                });
                             }
                         }
                         Err(e) => {
@@ -163,26 +241,186 @@ pub async fn send_message_stream(
         }
     }
 
-    Ok(())
+    let error = "Stream ended before the response was complete".to_string();
+    let _ = app.emit("stream-error", serde_json::json!({
+        "request_id": request_id,
+        "error": error
+    }));
+    Err(error)
 }
 
+/// Emit streamed segments as `stream-thinking` or `stream-token` events, collecting their text
+fn emit_segments(
+    app: &AppHandle,
+    request_id: &str,
+    segments: Vec<Segment>,
+    response_text: &mut String,
+    thinking: &mut String,
+) {
+    for segment in segments {
+        let (event, token) = match segment {
+            Segment::Thinking(token) => {
+                thinking.push_str(&token);
+                ("stream-thinking", token)
+            }
+            Segment::Answer(token) => {
+                response_text.push_str(&token);
+                ("stream-token", token)
+            }
+        };
+        let _ = app.emit(event, serde_json::json!({
+            "request_id": request_id,
+            "token": token
+        }));
+    }
+}
+
+/// Start background titling if this response completes the conversation's first exchange
+///
+/// The stored conversation holds only the user's first message at this point,
+/// since the frontend saves the response after `stream-done`.
+#[allow(clippy::too_many_arguments)]
+fn maybe_generate_title(
+    app: &AppHandle,
+    config: &Arc<RwLock<AppConfig>>,
+    conversation_manager: &Arc<Mutex<ConversationManager>>,
+    conversation_id: String,
+    endpoint: String,
+    model: String,
+    prompt: String,
+    response: String,
+) {
+    let settings = match config.read() {
+        Ok(config) => config.conversations.clone(),
+        Err(_) => return,
+    };
+    if !settings.auto_title || response.trim().is_empty() {
+        return;
+    }
+
+    let is_first_exchange = conversation_manager
+        .lock()
+        .ok()
+        .and_then(|manager| manager.load_conversation(&conversation_id).ok())
+        .map(|conversation| {
+            conversation.messages.len() == 1 && conversation.messages[0].role == "user"
+        })
+        .unwrap_or(false);
+    if !is_first_exchange {
+        return;
+    }
+
+    titling::spawn_title_generation(
+        app.clone(),
+        Arc::clone(conversation_manager),
+        conversation_id,
+        endpoint,
+        settings.title_model.unwrap_or(model),
+        prompt,
+        response,
+    );
+}
+
+/// Messages of a stored conversation, or none if it has not been saved yet
 #[tauri::command]
-pub fn get_chat_history() -> Result<Vec<ChatMessage>, String> {
-    // Try to load from file
-    match std::fs::read_to_string("chat_history.json") {
-        Ok(content) => {
-            serde_json::from_str::<Vec<ChatMessage>>(&content)
-                .map_err(|e| format!("Failed to parse history: {}", e))
-        }
-        Err(_) => Ok(Vec::new()), // Return empty if file doesn't exist
+pub fn get_chat_history(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: Option<String>,
+) -> Result<Vec<ChatMessage>, String> {
+    let Some(conversation_id) = conversation_id else {
+        return Ok(Vec::new());
+    };
+
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    if !manager.conversation_exists(&conversation_id) {
+        return Ok(Vec::new());
     }
+    manager.load_conversation(&conversation_id)
+        .map(|conversation| conversation.messages)
+        .map_err(|e| e.to_string())
 }
 
+/// Start a new conversation, returning its ID
+///
+/// Nothing is stored until the first message is saved with `save_message`.
 #[tauri::command]
-pub fn new_conversation() -> Result<(), String> {
-    // Clear chat history
-    std::fs::write("chat_history.json", "[]")
-        .map_err(|e| format!("Failed to clear history: {}", e))
+pub fn new_conversation() -> Result<String, String> {
+    Ok(uuid::Uuid::new_v4().to_string())
+}
+
+// Conversation storage commands
+
+/// List stored conversations, pinned first, then newest first
+#[tauri::command]
+pub fn list_conversations(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+) -> Result<Vec<ConversationMetadata>, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.list_conversations().map_err(|e| e.to_string())
+}
+
+/// Load a stored conversation
+#[tauri::command]
+pub fn load_conversation(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+) -> Result<Conversation, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.load_conversation(&conversation_id).map_err(|e| e.to_string())
+}
+
+/// Append a message to a conversation, storing the conversation if it is new
+#[tauri::command]
+pub fn save_message(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+    role: String,
+    content: String,
+    model: Option<String>,
+    metadata: Option<MessageMetadata>,
+    thinking: Option<String>,
+) -> Result<ConversationMetadata, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    let message = ChatMessage::new(role, content)
+        .with_metadata(metadata.unwrap_or_default())
+        .with_thinking(thinking.filter(|t| !t.trim().is_empty()));
+    let conversation = manager
+        .append_message(&conversation_id, message, model)
+        .map_err(|e| e.to_string())?;
+    Ok(ConversationMetadata::from_conversation(&conversation))
+}
+
+/// Delete a stored conversation
+#[tauri::command]
+pub fn delete_conversation(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+) -> Result<(), String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.delete_conversation(&conversation_id).map_err(|e| e.to_string())
+}
+
+/// Rename a stored conversation
+#[tauri::command]
+pub fn rename_conversation(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+    name: String,
+) -> Result<(), String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.rename_conversation(&conversation_id, &name).map_err(|e| e.to_string())
 }
 
 // Persona management commands
@@ -375,3 +613,349 @@ pub fn get_active_endpoint(
     
     config.backend.get_active_endpoint_url()
 }
+
+// Message editing commands
+
+/// Replace a user message's content and drop the messages after it
+#[tauri::command]
+pub fn edit_message(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+    message_index: usize,
+    content: String,
+) -> Result<Conversation, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.edit_message(&conversation_id, message_index, content).map_err(|e| e.to_string())
+}
+
+/// Delete a single message
+#[tauri::command]
+pub fn delete_message(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+    message_index: usize,
+) -> Result<Conversation, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.delete_message(&conversation_id, message_index).map_err(|e| e.to_string())
+}
+
+/// Keep messages up to and including `message_index`
+#[tauri::command]
+pub fn truncate_after(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+    message_index: usize,
+) -> Result<Conversation, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.truncate_after(&conversation_id, message_index).map_err(|e| e.to_string())
+}
+
+/// List the branches of a conversation, created by edits and regenerations
+#[tauri::command]
+pub fn list_branches(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+) -> Result<Vec<BranchInfo>, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.list_branches(&conversation_id).map_err(|e| e.to_string())
+}
+
+/// Make the branch containing `message_id` the active path
+#[tauri::command]
+pub fn switch_branch(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+    message_id: String,
+) -> Result<Conversation, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.switch_branch(&conversation_id, &message_id).map_err(|e| e.to_string())
+}
+
+/// Delete an inactive branch
+#[tauri::command]
+pub fn prune_branch(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+    leaf_id: String,
+) -> Result<Conversation, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.prune_branch(&conversation_id, &leaf_id).map_err(|e| e.to_string())
+}
+
+/// Replace a response with a new one, streamed like `send_message_stream`
+///
+/// The response answering the last user message at or before `message_index`
+/// (or the last user message) is replaced, along with everything after it,
+/// once the new response has streamed. If the request fails or the model
+/// returns nothing, the old response is kept. The new response is saved to
+/// the conversation before `stream-done` is emitted.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn regenerate_response(
+    app: AppHandle,
+    conversation_id: String,
+    message_index: Option<usize>,
+    model: String,
+    request_id: String,
+    system_prompt: Option<String>,
+    persona_id: Option<String>,
+    connection_manager: State<'_, Arc<ConnectionManager>>,
+    persona_manager: State<'_, PersonaManager>,
+    conversation_manager: State<'_, Arc<Mutex<ConversationManager>>>,
+) -> Result<(), String> {
+    let ollama_url = connection_manager.get_active_endpoint()?;
+
+    let (user_index, prompt) = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?
+        .regeneration_prompt(&conversation_id, message_index)
+        .map_err(|e| e.to_string())?;
+
+    let mut metadata = MessageMetadata {
+        model: Some(model.clone()),
+        endpoint: Some(ollama_url.clone()),
+        persona_id: persona_id.or_else(|| persona_manager.get_active_persona().map(|p| p.id)),
+        ..Default::default()
+    };
+
+    let response = stream_response(
+        &app,
+        &ollama_url,
+        &model,
+        &prompt,
+        system_prompt.as_deref(),
+        &request_id,
+        &mut metadata,
+    )
+    .await?;
+    if response.text.trim().is_empty() {
+        let error = "Model returned an empty response; the previous one was kept".to_string();
+        let _ = app.emit("stream-error", serde_json::json!({
+            "request_id": request_id,
+            "error": error
+        }));
+        return Err(error);
+    }
+
+    let message = ChatMessage::new("assistant".to_string(), response.text)
+        .with_metadata(metadata.clone())
+        .with_thinking(response.thinking);
+    conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?
+        .replace_response(&conversation_id, user_index, message, Some(model))
+        .map_err(|e| e.to_string())?;
+
+    let _ = app.emit("stream-done", serde_json::json!({
+        "request_id": request_id,
+        "metadata": metadata
+    }));
+    Ok(())
+}
+
+// Conversation organization commands
+
+/// A tag and the number of conversations carrying it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// List all tags in use
+#[tauri::command]
+pub fn list_tags(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+) -> Result<Vec<TagCount>, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    let tags = manager.list_tags().map_err(|e| e.to_string())?;
+    Ok(tags.into_iter().map(|(tag, count)| TagCount { tag, count }).collect())
+}
+
+/// Add a tag to a conversation; returns false if it was already tagged
+#[tauri::command]
+pub fn add_conversation_tag(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+    tag: String,
+) -> Result<bool, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.add_tag(&conversation_id, &tag).map_err(|e| e.to_string())
+}
+
+/// Remove a tag from a conversation; returns false if it was not tagged
+#[tauri::command]
+pub fn remove_conversation_tag(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+    tag: String,
+) -> Result<bool, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.remove_tag(&conversation_id, &tag).map_err(|e| e.to_string())
+}
+
+/// Rename a tag on every conversation; returns the number changed
+#[tauri::command]
+pub fn rename_tag(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    old_tag: String,
+    new_tag: String,
+) -> Result<usize, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.rename_tag(&old_tag, &new_tag).map_err(|e| e.to_string())
+}
+
+/// Remove a tag from every conversation; returns the number changed
+#[tauri::command]
+pub fn delete_tag(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    tag: String,
+) -> Result<usize, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.delete_tag(&tag).map_err(|e| e.to_string())
+}
+
+/// Pin or unpin a conversation
+#[tauri::command]
+pub fn set_conversation_pinned(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+    pinned: bool,
+) -> Result<(), String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.set_pinned(&conversation_id, pinned).map_err(|e| e.to_string())
+}
+
+/// Move a conversation into a folder, or out of any folder with `None`
+#[tauri::command]
+pub fn set_conversation_folder(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+    conversation_id: String,
+    folder: Option<String>,
+) -> Result<(), String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.set_folder(&conversation_id, folder.as_deref()).map_err(|e| e.to_string())
+}
+
+/// List all folders in use
+#[tauri::command]
+pub fn list_folders(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+) -> Result<Vec<String>, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.list_folders().map_err(|e| e.to_string())
+}
+
+/// Render message markdown (CommonMark with GFM tables, strikethrough and task
+/// lists) to sanitized HTML
+#[tauri::command]
+pub fn render_markdown(content: String) -> String {
+    markdown::render_html(&content)
+}
+
+/// A search match with its context split into highlighted segments
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub conversation_id: String,
+    pub conversation_name: String,
+    pub message_index: usize,
+    pub role: String,
+    pub context: String,
+    pub segments: Vec<MessageSegment>,
+}
+
+/// Search the text of all stored conversations
+#[tauri::command]
+pub fn search_conversations(
+    query: String,
+    case_sensitive: Option<bool>,
+    whole_word: Option<bool>,
+    match_accents: Option<bool>,
+    fuzzy: Option<bool>,
+    stem_language: Option<String>,
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+) -> Result<Vec<SearchHit>, String> {
+    let stemming = match stem_language {
+        Some(name) => Some(
+            language_from_name(&name).ok_or_else(|| format!("Unsupported stemming language: {}", name))?,
+        ),
+        None => None,
+    };
+
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    let query = SearchQuery::new(query)
+        .with_case_sensitive(case_sensitive.unwrap_or(false))
+        .with_whole_word(whole_word.unwrap_or(false))
+        .with_diacritic_sensitive(match_accents.unwrap_or(false))
+        .with_max_edits(if fuzzy.unwrap_or(false) { 2 } else { 0 })
+        .with_stemming(stemming);
+    let results = manager.search(&query).map_err(|e| e.to_string())?;
+
+    let names: HashMap<String, String> = manager.list_conversations()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|metadata| (metadata.id, metadata.name))
+        .collect();
+
+    Ok(results
+        .into_iter()
+        .map(|result| SearchHit {
+            conversation_name: names.get(&result.conversation_id).cloned().unwrap_or_default(),
+            segments: highlight_matches(&result.context, &result.context_matches(&query)),
+            conversation_id: result.conversation_id,
+            message_index: result.message_index,
+            role: result.role,
+            context: result.context,
+        })
+        .collect())
+}
+
+/// Re-index every stored conversation, returning how many were indexed
+#[tauri::command]
+pub fn rebuild_search_index(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+) -> Result<usize, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.rebuild_search_index().map_err(|e| e.to_string())
+}
+
+/// Whether search is available and how much of the history is indexed
+#[tauri::command]
+pub fn get_search_status(
+    conversation_manager: State<Arc<Mutex<ConversationManager>>>,
+) -> Result<SearchStatus, String> {
+    let manager = conversation_manager.lock()
+        .map_err(|e| format!("Failed to acquire conversation lock: {}", e))?;
+
+    manager.search_status().map_err(|e| e.to_string())
+}
@@ -8,6 +8,8 @@ pub struct AppConfig {
     pub app: AppSettings,
     pub backend: BackendSettings,
     pub ui: UISettings,
+    #[serde(default)]
+    pub conversations: ConversationSettings,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -298,6 +300,30 @@ fn default_theme() -> String {
     "Hacker Green".to_string()
 }
 
+/// Settings for stored conversations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSettings {
+    /// Generate a short title in the background after the first exchange
+    #[serde(default = "default_auto_title")]
+    pub auto_title: bool,
+    /// Model used to generate titles; the conversation's own model when unset
+    #[serde(default)]
+    pub title_model: Option<String>,
+}
+
+fn default_auto_title() -> bool {
+    true
+}
+
+impl Default for ConversationSettings {
+    fn default() -> Self {
+        Self {
+            auto_title: default_auto_title(),
+            title_model: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ColorTheme {
     HackerGreen,
@@ -442,6 +468,7 @@ impl Default for AppConfig {
                 max_chat_history: 1000,
                 theme: "Hacker Green".to_string(),
             },
+            conversations: ConversationSettings::default(),
         }
     }
 }
@@ -1,18 +1,36 @@
-use crate::app::ChatMessage;
 use anyhow::{Context, Result};
+use prometheus_chat::{BranchInfo, ChatMessage, MessageNode, MessageTree};
+use prometheus_search::{SearchEngine, SearchMessage, SearchQuery, SearchResult, Searchable};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+/// Name of the persisted search index in the conversations directory
+const SEARCH_INDEX_FILE: &str = "search_index.json";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Conversation {
     pub id: String,
     pub name: String,
+    /// Messages on the active path, in order
     pub messages: Vec<ChatMessage>,
+    /// Messages on inactive branches, e.g. the original tail of an edited prompt
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branch_nodes: Vec<MessageNode>,
     pub created_at: String,
     pub updated_at: String,
     pub model: Option<String>,
+    /// Lowercase labels used to organize and filter conversations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Pinned conversations are listed before all others
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// Slash-separated folder path, e.g. "work/reviews"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
 }
 
 impl Conversation {
@@ -22,9 +40,13 @@ impl Conversation {
             id: Uuid::new_v4().to_string(),
             name,
             messages: Vec::new(),
+            branch_nodes: Vec::new(),
             created_at: now.clone(),
             updated_at: now,
             model,
+            tags: Vec::new(),
+            pinned: false,
+            folder: None,
         }
     }
 
@@ -42,26 +64,129 @@ impl Conversation {
         self.update_timestamp();
     }
 
-    pub fn remove_message(&mut self, index: usize) {
-        if index < self.messages.len() {
-            self.messages.remove(index);
-            self.update_timestamp();
+    /// Add a tag, returning `false` if the conversation already had it
+    pub fn add_tag(&mut self, tag: &str) -> Result<bool> {
+        let tag = normalize_tag(tag)?;
+        if self.tags.contains(&tag) {
+            return Ok(false);
         }
+        self.tags.push(tag);
+        self.tags.sort();
+        Ok(true)
     }
 
-    pub fn update_message(&mut self, index: usize, content: String) {
-        if index < self.messages.len() {
-            self.messages[index].content = content;
-            self.update_timestamp();
-        }
+    /// Remove a tag, returning `false` if the conversation did not have it
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let tag = tag_key(tag);
+        let before = self.tags.len();
+        self.tags.retain(|t| *t != tag);
+        self.tags.len() != before
     }
 
-    pub fn clear_messages_after(&mut self, index: usize) {
-        if index < self.messages.len() {
-            self.messages.truncate(index + 1);
-            self.update_timestamp();
-        }
+    /// Move the conversation into a folder, or out of any folder with `None`
+    pub fn set_folder(&mut self, folder: Option<&str>) -> Result<()> {
+        self.folder = match folder {
+            Some(folder) => normalize_folder(folder)?,
+            None => None,
+        };
+        Ok(())
+    }
+}
+
+impl MessageTree for Conversation {
+    fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    fn messages_mut(&mut self) -> &mut Vec<ChatMessage> {
+        &mut self.messages
+    }
+
+    fn branch_nodes(&self) -> &[MessageNode] {
+        &self.branch_nodes
+    }
+
+    fn branch_nodes_mut(&mut self) -> &mut Vec<MessageNode> {
+        &mut self.branch_nodes
+    }
+
+    fn touch(&mut self) {
+        self.update_timestamp();
+    }
+}
+
+impl Searchable for Conversation {
+    fn search_id(&self) -> &str {
+        &self.id
+    }
+
+    fn search_messages(&self) -> Vec<SearchMessage<'_>> {
+        self.messages
+            .iter()
+            .map(|m| SearchMessage {
+                role: &m.role,
+                content: &m.content,
+                timestamp: Some(&m.timestamp),
+                model: m.metadata.model.as_deref(),
+            })
+            .collect()
+    }
+
+    fn search_updated_at(&self) -> Option<&str> {
+        Some(&self.updated_at)
+    }
+
+    fn search_name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+
+    fn search_model(&self) -> Option<&str> {
+        self.model.as_deref()
     }
+
+    fn search_tags(&self) -> Vec<&str> {
+        self.tags.iter().map(String::as_str).collect()
+    }
+}
+
+/// Normalize a tag: trimmed, lowercase, without a leading '#', whitespace or commas
+pub fn normalize_tag(tag: &str) -> Result<String> {
+    let tag = tag_key(tag);
+    if tag.is_empty() {
+        anyhow::bail!("Tag cannot be empty");
+    }
+    if tag.chars().any(|c| c.is_whitespace() || c == ',') {
+        anyhow::bail!("Tag cannot contain spaces or commas: {}", tag);
+    }
+    Ok(tag)
+}
+
+fn tag_key(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+/// Normalize a folder path to `a/b/c` form, or `None` for an empty path
+pub fn normalize_folder(folder: &str) -> Result<Option<String>> {
+    let parts: Vec<&str> = folder
+        .split('/')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+    if parts.iter().any(|p| *p == "." || *p == "..") {
+        anyhow::bail!("Folder path cannot contain '.' or '..': {}", folder);
+    }
+    Ok(if parts.is_empty() { None } else { Some(parts.join("/")) })
+}
+
+fn check_message_index(conversation: &Conversation, index: usize) -> Result<()> {
+    if index >= conversation.messages.len() {
+        anyhow::bail!(
+            "Message index {} is out of range for a conversation with {} messages",
+            index,
+            conversation.messages.len()
+        );
+    }
+    Ok(())
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,6 +196,12 @@ pub struct ConversationMetadata {
     pub preview: String,
     pub updated_at: String,
     pub message_count: usize,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub folder: Option<String>,
 }
 
 impl ConversationMetadata {
@@ -85,8 +216,8 @@ impl ConversationMetadata {
                 .map(|m| &m.content)
                 .unwrap_or(&conv.messages[0].content);
             
-            if first_user_msg.len() > 50 {
-                format!("{}...", &first_user_msg[..50])
+            if first_user_msg.chars().count() > 50 {
+                format!("{}...", first_user_msg.chars().take(50).collect::<String>())
             } else {
                 first_user_msg.clone()
             }
@@ -98,8 +229,15 @@ impl ConversationMetadata {
             preview,
             updated_at: conv.updated_at.clone(),
             message_count: conv.messages.len(),
+            tags: conv.tags.clone(),
+            pinned: conv.pinned,
+            folder: conv.folder.clone(),
         }
     }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(&tag_key(tag))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -127,15 +265,30 @@ impl MetadataFile {
         self.conversations.retain(|m| m.id != id);
     }
 
+    /// Sort pinned conversations first, then newest first
     pub fn sort_by_date(&mut self) {
-        self.conversations.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        self.conversations.sort_by(|a, b| {
+            b.pinned
+                .cmp(&a.pinned)
+                .then_with(|| b.updated_at.cmp(&a.updated_at))
+        });
     }
 }
 
+/// State of the conversation search index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchStatus {
+    pub enabled: bool,
+    pub indexed_conversations: usize,
+    pub total_conversations: usize,
+}
+
 #[derive(Clone)]
 pub struct ConversationManager {
     conversations_dir: PathBuf,
     metadata_path: PathBuf,
+    /// Kept up to date on every save and delete once search is enabled
+    search: Option<Arc<Mutex<SearchEngine>>>,
 }
 
 impl ConversationManager {
@@ -145,9 +298,140 @@ impl ConversationManager {
         Self {
             conversations_dir,
             metadata_path,
+            search: None,
+        }
+    }
+
+    pub fn with_directory<P: Into<PathBuf>>(conversations_dir: P) -> Self {
+        let conversations_dir = conversations_dir.into();
+        let metadata_path = conversations_dir.join("metadata.json");
+        Self {
+            conversations_dir,
+            metadata_path,
+            search: None,
         }
     }
 
+    /// Store conversations in a `conversations` directory under the app data directory
+    pub fn in_app_data_dir(app_data_dir: &Path) -> Self {
+        Self::with_directory(app_data_dir.join("conversations"))
+    }
+
+    /// Import the single-file history written by older versions
+    ///
+    /// The messages in `legacy_path` become one new conversation and the file
+    /// is renamed to `<name>.migrated` so it is only imported once.
+    ///
+    /// # Returns
+    /// The imported conversation, or `None` if there was nothing to import
+    pub fn migrate_legacy_history(&self, legacy_path: &Path) -> Result<Option<Conversation>> {
+        if !legacy_path.exists() {
+            return Ok(None);
+        }
+
+        let content = fs::read_to_string(legacy_path)
+            .context("Failed to read legacy chat history")?;
+        let messages: Vec<ChatMessage> = if content.trim().is_empty() {
+            Vec::new()
+        } else {
+            serde_json::from_str(&content).context("Failed to parse legacy chat history")?
+        };
+
+        let imported = if messages.is_empty() {
+            None
+        } else {
+            let mut conversation = Conversation::new("Imported chat".to_string(), None);
+            conversation.created_at = messages[0].timestamp.clone();
+            conversation.messages = messages;
+            self.save_conversation(&conversation)?;
+            Some(conversation)
+        };
+
+        let mut migrated_name = legacy_path.as_os_str().to_owned();
+        migrated_name.push(".migrated");
+        fs::rename(legacy_path, &migrated_name)
+            .context("Failed to rename legacy chat history")?;
+
+        Ok(imported)
+    }
+
+    /// Enable search, loading the persisted index or rebuilding it if missing or unreadable
+    pub fn enable_search(&mut self) -> Result<()> {
+        match SearchEngine::load_from_file(&self.search_index_path()) {
+            Ok(engine) => {
+                self.search = Some(Arc::new(Mutex::new(engine)));
+            }
+            Err(_) => {
+                self.search = Some(Arc::new(Mutex::new(SearchEngine::new())));
+                self.rebuild_search_index()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn search_index_path(&self) -> PathBuf {
+        self.conversations_dir.join(SEARCH_INDEX_FILE)
+    }
+
+    /// Search stored messages
+    pub fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+        let search = self.search.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Search is not enabled"))?;
+        let engine = search.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire search index lock: {}", e))?;
+        Ok(engine.search(query)?)
+    }
+
+    /// Re-index every stored conversation and persist the index
+    ///
+    /// # Returns
+    /// The number of conversations indexed
+    pub fn rebuild_search_index(&self) -> Result<usize> {
+        let mut conversations = Vec::new();
+        for metadata in self.list_conversations()? {
+            match self.load_conversation(&metadata.id) {
+                Ok(conversation) => conversations.push(conversation),
+                Err(e) => eprintln!("Skipping conversation {} while indexing: {}", metadata.id, e),
+            }
+        }
+
+        let count = conversations.len();
+        self.update_search_index(|engine| engine.rebuild_index(&conversations))?;
+        Ok(count)
+    }
+
+    pub fn search_status(&self) -> Result<SearchStatus> {
+        let total_conversations = self.list_conversations()?.len();
+        let indexed_conversations = match &self.search {
+            Some(search) => search.lock()
+                .map_err(|e| anyhow::anyhow!("Failed to acquire search index lock: {}", e))?
+                .indexed_count(),
+            None => 0,
+        };
+        Ok(SearchStatus {
+            enabled: self.search.is_some(),
+            indexed_conversations,
+            total_conversations,
+        })
+    }
+
+    /// Apply `change` to the search index, if enabled, and persist it
+    fn update_search_index(&self, change: impl FnOnce(&mut SearchEngine)) -> Result<()> {
+        let Some(search) = &self.search else {
+            return Ok(());
+        };
+        let mut engine = search.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire search index lock: {}", e))?;
+        change(&mut engine);
+
+        if !self.conversations_dir.exists() {
+            fs::create_dir_all(&self.conversations_dir)
+                .context("Failed to create conversations directory")?;
+        }
+        engine.save_to_file(&self.search_index_path())
+            .map_err(|e| anyhow::anyhow!("Failed to save search index: {}", e))
+    }
+
     pub fn load_metadata(&self) -> Result<MetadataFile> {
         if !self.metadata_path.exists() {
             return Ok(MetadataFile::new());
@@ -173,8 +457,21 @@ impl ConversationManager {
         Ok(())
     }
 
+    /// Path of a conversation's file
+    ///
+    /// IDs come from the frontend, so anything but a UUID is rejected rather
+    /// than joined into a path that could point outside the directory.
+    fn conversation_path(&self, id: &str) -> Result<PathBuf> {
+        match Uuid::parse_str(id) {
+            Ok(uuid) if uuid.to_string() == id => {
+                Ok(self.conversations_dir.join(format!("{}.json", id)))
+            }
+            _ => anyhow::bail!("Invalid conversation ID: {}", id),
+        }
+    }
+
     pub fn load_conversation(&self, id: &str) -> Result<Conversation> {
-        let path = self.conversations_dir.join(format!("{}.json", id));
+        let path = self.conversation_path(id)?;
         
         if !path.exists() {
             anyhow::bail!("Conversation file not found: {}", id);
@@ -190,8 +487,14 @@ impl ConversationManager {
     }
 
     pub fn save_conversation(&self, conversation: &Conversation) -> Result<()> {
-        let path = self.conversations_dir.join(format!("{}.json", conversation.id));
-        
+        let path = self.conversation_path(&conversation.id)?;
+
+        // Ensure the conversations directory exists
+        if !self.conversations_dir.exists() {
+            fs::create_dir_all(&self.conversations_dir)
+                .context("Failed to create conversations directory")?;
+        }
+
         let content = serde_json::to_string_pretty(conversation)
             .context("Failed to serialize conversation")?;
         
@@ -202,12 +505,20 @@ impl ConversationManager {
         let mut metadata = self.load_metadata()?;
         metadata.add_or_update(ConversationMetadata::from_conversation(conversation));
         self.save_metadata(&metadata)?;
+
+        // The conversation itself is saved; a stale index is rebuilt on demand
+        if let Err(e) = self.update_search_index(|engine| {
+            engine.remove_conversation(&conversation.id);
+            engine.index_conversation(conversation);
+        }) {
+            eprintln!("Failed to update search index: {}", e);
+        }
         
         Ok(())
     }
 
     pub fn delete_conversation(&self, id: &str) -> Result<()> {
-        let path = self.conversations_dir.join(format!("{}.json", id));
+        let path = self.conversation_path(id)?;
         
         if path.exists() {
             fs::remove_file(&path)
@@ -218,6 +529,10 @@ impl ConversationManager {
         let mut metadata = self.load_metadata()?;
         metadata.remove(id);
         self.save_metadata(&metadata)?;
+
+        if let Err(e) = self.update_search_index(|engine| engine.remove_conversation(id)) {
+            eprintln!("Failed to update search index: {}", e);
+        }
         
         Ok(())
     }
@@ -226,4 +541,503 @@ impl ConversationManager {
         let metadata = self.load_metadata()?;
         Ok(metadata.conversations)
     }
+
+    /// All tags in use, with the number of conversations carrying each
+    pub fn list_tags(&self) -> Result<Vec<(String, usize)>> {
+        let mut counts: std::collections::BTreeMap<String, usize> = Default::default();
+        for conversation in self.list_conversations()? {
+            for tag in conversation.tags {
+                *counts.entry(tag).or_default() += 1;
+            }
+        }
+        Ok(counts.into_iter().collect())
+    }
+
+    /// All folders in use, sorted
+    pub fn list_folders(&self) -> Result<Vec<String>> {
+        let mut folders: Vec<String> = self
+            .list_conversations()?
+            .into_iter()
+            .filter_map(|c| c.folder)
+            .collect();
+        folders.sort();
+        folders.dedup();
+        Ok(folders)
+    }
+
+    /// Load a conversation, apply `change` and save it again
+    fn update_conversation<T>(
+        &self,
+        id: &str,
+        change: impl FnOnce(&mut Conversation) -> Result<T>,
+    ) -> Result<T> {
+        let mut conversation = self.load_conversation(id)?;
+        let result = change(&mut conversation)?;
+        self.save_conversation(&conversation)?;
+        Ok(result)
+    }
+
+    /// Append a message to a conversation, creating it under `id` if it is not stored yet
+    pub fn append_message(
+        &self,
+        id: &str,
+        message: ChatMessage,
+        model: Option<String>,
+    ) -> Result<Conversation> {
+        let mut conversation = if self.conversation_path(id)?.exists() {
+            self.load_conversation(id)?
+        } else {
+            let mut conversation = Conversation::with_timestamp_name(model.clone());
+            conversation.id = id.to_string();
+            conversation
+        };
+        if model.is_some() {
+            conversation.model = model;
+        }
+        conversation.add_message(message);
+        self.save_conversation(&conversation)?;
+        Ok(conversation)
+    }
+
+    /// Replace a user message's content with an edited sibling
+    ///
+    /// The original message and the replies to it are kept as an inactive branch,
+    /// and the active path ends at the edited message.
+    pub fn edit_message(&self, id: &str, index: usize, content: String) -> Result<Conversation> {
+        self.update_conversation(id, |c| {
+            check_message_index(c, index)?;
+            if c.messages[index].role != "user" {
+                anyhow::bail!("Only user messages can be edited");
+            }
+            c.update_message(index, content);
+            Ok(c.clone())
+        })
+    }
+
+    pub fn delete_message(&self, id: &str, index: usize) -> Result<Conversation> {
+        self.update_conversation(id, |c| {
+            check_message_index(c, index)?;
+            c.remove_message(index);
+            Ok(c.clone())
+        })
+    }
+
+    /// End the active path at `index`, keeping the later messages as an inactive branch
+    pub fn truncate_after(&self, id: &str, index: usize) -> Result<Conversation> {
+        self.update_conversation(id, |c| {
+            check_message_index(c, index)?;
+            c.clear_messages_after(index);
+            Ok(c.clone())
+        })
+    }
+
+    /// Find the user message whose response is regenerated, without changing anything
+    ///
+    /// That is the last user message at or before `index`, or the last user
+    /// message if `index` is `None`. The old response stays stored until
+    /// [`Self::replace_response`] is called with the new one.
+    ///
+    /// # Returns
+    /// The index and content of the user message to send again
+    pub fn regeneration_prompt(&self, id: &str, index: Option<usize>) -> Result<(usize, String)> {
+        let c = self.load_conversation(id)?;
+        let end = match index {
+            Some(index) => {
+                check_message_index(&c, index)?;
+                index
+            }
+            None => c.messages.len().saturating_sub(1),
+        };
+        let user_index = c.messages.iter()
+            .take(end + 1)
+            .rposition(|m| m.role == "user")
+            .ok_or_else(|| anyhow::anyhow!("No user message to regenerate a response for"))?;
+        Ok((user_index, c.messages[user_index].content.clone()))
+    }
+
+    /// Answer the user message at `user_index` with `response`
+    ///
+    /// The previous answer and everything after it are kept as an inactive branch.
+    pub fn replace_response(
+        &self,
+        id: &str,
+        user_index: usize,
+        response: ChatMessage,
+        model: Option<String>,
+    ) -> Result<Conversation> {
+        self.update_conversation(id, |c| {
+            check_message_index(c, user_index)?;
+            if c.messages[user_index].role != "user" {
+                anyhow::bail!("Message {} is not a user message", user_index);
+            }
+            c.clear_messages_after(user_index);
+            if model.is_some() {
+                c.model = model;
+            }
+            c.add_message(response);
+            Ok(c.clone())
+        })
+    }
+
+    /// List every branch of a conversation, ordered by the time of its last message
+    pub fn list_branches(&self, id: &str) -> Result<Vec<BranchInfo>> {
+        Ok(self.load_conversation(id)?.list_branches())
+    }
+
+    /// Make the branch containing `message_id` the active path
+    pub fn switch_branch(&self, id: &str, message_id: &str) -> Result<Conversation> {
+        self.update_conversation(id, |c| {
+            c.switch_branch(message_id)?;
+            Ok(c.clone())
+        })
+    }
+
+    /// Delete an inactive branch, keeping messages it shares with other branches
+    pub fn prune_branch(&self, id: &str, leaf_id: &str) -> Result<Conversation> {
+        self.update_conversation(id, |c| {
+            c.prune_branch(leaf_id)?;
+            Ok(c.clone())
+        })
+    }
+
+    pub fn rename_conversation(&self, id: &str, name: &str) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            anyhow::bail!("Conversation name cannot be empty");
+        }
+        self.update_conversation(id, |c| {
+            c.name = name.to_string();
+            Ok(())
+        })
+    }
+
+    pub fn conversation_exists(&self, id: &str) -> bool {
+        self.conversation_path(id).map(|path| path.exists()).unwrap_or(false)
+    }
+
+    pub fn add_tag(&self, id: &str, tag: &str) -> Result<bool> {
+        self.update_conversation(id, |c| c.add_tag(tag))
+    }
+
+    pub fn remove_tag(&self, id: &str, tag: &str) -> Result<bool> {
+        self.update_conversation(id, |c| Ok(c.remove_tag(tag)))
+    }
+
+    /// Rename a tag on every conversation, returning how many were changed
+    pub fn rename_tag(&self, old: &str, new: &str) -> Result<usize> {
+        let new = normalize_tag(new)?;
+        let mut changed = 0;
+        for metadata in self.list_conversations()? {
+            if metadata.has_tag(old) {
+                self.update_conversation(&metadata.id, |c| {
+                    c.remove_tag(old);
+                    c.add_tag(&new)
+                })?;
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Remove a tag from every conversation, returning how many were changed
+    pub fn delete_tag(&self, tag: &str) -> Result<usize> {
+        let mut changed = 0;
+        for metadata in self.list_conversations()? {
+            if metadata.has_tag(tag) {
+                self.update_conversation(&metadata.id, |c| Ok(c.remove_tag(tag)))?;
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    pub fn set_pinned(&self, id: &str, pinned: bool) -> Result<()> {
+        self.update_conversation(id, |c| {
+            c.pinned = pinned;
+            Ok(())
+        })
+    }
+
+    pub fn set_folder(&self, id: &str, folder: Option<&str>) -> Result<()> {
+        self.update_conversation(id, |c| c.set_folder(folder))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: &str = "6f1c3c1e-8a4e-4d55-9d6a-0c8f4b7e2a01";
+    const B: &str = "6f1c3c1e-8a4e-4d55-9d6a-0c8f4b7e2a02";
+    const MISSING: &str = "6f1c3c1e-8a4e-4d55-9d6a-0c8f4b7e2a03";
+
+    fn conversation(id: &str, updated_at: &str) -> Conversation {
+        let mut conversation = Conversation::new(format!("Chat {}", id), None);
+        conversation.id = id.to_string();
+        conversation.updated_at = updated_at.to_string();
+        conversation
+    }
+
+    #[test]
+    fn test_sort_by_date_keeps_pinned_first() {
+        let mut metadata = MetadataFile::new();
+        let mut pinned = conversation("pinned", "2024-01-01");
+        pinned.pinned = true;
+        metadata.add_or_update(ConversationMetadata::from_conversation(&pinned));
+        metadata.add_or_update(ConversationMetadata::from_conversation(&conversation("new", "2024-03-01")));
+
+        let ids: Vec<&str> = metadata.conversations.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["pinned", "new"]);
+    }
+
+    #[test]
+    fn test_manager_tags_pin_and_folder() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let conv = conversation(A, "2024-01-01");
+        manager.save_conversation(&conv).unwrap();
+
+        assert!(manager.add_tag(A, "#Rust").unwrap());
+        manager.set_pinned(A, true).unwrap();
+        manager.set_folder(A, Some("/work/")).unwrap();
+        assert_eq!(manager.list_tags().unwrap(), vec![("rust".to_string(), 1)]);
+        assert_eq!(manager.list_folders().unwrap(), vec!["work"]);
+
+        assert_eq!(manager.rename_tag("rust", "rustlang").unwrap(), 1);
+        let listed = manager.list_conversations().unwrap();
+        assert_eq!(listed[0].tags, vec!["rustlang"]);
+        assert!(listed[0].pinned);
+
+        assert_eq!(manager.delete_tag("rustlang").unwrap(), 1);
+        assert!(manager.list_tags().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_migrate_legacy_history() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::in_app_data_dir(temp_dir.path());
+        let legacy_path = temp_dir.path().join("chat_history.json");
+        fs::write(&legacy_path, r#"[
+            {"role": "user", "content": "Hello", "timestamp": "2024-01-01T00:00:00+00:00"},
+            {"role": "assistant", "content": "Hi!", "timestamp": "2024-01-01T00:00:05+00:00"}
+        ]"#).unwrap();
+
+        let imported = manager.migrate_legacy_history(&legacy_path).unwrap().unwrap();
+        assert_eq!(imported.messages.len(), 2);
+        assert!(!legacy_path.exists());
+        assert!(temp_dir.path().join("chat_history.json.migrated").exists());
+
+        let listed = manager.list_conversations().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].preview, "Hello");
+
+        // Nothing left to import the second time
+        assert!(manager.migrate_legacy_history(&legacy_path).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_append_message_creates_then_extends_conversation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let id = Uuid::new_v4().to_string();
+        let message = |role: &str, content: &str| ChatMessage::new(role.to_string(), content.to_string());
+
+        assert!(!manager.conversation_exists(&id));
+        manager.append_message(&id, message("user", "Hello"), Some("llama3".to_string())).unwrap();
+        let conversation = manager.append_message(&id, message("assistant", "Hi!"), None).unwrap();
+        assert_eq!(conversation.messages.len(), 2);
+        assert_eq!(conversation.model.as_deref(), Some("llama3"));
+        assert_eq!(manager.list_conversations().unwrap().len(), 1);
+
+        assert!(manager.append_message("../escape", message("user", "x"), None).is_err());
+    }
+
+    #[test]
+    fn test_rename_conversation() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        manager.save_conversation(&conversation(A, "2024-01-01")).unwrap();
+
+        manager.rename_conversation(A, "  Sorting in Rust ").unwrap();
+        assert_eq!(manager.load_conversation(A).unwrap().name, "Sorting in Rust");
+        assert!(manager.rename_conversation(A, "   ").is_err());
+        assert!(manager.rename_conversation(MISSING, "Name").is_err());
+    }
+
+    #[test]
+    fn test_ids_outside_the_directory_are_rejected() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let conversations_dir = temp_dir.path().join("conversations");
+        let manager = ConversationManager::with_directory(&conversations_dir);
+        manager.save_conversation(&conversation(A, "2024-01-01")).unwrap();
+        let outside = temp_dir.path().join("outside.json");
+        fs::write(&outside, serde_json::to_string(&conversation(B, "2024-01-01")).unwrap()).unwrap();
+
+        for id in ["../outside", "..\\outside", "/etc/passwd", "", "a", A.to_uppercase().as_str()] {
+            assert!(manager.load_conversation(id).is_err(), "{}", id);
+            assert!(manager.delete_conversation(id).is_err(), "{}", id);
+            assert!(manager.rename_conversation(id, "Name").is_err(), "{}", id);
+            assert!(manager.add_tag(id, "tag").is_err(), "{}", id);
+            assert!(!manager.conversation_exists(id), "{}", id);
+        }
+        let mut escaping = conversation(B, "2024-01-01");
+        escaping.id = "../outside".to_string();
+        assert!(manager.save_conversation(&escaping).is_err());
+
+        assert!(outside.exists());
+        assert_eq!(manager.list_conversations().unwrap().len(), 1);
+    }
+
+    fn saved_exchange(manager: &ConversationManager) -> Conversation {
+        let mut conversation = conversation(A, "2024-01-01");
+        for (role, content) in [("user", "Q1"), ("assistant", "A1"), ("user", "Q2"), ("assistant", "A2")] {
+            let mut message = ChatMessage::new(role.to_string(), content.to_string());
+            message.timestamp = "2024-01-01T00:00:00+00:00".to_string();
+            conversation.messages.push(message);
+        }
+        manager.save_conversation(&conversation).unwrap();
+        conversation
+    }
+
+    fn contents(conversation: &Conversation) -> Vec<&str> {
+        conversation.messages.iter().map(|m| m.content.as_str()).collect()
+    }
+
+    #[test]
+    fn test_edit_message_keeps_old_tail_as_branch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        saved_exchange(&manager);
+
+        assert!(manager.edit_message(A, 1, "edited".to_string()).is_err());
+        assert!(manager.edit_message(A, 9, "edited".to_string()).is_err());
+
+        let edited = manager.edit_message(A, 0, "Q1 edited".to_string()).unwrap();
+        assert_eq!(contents(&edited), vec!["Q1 edited"]);
+        assert_eq!(contents(&manager.load_conversation(A).unwrap()), vec!["Q1 edited"]);
+
+        // The original question and its replies are still there to switch back to
+        let branches = manager.list_branches(A).unwrap();
+        assert_eq!(branches.len(), 2);
+        let original = branches.iter().find(|b| !b.active).unwrap();
+        let restored = manager.switch_branch(A, &original.leaf_id).unwrap();
+        assert_eq!(contents(&restored), vec!["Q1", "A1", "Q2", "A2"]);
+    }
+
+    #[test]
+    fn test_delete_message_and_truncate_after() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        saved_exchange(&manager);
+
+        let deleted = manager.delete_message(A, 1).unwrap();
+        assert_eq!(contents(&deleted), vec!["Q1", "Q2", "A2"]);
+
+        let truncated = manager.truncate_after(A, 1).unwrap();
+        assert_eq!(contents(&truncated), vec!["Q1", "Q2"]);
+        assert!(manager.truncate_after(A, 5).is_err());
+
+        // The dropped answer is kept as a branch
+        assert_eq!(manager.list_branches(A).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_regeneration_keeps_old_answer_until_replaced() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        saved_exchange(&manager);
+        let all = vec!["Q1", "A1", "Q2", "A2"];
+
+        // Finding the prompt changes nothing, so a failed request loses no answer
+        assert_eq!(manager.regeneration_prompt(A, Some(1)).unwrap(), (0, "Q1".to_string()));
+        assert_eq!(manager.regeneration_prompt(A, None).unwrap(), (2, "Q2".to_string()));
+        assert_eq!(contents(&manager.load_conversation(A).unwrap()), all);
+        assert!(manager.regeneration_prompt(A, Some(9)).is_err());
+
+        // Regenerating the first answer drops everything after the first question
+        let response = ChatMessage::new("assistant".to_string(), "A1 again".to_string());
+        let replaced = manager.replace_response(A, 0, response.clone(), None).unwrap();
+        assert_eq!(contents(&replaced), vec!["Q1", "A1 again"]);
+        assert_eq!(contents(&manager.load_conversation(A).unwrap()), vec!["Q1", "A1 again"]);
+
+        assert!(manager.replace_response(A, 1, response, None).is_err());
+    }
+
+    #[test]
+    fn test_branches_are_listed_switched_and_pruned() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        saved_exchange(&manager);
+        let response = ChatMessage::new("assistant".to_string(), "A2 again".to_string());
+        manager.replace_response(A, 2, response, None).unwrap();
+
+        let branches = manager.list_branches(A).unwrap();
+        assert_eq!(branches.len(), 2);
+        let old = branches.iter().find(|b| !b.active).unwrap();
+        assert_eq!(old.preview, "A2");
+
+        let switched = manager.switch_branch(A, &old.leaf_id).unwrap();
+        assert_eq!(contents(&switched), vec!["Q1", "A1", "Q2", "A2"]);
+        assert_eq!(contents(&manager.load_conversation(A).unwrap()), vec!["Q1", "A1", "Q2", "A2"]);
+
+        let regenerated = manager.list_branches(A).unwrap().into_iter().find(|b| !b.active).unwrap();
+        assert!(manager.prune_branch(A, &old.leaf_id).is_err());
+        let pruned = manager.prune_branch(A, &regenerated.leaf_id).unwrap();
+        assert!(pruned.branch_nodes.is_empty());
+        assert!(manager.switch_branch(A, "missing").is_err());
+    }
+
+    #[test]
+    fn test_flat_conversation_files_load_with_message_ids() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = ConversationManager::with_directory(temp_dir.path());
+        let json = format!(r#"{{
+            "id": "{}",
+            "name": "Old chat",
+            "messages": [
+                {{"role": "user", "content": "Hello", "timestamp": "2024-01-01T00:00:00+00:00"}},
+                {{"role": "assistant", "content": "Hi", "timestamp": "2024-01-01T00:00:01+00:00"}}
+            ],
+            "created_at": "2024-01-01T00:00:00+00:00",
+            "updated_at": "2024-01-01T00:00:01+00:00",
+            "model": null
+        }}"#, A);
+        fs::write(temp_dir.path().join(format!("{}.json", A)), json).unwrap();
+
+        let conversation = manager.load_conversation(A).unwrap();
+        assert_ne!(conversation.messages[0].id, conversation.messages[1].id);
+        assert!(!conversation.has_branches());
+    }
+
+    #[test]
+    fn test_search_index_follows_saves_and_deletes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut manager = ConversationManager::with_directory(temp_dir.path());
+        let mut first = conversation(A, "2024-01-01");
+        first.messages.push(ChatMessage::new("user".to_string(), "Tell me about lifetimes".to_string()));
+        manager.save_conversation(&first).unwrap();
+
+        // Enabling search indexes what is already stored
+        manager.enable_search().unwrap();
+        let query = SearchQuery::new("lifetimes".to_string());
+        assert_eq!(manager.search(&query).unwrap().len(), 1);
+
+        let mut second = conversation(B, "2024-01-02");
+        second.messages.push(ChatMessage::new("user".to_string(), "More lifetimes".to_string()));
+        manager.save_conversation(&second).unwrap();
+        assert_eq!(manager.search(&query).unwrap().len(), 2);
+
+        manager.delete_conversation(A).unwrap();
+        let results = manager.search(&query).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].conversation_id, B);
+
+        // The persisted index is picked up by a new manager
+        let mut reopened = ConversationManager::with_directory(temp_dir.path());
+        reopened.enable_search().unwrap();
+        let status = reopened.search_status().unwrap();
+        assert!(status.enabled);
+        assert_eq!(status.indexed_conversations, 1);
+        assert_eq!(status.total_conversations, 1);
+    }
 }
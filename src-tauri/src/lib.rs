@@ -1,6 +1,8 @@
 mod commands;
-mod persona;
+mod titling;
 pub mod config;
+pub mod conversation;
+pub mod markdown;
 pub mod network;
 
 use commands::{
@@ -9,12 +11,21 @@ use commands::{
     add_remote_endpoint, remove_remote_endpoint, update_remote_endpoint,
     list_remote_endpoints, test_remote_endpoint,
     set_connection_mode, get_connection_mode, set_active_remote_endpoint,
-    get_active_endpoint
+    get_active_endpoint,
+    list_conversations, load_conversation, save_message, delete_conversation, rename_conversation,
+    edit_message, delete_message, truncate_after, regenerate_response,
+    list_branches, switch_branch, prune_branch,
+    list_tags, add_conversation_tag, remove_conversation_tag, rename_tag, delete_tag,
+    set_conversation_pinned, set_conversation_folder, list_folders,
+    search_conversations, rebuild_search_index, get_search_status,
+    render_markdown
 };
-use persona::PersonaManager;
+pub use commands::generate_request_body;
+use prometheus_personas::PersonaManager;
 use config::AppConfig;
+use conversation::ConversationManager;
 use network::{ConnectionManager, OllamaClient};
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -38,6 +49,30 @@ pub fn run() {
       get_connection_mode,
       set_active_remote_endpoint,
       get_active_endpoint,
+      list_conversations,
+      load_conversation,
+      save_message,
+      delete_conversation,
+      rename_conversation,
+      edit_message,
+      delete_message,
+      truncate_after,
+      regenerate_response,
+      list_branches,
+      switch_branch,
+      prune_branch,
+      list_tags,
+      add_conversation_tag,
+      remove_conversation_tag,
+      rename_tag,
+      delete_tag,
+      set_conversation_pinned,
+      set_conversation_folder,
+      list_folders,
+      search_conversations,
+      rebuild_search_index,
+      get_search_status,
+      render_markdown,
     ])
     .setup(|app| {
       if cfg!(debug_assertions) {
@@ -48,13 +83,8 @@ pub fn run() {
         )?;
       }
       
-      // Initialize PersonaManager with default persona
-      let mut persona_manager = PersonaManager::new();
-      
-      // Try to load additional personas from config file
-      // Look for personas.json in the current directory for now
-      let persona_config_path = std::path::PathBuf::from("personas.json");
-      persona_manager.load_additional_personas(persona_config_path);
+      // Default persona plus those in personas.json, shared with the CLI
+      let persona_manager = PersonaManager::load();
       
       // Store the PersonaManager in app state
       app.manage(persona_manager);
@@ -92,6 +122,27 @@ pub fn run() {
       app.manage(config);
       app.manage(connection_manager);
       
+      // Store conversations in the platform app data directory
+      let mut conversation_manager = match app.path().app_data_dir() {
+        Ok(dir) => ConversationManager::in_app_data_dir(&dir),
+        Err(e) => {
+          eprintln!("Failed to resolve app data directory, using ./conversations: {}", e);
+          ConversationManager::new()
+        }
+      };
+      
+      // Import the single-file history written by older versions
+      if let Err(e) = conversation_manager.migrate_legacy_history(std::path::Path::new("chat_history.json")) {
+        eprintln!("Failed to migrate chat_history.json: {}", e);
+      }
+      
+      // Load the persisted search index, or build it from stored conversations
+      if let Err(e) = conversation_manager.enable_search() {
+        eprintln!("Failed to initialize search index: {}", e);
+      }
+      
+      app.manage(Arc::new(Mutex::new(conversation_manager)));
+      
       Ok(())
     })
     .run(tauri::generate_context!())
@@ -1,210 +1,375 @@
-use regex::Regex;
-use once_cell::sync::Lazy;
-
-#[derive(Debug, Clone)]
+use ammonia::Builder;
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// A node of a parsed message
+///
+/// Block and inline containers hold their children, so the segments of a
+/// message form a tree mirroring its CommonMark structure.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "content", rename_all = "snake_case")]
 pub enum MessageSegment {
     Text(String),
     CodeBlock { language: Option<String>, code: String },
     InlineCode(String),
-    Bold(String),
-    Italic(String),
-    ListItem(String),
+    Bold(Vec<MessageSegment>),
+    Italic(Vec<MessageSegment>),
+    Strikethrough(Vec<MessageSegment>),
+    /// A link; only http(s), mailto and relative URLs are kept
+    Link { url: String, title: Option<String>, children: Vec<MessageSegment> },
+    Image { url: String, alt: String },
+    Paragraph(Vec<MessageSegment>),
+    Heading { level: u8, children: Vec<MessageSegment> },
+    BlockQuote(Vec<MessageSegment>),
+    /// A list, numbered from `start` when ordered
+    List { start: Option<u64>, items: Vec<MessageSegment> },
+    /// A list item; `checked` is set for task list items
+    ListItem { checked: Option<bool>, children: Vec<MessageSegment> },
+    Table { alignments: Vec<ColumnAlignment>, rows: Vec<TableRow> },
+    LineBreak,
+    ThematicBreak,
     Highlighted(String), // For search result highlighting
 }
 
-static CODE_BLOCK_REGEX: Lazy<Regex> = Lazy::new(|| {
-    // Match code blocks with optional language, handling various newline scenarios
-    Regex::new(r"```(\w+)?\s*\n?([\s\S]*?)\n?```").unwrap()
-});
-
-static INLINE_CODE_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"`([^`]+)`").unwrap()
-});
-
-static BOLD_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"\*\*([^\*]+)\*\*").unwrap()
-});
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnAlignment {
+    None,
+    Left,
+    Center,
+    Right,
+}
 
-static ITALIC_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"\*([^\*]+)\*").unwrap()
-});
+/// A table row, whose cells hold inline segments
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TableRow {
+    pub header: bool,
+    pub cells: Vec<Vec<MessageSegment>>,
+}
 
-pub fn parse_message(content: &str) -> Vec<MessageSegment> {
-    let mut segments = Vec::new();
+/// CommonMark with the GitHub extensions used in chat answers
+fn parser_options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
 
-    // First, extract code blocks
-    let mut code_blocks = Vec::new();
-    for cap in CODE_BLOCK_REGEX.captures_iter(content) {
-        let full_match = cap.get(0).unwrap();
-        let language = cap.get(1).map(|m| m.as_str().to_string());
-        let code = cap.get(2).map(|m| m.as_str().to_string()).unwrap_or_default();
-        
-        code_blocks.push((full_match.start(), full_match.end(), language, code));
-    }
+/// An element being parsed, collecting its children until it ends
+struct Frame<'a> {
+    tag: Tag<'a>,
+    children: Vec<MessageSegment>,
+    /// Cells of a table row being parsed
+    cells: Vec<Vec<MessageSegment>>,
+    /// Rows of a table being parsed
+    rows: Vec<TableRow>,
+    /// Task list marker of a list item
+    checked: Option<bool>,
+}
 
-    // Process content, skipping code blocks
-    let mut current_pos = 0;
-    for (start, end, language, code) in code_blocks {
-        // Process text before code block
-        if current_pos < start {
-            let text_segment = &content[current_pos..start];
-            segments.extend(parse_inline_formatting(text_segment));
+impl<'a> Frame<'a> {
+    fn new(tag: Tag<'a>) -> Self {
+        Self {
+            tag,
+            children: Vec::new(),
+            cells: Vec::new(),
+            rows: Vec::new(),
+            checked: None,
         }
-
-        // Add code block
-        segments.push(MessageSegment::CodeBlock { language, code });
-        current_pos = end;
     }
+}
 
-    // Process remaining text after last code block
-    if current_pos < content.len() {
-        let text_segment = &content[current_pos..];
-        segments.extend(parse_inline_formatting(text_segment));
+/// Parse message markdown (CommonMark with GFM tables, strikethrough and task lists)
+///
+/// Raw HTML is kept as text.
+pub fn parse_message(content: &str) -> Vec<MessageSegment> {
+    let mut root = Vec::new();
+    let mut stack: Vec<Frame> = Vec::new();
+
+    for event in Parser::new_ext(content, parser_options()) {
+        match event {
+            Event::Start(tag) => stack.push(Frame::new(tag)),
+            Event::End(_) => {
+                let Some(frame) = stack.pop() else { continue };
+                close(frame, stack.last_mut(), &mut root);
+            }
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                push_text(children(&mut stack, &mut root), &text);
+            }
+            Event::Code(code) => {
+                children(&mut stack, &mut root).push(MessageSegment::InlineCode(code.into_string()));
+            }
+            Event::InlineMath(math) | Event::DisplayMath(math) => {
+                children(&mut stack, &mut root).push(MessageSegment::InlineCode(math.into_string()));
+            }
+            Event::FootnoteReference(label) => {
+                push_text(children(&mut stack, &mut root), &format!("[^{}]", label));
+            }
+            Event::SoftBreak => push_text(children(&mut stack, &mut root), "\n"),
+            Event::HardBreak => children(&mut stack, &mut root).push(MessageSegment::LineBreak),
+            Event::Rule => children(&mut stack, &mut root).push(MessageSegment::ThematicBreak),
+            Event::TaskListMarker(checked) => {
+                if let Some(frame) = stack.last_mut() {
+                    frame.checked = Some(checked);
+                }
+            }
+        }
     }
 
     // If no segments were created, return the original text
-    if segments.is_empty() {
-        segments.push(MessageSegment::Text(content.to_string()));
+    if root.is_empty() {
+        root.push(MessageSegment::Text(content.to_string()));
     }
 
-    segments
+    root
 }
 
-fn parse_inline_formatting(text: &str) -> Vec<MessageSegment> {
-    let mut segments = Vec::new();
-    let lines: Vec<&str> = text.lines().collect();
+/// The children of the innermost open element
+fn children<'s>(stack: &'s mut [Frame], root: &'s mut Vec<MessageSegment>) -> &'s mut Vec<MessageSegment> {
+    match stack.last_mut() {
+        Some(frame) => &mut frame.children,
+        None => root,
+    }
+}
 
-    for (idx, line) in lines.iter().enumerate() {
-        // Check if it's a list item
-        if line.trim_start().starts_with("- ") || line.trim_start().starts_with("* ") {
-            let item_text = line.trim_start().trim_start_matches("- ").trim_start_matches("* ");
-            segments.push(MessageSegment::ListItem(item_text.to_string()));
-            continue;
-        }
+/// Add text, merging it with preceding text
+fn push_text(segments: &mut Vec<MessageSegment>, text: &str) {
+    if let Some(MessageSegment::Text(previous)) = segments.last_mut() {
+        previous.push_str(text);
+    } else {
+        segments.push(MessageSegment::Text(text.to_string()));
+    }
+}
 
-        // Parse inline code, bold, and italic
-        let current_line = line.to_string();
-        let mut line_segments = Vec::new();
-        let mut last_pos = 0;
+/// Turn a finished element into a segment of its parent
+fn close(frame: Frame, parent: Option<&mut Frame>, root: &mut Vec<MessageSegment>) {
+    let Frame { tag, children, cells, rows, checked } = frame;
 
-        // Find all inline code matches
-        let mut inline_code_matches = Vec::new();
-        for cap in INLINE_CODE_REGEX.captures_iter(&current_line) {
-            if let Some(m) = cap.get(0) {
-                inline_code_matches.push((m.start(), m.end(), cap.get(1).unwrap().as_str().to_string()));
+    // Table parts are collected on the parent rather than as segments
+    match tag {
+        Tag::TableCell => {
+            if let Some(parent) = parent {
+                parent.cells.push(children);
             }
+            return;
         }
-
-        // Process inline code
-        for (start, end, code) in inline_code_matches {
-            if start > last_pos {
-                let text_before = &current_line[last_pos..start];
-                if !text_before.is_empty() {
-                    line_segments.extend(parse_bold_italic(text_before));
-                }
+        Tag::TableHead | Tag::TableRow => {
+            if let Some(parent) = parent {
+                parent.rows.push(TableRow {
+                    header: matches!(tag, Tag::TableHead),
+                    cells,
+                });
             }
-            line_segments.push(MessageSegment::InlineCode(code));
-            last_pos = end;
+            return;
         }
+        _ => {}
+    }
 
-        // Process remaining text
-        if last_pos < current_line.len() {
-            let remaining_text = &current_line[last_pos..];
-            if !remaining_text.is_empty() {
-                line_segments.extend(parse_bold_italic(remaining_text));
+    let segments: Vec<MessageSegment> = match tag {
+        Tag::Paragraph => vec![MessageSegment::Paragraph(children)],
+        Tag::Heading { level, .. } => vec![MessageSegment::Heading {
+            level: heading_level(level),
+            children,
+        }],
+        Tag::BlockQuote(_) => vec![MessageSegment::BlockQuote(children)],
+        Tag::CodeBlock(kind) => {
+            let language = match kind {
+                CodeBlockKind::Fenced(info) => info.split_whitespace().next().map(str::to_string),
+                CodeBlockKind::Indented => None,
+            };
+            let mut code = plain_text(&children);
+            if code.ends_with('\n') {
+                code.pop();
             }
+            vec![MessageSegment::CodeBlock { language, code }]
         }
-
-        // If no inline formatting was found, just add the line as text
-        if line_segments.is_empty() {
-            line_segments.push(MessageSegment::Text(line.to_string()));
-        }
-
-        segments.extend(line_segments);
-        
-        // Add newline between lines (except for the last one)
-        if idx < lines.len() - 1 {
-            segments.push(MessageSegment::Text("\n".to_string()));
+        Tag::List(start) => vec![MessageSegment::List { start, items: children }],
+        Tag::Item => vec![MessageSegment::ListItem { checked, children }],
+        Tag::Table(alignments) => vec![MessageSegment::Table {
+            alignments: alignments.into_iter().map(column_alignment).collect(),
+            rows,
+        }],
+        Tag::Emphasis => vec![MessageSegment::Italic(children)],
+        Tag::Strong => vec![MessageSegment::Bold(children)],
+        Tag::Strikethrough => vec![MessageSegment::Strikethrough(children)],
+        Tag::Link { dest_url, title, .. } if is_safe_url(&dest_url) => vec![MessageSegment::Link {
+            url: dest_url.into_string(),
+            title: (!title.is_empty()).then(|| title.into_string()),
+            children,
+        }],
+        Tag::Image { dest_url, .. } if is_safe_url(&dest_url) => vec![MessageSegment::Image {
+            url: dest_url.into_string(),
+            alt: plain_text(&children),
+        }],
+        // Unsafe links, HTML blocks and other extensions keep only their content
+        _ => children,
+    };
+
+    let target = match parent {
+        Some(parent) => &mut parent.children,
+        None => root,
+    };
+    for segment in segments {
+        match segment {
+            MessageSegment::Text(text) => push_text(target, &text),
+            segment => target.push(segment),
         }
     }
+}
 
-    segments
+fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
 }
 
-fn parse_bold_italic(text: &str) -> Vec<MessageSegment> {
-    let mut segments = Vec::new();
-    let mut last_pos = 0;
+fn column_alignment(alignment: Alignment) -> ColumnAlignment {
+    match alignment {
+        Alignment::None => ColumnAlignment::None,
+        Alignment::Left => ColumnAlignment::Left,
+        Alignment::Center => ColumnAlignment::Center,
+        Alignment::Right => ColumnAlignment::Right,
+    }
+}
 
-    // Find bold matches
-    let mut bold_matches = Vec::new();
-    for cap in BOLD_REGEX.captures_iter(text) {
-        if let Some(m) = cap.get(0) {
-            bold_matches.push((m.start(), m.end(), cap.get(1).unwrap().as_str().to_string()));
+/// Whether a link target can't run script: http(s), mailto or relative
+fn is_safe_url(url: &str) -> bool {
+    match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => {
+            ["http", "https", "mailto"].iter().any(|safe| scheme.eq_ignore_ascii_case(safe))
         }
+        _ => true,
     }
+}
 
-    for (start, end, bold_text) in bold_matches {
-        if start > last_pos {
-            let text_before = &text[last_pos..start];
-            if !text_before.is_empty() {
-                segments.extend(parse_italic(text_before));
+/// The text of segments without their formatting
+pub fn plain_text(segments: &[MessageSegment]) -> String {
+    let mut text = String::new();
+    for segment in segments {
+        match segment {
+            MessageSegment::Text(t) | MessageSegment::InlineCode(t) | MessageSegment::Highlighted(t) => {
+                text.push_str(t)
+            }
+            MessageSegment::CodeBlock { code, .. } => text.push_str(code),
+            MessageSegment::Image { alt, .. } => text.push_str(alt),
+            MessageSegment::LineBreak => text.push('\n'),
+            MessageSegment::ThematicBreak => {}
+            MessageSegment::Bold(children)
+            | MessageSegment::Italic(children)
+            | MessageSegment::Strikethrough(children)
+            | MessageSegment::Paragraph(children)
+            | MessageSegment::BlockQuote(children)
+            | MessageSegment::Link { children, .. }
+            | MessageSegment::Heading { children, .. }
+            | MessageSegment::ListItem { children, .. } => text.push_str(&plain_text(children)),
+            MessageSegment::List { items, .. } => text.push_str(&plain_text(items)),
+            MessageSegment::Table { rows, .. } => {
+                for row in rows {
+                    for cell in &row.cells {
+                        text.push_str(&plain_text(cell));
+                    }
+                }
             }
         }
-        segments.push(MessageSegment::Bold(bold_text));
-        last_pos = end;
     }
+    text
+}
 
-    if last_pos < text.len() {
-        let remaining = &text[last_pos..];
-        if !remaining.is_empty() {
-            segments.extend(parse_italic(remaining));
-        }
-    }
+/// Render message markdown to HTML that is safe to insert into the page
+///
+/// Raw HTML in the message is sanitized: scripts, event handlers and unsafe
+/// URLs are removed.
+pub fn render_html(content: &str) -> String {
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, Parser::new_ext(content, parser_options()));
+
+    Builder::default()
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["checked"])
+        .set_tag_attribute_value("input", "type", "checkbox")
+        .set_tag_attribute_value("input", "disabled", "")
+        .add_tag_attributes("code", ["class"])
+        .add_tag_attributes("ol", ["start"])
+        .url_schemes(HashSet::from(["http", "https", "mailto"]))
+        .clean(&html)
+        .to_string()
+}
 
-    if segments.is_empty() {
-        segments.push(MessageSegment::Text(text.to_string()));
+/// Highlight search matches in text by creating segments with highlighted regions
+pub fn highlight_matches(text: &str, positions: &[(usize, usize)]) -> Vec<MessageSegment> {
+    if positions.is_empty() {
+        return vec![MessageSegment::Text(text.to_string())];
     }
 
-    segments
-}
-
-fn parse_italic(text: &str) -> Vec<MessageSegment> {
     let mut segments = Vec::new();
+    let mut sorted_positions = positions.to_vec();
+    
+    // Sort positions by start index
+    sorted_positions.sort_by_key(|&(start, _)| start);
+    
+    // Merge overlapping positions
+    let mut merged_positions = Vec::new();
+    let mut current_start = sorted_positions[0].0;
+    let mut current_end = sorted_positions[0].1;
+    
+    for &(start, end) in sorted_positions.iter().skip(1) {
+        if start <= current_end {
+            // Overlapping or adjacent, merge them
+            current_end = current_end.max(end);
+        } else {
+            // Non-overlapping, save current and start new
+            merged_positions.push((current_start, current_end));
+            current_start = start;
+            current_end = end;
+        }
+    }
+    merged_positions.push((current_start, current_end));
+    
+    // Build segments with highlighted regions
     let mut last_pos = 0;
-
-    // Find italic matches (but not bold)
-    for cap in ITALIC_REGEX.captures_iter(text) {
-        if let Some(m) = cap.get(0) {
-            // Skip if this is part of a bold marker (**)
-            if m.start() > 0 && text.chars().nth(m.start() - 1) == Some('*') {
-                continue;
-            }
-            if m.end() < text.len() && text.chars().nth(m.end()) == Some('*') {
-                continue;
-            }
-
-            if m.start() > last_pos {
-                let text_before = &text[last_pos..m.start()];
-                if !text_before.is_empty() {
-                    segments.push(MessageSegment::Text(text_before.to_string()));
-                }
+    
+    for (start, end) in merged_positions {
+        // Ensure positions are within bounds
+        let start = start.min(text.len());
+        let end = end.min(text.len());
+        
+        if start >= end {
+            continue;
+        }
+        
+        // Add text before highlight
+        if start > last_pos {
+            let before_text = &text[last_pos..start];
+            if !before_text.is_empty() {
+                segments.push(MessageSegment::Text(before_text.to_string()));
             }
-            segments.push(MessageSegment::Italic(cap.get(1).unwrap().as_str().to_string()));
-            last_pos = m.end();
         }
+        
+        // Add highlighted text
+        let highlighted_text = &text[start..end];
+        if !highlighted_text.is_empty() {
+            segments.push(MessageSegment::Highlighted(highlighted_text.to_string()));
+        }
+        
+        last_pos = end;
     }
-
+    
+    // Add remaining text after last highlight
     if last_pos < text.len() {
-        let remaining = &text[last_pos..];
-        if !remaining.is_empty() {
-            segments.push(MessageSegment::Text(remaining.to_string()));
+        let remaining_text = &text[last_pos..];
+        if !remaining_text.is_empty() {
+            segments.push(MessageSegment::Text(remaining_text.to_string()));
         }
     }
-
+    
+    // If no segments were created, return original text
     if segments.is_empty() {
         segments.push(MessageSegment::Text(text.to_string()));
     }
-
+    
     segments
 }
 
@@ -212,12 +377,42 @@ fn parse_italic(text: &str) -> Vec<MessageSegment> {
 mod tests {
     use super::*;
 
+    /// Whether any segment of the tree matches
+    fn any_segment(segments: &[MessageSegment], predicate: &dyn Fn(&MessageSegment) -> bool) -> bool {
+        segments.iter().any(|segment| {
+            predicate(segment)
+                || match segment {
+                    MessageSegment::Bold(children)
+                    | MessageSegment::Italic(children)
+                    | MessageSegment::Strikethrough(children)
+                    | MessageSegment::Paragraph(children)
+                    | MessageSegment::BlockQuote(children)
+                    | MessageSegment::Link { children, .. }
+                    | MessageSegment::Heading { children, .. }
+                    | MessageSegment::ListItem { children, .. } => any_segment(children, predicate),
+                    MessageSegment::List { items, .. } => any_segment(items, predicate),
+                    MessageSegment::Table { rows, .. } => rows
+                        .iter()
+                        .any(|row| row.cells.iter().any(|cell| any_segment(cell, predicate))),
+                    _ => false,
+                }
+        })
+    }
+
+    fn text(text: &str) -> MessageSegment {
+        MessageSegment::Text(text.to_string())
+    }
+
     #[test]
     fn test_code_block() {
         let input = "Here's some code:\n```rust\nfn main() {\n    println!(\"Hello\");\n}\n```\nThat's it!";
         let segments = parse_message(input);
         
         assert!(segments.iter().any(|s| matches!(s, MessageSegment::CodeBlock { .. })));
+        assert!(segments.contains(&MessageSegment::CodeBlock {
+            language: Some("rust".to_string()),
+            code: "fn main() {\n    println!(\"Hello\");\n}".to_string(),
+        }));
     }
 
     #[test]
@@ -225,7 +420,7 @@ mod tests {
         let input = "Use `println!` to print.";
         let segments = parse_message(input);
         
-        assert!(segments.iter().any(|s| matches!(s, MessageSegment::InlineCode(_))));
+        assert!(any_segment(&segments, &|s| matches!(s, MessageSegment::InlineCode(_))));
     }
 
     #[test]
@@ -233,7 +428,7 @@ mod tests {
         let input = "This is **bold** text.";
         let segments = parse_message(input);
         
-        assert!(segments.iter().any(|s| matches!(s, MessageSegment::Bold(_))));
+        assert!(any_segment(&segments, &|s| matches!(s, MessageSegment::Bold(_))));
     }
 
     #[test]
@@ -241,7 +436,7 @@ mod tests {
         let input = "This is *italic* text.";
         let segments = parse_message(input);
         
-        assert!(segments.iter().any(|s| matches!(s, MessageSegment::Italic(_))));
+        assert!(any_segment(&segments, &|s| matches!(s, MessageSegment::Italic(_))));
     }
 
     #[test]
@@ -249,7 +444,127 @@ mod tests {
         let input = "- Item 1\n- Item 2";
         let segments = parse_message(input);
         
-        assert!(segments.iter().any(|s| matches!(s, MessageSegment::ListItem(_))));
+        assert!(any_segment(&segments, &|s| matches!(s, MessageSegment::ListItem { .. })));
+    }
+
+    #[test]
+    fn test_italic_inside_bold() {
+        let segments = parse_message("**bold *and italic* text**");
+        assert_eq!(
+            segments,
+            vec![MessageSegment::Paragraph(vec![MessageSegment::Bold(vec![
+                text("bold "),
+                MessageSegment::Italic(vec![text("and italic")]),
+                text(" text"),
+            ])])]
+        );
+    }
+
+    #[test]
+    fn test_headings_and_block_quotes() {
+        let segments = parse_message("## Setup\n\n> Quoted ~~old~~ text");
+        assert_eq!(
+            segments,
+            vec![
+                MessageSegment::Heading { level: 2, children: vec![text("Setup")] },
+                MessageSegment::BlockQuote(vec![MessageSegment::Paragraph(vec![
+                    text("Quoted "),
+                    MessageSegment::Strikethrough(vec![text("old")]),
+                    text(" text"),
+                ])]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nested_numbered_and_task_lists() {
+        let segments = parse_message("3. First\n   - [x] done\n   - [ ] todo\n4. Second");
+        let MessageSegment::List { start, items } = &segments[0] else {
+            panic!("Expected a list, got {:?}", segments);
+        };
+        assert_eq!(*start, Some(3));
+        assert_eq!(items.len(), 2);
+
+        let MessageSegment::ListItem { children, .. } = &items[0] else {
+            panic!("Expected a list item");
+        };
+        assert_eq!(children[0], text("First"));
+        assert_eq!(
+            children[1],
+            MessageSegment::List {
+                start: None,
+                items: vec![
+                    MessageSegment::ListItem { checked: Some(true), children: vec![text("done")] },
+                    MessageSegment::ListItem { checked: Some(false), children: vec![text("todo")] },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_links() {
+        let segments = parse_message("[docs](https://docs.rs \"Docs\") and [bad](javascript:alert(1))");
+        assert_eq!(
+            segments,
+            vec![MessageSegment::Paragraph(vec![
+                MessageSegment::Link {
+                    url: "https://docs.rs".to_string(),
+                    title: Some("Docs".to_string()),
+                    children: vec![text("docs")],
+                },
+                text(" and bad"),
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let segments = parse_message("| Name | Size |\n|:-----|-----:|\n| `a` | 1 |");
+        assert_eq!(
+            segments,
+            vec![MessageSegment::Table {
+                alignments: vec![ColumnAlignment::Left, ColumnAlignment::Right],
+                rows: vec![
+                    TableRow { header: true, cells: vec![vec![text("Name")], vec![text("Size")]] },
+                    TableRow {
+                        header: false,
+                        cells: vec![vec![MessageSegment::InlineCode("a".to_string())], vec![text("1")]],
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_segments_serialize_as_tree() {
+        let json = serde_json::to_value(parse_message("**hi**")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "type": "paragraph",
+                "content": [{ "type": "bold", "content": [{ "type": "text", "content": "hi" }] }]
+            }])
+        );
+    }
+
+    #[test]
+    fn test_render_html_is_sanitized() {
+        let html = render_html(
+            "# Title\n\n<script>alert(1)</script>\n\n[x](javascript:alert(1)) <img src=x onerror=alert(1)>\n\n- [x] done",
+        );
+        assert!(html.contains("<h1>Title</h1>"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("javascript:"));
+        assert!(!html.contains("onerror"));
+        assert!(html.contains("type=\"checkbox\""));
+        assert!(html.contains("disabled"));
+    }
+
+    #[test]
+    fn test_render_html_keeps_code_language() {
+        let html = render_html("```rust\nlet x = 1 < 2;\n```");
+        assert!(html.contains("<code class=\"language-rust\">"));
+        assert!(html.contains("1 &lt; 2"));
     }
 
     #[test]
@@ -283,78 +598,3 @@ mod tests {
         assert!(!segments.is_empty());
     }
 }
-
-/// Highlight search matches in text by creating segments with highlighted regions
-pub fn highlight_matches(text: &str, positions: &[(usize, usize)]) -> Vec<MessageSegment> {
-    if positions.is_empty() {
-        return vec![MessageSegment::Text(text.to_string())];
-    }
-
-    let mut segments = Vec::new();
-    let mut sorted_positions = positions.to_vec();
-    
-    // Sort positions by start index
-    sorted_positions.sort_by_key(|&(start, _)| start);
-    
-    // Merge overlapping positions
-    let mut merged_positions = Vec::new();
-    let mut current_start = sorted_positions[0].0;
-    let mut current_end = sorted_positions[0].1;
-    
-    for &(start, end) in sorted_positions.iter().skip(1) {
-        if start <= current_end {
-            // Overlapping or adjacent, merge them
-            current_end = current_end.max(end);
-        } else {
-            // Non-overlapping, save current and start new
-            merged_positions.push((current_start, current_end));
-            current_start = start;
-            current_end = end;
-        }
-    }
-    merged_positions.push((current_start, current_end));
-    
-    // Build segments with highlighted regions
-    let mut last_pos = 0;
-    
-    for (start, end) in merged_positions {
-        // Ensure positions are within bounds
-        let start = start.min(text.len());
-        let end = end.min(text.len());
-        
-        if start >= end {
-            continue;
-        }
-        
-        // Add text before highlight
-        if start > last_pos {
-            let before_text = &text[last_pos..start];
-            if !before_text.is_empty() {
-                segments.push(MessageSegment::Text(before_text.to_string()));
-            }
-        }
-        
-        // Add highlighted text
-        let highlighted_text = &text[start..end];
-        if !highlighted_text.is_empty() {
-            segments.push(MessageSegment::Highlighted(highlighted_text.to_string()));
-        }
-        
-        last_pos = end;
-    }
-    
-    // Add remaining text after last highlight
-    if last_pos < text.len() {
-        let remaining_text = &text[last_pos..];
-        if !remaining_text.is_empty() {
-            segments.push(MessageSegment::Text(remaining_text.to_string()));
-        }
-    }
-    
-    // If no segments were created, return original text
-    if segments.is_empty() {
-        segments.push(MessageSegment::Text(text.to_string()));
-    }
-    
-    segments
-}
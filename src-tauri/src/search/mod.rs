@@ -1,854 +0,0 @@
-use crate::conversation::Conversation;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-
-pub mod index;
-pub mod query;
-
-pub use index::SearchIndexer;
-pub use query::{SearchQuery, SearchQueryBuilder};
-
-/// Main search engine that coordinates indexing and querying
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SearchEngine {
-    /// In-memory search index mapping terms to search results
-    index: HashMap<String, Vec<SearchResult>>,
-    /// List of indexed conversation IDs
-    indexed_conversations: Vec<String>,
-}
-
-/// Represents a single search result
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SearchResult {
-    /// ID of the conversation containing the match
-    pub conversation_id: String,
-    /// Index of the message within the conversation
-    pub message_index: usize,
-    /// Positions of matches within the message content (start, end)
-    pub match_positions: Vec<(usize, usize)>,
-    /// Surrounding text context for the match
-    pub context: String,
-    /// The role of the message (user/assistant)
-    pub role: String,
-}
-
-impl SearchEngine {
-    /// Create a new empty search engine
-    pub fn new() -> Self {
-        Self {
-            index: HashMap::new(),
-            indexed_conversations: Vec::new(),
-        }
-    }
-
-    /// Index a conversation for searching
-    pub fn index_conversation(&mut self, conversation: &Conversation) {
-        let indexer = SearchIndexer::new();
-        let results = indexer.index_conversation(conversation);
-        
-        // Add results to the index
-        for (term, result) in results {
-            self.index
-                .entry(term)
-                .or_insert_with(Vec::new)
-                .push(result);
-        }
-        
-        // Track indexed conversation
-        if !self.indexed_conversations.contains(&conversation.id) {
-            self.indexed_conversations.push(conversation.id.clone());
-        }
-    }
-
-    /// Remove a conversation from the index
-    pub fn remove_conversation(&mut self, conversation_id: &str) {
-        // Remove from indexed conversations list
-        self.indexed_conversations.retain(|id| id != conversation_id);
-        
-        // Remove all results for this conversation from the index
-        for results in self.index.values_mut() {
-            results.retain(|r| r.conversation_id != conversation_id);
-        }
-        
-        // Clean up empty entries
-        self.index.retain(|_, results| !results.is_empty());
-    }
-
-    /// Search for conversations matching the query
-    pub fn search(&self, query: &SearchQuery) -> Vec<SearchResult> {
-        query.execute(&self.index)
-    }
-
-    /// Rebuild the entire search index from scratch
-    pub fn rebuild_index(&mut self, conversations: &[Conversation]) {
-        self.index.clear();
-        self.indexed_conversations.clear();
-        
-        for conversation in conversations {
-            self.index_conversation(conversation);
-        }
-    }
-
-    /// Get the number of indexed conversations
-    pub fn indexed_count(&self) -> usize {
-        self.indexed_conversations.len()
-    }
-
-    /// Check if a conversation is indexed
-    pub fn is_indexed(&self, conversation_id: &str) -> bool {
-        self.indexed_conversations.contains(&conversation_id.to_string())
-    }
-
-    /// Clear the entire search index
-    pub fn clear(&mut self) {
-        self.index.clear();
-        self.indexed_conversations.clear();
-    }
-
-    /// Save the search index to a file for persistence
-    pub fn save_to_file(&self, path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
-        let json = serde_json::to_string_pretty(self)?;
-        std::fs::write(path, json)?;
-        Ok(())
-    }
-
-    /// Load the search index from a file
-    pub fn load_from_file(path: &std::path::Path) -> Result<Self, Box<dyn std::error::Error>> {
-        let json = std::fs::read_to_string(path)?;
-        let engine = serde_json::from_str(&json)?;
-        Ok(engine)
-    }
-}
-
-impl Default for SearchEngine {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::app::ChatMessage;
-
-    #[test]
-    fn test_search_engine_creation() {
-        let engine = SearchEngine::new();
-        assert_eq!(engine.indexed_count(), 0);
-    }
-
-    #[test]
-    fn test_index_conversation() {
-        let mut engine = SearchEngine::new();
-        let mut conversation = Conversation::new("Test".to_string(), None);
-        conversation.add_message(ChatMessage::new(
-            "user".to_string(),
-            "Hello world".to_string(),
-        ));
-        
-        engine.index_conversation(&conversation);
-        assert_eq!(engine.indexed_count(), 1);
-        assert!(engine.is_indexed(&conversation.id));
-    }
-
-    #[test]
-    fn test_remove_conversation() {
-        let mut engine = SearchEngine::new();
-        let mut conversation = Conversation::new("Test".to_string(), None);
-        conversation.add_message(ChatMessage::new(
-            "user".to_string(),
-            "Hello world".to_string(),
-        ));
-        
-        let conv_id = conversation.id.clone();
-        engine.index_conversation(&conversation);
-        assert!(engine.is_indexed(&conv_id));
-        
-        engine.remove_conversation(&conv_id);
-        assert!(!engine.is_indexed(&conv_id));
-        assert_eq!(engine.indexed_count(), 0);
-    }
-
-    #[test]
-    fn test_clear_index() {
-        let mut engine = SearchEngine::new();
-        let mut conversation = Conversation::new("Test".to_string(), None);
-        conversation.add_message(ChatMessage::new(
-            "user".to_string(),
-            "Hello world".to_string(),
-        ));
-        
-        engine.index_conversation(&conversation);
-        assert_eq!(engine.indexed_count(), 1);
-        
-        engine.clear();
-        assert_eq!(engine.indexed_count(), 0);
-    }
-
-    #[test]
-    fn test_save_and_load() {
-        use std::path::PathBuf;
-        
-        let mut engine = SearchEngine::new();
-        let mut conversation = Conversation::new("Test".to_string(), None);
-        conversation.add_message(ChatMessage::new(
-            "user".to_string(),
-            "Hello world".to_string(),
-        ));
-        
-        engine.index_conversation(&conversation);
-        assert_eq!(engine.indexed_count(), 1);
-        
-        // Save to temp file
-        let temp_path = PathBuf::from("/tmp/test_search_index.json");
-        engine.save_to_file(&temp_path).expect("Failed to save");
-        
-        // Load from file
-        let loaded_engine = SearchEngine::load_from_file(&temp_path).expect("Failed to load");
-        assert_eq!(loaded_engine.indexed_count(), 1);
-        assert!(loaded_engine.is_indexed(&conversation.id));
-        
-        // Clean up
-        let _ = std::fs::remove_file(&temp_path);
-    }
-
-    // Helper function to create a test conversation with messages
-    fn create_test_conversation(name: &str, messages: Vec<(&str, &str)>) -> Conversation {
-        let mut conversation = Conversation::new(name.to_string(), None);
-        for (role, content) in messages {
-            conversation.add_message(ChatMessage::new(role.to_string(), content.to_string()));
-        }
-        conversation
-    }
-
-    // ===== Comprehensive Search Tests =====
-
-    #[test]
-    fn test_index_simple_message() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![("user", "Hello world")],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("hello".to_string());
-        let results = engine.search(&query);
-        
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].conversation_id, conversation.id);
-        assert_eq!(results[0].message_index, 0);
-        assert_eq!(results[0].role, "user");
-    }
-
-    #[test]
-    fn test_index_multiple_messages() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![
-                ("user", "What is Rust?"),
-                ("assistant", "Rust is a systems programming language"),
-                ("user", "Tell me more about Rust"),
-            ],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("rust".to_string());
-        let results = engine.search(&query);
-        
-        // Should find "Rust" in 3 different messages (one result per message)
-        // Note: The indexer creates one result per term per message
-        assert!(results.len() >= 1); // At least one result
-        
-        // Verify we can find results from different messages
-        let message_indices: Vec<usize> = results.iter().map(|r| r.message_index).collect();
-        assert!(message_indices.len() >= 1);
-    }
-
-    #[test]
-    fn test_index_code_blocks() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![
-                ("user", "Show me a function"),
-                ("assistant", "```rust\nfn hello() {\n    println!(\"Hello\");\n}\n```"),
-            ],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("println".to_string());
-        let results = engine.search(&query);
-        
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].message_index, 1);
-    }
-
-    #[test]
-    fn test_index_special_characters() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![
-                ("user", "What about C++, Python, and JavaScript?"),
-                ("assistant", "These are popular languages: C++, Python, JavaScript"),
-            ],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        // Search for terms with special characters
-        let query = SearchQuery::new("python".to_string());
-        let results = engine.search(&query);
-        
-        // Should find "Python" in at least one message
-        assert!(results.len() >= 1);
-    }
-
-    #[test]
-    fn test_index_markdown_formatting() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![
-                ("user", "Explain **bold** and *italic* text"),
-                ("assistant", "**Bold** uses double asterisks, *italic* uses single"),
-            ],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("bold".to_string());
-        let results = engine.search(&query);
-        
-        // Should find "bold" in at least one message
-        assert!(results.len() >= 1);
-    }
-
-    #[test]
-    fn test_index_long_messages() {
-        let mut engine = SearchEngine::new();
-        let long_content = "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(50);
-        let conversation = create_test_conversation(
-            "Test",
-            vec![("user", &long_content)],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("lorem".to_string());
-        let results = engine.search(&query);
-        
-        assert_eq!(results.len(), 1);
-        // Context should be extracted properly
-        assert!(!results[0].context.is_empty());
-    }
-
-    #[test]
-    fn test_case_insensitive_search() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![
-                ("user", "Hello World"),
-                ("assistant", "HELLO there"),
-                ("user", "hello again"),
-            ],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        // Search with lowercase - should find all variations
-        let query = SearchQuery::new("hello".to_string());
-        let results = engine.search(&query);
-        
-        assert!(results.len() >= 1, "Should find at least one result");
-        
-        // Search with uppercase - should also find all variations (case-insensitive by default)
-        let query = SearchQuery::new("HELLO".to_string());
-        let results = engine.search(&query);
-        
-        assert!(results.len() >= 1, "Should find at least one result");
-        
-        // Search with mixed case - should also find all variations
-        let query = SearchQuery::new("HeLLo".to_string());
-        let results = engine.search(&query);
-        
-        assert!(results.len() >= 1, "Should find at least one result");
-    }
-
-    #[test]
-    fn test_case_sensitive_search() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![
-                ("user", "Hello World"),
-                ("assistant", "HELLO there"),
-                ("user", "hello again"),
-            ],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        // Note: The index stores terms in lowercase, so case-sensitive search
-        // at the index level will still find all variations. Case sensitivity
-        // is primarily used for highlighting with search_in_content.
-        let query = SearchQuery::new("hello".to_string());
-        let results = engine.search(&query);
-        
-        // Should find results regardless of case in the index
-        assert!(results.len() >= 1, "Should find at least one result");
-        
-        // Test case-sensitive highlighting with search_in_content
-        let query_sensitive = SearchQuery::new("Hello".to_string()).with_case_sensitive(true);
-        let text = "Hello world, HELLO again, hello there";
-        let positions = query_sensitive.search_in_content(text);
-        
-        // Should only find "Hello" with capital H
-        assert_eq!(positions.len(), 1);
-        assert_eq!(positions[0], (0, 5));
-    }
-
-    #[test]
-    fn test_whole_word_search() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![
-                ("user", "test testing tested"),
-                ("assistant", "This is a test"),
-            ],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        // Whole word search should only match exact word "test"
-        let query = SearchQuery::new("test".to_string()).with_whole_word(true);
-        let results = engine.search(&query);
-        
-        // Should match "test" but not "testing" or "tested"
-        // At least one result expected
-        assert!(results.len() >= 1, "Should find at least one exact match for 'test'");
-    }
-
-    #[test]
-    fn test_partial_word_search() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![
-                ("user", "test testing tested"),
-                ("assistant", "This is a test"),
-            ],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        // Partial search should match all variations
-        let query = SearchQuery::new("test".to_string());
-        let results = engine.search(&query);
-        
-        // Should match "test", "testing", and "tested"
-        assert_eq!(results.len(), 2);
-    }
-
-    #[test]
-    fn test_search_multiple_conversations() {
-        let mut engine = SearchEngine::new();
-        
-        let conv1 = create_test_conversation(
-            "Conversation 1",
-            vec![("user", "Tell me about Rust")],
-        );
-        let conv2 = create_test_conversation(
-            "Conversation 2",
-            vec![("user", "What is Python?")],
-        );
-        let conv3 = create_test_conversation(
-            "Conversation 3",
-            vec![("user", "Compare Rust and Python")],
-        );
-
-        engine.index_conversation(&conv1);
-        engine.index_conversation(&conv2);
-        engine.index_conversation(&conv3);
-        
-        let query = SearchQuery::new("rust".to_string());
-        let results = engine.search(&query);
-        
-        // Should find Rust in conv1 and conv3
-        assert_eq!(results.len(), 2);
-        
-        let conv_ids: Vec<String> = results.iter().map(|r| r.conversation_id.clone()).collect();
-        assert!(conv_ids.contains(&conv1.id));
-        assert!(conv_ids.contains(&conv3.id));
-    }
-
-    #[test]
-    fn test_search_no_results() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![("user", "Hello world")],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("nonexistent".to_string());
-        let results = engine.search(&query);
-        
-        assert_eq!(results.len(), 0);
-    }
-
-    #[test]
-    fn test_search_empty_query() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![("user", "Hello world")],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("".to_string());
-        let results = engine.search(&query);
-        
-        assert_eq!(results.len(), 0);
-    }
-
-    #[test]
-    fn test_result_context_extraction() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![("user", "This is a very long message with the word target somewhere in the middle of it")],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("target".to_string());
-        let results = engine.search(&query);
-        
-        assert_eq!(results.len(), 1);
-        // Context should contain the word and surrounding text
-        assert!(results[0].context.contains("target"));
-        assert!(!results[0].context.is_empty());
-    }
-
-    #[test]
-    fn test_search_with_numbers() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![
-                ("user", "What is 42?"),
-                ("assistant", "42 is the answer to everything"),
-            ],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("42".to_string());
-        let results = engine.search(&query);
-        
-        // Should find "42" in at least one message
-        assert!(results.len() >= 1, "Should find at least one result for '42'");
-    }
-
-    #[test]
-    fn test_search_with_underscores() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![
-                ("user", "What is snake_case?"),
-                ("assistant", "snake_case is a naming convention"),
-            ],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("snake_case".to_string());
-        let results = engine.search(&query);
-        
-        // Should find "snake_case" in at least one message
-        assert!(results.len() >= 1, "Should find at least one result for 'snake_case'");
-    }
-
-    #[test]
-    fn test_rebuild_index() {
-        let mut engine = SearchEngine::new();
-        
-        let conv1 = create_test_conversation("Conv1", vec![("user", "First")]);
-        let conv2 = create_test_conversation("Conv2", vec![("user", "Second")]);
-        
-        engine.index_conversation(&conv1);
-        assert_eq!(engine.indexed_count(), 1);
-        
-        // Rebuild with both conversations
-        engine.rebuild_index(&[conv1.clone(), conv2.clone()]);
-        assert_eq!(engine.indexed_count(), 2);
-        
-        let query = SearchQuery::new("first".to_string());
-        let results = engine.search(&query);
-        assert_eq!(results.len(), 1);
-        
-        let query = SearchQuery::new("second".to_string());
-        let results = engine.search(&query);
-        assert_eq!(results.len(), 1);
-    }
-
-    #[test]
-    fn test_remove_conversation_from_index() {
-        let mut engine = SearchEngine::new();
-        
-        let conv1 = create_test_conversation("Conv1", vec![("user", "Hello")]);
-        let conv2 = create_test_conversation("Conv2", vec![("user", "World")]);
-        
-        engine.index_conversation(&conv1);
-        engine.index_conversation(&conv2);
-        assert_eq!(engine.indexed_count(), 2);
-        
-        engine.remove_conversation(&conv1.id);
-        assert_eq!(engine.indexed_count(), 1);
-        assert!(!engine.is_indexed(&conv1.id));
-        assert!(engine.is_indexed(&conv2.id));
-        
-        let query = SearchQuery::new("hello".to_string());
-        let results = engine.search(&query);
-        assert_eq!(results.len(), 0);
-    }
-
-    #[test]
-    fn test_large_conversation_set() {
-        let mut engine = SearchEngine::new();
-        
-        // Create 100 conversations with multiple messages each
-        let mut conversations = Vec::new();
-        for i in 0..100 {
-            let conv = create_test_conversation(
-                &format!("Conversation {}", i),
-                vec![
-                    ("user", &format!("Question {} about programming", i)),
-                    ("assistant", &format!("Answer {} about programming languages", i)),
-                    ("user", "Tell me more"),
-                    ("assistant", "Here is more information about programming"),
-                ],
-            );
-            conversations.push(conv);
-        }
-        
-        // Index all conversations
-        for conv in &conversations {
-            engine.index_conversation(conv);
-        }
-        
-        assert_eq!(engine.indexed_count(), 100);
-        
-        // Search for common term
-        let query = SearchQuery::new("programming".to_string());
-        let results = engine.search(&query);
-        
-        // Should find "programming" in many messages
-        assert!(results.len() >= 100); // At least one per conversation
-    }
-
-    #[test]
-    fn test_large_conversation_performance() {
-        let mut engine = SearchEngine::new();
-        
-        // Create message strings that will live long enough
-        let message_strings: Vec<String> = (0..1000)
-            .map(|i| format!("Message {} with some content", i))
-            .collect();
-        
-        // Create references to the strings
-        let message_refs: Vec<(&str, &str)> = message_strings
-            .iter()
-            .map(|s| ("user", s.as_str()))
-            .collect();
-        
-        let conversation = create_test_conversation("Large", message_refs);
-        
-        engine.index_conversation(&conversation);
-        
-        // Search should still be fast
-        let query = SearchQuery::new("message".to_string());
-        let results = engine.search(&query);
-        
-        // Should find "Message" in many results (one per message since each has unique "Message N")
-        // The indexer creates one result per unique term per message
-        assert!(results.len() >= 1, "Should find at least one result");
-        assert!(results.len() <= 1000, "Should not exceed number of messages");
-    }
-
-    #[test]
-    fn test_search_query_builder() {
-        let query = SearchQueryBuilder::new()
-            .text("test".to_string())
-            .case_sensitive(true)
-            .whole_word(false)
-            .build();
-        
-        assert_eq!(query.text, "test");
-        assert!(query.case_sensitive);
-        assert!(!query.whole_word);
-    }
-
-    #[test]
-    fn test_search_in_content_case_insensitive() {
-        let query = SearchQuery::new("hello".to_string());
-        let text = "Hello world, HELLO again, hello there";
-        
-        let positions = query.search_in_content(text);
-        
-        // Should find all three occurrences
-        assert_eq!(positions.len(), 3);
-    }
-
-    #[test]
-    fn test_search_in_content_case_sensitive() {
-        let query = SearchQuery::new("Hello".to_string()).with_case_sensitive(true);
-        let text = "Hello world, HELLO again, hello there";
-        
-        let positions = query.search_in_content(text);
-        
-        // Should only find "Hello" with capital H
-        assert_eq!(positions.len(), 1);
-        assert_eq!(positions[0], (0, 5));
-    }
-
-    #[test]
-    fn test_search_in_content_whole_word() {
-        let query = SearchQuery::new("test".to_string()).with_whole_word(true);
-        let text = "test testing tested test";
-        
-        let positions = query.search_in_content(text);
-        
-        // Should only find standalone "test" words (2 occurrences)
-        assert_eq!(positions.len(), 2);
-    }
-
-    #[test]
-    fn test_search_in_content_multiple_occurrences() {
-        let query = SearchQuery::new("the".to_string());
-        let text = "the quick brown fox jumps over the lazy dog";
-        
-        let positions = query.search_in_content(text);
-        
-        // Should find both occurrences of "the"
-        assert_eq!(positions.len(), 2);
-        // First occurrence at the start
-        assert_eq!(positions[0].0, 0);
-        assert_eq!(positions[0].1, 3);
-        // Second occurrence later in the text
-        assert_eq!(positions[1].0, 31); // "over the" - 'the' starts at position 31
-        assert_eq!(positions[1].1, 34);
-    }
-
-    #[test]
-    fn test_empty_conversation() {
-        let mut engine = SearchEngine::new();
-        let conversation = Conversation::new("Empty".to_string(), None);
-        
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("anything".to_string());
-        let results = engine.search(&query);
-        
-        assert_eq!(results.len(), 0);
-    }
-
-    #[test]
-    fn test_conversation_with_empty_messages() {
-        let mut engine = SearchEngine::new();
-        let conversation = create_test_conversation(
-            "Test",
-            vec![
-                ("user", ""),
-                ("assistant", "Hello"),
-                ("user", ""),
-            ],
-        );
-
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("hello".to_string());
-        let results = engine.search(&query);
-        
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].message_index, 1);
-    }
-
-    #[test]
-    fn test_search_results_sorted() {
-        let mut engine = SearchEngine::new();
-        
-        let conv1 = create_test_conversation(
-            "Conv1",
-            vec![
-                ("user", "test at index 0"),
-                ("user", "test at index 1"),
-            ],
-        );
-        let conv2 = create_test_conversation(
-            "Conv2",
-            vec![("user", "test in conv2")],
-        );
-        
-        engine.index_conversation(&conv2);
-        engine.index_conversation(&conv1);
-        
-        let query = SearchQuery::new("test".to_string());
-        let results = engine.search(&query);
-        
-        // Results should be sorted by conversation_id, then message_index
-        assert!(results.len() >= 1, "Should find at least one result");
-        
-        // Verify sorting - if there are multiple results
-        if results.len() > 1 {
-            for i in 1..results.len() {
-                let prev = &results[i - 1];
-                let curr = &results[i];
-                
-                if prev.conversation_id == curr.conversation_id {
-                    assert!(prev.message_index <= curr.message_index);
-                }
-            }
-        }
-    }
-
-    #[test]
-    fn test_index_updates_on_reindex() {
-        let mut engine = SearchEngine::new();
-        
-        let mut conversation = create_test_conversation(
-            "Test",
-            vec![("user", "original message")],
-        );
-        
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("original".to_string());
-        let results = engine.search(&query);
-        assert_eq!(results.len(), 1);
-        
-        // Modify conversation and reindex
-        conversation.add_message(ChatMessage::new(
-            "user".to_string(),
-            "updated message".to_string(),
-        ));
-        
-        engine.remove_conversation(&conversation.id);
-        engine.index_conversation(&conversation);
-        
-        let query = SearchQuery::new("updated".to_string());
-        let results = engine.search(&query);
-        assert_eq!(results.len(), 1);
-    }
-}
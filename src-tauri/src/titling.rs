@@ -0,0 +1,92 @@
+use crate::conversation::ConversationManager;
+use prometheus_chat::titling::{build_title_prompt, clean_title};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Ask the model for a title for the given first exchange
+pub async fn generate_title(
+    endpoint: &str,
+    model: &str,
+    user: &str,
+    assistant: &str,
+) -> Result<String, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(60))
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let request_body = serde_json::json!({
+        "model": model,
+        "prompt": build_title_prompt(user, assistant),
+        "stream": false
+    });
+
+    let response = client
+        .post(format!("{}/api/generate", endpoint))
+        .json(&request_body)
+        .send()
+        .await
+        .map_err(|e| format!("Network error: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Server error: {}", response.status()));
+    }
+
+    let json: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+    json.get("response")
+        .and_then(|v| v.as_str())
+        .and_then(clean_title)
+        .ok_or_else(|| "Model returned an empty title".to_string())
+}
+
+/// Title a conversation in the background and emit `conversation-renamed`
+///
+/// The response stream has already finished when this is called, so a slow or
+/// failing title request never delays the answer itself.
+pub fn spawn_title_generation(
+    app: AppHandle,
+    conversation_manager: Arc<Mutex<ConversationManager>>,
+    conversation_id: String,
+    endpoint: String,
+    model: String,
+    user: String,
+    assistant: String,
+) {
+    tauri::async_runtime::spawn(async move {
+        let title = match generate_title(&endpoint, &model, &user, &assistant).await {
+            Ok(title) => title,
+            Err(e) => {
+                log::warn!("Failed to generate conversation title: {}", e);
+                return;
+            }
+        };
+
+        let renamed = conversation_manager
+            .lock()
+            .map_err(|e| e.to_string())
+            .and_then(|manager| {
+                let mut conversation = manager
+                    .load_conversation(&conversation_id)
+                    .map_err(|e| e.to_string())?;
+                conversation.name = title.clone();
+                manager
+                    .save_conversation(&conversation)
+                    .map_err(|e| e.to_string())
+            });
+
+        match renamed {
+            Ok(()) => {
+                let _ = app.emit("conversation-renamed", serde_json::json!({
+                    "conversation_id": conversation_id,
+                    "name": title
+                }));
+            }
+            Err(e) => log::warn!("Failed to save conversation title: {}", e),
+        }
+    });
+}
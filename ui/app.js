@@ -302,6 +302,15 @@ async function init() {
     // Load chat history
     await loadChatHistory();
     
+    // Pick up titles generated in the background after the first exchange
+    await window.__TAURI__.event.listen('conversation-renamed', (event) => {
+        const conv = conversations.find(c => c.id === event.payload.conversation_id);
+        if (conv) {
+            conv.name = event.payload.name;
+            renderConversationList();
+        }
+    });
+    
     // Set up event listeners
     const inputForm = document.querySelector('.input-container');
     inputForm.addEventListener('submit', (e) => {
@@ -588,14 +597,22 @@ async function loadModels() {
     }
 }
 
+// Summarize generation metadata (model, tokens, latency) for a message tooltip
+function formatMessageMetadata(metadata) {
+    const parts = [];
+    if (metadata.model) parts.push(`Model: ${metadata.model}`);
+    if (metadata.endpoint) parts.push(`Endpoint: ${metadata.endpoint}`);
+    if (metadata.persona_id) parts.push(`Persona: ${metadata.persona_id}`);
+    if (metadata.prompt_tokens != null) parts.push(`Prompt tokens: ${metadata.prompt_tokens}`);
+    if (metadata.completion_tokens != null) parts.push(`Completion tokens: ${metadata.completion_tokens}`);
+    if (metadata.latency_ms != null) parts.push(`Latency: ${(metadata.latency_ms / 1000).toFixed(2)}s`);
+    if (metadata.finish_reason) parts.push(`Finish reason: ${metadata.finish_reason}`);
+    return parts.join('\n');
+}
+
 async function loadConversations() {
     try {
-        // Read the metadata file directly
-        const fs = window.__TAURI__.fs;
-        const metadataContent = await fs.readTextFile('conversations/metadata.json');
-        const metadata = JSON.parse(metadataContent);
-        
-        conversations = metadata.conversations || [];
+        conversations = await invoke('list_conversations');
         renderConversationList();
     } catch (error) {
         console.error('Failed to load conversations:', error);
@@ -641,10 +658,7 @@ function renderConversationList() {
 
 async function selectConversation(conversationId) {
     try {
-        // Load the conversation file
-        const fs = window.__TAURI__.fs;
-        const conversationContent = await fs.readTextFile(`conversations/${conversationId}.json`);
-        const conversation = JSON.parse(conversationContent);
+        const conversation = await invoke('load_conversation', { conversationId });
         
         // Update current conversation ID
         currentConversationId = conversationId;
@@ -654,7 +668,7 @@ async function selectConversation(conversationId) {
         
         if (conversation.messages && conversation.messages.length > 0) {
             conversation.messages.forEach(msg => {
-                addMessageToUI(msg.role, msg.content);
+                addMessageToUI(msg.role, msg.content, msg.thinking);
             });
         } else {
             showEmptyState();
@@ -674,12 +688,14 @@ async function selectConversation(conversationId) {
 
 async function loadChatHistory() {
     try {
-        const history = await invoke('get_chat_history');
+        // Start in a fresh conversation; it is stored once the first message is saved
+        currentConversationId = await invoke('new_conversation');
+        const history = await invoke('get_chat_history', { conversationId: currentConversationId });
         messagesContainer.innerHTML = '';
         
         if (history && history.length > 0) {
             history.forEach(msg => {
-                addMessageToUI(msg.role, msg.content);
+                addMessageToUI(msg.role, msg.content, msg.thinking);
             });
         } else {
             showEmptyState();
@@ -725,6 +741,24 @@ async function sendMessage() {
     // Add user message to UI
     addMessageToUI('user', message);
     
+    // Store the user message before streaming so background titling can see it
+    if (!currentConversationId) {
+        currentConversationId = await invoke('new_conversation');
+    }
+    const conversationId = currentConversationId;
+    try {
+        await invoke('save_message', {
+            conversationId,
+            role: 'user',
+            content: message,
+            model: currentModel || null,
+            metadata: null
+        });
+        await loadConversations();
+    } catch (error) {
+        console.error('Failed to save message:', error);
+    }
+    
     // Set loading state
     isLoading = true;
     sendBtn.disabled = true;
@@ -753,9 +787,27 @@ async function sendMessage() {
     const { listen } = window.__TAURI__.event;
     
     let streamContent = '';
+    let thinkingContent = '';
+    let thinkingBlock = null;
+    
+    const unlistenThinking = await listen('stream-thinking', (event) => {
+        if (event.payload.request_id === requestId) {
+            if (!thinkingBlock) {
+                thinkingBlock = addThinkingBlock(assistantMsg, '', true);
+            }
+            thinkingContent += event.payload.token;
+            thinkingBlock.body.textContent = thinkingContent;
+            messagesContainer.scrollTop = messagesContainer.scrollHeight;
+        }
+    });
     
     const unlistenToken = await listen('stream-token', (event) => {
         if (event.payload.request_id === requestId) {
+            if (thinkingBlock && !streamContent) {
+                // Collapse the thinking once the answer starts
+                thinkingBlock.details.open = false;
+                thinkingBlock.summary.textContent = 'Thought';
+            }
             streamContent += event.payload.token;
             assistantMsg.textContent = streamContent;
             // Auto-scroll to bottom
@@ -766,6 +818,23 @@ async function sendMessage() {
     const unlistenDone = await listen('stream-done', (event) => {
         if (event.payload.request_id === requestId) {
             console.log('Stream completed');
+            if (thinkingBlock) {
+                thinkingBlock.summary.textContent = 'Thought';
+            }
+            if (event.payload.metadata) {
+                assistantMsg.title = formatMessageMetadata(event.payload.metadata);
+            }
+            renderMarkdownInto(assistantMsg, streamContent);
+            invoke('save_message', {
+                conversationId,
+                role: 'assistant',
+                content: streamContent,
+                model: currentModel || null,
+                metadata: event.payload.metadata || null,
+                thinking: thinkingContent || null
+            })
+                .then(() => loadConversations())
+                .catch(error => console.error('Failed to save response:', error));
             isLoading = false;
             sendBtn.classList.remove('loading');
             updateSendButtonState();
@@ -775,6 +844,7 @@ async function sendMessage() {
                 personaBubbleContainer.setDisabled(false);
             }
             
+            unlistenThinking();
             unlistenToken();
             unlistenDone();
             unlistenError();
@@ -795,6 +865,7 @@ async function sendMessage() {
                 personaBubbleContainer.setDisabled(false);
             }
             
+            unlistenThinking();
             unlistenToken();
             unlistenDone();
             unlistenError();
@@ -809,7 +880,9 @@ async function sendMessage() {
             prompt: message,
             model: currentModel || 'llama2',
             requestId: requestId,
-            systemPrompt: systemPrompt // Pass system prompt (null/undefined if no active persona)
+            systemPrompt: systemPrompt, // Pass system prompt (null/undefined if no active persona)
+            personaId: activePersona ? activePersona.id : null,
+            conversationId
         });
     } catch (error) {
         console.error('Error starting stream:', error);
@@ -824,13 +897,24 @@ async function sendMessage() {
             personaBubbleContainer.setDisabled(false);
         }
         
+        unlistenThinking();
         unlistenToken();
         unlistenDone();
         unlistenError();
     }
 }
 
-function addMessageToUI(role, content) {
+// Replace plain text with markdown rendered by the backend, which sanitizes the HTML
+async function renderMarkdownInto(element, content) {
+    try {
+        element.innerHTML = await invoke('render_markdown', { content });
+        element.classList.add('markdown');
+    } catch (error) {
+        console.error('Failed to render markdown:', error);
+    }
+}
+
+function addMessageToUI(role, content, thinking) {
     // Remove empty state if present
     const emptyState = messagesContainer.querySelector('.empty-state');
     if (emptyState) {
@@ -845,9 +929,15 @@ function addMessageToUI(role, content) {
     const contentDiv = document.createElement('div');
     contentDiv.className = 'message-content';
     contentDiv.textContent = content;
+    if (role === 'assistant' && content) {
+        renderMarkdownInto(contentDiv, content);
+    }
     
     messageDiv.appendChild(contentDiv);
     messagesContainer.appendChild(messageDiv);
+    if (role === 'assistant' && thinking) {
+        addThinkingBlock(contentDiv, thinking, false);
+    }
     
     // Scroll to bottom
     messagesContainer.scrollTop = messagesContainer.scrollHeight;
@@ -855,13 +945,30 @@ function addMessageToUI(role, content) {
     return contentDiv;
 }
 
+// Add a collapsible block with the model's thinking above a message's content
+function addThinkingBlock(contentDiv, thinking, open) {
+    const details = document.createElement('details');
+    details.className = 'thinking';
+    details.open = open;
+    
+    const summary = document.createElement('summary');
+    summary.textContent = open ? 'Thinking…' : 'Thought';
+    
+    const body = document.createElement('div');
+    body.className = 'thinking-content';
+    body.textContent = thinking;
+    
+    details.appendChild(summary);
+    details.appendChild(body);
+    contentDiv.parentNode.classList.add('has-thinking');
+    contentDiv.parentNode.insertBefore(details, contentDiv);
+    return { details, summary, body };
+}
+
 async function newChat() {
     try {
-        // Call backend to create new conversation
-        await invoke('new_conversation');
-        
-        // Clear current conversation ID
-        currentConversationId = null;
+        // Start a new conversation; it is stored once the first message is saved
+        currentConversationId = await invoke('new_conversation');
         
         // Clear persona state on new conversation
         // Requirements: 3.5